@@ -15,6 +15,7 @@ fn payment_benchmark(c: &mut Criterion) {
         pool.clone(),
         wallet_service.clone(),
         "otp_secret".to_string(),
+        "qr_secret".to_string(),
         Arc::new(MockNatsClient::new()),
     ));
 
@@ -32,19 +32,211 @@ CREATE INDEX idx_otp_expired ON otp_store (expires_at) WHERE expires_at < NOW();
 CREATE INDEX idx_refresh_expired ON refresh_tokens (expires_at) WHERE expires_at < NOW();
 CREATE INDEX idx_refresh_user ON refresh_tokens (user_id);
 
+-- payment_handles (one vanity handle per user, e.g. 'alice@pay')
+CREATE TABLE payment_handles (
+    handle TEXT PRIMARY KEY, -- lowercase, '@pay' included
+    user_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- released_handles (a changed handle keeps paying its previous owner until held_until)
+CREATE TABLE released_handles (
+    handle TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    held_until TIMESTAMPTZ NOT NULL
+);
+
+-- fee_plans (what merchants pay per payment; merchants.fee_plan points here)
+CREATE TABLE fee_plans (
+    plan_code TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- fee_plan_rules (slabs by amount, optionally for one merchant category)
+CREATE TABLE fee_plan_rules (
+    rule_id BIGSERIAL PRIMARY KEY,
+    plan_code TEXT NOT NULL REFERENCES fee_plans(plan_code) ON DELETE CASCADE,
+    category_code TEXT, -- MCC; NULL applies to every category
+    min_amount BIGINT NOT NULL DEFAULT 0, -- paise, inclusive
+    max_amount BIGINT, -- paise, exclusive; NULL is open-ended
+    mdr_bps INT NOT NULL DEFAULT 0 CHECK (mdr_bps BETWEEN 0 AND 1000),
+    flat_fee BIGINT NOT NULL DEFAULT 0 CHECK (flat_fee >= 0), -- paise per payment
+    max_fee BIGINT CHECK (max_fee >= 0), -- cap before GST
+    CHECK (max_amount IS NULL OR max_amount > min_amount)
+);
+
+CREATE INDEX idx_fee_plan_rules_plan ON fee_plan_rules (plan_code);
+
+INSERT INTO fee_plans (plan_code, name) VALUES
+    ('STANDARD', 'Standard'),
+    ('DISCOUNTED', 'Discounted'),
+    ('ZERO_MDR', 'Zero MDR');
+
+INSERT INTO fee_plan_rules (plan_code, mdr_bps) VALUES
+    ('STANDARD', 180),
+    ('DISCOUNTED', 100);
+
+-- merchants (business identity; merchant_id is also the merchant's wallet id)
+CREATE TABLE merchants (
+    merchant_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_user_id UUID NOT NULL REFERENCES users(id),
+    legal_name TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    gstin TEXT UNIQUE, -- NULL for unregistered small businesses
+    pan TEXT NOT NULL,
+    category_code TEXT NOT NULL, -- merchant category code (MCC)
+    settlement_account_number TEXT NOT NULL,
+    settlement_ifsc TEXT NOT NULL,
+    settlement_account_name TEXT NOT NULL,
+    fee_plan TEXT NOT NULL DEFAULT 'STANDARD' REFERENCES fee_plans(plan_code),
+    settlement_cycle TEXT NOT NULL DEFAULT 'T1', -- 'T0' (same day) or 'T1' (next day)
+    status TEXT NOT NULL DEFAULT 'PENDING', -- 'PENDING', 'ACTIVE', 'SUSPENDED', 'REJECTED'
+    status_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- index for owner dashboards
+CREATE INDEX idx_merchants_owner ON merchants (owner_user_id);
+
 -- payment-service
--- transaction_journal (immutable, append-only)
+-- transaction_journal (one row per payment; status is its current state)
 CREATE TABLE transaction_journal (
     tx_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     from_user_id UUID NOT NULL,
     to_user_id UUID NOT NULL,
     amount BIGINT NOT NULL CHECK (amount > 0),
     currency TEXT NOT NULL DEFAULT 'INR',
-    status TEXT NOT NULL, -- 'SUCCESS', 'FAILED', 'PENDING'
+    status TEXT NOT NULL, -- 'INITIATED', 'SUCCESS', 'FAILED', 'REVERSED'
     idempotency_key TEXT NOT NULL UNIQUE,
+    request_hash TEXT, -- fingerprint of the originating request, for idempotent replay
+    kind TEXT NOT NULL DEFAULT 'PAYMENT', -- 'PAYMENT' or 'REFUND'
+    original_tx_id UUID REFERENCES transaction_journal(tx_id), -- set for refunds
+    method TEXT, -- entry point, e.g. 'dynamic_qr'; refunds carry the original payment's
+    merchant_id UUID REFERENCES merchants(merchant_id), -- set when either side is a merchant wallet
+    payment_type TEXT GENERATED ALWAYS AS (CASE WHEN merchant_id IS NULL THEN 'P2P' ELSE 'MERCHANT' END) STORED,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+-- index for merchant reporting
+CREATE INDEX idx_journal_merchant ON transaction_journal (merchant_id, created_at) WHERE merchant_id IS NOT NULL;
+
+-- index for refund totals
+CREATE INDEX idx_journal_original ON transaction_journal (original_tx_id) WHERE original_tx_id IS NOT NULL;
+
+-- payment_state_transitions (immutable, append-only)
+CREATE TABLE payment_state_transitions (
+    id BIGSERIAL PRIMARY KEY,
+    tx_id UUID NOT NULL REFERENCES transaction_journal(tx_id),
+    from_state TEXT,          -- NULL for the initial INITIATED row
+    to_state TEXT NOT NULL,
+    reason TEXT,              -- error or operator note
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- index for timeline lookups
+CREATE INDEX idx_transitions_tx ON payment_state_transitions (tx_id, created_at);
+
+-- collect_requests (request money; approving runs a normal payment)
+CREATE TABLE collect_requests (
+    request_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    requester_id UUID NOT NULL, -- receives the money
+    payer_id UUID NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    note TEXT,
+    status TEXT NOT NULL DEFAULT 'PENDING', -- 'PENDING', 'APPROVED', 'PAID', 'DECLINED', 'EXPIRED'
+    tx_id UUID REFERENCES transaction_journal(tx_id), -- set once paid
+    attempts INT NOT NULL DEFAULT 0, -- approvals so far; part of the payment idempotency key
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- index for pending lists and the expiry job
+CREATE INDEX idx_collect_payer_pending ON collect_requests (payer_id) WHERE status = 'PENDING';
+CREATE INDEX idx_collect_requester_pending ON collect_requests (requester_id) WHERE status = 'PENDING';
+CREATE INDEX idx_collect_expiry ON collect_requests (expires_at) WHERE status = 'PENDING';
+
+-- splits (a bill shared between the creator and participants)
+CREATE TABLE splits (
+    split_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    creator_id UUID NOT NULL,
+    description TEXT NOT NULL,
+    total_amount BIGINT NOT NULL CHECK (total_amount > 0),
+    split_type TEXT NOT NULL, -- 'EQUAL', 'AMOUNT', 'PERCENTAGE'
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_splits_creator ON splits (creator_id);
+
+-- split_participants (paid state lives on the linked collect request)
+CREATE TABLE split_participants (
+    split_id UUID NOT NULL REFERENCES splits(split_id),
+    user_id UUID NOT NULL,
+    share BIGINT NOT NULL CHECK (share > 0),
+    collect_request_id UUID REFERENCES collect_requests(request_id), -- latest request for this share
+    reminder_count INT NOT NULL DEFAULT 0,
+    last_reminded_at TIMESTAMPTZ,
+    PRIMARY KEY (split_id, user_id)
+);
+
+CREATE INDEX idx_split_participants_user ON split_participants (user_id);
+
+-- mandates (standing instructions: payee may debit up to max_amount per cycle)
+CREATE TABLE mandates (
+    mandate_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payer_id UUID NOT NULL,
+    payee_id UUID NOT NULL,
+    max_amount BIGINT NOT NULL CHECK (max_amount > 0),
+    next_amount BIGINT CHECK (next_amount > 0), -- presented by the payee for the next debit
+    frequency TEXT NOT NULL,                    -- 'DAILY', 'WEEKLY', 'MONTHLY'
+    status TEXT NOT NULL DEFAULT 'ACTIVE',      -- 'ACTIVE', 'PAUSED', 'REVOKED', 'COMPLETED'
+    first_debit_at TIMESTAMPTZ NOT NULL,        -- anchors the schedule; every cycle is counted from it
+    next_debit_at TIMESTAMPTZ NOT NULL,         -- start of the current cycle
+    retry_at TIMESTAMPTZ,                       -- set while a failed debit waits for retry
+    attempts INT NOT NULL DEFAULT 0,            -- failed attempts in the current cycle
+    notified_for TIMESTAMPTZ,                   -- cycle the pre-debit notice was sent for
+    end_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- index for the scheduler
+CREATE INDEX idx_mandates_due ON mandates (COALESCE(retry_at, next_debit_at)) WHERE status = 'ACTIVE';
+CREATE INDEX idx_mandates_payer ON mandates (payer_id);
+CREATE INDEX idx_mandates_payee ON mandates (payee_id);
+
+-- mandate_executions (one row per debit attempt)
+CREATE TABLE mandate_executions (
+    id BIGSERIAL PRIMARY KEY,
+    mandate_id UUID NOT NULL REFERENCES mandates(mandate_id),
+    due_at TIMESTAMPTZ NOT NULL, -- cycle the attempt belongs to
+    attempt INT NOT NULL,
+    amount BIGINT NOT NULL,
+    tx_id UUID REFERENCES transaction_journal(tx_id), -- set on success
+    status TEXT NOT NULL, -- 'PROCESSING', 'SUCCESS', 'RETRY_SCHEDULED', 'FAILED'
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(), -- claim time while PROCESSING
+    UNIQUE (mandate_id, due_at, attempt)
+);
+
+CREATE INDEX idx_mandate_executions_mandate ON mandate_executions (mandate_id, created_at);
+
+-- qr_redemptions (dynamic QR codes are single-use)
+CREATE TABLE qr_redemptions (
+    qr_id UUID PRIMARY KEY,  -- id embedded in the signed code
+    payee_id UUID NOT NULL,
+    reference TEXT NOT NULL, -- merchant's order reference
+    idempotency_key TEXT NOT NULL,
+    tx_id UUID REFERENCES transaction_journal(tx_id), -- set once paid
+    redeemed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_qr_redemptions_reference ON qr_redemptions (payee_id, reference);
+
 -- daily_limits (track per user per day)
 CREATE TABLE daily_limits (
     user_id UUID PRIMARY KEY,
@@ -62,11 +254,32 @@ CREATE INDEX idx_daily_limits_reset ON daily_limits (reset_date);
 CREATE TABLE wallets (
     user_id UUID PRIMARY KEY,
     balance BIGINT NOT NULL CHECK (balance >= 0),
+    held BIGINT NOT NULL DEFAULT 0 CHECK (held >= 0), -- sum of ACTIVE holds
     version INT NOT NULL DEFAULT 0,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
     updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+-- wallet_holds (authorize now, capture or void later)
+CREATE TABLE wallet_holds (
+    hold_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES wallets(user_id),
+    payee_user_id UUID NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    captured BIGINT NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'ACTIVE', -- 'ACTIVE', 'CAPTURED', 'VOIDED', 'EXPIRED'
+    reference TEXT NOT NULL UNIQUE,        -- idempotency key of the authorization
+    captured_tx_id UUID REFERENCES transaction_journal(tx_id),
+    captured_from_balance BIGINT,          -- capture receipt, replayed on retries
+    captured_to_balance BIGINT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- index for the expiry job
+CREATE INDEX idx_holds_active_expiry ON wallet_holds (expires_at) WHERE status = 'ACTIVE';
+
 -- idempotency keys (24h TTL)
 CREATE TABLE idempotency_keys (
     idempotency_key TEXT PRIMARY KEY,
@@ -115,6 +328,370 @@ CREATE TRIGGER wallet_audit_trigger
     EXECUTE FUNCTION log_wallet_change();
 
 
+-- ledger (double-entry)
+-- ledger_accounts: one per user wallet + fixed system accounts
+CREATE TABLE ledger_accounts (
+    account_code TEXT PRIMARY KEY, -- 'wallet:<user_id>', 'system:float', ...
+    account_type TEXT NOT NULL,    -- 'USER_WALLET', 'SYSTEM_FLOAT', 'FEES', 'SUSPENSE'
+    user_id UUID UNIQUE,           -- set only for USER_WALLET
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO ledger_accounts (account_code, account_type) VALUES
+    ('system:float', 'SYSTEM_FLOAT'),
+    ('system:fees', 'FEES'),
+    ('system:suspense', 'SUSPENSE');
+
+-- ledger_entries (immutable, one per business event)
+CREATE TABLE ledger_entries (
+    entry_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    reference TEXT NOT NULL UNIQUE, -- idempotency key of the operation
+    description TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- ledger_postings (credit > 0, debit < 0; must sum to zero per entry)
+CREATE TABLE ledger_postings (
+    id BIGSERIAL PRIMARY KEY,
+    entry_id UUID NOT NULL REFERENCES ledger_entries(entry_id),
+    account_code TEXT NOT NULL REFERENCES ledger_accounts(account_code),
+    amount BIGINT NOT NULL CHECK (amount <> 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_postings_account ON ledger_postings (account_code);
+CREATE INDEX idx_postings_entry ON ledger_postings (entry_id);
+
+-- Balance check runs at commit, once every leg of the entry is inserted
+CREATE OR REPLACE FUNCTION check_entry_balanced()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF (SELECT SUM(amount) FROM ledger_postings WHERE entry_id = NEW.entry_id) <> 0 THEN
+        RAISE EXCEPTION 'ledger entry % does not balance', NEW.entry_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER ledger_entry_balanced
+    AFTER INSERT ON ledger_postings
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW
+    EXECUTE FUNCTION check_entry_balanced();
+
+-- Postings are append-only — corrections are new entries
+CREATE OR REPLACE FUNCTION forbid_posting_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'ledger postings are immutable';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER ledger_postings_immutable
+    BEFORE UPDATE OR DELETE ON ledger_postings
+    FOR EACH ROW
+    EXECUTE FUNCTION forbid_posting_change();
+
+-- Balances are derived from postings, never stored
+CREATE VIEW ledger_balances AS
+SELECT
+    a.account_code,
+    a.account_type,
+    a.user_id,
+    COALESCE(SUM(p.amount), 0)::BIGINT AS balance
+FROM ledger_accounts a
+LEFT JOIN ledger_postings p ON p.account_code = a.account_code
+GROUP BY a.account_code, a.account_type, a.user_id;
+
+-- payment_intents (merchant checkout, v1 API)
+CREATE TABLE payment_intents (
+    intent_id UUID PRIMARY KEY,
+    merchant_id UUID NOT NULL REFERENCES merchants(merchant_id),
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    currency TEXT NOT NULL DEFAULT 'INR',
+    order_id TEXT NOT NULL, -- merchant's reference
+    description TEXT,
+    return_url TEXT NOT NULL,
+    client_secret TEXT NOT NULL, -- handed to the customer's checkout page
+    status TEXT NOT NULL DEFAULT 'REQUIRES_CONFIRMATION', -- 'REQUIRES_CONFIRMATION', 'PROCESSING', 'SUCCEEDED', 'CANCELED'
+    customer_user_id UUID, -- set on confirmation
+    tx_id UUID REFERENCES transaction_journal(tx_id), -- set once succeeded
+    attempts INT NOT NULL DEFAULT 0, -- confirmations, each with its own payment key
+    last_payment_error TEXT,
+    cancellation_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (merchant_id, order_id)
+);
+
+-- merchant_api_keys (server-to-server credentials; the secret key is kept only encrypted)
+CREATE TABLE merchant_api_keys (
+    key_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    merchant_id UUID NOT NULL REFERENCES merchants(merchant_id),
+    name TEXT,
+    publishable_hash TEXT NOT NULL UNIQUE, -- SHA-256 of the publishable key, for lookup
+    publishable_prefix TEXT NOT NULL, -- for display
+    secret_ciphertext TEXT NOT NULL, -- secret key, AES-256-GCM under API_KEY_ENCRYPTION_SECRET, bound to key_id
+    secret_last4 TEXT NOT NULL,
+    scopes TEXT[] NOT NULL,
+    status TEXT NOT NULL DEFAULT 'ACTIVE', -- 'ACTIVE', 'REVOKED'
+    expires_at TIMESTAMPTZ, -- end of the grace period once rotated
+    rotated_to UUID REFERENCES merchant_api_keys(key_id),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_merchant_api_keys_merchant ON merchant_api_keys (merchant_id);
+
+-- api_key_nonces (nonces seen on signed requests, kept past the timestamp window)
+CREATE TABLE api_key_nonces (
+    key_id UUID NOT NULL REFERENCES merchant_api_keys(key_id),
+    nonce TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (key_id, nonce)
+);
+
+CREATE INDEX idx_api_key_nonces_expiry ON api_key_nonces (expires_at);
+
+-- settlement (merchant wallet → merchant bank account)
+-- settlement_batches (one payout per merchant per cycle run)
+CREATE TABLE settlement_batches (
+    batch_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    merchant_id UUID NOT NULL REFERENCES merchants(merchant_id),
+    cycle TEXT NOT NULL, -- 'T0', 'T1'
+    cutoff_at TIMESTAMPTZ NOT NULL, -- covers merchant payments made before this
+    payment_count INT NOT NULL,
+    refund_count INT NOT NULL,
+    gross BIGINT NOT NULL, -- payments received, in paise
+    refunds BIGINT NOT NULL,
+    fees BIGINT NOT NULL,
+    gst BIGINT NOT NULL, -- on fees
+    net BIGINT NOT NULL CHECK (net > 0), -- gross - refunds - fees - gst
+    account_number TEXT NOT NULL, -- settlement account at batch time
+    ifsc TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'PENDING', -- 'PENDING', 'PROCESSING', 'PAID', 'FAILED'
+    attempts INT NOT NULL DEFAULT 0,
+    next_retry_at TIMESTAMPTZ, -- NULL once automatic retries are exhausted
+    last_error TEXT,
+    utr TEXT UNIQUE, -- bank reference of the successful payout
+    paid_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- index for merchant reports
+CREATE INDEX idx_settlement_batches_merchant ON settlement_batches (merchant_id, created_at);
+
+-- index for the payout job
+CREATE INDEX idx_settlement_batches_due ON settlement_batches (created_at) WHERE status IN ('PENDING', 'PROCESSING', 'FAILED');
+
+-- settlement_items (a journal row is settled at most once)
+CREATE TABLE settlement_items (
+    tx_id UUID PRIMARY KEY REFERENCES transaction_journal(tx_id),
+    batch_id UUID NOT NULL REFERENCES settlement_batches(batch_id),
+    amount BIGINT NOT NULL -- positive for payments, negative for refunds
+);
+
+CREATE INDEX idx_settlement_items_batch ON settlement_items (batch_id);
+
+-- settlement_attempts (one row per call to the bank)
+CREATE TABLE settlement_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    batch_id UUID NOT NULL REFERENCES settlement_batches(batch_id),
+    attempt INT NOT NULL,
+    status TEXT NOT NULL, -- 'PAID', 'FAILED'
+    utr TEXT,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (batch_id, attempt)
+);
+
+-- fees (per-payment fees posted with each settlement batch, invoiced monthly)
+CREATE SEQUENCE fee_invoice_seq;
+
+-- fee_invoices (one GST invoice per merchant per month)
+CREATE TABLE fee_invoices (
+    invoice_id UUID PRIMARY KEY,
+    invoice_number TEXT NOT NULL UNIQUE, -- FEE/YYYYMM/nnnnnn
+    merchant_id UUID NOT NULL REFERENCES merchants(merchant_id),
+    legal_name TEXT NOT NULL, -- as at invoicing
+    gstin TEXT,
+    period_start DATE NOT NULL,
+    period_end DATE NOT NULL,
+    transaction_count INT NOT NULL,
+    taxable_value BIGINT NOT NULL, -- fees, in paise
+    cgst BIGINT NOT NULL,
+    sgst BIGINT NOT NULL,
+    igst BIGINT NOT NULL,
+    total BIGINT NOT NULL, -- taxable_value + GST
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (merchant_id, period_start)
+);
+
+-- transaction_fees (fee and GST charged on one payment)
+CREATE TABLE transaction_fees (
+    tx_id UUID PRIMARY KEY REFERENCES transaction_journal(tx_id),
+    merchant_id UUID NOT NULL REFERENCES merchants(merchant_id),
+    batch_id UUID NOT NULL REFERENCES settlement_batches(batch_id),
+    plan_code TEXT NOT NULL,
+    rule_id BIGINT, -- NULL when no rule matched (no fee)
+    fee BIGINT NOT NULL,
+    gst BIGINT NOT NULL,
+    invoice_id UUID REFERENCES fee_invoices(invoice_id),
+    posted_at TIMESTAMPTZ NOT NULL DEFAULT NOW() -- with the settlement batch
+);
+
+-- index for invoicing
+CREATE INDEX idx_transaction_fees_uninvoiced ON transaction_fees (merchant_id, posted_at) WHERE invoice_id IS NULL;
+
+-- merchant reporting (reports read hourly rollups, never the journal)
+-- merchant_hourly_rollups (one row per merchant, IST hour and payment method)
+CREATE TABLE merchant_hourly_rollups (
+    merchant_id UUID NOT NULL REFERENCES merchants(merchant_id),
+    hour TIMESTAMP NOT NULL, -- IST wall-clock hour the payments and refunds were made in
+    method TEXT NOT NULL, -- journal method; 'unknown' for rows recorded before it was
+    payment_count INT NOT NULL,
+    gross BIGINT NOT NULL, -- successful payments, in paise
+    refund_count INT NOT NULL,
+    refunds BIGINT NOT NULL,
+    fees BIGINT NOT NULL, -- charged once settled
+    gst BIGINT NOT NULL,
+    net BIGINT NOT NULL, -- gross - refunds - fees - gst
+    unsettled BIGINT NOT NULL, -- part of net not yet in a settlement batch
+    settling BIGINT NOT NULL, -- in a PENDING, PROCESSING or FAILED batch
+    settled BIGINT NOT NULL, -- in a PAID batch
+    refreshed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (merchant_id, hour, method)
+);
+
+-- merchant_rollup_queue (hours whose rollup is out of date)
+CREATE TABLE merchant_rollup_queue (
+    merchant_id UUID NOT NULL,
+    hour TIMESTAMP NOT NULL,
+    queued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (merchant_id, hour)
+);
+
+CREATE INDEX idx_merchant_rollup_queue_age ON merchant_rollup_queue (queued_at);
+
+-- Anything that changes a merchant's numbers queues the hour it falls in.
+-- An hour already queued is updated rather than skipped, so the change holds
+-- its queue row locked until it commits and a refresh cannot take the hour
+-- off the queue before the change is visible to it.
+CREATE OR REPLACE FUNCTION queue_journal_rollup()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO merchant_rollup_queue (merchant_id, hour)
+    VALUES (NEW.merchant_id, date_trunc('hour', NEW.created_at AT TIME ZONE 'Asia/Kolkata'))
+    ON CONFLICT (merchant_id, hour) DO UPDATE SET queued_at = EXCLUDED.queued_at;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER journal_rollup_trigger
+    AFTER INSERT OR UPDATE OF status ON transaction_journal
+    FOR EACH ROW
+    WHEN (NEW.merchant_id IS NOT NULL)
+    EXECUTE FUNCTION queue_journal_rollup();
+
+CREATE OR REPLACE FUNCTION queue_settlement_item_rollup()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO merchant_rollup_queue (merchant_id, hour)
+    SELECT tj.merchant_id, date_trunc('hour', tj.created_at AT TIME ZONE 'Asia/Kolkata')
+    FROM transaction_journal tj
+    WHERE tj.tx_id = NEW.tx_id AND tj.merchant_id IS NOT NULL
+    ON CONFLICT (merchant_id, hour) DO UPDATE SET queued_at = EXCLUDED.queued_at;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Fees are posted in the same transaction as the items, so this covers them too
+CREATE TRIGGER settlement_item_rollup_trigger
+    AFTER INSERT ON settlement_items
+    FOR EACH ROW
+    EXECUTE FUNCTION queue_settlement_item_rollup();
+
+CREATE OR REPLACE FUNCTION queue_settlement_batch_rollup()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO merchant_rollup_queue (merchant_id, hour)
+    SELECT DISTINCT tj.merchant_id, date_trunc('hour', tj.created_at AT TIME ZONE 'Asia/Kolkata')
+    FROM settlement_items si
+    JOIN transaction_journal tj ON tj.tx_id = si.tx_id
+    WHERE si.batch_id = NEW.batch_id
+    ON CONFLICT (merchant_id, hour) DO UPDATE SET queued_at = EXCLUDED.queued_at;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER settlement_batch_rollup_trigger
+    AFTER UPDATE OF status ON settlement_batches
+    FOR EACH ROW
+    WHEN (OLD.status IS DISTINCT FROM NEW.status)
+    EXECUTE FUNCTION queue_settlement_batch_rollup();
+
+-- webhooks (signed merchant notifications)
+-- webhook_endpoints (where a merchant wants events sent)
+CREATE TABLE webhook_endpoints (
+    endpoint_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    merchant_id UUID NOT NULL REFERENCES merchants(merchant_id),
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL, -- HMAC key for Webhook-Signature
+    events TEXT[] NOT NULL, -- subscribed event types, '*' for all
+    status TEXT NOT NULL DEFAULT 'ACTIVE', -- 'ACTIVE', 'DISABLED'
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_endpoints_merchant ON webhook_endpoints (merchant_id);
+
+-- webhook_events (the outbox: written with the change it reports; the payload is stored exactly as signed)
+CREATE TABLE webhook_events (
+    event_id UUID PRIMARY KEY,
+    merchant_id UUID NOT NULL REFERENCES merchants(merchant_id),
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    source_key TEXT UNIQUE, -- what the event reports, for events emitted outside the change's transaction
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_events_merchant ON webhook_events (merchant_id, created_at);
+
+-- webhook_deliveries (one per event and endpoint)
+CREATE TABLE webhook_deliveries (
+    delivery_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event_id UUID NOT NULL REFERENCES webhook_events(event_id),
+    endpoint_id UUID NOT NULL REFERENCES webhook_endpoints(endpoint_id),
+    status TEXT NOT NULL DEFAULT 'PENDING', -- 'PENDING', 'SUCCEEDED', 'FAILED'
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ, -- NULL once SUCCEEDED or FAILED
+    last_status_code INT,
+    last_error TEXT,
+    delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (event_id, endpoint_id)
+);
+
+-- index for the delivery job
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries (next_attempt_at) WHERE status = 'PENDING';
+
+-- webhook_attempts (one row per POST to the endpoint)
+CREATE TABLE webhook_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    delivery_id UUID NOT NULL REFERENCES webhook_deliveries(delivery_id),
+    attempt INT NOT NULL,
+    status_code INT, -- NULL when no response came back
+    error TEXT,
+    duration_ms INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (delivery_id, attempt)
+);
+
+
 
 -- fraud_flags table
 CREATE TABLE fraud_flags (
@@ -159,4 +736,13 @@ CREATE TABLE fake_bank_accounts (
     ifsc TEXT NOT NULL,
     name TEXT NOT NULL,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- fake_bank_transfers (a payout reference is paid at most once)
+CREATE TABLE fake_bank_transfers (
+    reference UUID PRIMARY KEY,
+    to_user_id UUID NOT NULL,
+    amount BIGINT NOT NULL,
+    utr TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
\ No newline at end of file
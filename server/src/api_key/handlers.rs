@@ -0,0 +1,72 @@
+// src/api_key/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::Path,
+};
+use uuid::Uuid;
+use crate::api_key::{ApiKeyService, models::*};
+use crate::merchant::models::MerchantError;
+
+/// Creates a key pair; both keys are shown in full only in this response.
+pub async fn create_api_key(
+    Extension(api_key_service): Extension<std::sync::Arc<ApiKeyService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<IssuedApiKey>, (http::StatusCode, Json<serde_json::Value>)> {
+    let issued = api_key_service.create(user_id, merchant_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(issued))
+}
+
+pub async fn list_api_keys(
+    Extension(api_key_service): Extension<std::sync::Arc<ApiKeyService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+) -> Result<Json<Vec<ApiKey>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let keys = api_key_service.list(user_id, merchant_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(keys))
+}
+
+pub async fn rotate_api_key(
+    Extension(api_key_service): Extension<std::sync::Arc<ApiKeyService>>,
+    user_id: Uuid, // from JWT middleware
+    Path((merchant_id, key_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<RotateApiKeyRequest>,
+) -> Result<Json<IssuedApiKey>, (http::StatusCode, Json<serde_json::Value>)> {
+    let issued = api_key_service.rotate(user_id, merchant_id, key_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(issued))
+}
+
+pub async fn revoke_api_key(
+    Extension(api_key_service): Extension<std::sync::Arc<ApiKeyService>>,
+    user_id: Uuid, // from JWT middleware
+    Path((merchant_id, key_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ApiKey>, (http::StatusCode, Json<serde_json::Value>)> {
+    let key = api_key_service.revoke(user_id, merchant_id, key_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(key))
+}
+
+fn error_response(e: ApiKeyError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        ApiKeyError::NotFound(_) | ApiKeyError::MerchantError(MerchantError::NotFound(_)) => http::StatusCode::NOT_FOUND,
+        ApiKeyError::InvalidStatus(_) => http::StatusCode::CONFLICT,
+        ApiKeyError::ValidationError(_) => http::StatusCode::BAD_REQUEST,
+        _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
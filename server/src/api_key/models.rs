@@ -0,0 +1,132 @@
+// src/api_key/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+use sqlx::types::Uuid;
+use crate::merchant::models::MerchantError;
+
+pub const SCOPE_PAYMENT_INTENTS_READ: &str = "payment_intents:read";
+pub const SCOPE_PAYMENT_INTENTS_WRITE: &str = "payment_intents:write";
+pub const SCOPE_REFUNDS_WRITE: &str = "refunds:write";
+
+/// Every scope a key can be granted.
+pub const SCOPES: &[&str] = &[
+    SCOPE_PAYMENT_INTENTS_READ,
+    SCOPE_PAYMENT_INTENTS_WRITE,
+    SCOPE_REFUNDS_WRITE,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateApiKeyRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: Option<String>,
+
+    #[validate(length(min = 1, max = 8), custom = "validate_scopes")]
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RotateApiKeyRequest {
+    /// How long the old key keeps working; 0 retires it at once.
+    #[validate(range(max = 168))]
+    pub grace_hours: Option<u32>, // default 24
+}
+
+/// A key as listed to its merchant. Only a display prefix of the
+/// publishable key and the last characters of the secret are kept.
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct ApiKey {
+    pub key_id: Uuid,
+    pub merchant_id: Uuid,
+    pub name: Option<String>,
+    pub publishable_prefix: String,
+    pub secret_last4: String,
+    pub scopes: Vec<String>,
+    pub status: String, // 'ACTIVE', 'REVOKED'
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>, // set once rotated
+    pub rotated_to: Option<Uuid>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A freshly created key pair. The full keys are returned this once.
+#[derive(Debug, Serialize)]
+pub struct IssuedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub publishable_key: String,
+    pub secret_key: String,
+}
+
+/// A server-to-server request as received, before it is trusted.
+#[derive(Debug)]
+pub struct SignedRequest<'a> {
+    pub publishable_key: &'a str,
+    pub timestamp: i64,
+    pub nonce: &'a str,
+    pub signature: &'a str,
+    pub method: &'a str,
+    pub path: &'a str, // path and query, as sent
+    pub body: &'a [u8],
+}
+
+/// The merchant a verified request acts for; set by the API key middleware.
+#[derive(Debug, Clone)]
+pub struct ApiKeyContext {
+    pub key_id: Uuid,
+    pub merchant_id: Uuid,
+    pub owner_user_id: Uuid,
+    pub scopes: Vec<String>,
+}
+
+impl ApiKeyContext {
+    pub fn require(&self, scope: &str) -> Result<(), ApiKeyError> {
+        if self.scopes.iter().any(|s| s == scope) {
+            Ok(())
+        } else {
+            Err(ApiKeyError::MissingScope(scope.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiKeyError {
+    #[error("API key not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("API key is {0}")]
+    InvalidStatus(String),
+
+    #[error("Unknown, revoked or expired API key")]
+    InvalidKey,
+
+    #[error("Signature does not match the request")]
+    InvalidSignature,
+
+    #[error("Request timestamp is outside the allowed window")]
+    StaleTimestamp,
+
+    #[error("Nonce has already been used")]
+    ReplayedNonce,
+
+    #[error("API key lacks the {0} scope")]
+    MissingScope(String),
+
+    #[error("Merchant error: {0}")]
+    MerchantError(#[from] MerchantError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+fn validate_scopes(scopes: &[String]) -> Result<(), ValidationError> {
+    if scopes.iter().all(|s| SCOPES.contains(&s.as_str())) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_scope"))
+    }
+}
@@ -0,0 +1,307 @@
+// src/api_key/service.rs
+
+use crate::api_key::models::*;
+use crate::api_key::signature::{self, TOLERANCE_SECS};
+use crate::auth::crypto::{self, verify_hmac};
+use crate::merchant::MerchantService;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+const DEFAULT_GRACE_HOURS: u32 = 24;
+
+/// Merchant API keys. Each key is a pair: the publishable key names it in
+/// `X-Api-Key`, and the secret key signs requests. The publishable key is
+/// stored as a SHA-256 digest, to look the key up by. Checking a signature
+/// needs the secret itself, so it is stored encrypted under
+/// `encryption_secret`, which never reaches the database.
+pub struct ApiKeyService {
+    db: PgPool,
+    merchant_service: Arc<MerchantService>,
+    encryption_secret: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct KeyCredentials {
+    key_id: Uuid,
+    merchant_id: Uuid,
+    owner_user_id: Uuid,
+    secret_ciphertext: String,
+    scopes: Vec<String>,
+}
+
+impl ApiKeyService {
+    pub fn new(db: PgPool, merchant_service: Arc<MerchantService>, encryption_secret: String) -> Self {
+        Self { db, merchant_service, encryption_secret }
+    }
+
+    #[instrument(skip(self, req), fields(merchant_id = %merchant_id))]
+    pub async fn create(
+        &self,
+        owner_user_id: Uuid,
+        merchant_id: Uuid,
+        req: CreateApiKeyRequest,
+    ) -> Result<IssuedApiKey, ApiKeyError> {
+        req.validate()?;
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let mut tx = self.db.begin().await?;
+        let issued = self.issue(&mut tx, merchant_id, req.name.as_deref(), &req.scopes).await?;
+        tx.commit().await?;
+
+        counter!("merchant_api_keys_total", 1, "event" => "created");
+        info!(key_id = %issued.key.key_id, "API key created");
+        Ok(issued)
+    }
+
+    pub async fn list(&self, owner_user_id: Uuid, merchant_id: Uuid) -> Result<Vec<ApiKey>, ApiKeyError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let keys = sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT key_id, merchant_id, name, publishable_prefix, secret_last4, scopes, status,
+                   expires_at, rotated_to, last_used_at, revoked_at, created_at
+            FROM merchant_api_keys
+            WHERE merchant_id = $1
+            ORDER BY created_at
+            "#,
+            merchant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(keys)
+    }
+
+    /// Issues a replacement with the same name and scopes. The old key keeps
+    /// working for the grace period so the merchant can deploy the new one.
+    #[instrument(skip(self, req), fields(merchant_id = %merchant_id, key_id = %key_id))]
+    pub async fn rotate(
+        &self,
+        owner_user_id: Uuid,
+        merchant_id: Uuid,
+        key_id: Uuid,
+        req: RotateApiKeyRequest,
+    ) -> Result<IssuedApiKey, ApiKeyError> {
+        req.validate()?;
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let mut tx = self.db.begin().await?;
+        let old = sqlx::query!(
+            r#"
+            SELECT name, scopes, status, rotated_to
+            FROM merchant_api_keys
+            WHERE key_id = $1 AND merchant_id = $2
+            FOR UPDATE
+            "#,
+            key_id,
+            merchant_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(ApiKeyError::NotFound(key_id))?;
+
+        if old.status != "ACTIVE" {
+            return Err(ApiKeyError::InvalidStatus(old.status));
+        }
+        if old.rotated_to.is_some() {
+            return Err(ApiKeyError::InvalidStatus("ROTATED".to_string()));
+        }
+
+        let issued = self.issue(&mut tx, merchant_id, old.name.as_deref(), &old.scopes).await?;
+        let grace = chrono::Duration::hours(req.grace_hours.unwrap_or(DEFAULT_GRACE_HOURS) as i64);
+        sqlx::query!(
+            r#"
+            UPDATE merchant_api_keys
+            SET rotated_to = $2, expires_at = $3, updated_at = NOW()
+            WHERE key_id = $1
+            "#,
+            key_id,
+            issued.key.key_id,
+            chrono::Utc::now() + grace
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        counter!("merchant_api_keys_total", 1, "event" => "rotated");
+        info!(new_key_id = %issued.key.key_id, grace_hours = grace.num_hours(), "API key rotated");
+        Ok(issued)
+    }
+
+    /// Stops the key at once.
+    pub async fn revoke(&self, owner_user_id: Uuid, merchant_id: Uuid, key_id: Uuid) -> Result<ApiKey, ApiKeyError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            UPDATE merchant_api_keys
+            SET status = 'REVOKED', revoked_at = NOW(), updated_at = NOW()
+            WHERE key_id = $1 AND merchant_id = $2 AND status = 'ACTIVE'
+            RETURNING key_id, merchant_id, name, publishable_prefix, secret_last4, scopes, status,
+                      expires_at, rotated_to, last_used_at, revoked_at, created_at
+            "#,
+            key_id,
+            merchant_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let Some(key) = key else {
+            return Err(self.not_actionable(merchant_id, key_id).await);
+        };
+
+        counter!("merchant_api_keys_total", 1, "event" => "revoked");
+        info!(key_id = %key_id, "API key revoked");
+        Ok(key)
+    }
+
+    /// Checks a signed server-to-server request: live key, fresh timestamp,
+    /// matching signature and a nonce not seen before, in that order.
+    pub async fn authenticate(
+        &self,
+        req: &SignedRequest<'_>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<ApiKeyContext, ApiKeyError> {
+        if (now.timestamp() - req.timestamp).abs() > TOLERANCE_SECS {
+            return Err(ApiKeyError::StaleTimestamp);
+        }
+
+        let key = sqlx::query_as!(
+            KeyCredentials,
+            r#"
+            SELECT k.key_id, k.merchant_id, m.owner_user_id, k.secret_ciphertext, k.scopes
+            FROM merchant_api_keys k
+            JOIN merchants m ON m.merchant_id = k.merchant_id
+            WHERE k.publishable_hash = $1
+              AND k.status = 'ACTIVE'
+              AND (k.expires_at IS NULL OR k.expires_at > $2)
+            "#,
+            digest(req.publishable_key),
+            now
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(ApiKeyError::InvalidKey)?;
+
+        let Some(secret_key) = crypto::open(&self.encryption_secret, key.key_id.as_bytes(), &key.secret_ciphertext) else {
+            error!(key_id = %key.key_id, "API key secret could not be decrypted");
+            return Err(ApiKeyError::InvalidKey);
+        };
+
+        let message = signature::canonical_request(req.method, req.path, req.timestamp, req.nonce, req.body);
+        if !verify_hmac(&signature::signing_key(&secret_key), &message, req.signature) {
+            counter!("merchant_api_auth_failures_total", 1, "reason" => "signature");
+            warn!(key_id = %key.key_id, "API request signature mismatch");
+            return Err(ApiKeyError::InvalidSignature);
+        }
+
+        // Only signed requests reach here, so nobody can burn another key's nonces
+        let fresh = sqlx::query!(
+            r#"
+            INSERT INTO api_key_nonces (key_id, nonce, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (key_id, nonce) DO NOTHING
+            "#,
+            key.key_id,
+            req.nonce,
+            now + chrono::Duration::seconds(2 * TOLERANCE_SECS)
+        )
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+
+        if fresh == 0 {
+            counter!("merchant_api_auth_failures_total", 1, "reason" => "replay");
+            warn!(key_id = %key.key_id, "API request nonce replayed");
+            return Err(ApiKeyError::ReplayedNonce);
+        }
+
+        sqlx::query!("UPDATE merchant_api_keys SET last_used_at = $2 WHERE key_id = $1", key.key_id, now)
+            .execute(&self.db)
+            .await?;
+
+        Ok(ApiKeyContext {
+            key_id: key.key_id,
+            merchant_id: key.merchant_id,
+            owner_user_id: key.owner_user_id,
+            scopes: key.scopes,
+        })
+    }
+
+    /// Forgets nonces whose requests would now fail the timestamp check anyway.
+    pub async fn purge_nonces(&self) -> Result<u64, ApiKeyError> {
+        let purged = sqlx::query!("DELETE FROM api_key_nonces WHERE expires_at < NOW()")
+            .execute(&self.db)
+            .await?
+            .rows_affected();
+
+        Ok(purged)
+    }
+
+    /// Explains why a guarded status update matched no row.
+    async fn not_actionable(&self, merchant_id: Uuid, key_id: Uuid) -> ApiKeyError {
+        let status = sqlx::query_scalar!(
+            "SELECT status FROM merchant_api_keys WHERE key_id = $1 AND merchant_id = $2",
+            key_id,
+            merchant_id
+        )
+        .fetch_optional(&self.db)
+        .await;
+
+        match status {
+            Ok(Some(status)) => ApiKeyError::InvalidStatus(status),
+            Ok(None) => ApiKeyError::NotFound(key_id),
+            Err(e) => e.into(),
+        }
+    }
+
+    /// Generates a key pair; stores the publishable key's digest and the
+    /// secret key encrypted, bound to the new key's id.
+    async fn issue(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        merchant_id: Uuid,
+        name: Option<&str>,
+        scopes: &[String],
+    ) -> Result<IssuedApiKey, ApiKeyError> {
+        let key_id = Uuid::new_v4();
+        let publishable_key = format!("pk_live_{}", hex::encode(rand::thread_rng().gen::<[u8; 24]>()));
+        let secret_key = format!("sk_live_{}", hex::encode(rand::thread_rng().gen::<[u8; 32]>()));
+
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO merchant_api_keys (
+                key_id, merchant_id, name, publishable_hash, publishable_prefix, secret_ciphertext, secret_last4, scopes
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING key_id, merchant_id, name, publishable_prefix, secret_last4, scopes, status,
+                      expires_at, rotated_to, last_used_at, revoked_at, created_at
+            "#,
+            key_id,
+            merchant_id,
+            name,
+            digest(&publishable_key),
+            &publishable_key[..16],
+            crypto::seal(&self.encryption_secret, key_id.as_bytes(), &secret_key),
+            &secret_key[secret_key.len() - 4..],
+            scopes
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(IssuedApiKey { key, publishable_key, secret_key })
+    }
+}
+
+fn digest(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
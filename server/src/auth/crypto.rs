@@ -1,7 +1,8 @@
 // src/auth/crypto.rs
 
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce, aead::{Aead, Payload}};
 use hmac::{Hmac, Mac};
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
 use rand::Rng;
 use jsonwebtoken::{encode, decode, Header, Algorithm, EncodingKey, DecodingKey, Validation};
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -15,6 +16,55 @@ pub fn hash_mobile(mobile: &str, secret: &str) -> String {
     hex::encode(mac.finalize().into_bytes())
 }
 
+/// Hex HMAC-SHA256 of `message`, for payloads that a counterparty verifies
+/// with the shared `secret`.
+pub fn sign_hmac(secret: &str, message: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(message);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Checks a hex signature made by `sign_hmac`, in constant time.
+pub fn verify_hmac(secret: &str, message: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(message);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Encrypts `plaintext` with AES-256-GCM under a key derived from the
+/// server-held `secret`. `context` (e.g. the row's id) is authenticated too,
+/// so a sealed value cannot be moved to another row. Returns hex of the
+/// nonce followed by the ciphertext.
+pub fn seal(secret: &str, context: &[u8], plaintext: &str) -> String {
+    let cipher = Aes256Gcm::new_from_slice(&Sha256::digest(secret.as_bytes()))
+        .expect("SHA-256 output is a valid AES-256 key");
+    let nonce: [u8; 12] = rand::thread_rng().gen();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext.as_bytes(), aad: context })
+        .expect("AES-GCM encryption does not fail for in-memory input");
+    hex::encode([nonce.as_slice(), &ciphertext].concat())
+}
+
+/// Reverses `seal`. None if the value was tampered with, moved, or sealed
+/// under another secret.
+pub fn open(secret: &str, context: &[u8], sealed: &str) -> Option<String> {
+    let bytes = hex::decode(sealed).ok()?;
+    if bytes.len() < 12 {
+        return None;
+    }
+    let (nonce, ciphertext) = bytes.split_at(12);
+    let cipher = Aes256Gcm::new_from_slice(&Sha256::digest(secret.as_bytes())).ok()?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: context })
+        .ok()?;
+    String::from_utf8(plaintext).ok()
+}
+
 pub fn generate_otp() -> String {
     let mut rng = rand::thread_rng();
     (0..6)
@@ -52,9 +52,17 @@ pub async fn transfer(
         StatusCode::BAD_REQUEST,
         Json(serde_json::json!({ "error": "Invalid to_user_id" })),
     ))?;
+    let to_account = payload["to_account"].as_str();
     let amount = payload["amount"].as_i64().unwrap_or(10000);
+    let reference = match payload["reference"].as_str() {
+        Some(reference) => Some(Uuid::parse_str(reference).map_err(|_| (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "Invalid reference" })),
+        ))?),
+        None => None,
+    };
 
-    let resp = bank_service.transfer(from_account, to_user_id, amount)
+    let resp = bank_service.transfer(from_account, to_user_id, to_account, amount, reference)
         .await
         .map_err(|e| {
             (
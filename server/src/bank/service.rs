@@ -5,6 +5,9 @@ use sqlx::PgPool;
 use uuid::Uuid;
 use tracing::info;
 
+/// Payouts to this account number are always rejected.
+pub const CLOSED_ACCOUNT: &str = "999999999999";
+
 pub struct FakeBankService {
     db: PgPool,
 }
@@ -59,10 +62,62 @@ impl FakeBankService {
         })
     }
 
-    pub async fn transfer(&self, from_account: &str, to_user_id: Uuid, amount: i64) -> Result<BankTransferResponse, sqlx::Error> {
+    /// `to_account` is set for payouts to an external account (merchant
+    /// settlement); top-ups leave it empty. A transfer with a `reference` is
+    /// made at most once: repeating it answers with the original UTR.
+    pub async fn transfer(
+        &self,
+        from_account: &str,
+        to_user_id: Uuid,
+        to_account: Option<&str>,
+        amount: i64,
+        reference: Option<Uuid>,
+    ) -> Result<BankTransferResponse, sqlx::Error> {
+        // Simulate a rejected payout for testing
+        if to_account == Some(CLOSED_ACCOUNT) {
+            info!(from_account, to_user_id = %to_user_id, amount, "Bank transfer rejected");
+            return Ok(BankTransferResponse {
+                status: "failed".to_string(),
+                utr: String::new(),
+                message: "Beneficiary account is closed".to_string(),
+            });
+        }
+
         // Simulate UTR
         let utr = format!("UTR{}", Uuid::new_v4().to_string().replace("-", "").get(..10).unwrap());
 
+        if let Some(reference) = reference {
+            let inserted = sqlx::query!(
+                r#"
+                INSERT INTO fake_bank_transfers (reference, to_user_id, amount, utr)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (reference) DO NOTHING
+                "#,
+                reference,
+                to_user_id,
+                amount,
+                utr
+            )
+            .execute(&self.db)
+            .await?;
+
+            if inserted.rows_affected() == 0 {
+                let original = sqlx::query_scalar!(
+                    "SELECT utr FROM fake_bank_transfers WHERE reference = $1",
+                    reference
+                )
+                .fetch_one(&self.db)
+                .await?;
+
+                info!(reference = %reference, utr = original, "Duplicate bank transfer ignored");
+                return Ok(BankTransferResponse {
+                    status: "success".to_string(),
+                    utr: original,
+                    message: "Duplicate reference, transfer already made".to_string(),
+                });
+            }
+        }
+
         info!(from_account, to_user_id = %to_user_id, amount, utr, "Bank transfer simulated");
 
         Ok(BankTransferResponse {
@@ -71,4 +126,4 @@ impl FakeBankService {
             message: "Transfer initiated".to_string(),
         })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,69 @@
+// src/collect/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::Path,
+};
+use uuid::Uuid;
+use crate::collect::{CollectService, models::*};
+use crate::payment::models::{PaymentError, PaymentResponse};
+
+pub async fn create_collect(
+    Extension(collect_service): Extension<std::sync::Arc<CollectService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<CreateCollectRequest>,
+) -> Result<Json<CollectRequest>, (http::StatusCode, Json<serde_json::Value>)> {
+    let request = collect_service.create(user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(request))
+}
+
+pub async fn list_collects(
+    Extension(collect_service): Extension<std::sync::Arc<CollectService>>,
+    user_id: Uuid, // from JWT middleware
+) -> Result<Json<PendingCollects>, (http::StatusCode, Json<serde_json::Value>)> {
+    let pending = collect_service.list_pending(user_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(pending))
+}
+
+pub async fn approve_collect(
+    Extension(collect_service): Extension<std::sync::Arc<CollectService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(request_id): Path<Uuid>,
+) -> Result<Json<PaymentResponse>, (http::StatusCode, Json<serde_json::Value>)> {
+    let resp = collect_service.approve(user_id, request_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(resp))
+}
+
+pub async fn decline_collect(
+    Extension(collect_service): Extension<std::sync::Arc<CollectService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(request_id): Path<Uuid>,
+) -> Result<Json<CollectRequest>, (http::StatusCode, Json<serde_json::Value>)> {
+    let request = collect_service.decline(user_id, request_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(request))
+}
+
+fn error_response(e: CollectError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        CollectError::NotFound(_) | CollectError::PaymentError(PaymentError::UserNotFound(_)) => http::StatusCode::NOT_FOUND,
+        CollectError::NotPending(_) | CollectError::PaymentError(PaymentError::IdempotencyConflict) => http::StatusCode::CONFLICT,
+        CollectError::DatabaseError(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        CollectError::PaymentError(ref p) if p.is_internal() => http::StatusCode::INTERNAL_SERVER_ERROR,
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
@@ -0,0 +1,69 @@
+// src/collect/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use sqlx::types::Uuid;
+use crate::payment::models::PaymentError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateCollectRequest {
+    #[validate(regex = "MOBILE_REGEX")]
+    pub payer_mobile: Option<String>,
+
+    #[validate(length(min = 3, max = 300))]
+    pub payer_handle: Option<String>, // alternative to payer_mobile, e.g. "alice@pay"
+
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64, // in paise
+
+    #[validate(length(max = 140))]
+    pub note: Option<String>,
+
+    #[validate(range(min = 300, max = 604_800))] // 5 minutes to 7 days
+    pub expires_in_secs: Option<u64>, // defaults to 24 hours
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct CollectRequest {
+    pub request_id: Uuid,
+    pub requester_id: Uuid, // receives the money
+    pub payer_id: Uuid,
+    pub amount: i64,
+    pub note: Option<String>,
+    pub status: String, // 'PENDING', 'APPROVED', 'PAID', 'DECLINED', 'EXPIRED'
+    pub tx_id: Option<Uuid>, // set once paid
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingCollects {
+    pub incoming: Vec<CollectRequest>, // waiting on this user to pay
+    pub outgoing: Vec<CollectRequest>, // raised by this user
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CollectError {
+    #[error("Collect request not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Collect request is {0}")]
+    NotPending(String),
+
+    #[error("Cannot request money from yourself")]
+    SelfRequest,
+
+    #[error("Exactly one of payer_mobile or payer_handle is required")]
+    PayerRequired,
+
+    #[error("Payment failed: {0}")]
+    PaymentError(#[from] PaymentError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+const MOBILE_REGEX: &str = r"^\+91[6-9]\d{9}$";
@@ -0,0 +1,308 @@
+// src/collect/service.rs
+
+use crate::collect::models::*;
+use crate::payment::{PaymentService, models::{PaymentError, PaymentResponse, PaymentStatus}};
+use crate::ws::server::WsServer;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+const DEFAULT_EXPIRY_SECS: u64 = 24 * 60 * 60;
+
+/// Request-money flow: the requester asks a payer for an amount, the payer
+/// approves (which runs a normal payment) or declines, and unanswered
+/// requests expire.
+pub struct CollectService {
+    db: PgPool,
+    payment_service: Arc<PaymentService>,
+    ws_server: Arc<WsServer>,
+}
+
+impl CollectService {
+    pub fn new(db: PgPool, payment_service: Arc<PaymentService>, ws_server: Arc<WsServer>) -> Self {
+        Self {
+            db,
+            payment_service,
+            ws_server,
+        }
+    }
+
+    #[instrument(skip(self, req), fields(requester_id = %requester_id, amount = req.amount))]
+    pub async fn create(&self, requester_id: Uuid, req: CreateCollectRequest) -> Result<CollectRequest, CollectError> {
+        req.validate()?;
+
+        let payer_id = match (&req.payer_mobile, &req.payer_handle) {
+            (Some(mobile), None) => self.payment_service.resolve_mobile(mobile).await?,
+            (None, Some(handle)) => self.payment_service.resolve_handle(handle).await?,
+            _ => return Err(CollectError::PayerRequired),
+        };
+        self.create_for(requester_id, payer_id, req.amount, req.note.as_deref(), req.expires_in_secs).await
+    }
+
+    /// Raises a request against a payer that is already resolved.
+    pub async fn create_for(
+        &self,
+        requester_id: Uuid,
+        payer_id: Uuid,
+        amount: u64,
+        note: Option<&str>,
+        expires_in_secs: Option<u64>,
+    ) -> Result<CollectRequest, CollectError> {
+        if requester_id == payer_id {
+            return Err(CollectError::SelfRequest);
+        }
+
+        let request = sqlx::query_as!(
+            CollectRequest,
+            r#"
+            INSERT INTO collect_requests (requester_id, payer_id, amount, note, expires_at)
+            VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+            RETURNING request_id, requester_id, payer_id, amount, note, status, tx_id, expires_at, created_at
+            "#,
+            requester_id,
+            payer_id,
+            amount as i64,
+            note,
+            expires_in_secs.unwrap_or(DEFAULT_EXPIRY_SECS) as f64
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        counter!("collect_requests_total", 1, "status" => "PENDING");
+        info!(request_id = %request.request_id, "Collect request created");
+        self.notify(request.payer_id, &request).await;
+        Ok(request)
+    }
+
+    /// Pending requests where the user is either side.
+    pub async fn list_pending(&self, user_id: Uuid) -> Result<PendingCollects, CollectError> {
+        let pending = sqlx::query_as!(
+            CollectRequest,
+            r#"
+            SELECT request_id, requester_id, payer_id, amount, note, status, tx_id, expires_at, created_at
+            FROM collect_requests
+            WHERE (payer_id = $1 OR requester_id = $1)
+              AND status = 'PENDING'
+              AND expires_at > NOW()
+            ORDER BY created_at DESC
+            LIMIT 100
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let (incoming, outgoing) = pending.into_iter().partition(|r| r.payer_id == user_id);
+        Ok(PendingCollects { incoming, outgoing })
+    }
+
+    /// Payer accepts the request and pays it through the regular payment
+    /// pipeline. A failed payment puts the request back to PENDING so the
+    /// payer can try again with a fresh attempt; retrying an interrupted
+    /// approval replays the payment of the attempt in flight, and one made
+    /// while that payment is still running is refused as a conflict.
+    #[instrument(skip(self))]
+    pub async fn approve(&self, payer_id: Uuid, request_id: Uuid) -> Result<PaymentResponse, CollectError> {
+        // Step 1: Claim the request; each approval from PENDING is a new attempt
+        let request = sqlx::query!(
+            r#"
+            UPDATE collect_requests
+            SET status = 'APPROVED',
+                attempts = CASE WHEN status = 'PENDING' THEN attempts + 1 ELSE attempts END,
+                updated_at = NOW()
+            WHERE request_id = $1 AND payer_id = $2
+              AND (status = 'APPROVED' OR (status = 'PENDING' AND expires_at > NOW()))
+            RETURNING requester_id, amount, attempts
+            "#,
+            request_id,
+            payer_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let request = match request {
+            Some(r) => r,
+            None => return Err(self.not_actionable(payer_id, request_id).await),
+        };
+
+        // Step 2: Pay — the key is derived from the attempt, so each attempt
+        // pays at most once and a failed one does not poison the next
+        let result = self.payment_service.pay_user(
+            payer_id,
+            request.requester_id,
+            request.amount as u64,
+            &format!("collect:{}:{}", request_id, request.attempts),
+            "collect",
+            &request_id.to_string(),
+        ).await;
+
+        // Step 3: Record the outcome; only a successful payment marks it PAID
+        // and only a failed one gives the request back. A pending payment is
+        // another approval of this attempt still paying, and an internal
+        // error leaves the outcome unknown; both keep the claim so a retry
+        // replays the attempt instead of starting a new one.
+        let resp = match result {
+            Ok(resp) if resp.status == PaymentStatus::Success => resp,
+            Ok(resp) if resp.status == PaymentStatus::Pending => {
+                return Err(CollectError::NotPending("APPROVED".to_string()));
+            }
+            Ok(resp) => {
+                self.release(request_id).await?;
+                return Err(PaymentError::AttemptFailed(format!("payment {} is {:?}", resp.tx_id, resp.status)).into());
+            }
+            Err(e) if e.is_internal() => return Err(e.into()),
+            Err(e) => {
+                self.release(request_id).await?;
+                return Err(e.into());
+            }
+        };
+
+        // A concurrent approval that replayed the same payment may have
+        // recorded it already
+        let paid = sqlx::query_as!(
+            CollectRequest,
+            r#"
+            UPDATE collect_requests
+            SET status = 'PAID', tx_id = $1, updated_at = NOW()
+            WHERE request_id = $2 AND status = 'APPROVED'
+            RETURNING request_id, requester_id, payer_id, amount, note, status, tx_id, expires_at, created_at
+            "#,
+            resp.tx_id,
+            request_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        let paid = match paid {
+            Some(p) => p,
+            None => return Ok(resp),
+        };
+
+        counter!("collect_requests_total", 1, "status" => "PAID");
+        info!(request_id = %request_id, tx_id = %resp.tx_id, "Collect request paid");
+        self.notify(paid.requester_id, &paid).await;
+        Ok(resp)
+    }
+
+    /// Puts a claimed request back to PENDING after a failed attempt.
+    async fn release(&self, request_id: Uuid) -> Result<(), CollectError> {
+        sqlx::query!(
+            "UPDATE collect_requests SET status = 'PENDING', updated_at = NOW() WHERE request_id = $1 AND status = 'APPROVED'",
+            request_id
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn decline(&self, payer_id: Uuid, request_id: Uuid) -> Result<CollectRequest, CollectError> {
+        let declined = sqlx::query_as!(
+            CollectRequest,
+            r#"
+            UPDATE collect_requests
+            SET status = 'DECLINED', updated_at = NOW()
+            WHERE request_id = $1 AND payer_id = $2 AND status = 'PENDING'
+            RETURNING request_id, requester_id, payer_id, amount, note, status, tx_id, expires_at, created_at
+            "#,
+            request_id,
+            payer_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let declined = match declined {
+            Some(r) => r,
+            None => return Err(self.not_actionable(payer_id, request_id).await),
+        };
+
+        counter!("collect_requests_total", 1, "status" => "DECLINED");
+        info!(request_id = %request_id, "Collect request declined");
+        self.notify(declined.requester_id, &declined).await;
+        Ok(declined)
+    }
+
+    /// Re-sends the notification for a request still waiting on the payer.
+    #[instrument(skip(self))]
+    pub async fn remind(&self, requester_id: Uuid, request_id: Uuid) -> Result<CollectRequest, CollectError> {
+        let request = sqlx::query_as!(
+            CollectRequest,
+            r#"
+            SELECT request_id, requester_id, payer_id, amount, note, status, tx_id, expires_at, created_at
+            FROM collect_requests
+            WHERE request_id = $1 AND requester_id = $2
+            "#,
+            request_id,
+            requester_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(CollectError::NotFound(request_id))?;
+
+        if request.status != "PENDING" || request.expires_at <= chrono::Utc::now() {
+            return Err(CollectError::NotPending(request.status));
+        }
+
+        counter!("collect_reminders_total", 1);
+        self.notify(request.payer_id, &request).await;
+        Ok(request)
+    }
+
+    /// Moves unanswered requests past their expiry to EXPIRED and tells both
+    /// parties. Returns how many expired.
+    pub async fn expire_pending(&self) -> Result<u32, CollectError> {
+        let expired = sqlx::query_as!(
+            CollectRequest,
+            r#"
+            UPDATE collect_requests
+            SET status = 'EXPIRED', updated_at = NOW()
+            WHERE status = 'PENDING' AND expires_at <= NOW()
+            RETURNING request_id, requester_id, payer_id, amount, note, status, tx_id, expires_at, created_at
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for request in &expired {
+            self.notify(request.requester_id, request).await;
+            self.notify(request.payer_id, request).await;
+        }
+
+        if !expired.is_empty() {
+            counter!("collect_requests_total", expired.len() as u64, "status" => "EXPIRED");
+            info!(expired = expired.len(), "Expired collect requests");
+        }
+        Ok(expired.len() as u32)
+    }
+
+    /// Explains why an approve/decline matched nothing: the request is not
+    /// the payer's, or it has already been answered or expired.
+    async fn not_actionable(&self, payer_id: Uuid, request_id: Uuid) -> CollectError {
+        let status = sqlx::query!(
+            "SELECT status, expires_at FROM collect_requests WHERE request_id = $1 AND payer_id = $2",
+            request_id,
+            payer_id
+        )
+        .fetch_optional(&self.db)
+        .await;
+
+        match status {
+            Ok(Some(r)) if r.status == "PENDING" && r.expires_at <= chrono::Utc::now() => {
+                CollectError::NotPending("EXPIRED".to_string())
+            }
+            Ok(Some(r)) => CollectError::NotPending(r.status),
+            Ok(None) => CollectError::NotFound(request_id),
+            Err(e) => e.into(),
+        }
+    }
+
+    async fn notify(&self, user_id: Uuid, request: &CollectRequest) {
+        let notification = format!(
+            r#"{{"type":"collect","request_id":"{}","requester_id":"{}","payer_id":"{}","amount":{},"status":"{}"}}"#,
+            request.request_id, request.requester_id, request.payer_id, request.amount, request.status
+        );
+        self.ws_server.send_notification(&user_id.to_string(), &notification).await;
+    }
+}
@@ -0,0 +1,109 @@
+// src/fee/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::{Path, Query},
+};
+use uuid::Uuid;
+use crate::api_key::models::{ApiKeyContext, SCOPE_PAYMENT_INTENTS_READ};
+use crate::fee::{FeeService, models::*};
+use crate::merchant::models::MerchantError;
+use crate::middleware::admin::AdminOperator;
+use validator::Validate;
+
+/// `GET /v1/fees/quote?amount=` — what the key's merchant would pay on a
+/// payment of `amount`, before the customer confirms it.
+pub async fn quote_fees(
+    Extension(fee_service): Extension<std::sync::Arc<FeeService>>,
+    Extension(api_key): Extension<ApiKeyContext>, // from API key middleware
+    Query(query): Query<QuoteQuery>,
+) -> Result<Json<FeeBreakdown>, (http::StatusCode, Json<serde_json::Value>)> {
+    if let Err(e) = api_key.require(SCOPE_PAYMENT_INTENTS_READ) {
+        return Err((http::StatusCode::FORBIDDEN, Json(serde_json::json!({ "error": e.to_string() }))));
+    }
+
+    query.validate().map_err(|e| error_response(e.into()))?;
+
+    let quote = fee_service.quote(api_key.merchant_id, query.amount)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(quote))
+}
+
+pub async fn list_invoices(
+    Extension(fee_service): Extension<std::sync::Arc<FeeService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+) -> Result<Json<Vec<TaxInvoice>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let invoices = fee_service.list_invoices(user_id, merchant_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(invoices))
+}
+
+pub async fn get_invoice(
+    Extension(fee_service): Extension<std::sync::Arc<FeeService>>,
+    user_id: Uuid, // from JWT middleware
+    Path((merchant_id, invoice_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<TaxInvoice>, (http::StatusCode, Json<serde_json::Value>)> {
+    let invoice = fee_service.get_invoice(user_id, merchant_id, invoice_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(invoice))
+}
+
+pub async fn admin_list_fee_plans(
+    Extension(fee_service): Extension<std::sync::Arc<FeeService>>,
+    Extension(_operator): Extension<AdminOperator>, // from admin middleware
+) -> Result<Json<Vec<FeePlan>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let plans = fee_service.list_plans()
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(plans))
+}
+
+/// Creates a plan or replaces its rules; merchants on it pick the new
+/// rules up from their next settlement.
+pub async fn admin_upsert_fee_plan(
+    Extension(fee_service): Extension<std::sync::Arc<FeeService>>,
+    Extension(operator): Extension<AdminOperator>, // from admin middleware
+    Path(plan_code): Path<String>,
+    Json(payload): Json<UpsertFeePlanRequest>,
+) -> Result<Json<FeePlan>, (http::StatusCode, Json<serde_json::Value>)> {
+    let plan = fee_service.upsert_plan(&plan_code, payload, &operator.0)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(plan))
+}
+
+/// Invoices a month again, e.g. after the scheduled run failed.
+pub async fn admin_generate_invoices(
+    Extension(fee_service): Extension<std::sync::Arc<FeeService>>,
+    Extension(operator): Extension<AdminOperator>, // from admin middleware
+    Json(payload): Json<InvoiceMonthRequest>,
+) -> Result<Json<Vec<TaxInvoice>>, (http::StatusCode, Json<serde_json::Value>)> {
+    tracing::info!(month = %payload.month, operator = %operator.0, "Fee invoice run requested");
+    let invoices = fee_service.generate_invoices(payload.month)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(invoices))
+}
+
+fn error_response(e: FeeError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        FeeError::PlanNotFound(_)
+        | FeeError::InvoiceNotFound(_)
+        | FeeError::MerchantError(MerchantError::NotFound(_)) => http::StatusCode::NOT_FOUND,
+        FeeError::ValidationError(_) => http::StatusCode::BAD_REQUEST,
+        _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
@@ -0,0 +1,201 @@
+// src/fee/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+use sqlx::types::Uuid;
+use crate::merchant::models::MerchantError;
+
+/// GST charged on top of platform fees, in basis points.
+pub const GST_BPS: i64 = 1800;
+
+/// State the platform is registered in (Karnataka). Merchants in the same
+/// state are invoiced CGST + SGST, everyone else IGST.
+pub const PLATFORM_STATE_CODE: &str = "29";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct FeeRuleInput {
+    #[validate(regex = "MCC_REGEX")]
+    pub category_code: Option<String>, // None applies to every category
+
+    pub min_amount: u64, // paise, inclusive
+
+    pub max_amount: Option<u64>, // paise, exclusive; None is open-ended
+
+    #[validate(range(max = 1000))]
+    pub mdr_bps: u32,
+
+    #[validate(range(max = 100_000))]
+    pub flat_fee: u64, // paise per transaction
+
+    pub max_fee: Option<u64>, // cap on MDR + flat fee, before GST
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_slabs"))]
+pub struct UpsertFeePlanRequest {
+    #[validate(length(min = 1, max = 128))]
+    pub name: String,
+
+    #[validate(length(min = 1, max = 64))]
+    #[validate]
+    pub rules: Vec<FeeRuleInput>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct QuoteQuery {
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceMonthRequest {
+    pub month: chrono::NaiveDate, // any day in the month
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct FeeRule {
+    pub rule_id: i64,
+    pub category_code: Option<String>,
+    pub min_amount: i64,
+    pub max_amount: Option<i64>,
+    pub mdr_bps: i32,
+    pub flat_fee: i64,
+    pub max_fee: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FeePlan {
+    pub plan_code: String,
+    pub name: String,
+    pub rules: Vec<FeeRule>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The rules of a plan that can apply to one merchant category.
+#[derive(Debug, Clone)]
+pub struct FeeSchedule {
+    pub plan_code: String,
+    pub rules: Vec<FeeRule>,
+}
+
+impl FeeSchedule {
+    /// The slab containing `amount`; a rule for the merchant's own category
+    /// beats a catch-all one.
+    pub fn rule_for(&self, amount: i64) -> Option<&FeeRule> {
+        self.rules
+            .iter()
+            .filter(|r| amount >= r.min_amount && r.max_amount.map_or(true, |max| amount < max))
+            .min_by_key(|r| (r.category_code.is_none(), std::cmp::Reverse(r.min_amount)))
+    }
+
+    /// Fee and GST on one payment. No matching rule means no fee.
+    pub fn quote(&self, amount: i64) -> FeeBreakdown {
+        let rule = self.rule_for(amount);
+        let (mdr, flat_fee) = rule.map_or((0, 0), |r| (of_bps(amount, r.mdr_bps as i64), r.flat_fee));
+        let fee = match rule.and_then(|r| r.max_fee) {
+            Some(cap) => (mdr + flat_fee).min(cap),
+            None => mdr + flat_fee,
+        };
+        let gst = of_bps(fee, GST_BPS);
+
+        FeeBreakdown {
+            amount,
+            plan_code: self.plan_code.clone(),
+            rule_id: rule.map(|r| r.rule_id),
+            mdr,
+            flat_fee,
+            fee,
+            gst,
+            total_fee: fee + gst,
+            net: amount - fee - gst,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FeeBreakdown {
+    pub amount: i64,
+    pub plan_code: String,
+    pub rule_id: Option<i64>,
+    pub mdr: i64,
+    pub flat_fee: i64,
+    pub fee: i64, // after the cap
+    pub gst: i64,
+    pub total_fee: i64, // fee + gst
+    pub net: i64, // what the merchant keeps
+}
+
+/// Monthly GST invoice for the fees posted against a merchant.
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct TaxInvoice {
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub merchant_id: Uuid,
+    pub legal_name: String,
+    pub gstin: Option<String>, // None for unregistered merchants
+    pub period_start: chrono::NaiveDate,
+    pub period_end: chrono::NaiveDate, // inclusive
+    pub transaction_count: i32,
+    pub taxable_value: i64, // fees, in paise
+    pub cgst: i64,
+    pub sgst: i64,
+    pub igst: i64,
+    pub total: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FeeError {
+    #[error("Fee plan not found: {0}")]
+    PlanNotFound(String),
+
+    #[error("Invoice not found: {0}")]
+    InvoiceNotFound(Uuid),
+
+    #[error("Merchant error: {0}")]
+    MerchantError(#[from] MerchantError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+/// `amount * bps / 10000`, rounded half up to the paisa. Worked out in
+/// i128 so a large amount cannot overflow the multiplication.
+pub fn of_bps(amount: i64, bps: i64) -> i64 {
+    let value = (amount as i128 * bps as i128 + 5_000) / 10_000;
+    i64::try_from(value).unwrap_or(if value < 0 { i64::MIN } else { i64::MAX })
+}
+
+/// Splits GST by place of supply: half CGST and half SGST within the
+/// platform's state, IGST otherwise. Returns `(cgst, sgst, igst)`.
+pub fn split_gst(gst: i64, merchant_gstin: Option<&str>) -> (i64, i64, i64) {
+    match merchant_gstin.and_then(|g| g.get(..2)) {
+        Some(state) if state != PLATFORM_STATE_CODE => (0, 0, gst),
+        _ => (gst / 2, gst - gst / 2, 0),
+    }
+}
+
+const MCC_REGEX: &str = r"^\d{4}$";
+
+/// Slabs must be non-empty ranges, and no two slabs for the same category
+/// may cover the same amount.
+fn validate_slabs(req: &UpsertFeePlanRequest) -> Result<(), ValidationError> {
+    if !req.rules.iter().all(|r| r.max_amount.map_or(true, |max| max > r.min_amount)) {
+        return Err(ValidationError::new("empty_slab"));
+    }
+
+    let overlaps = |a: &FeeRuleInput, b: &FeeRuleInput| {
+        a.category_code == b.category_code
+            && a.max_amount.map_or(true, |max| b.min_amount < max)
+            && b.max_amount.map_or(true, |max| a.min_amount < max)
+    };
+    for (i, a) in req.rules.iter().enumerate() {
+        if req.rules[i + 1..].iter().any(|b| overlaps(a, b)) {
+            return Err(ValidationError::new("overlapping_slabs"));
+        }
+    }
+    Ok(())
+}
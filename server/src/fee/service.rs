@@ -0,0 +1,327 @@
+// src/fee/service.rs
+
+use crate::fee::models::*;
+use crate::merchant::{MerchantService, models::MerchantError};
+use chrono::Datelike;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+/// Fee plans and the fees charged under them. Fees are worked out per
+/// payment when it is settled (see `SettlementService::create_batch`), and
+/// billed once a month on a GST invoice.
+pub struct FeeService {
+    db: PgPool,
+    merchant_service: Arc<MerchantService>,
+}
+
+impl FeeService {
+    pub fn new(db: PgPool, merchant_service: Arc<MerchantService>) -> Self {
+        Self { db, merchant_service }
+    }
+
+    pub async fn list_plans(&self) -> Result<Vec<FeePlan>, FeeError> {
+        let codes = sqlx::query_scalar!("SELECT plan_code FROM fee_plans ORDER BY plan_code")
+            .fetch_all(&self.db)
+            .await?;
+
+        let mut plans = Vec::with_capacity(codes.len());
+        for code in codes {
+            plans.push(self.get_plan(&code).await?);
+        }
+        Ok(plans)
+    }
+
+    pub async fn get_plan(&self, plan_code: &str) -> Result<FeePlan, FeeError> {
+        let plan = sqlx::query!("SELECT plan_code, name, updated_at FROM fee_plans WHERE plan_code = $1", plan_code)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| FeeError::PlanNotFound(plan_code.to_string()))?;
+
+        let rules = sqlx::query_as!(
+            FeeRule,
+            r#"
+            SELECT rule_id, category_code, min_amount, max_amount, mdr_bps, flat_fee, max_fee
+            FROM fee_plan_rules
+            WHERE plan_code = $1
+            ORDER BY category_code NULLS FIRST, min_amount
+            "#,
+            plan_code
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(FeePlan {
+            plan_code: plan.plan_code,
+            name: plan.name,
+            rules,
+            updated_at: plan.updated_at,
+        })
+    }
+
+    /// Creates the plan or replaces all of its rules. Payments already
+    /// settled keep the fee they were charged.
+    #[instrument(skip(self, req), fields(plan_code, operator))]
+    pub async fn upsert_plan(&self, plan_code: &str, req: UpsertFeePlanRequest, operator: &str) -> Result<FeePlan, FeeError> {
+        req.validate()?;
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO fee_plans (plan_code, name)
+            VALUES ($1, $2)
+            ON CONFLICT (plan_code) DO UPDATE SET name = EXCLUDED.name, updated_at = NOW()
+            "#,
+            plan_code,
+            req.name
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!("DELETE FROM fee_plan_rules WHERE plan_code = $1", plan_code)
+            .execute(&mut *tx)
+            .await?;
+
+        for rule in &req.rules {
+            sqlx::query!(
+                r#"
+                INSERT INTO fee_plan_rules (plan_code, category_code, min_amount, max_amount, mdr_bps, flat_fee, max_fee)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                plan_code,
+                rule.category_code,
+                rule.min_amount as i64,
+                rule.max_amount.map(|a| a as i64),
+                rule.mdr_bps as i32,
+                rule.flat_fee as i64,
+                rule.max_fee.map(|a| a as i64)
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        info!(rules = req.rules.len(), operator, "Fee plan saved");
+        self.get_plan(plan_code).await
+    }
+
+    /// The rules that apply to a merchant under its current plan.
+    pub async fn schedule_for(&self, merchant_id: Uuid) -> Result<FeeSchedule, FeeError> {
+        let merchant = sqlx::query!("SELECT fee_plan, category_code FROM merchants WHERE merchant_id = $1", merchant_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or(MerchantError::NotFound(merchant_id))?;
+
+        let rules = sqlx::query_as!(
+            FeeRule,
+            r#"
+            SELECT rule_id, category_code, min_amount, max_amount, mdr_bps, flat_fee, max_fee
+            FROM fee_plan_rules
+            WHERE plan_code = $1 AND (category_code IS NULL OR category_code = $2)
+            "#,
+            merchant.fee_plan,
+            merchant.category_code
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(FeeSchedule { plan_code: merchant.fee_plan, rules })
+    }
+
+    /// What the merchant would pay on a payment of `amount`, before it is made.
+    pub async fn quote(&self, merchant_id: Uuid, amount: u64) -> Result<FeeBreakdown, FeeError> {
+        Ok(self.schedule_for(merchant_id).await?.quote(amount as i64))
+    }
+
+    /// Issues one invoice per merchant for the fees posted in the IST
+    /// calendar month containing `month`. Merchants already invoiced for the
+    /// month are skipped, so this is safe to run repeatedly. Only months that
+    /// have ended can be invoiced; fees are still being posted in this one.
+    #[instrument(skip(self))]
+    pub async fn generate_invoices(&self, month: chrono::NaiveDate) -> Result<Vec<TaxInvoice>, FeeError> {
+        let (period_start, period_end) = month_bounds(month);
+        let (current_month, _) = month_bounds(ist_today());
+        if period_start >= current_month {
+            let mut errors = validator::ValidationErrors::new();
+            errors.add("month", validator::ValidationError::new("month_not_ended"));
+            return Err(errors.into());
+        }
+        let (from, until) = (ist_midnight(period_start), ist_midnight(period_end.succ_opt().unwrap()));
+
+        let merchants = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT merchant_id
+            FROM transaction_fees
+            WHERE invoice_id IS NULL AND fee > 0 AND posted_at >= $1 AND posted_at < $2
+            "#,
+            from,
+            until
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut invoices = Vec::new();
+        for merchant_id in merchants {
+            if let Some(invoice) = self.invoice_merchant(merchant_id, period_start, period_end, from, until).await? {
+                invoices.push(invoice);
+            }
+        }
+
+        info!(count = invoices.len(), period = %period_start, "Fee invoices generated");
+        Ok(invoices)
+    }
+
+    /// Claims the invoice number first, then marks the merchant's fees as
+    /// invoiced and totals exactly the rows that statement marked, so a fee
+    /// posted meanwhile is either on the invoice or left for the next one.
+    async fn invoice_merchant(
+        &self,
+        merchant_id: Uuid,
+        period_start: chrono::NaiveDate,
+        period_end: chrono::NaiveDate,
+        from: chrono::DateTime<chrono::Utc>,
+        until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<TaxInvoice>, FeeError> {
+        let mut tx = self.db.begin().await?;
+
+        let merchant = sqlx::query!("SELECT legal_name, gstin FROM merchants WHERE merchant_id = $1", merchant_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let invoice_id = Uuid::new_v4();
+        let claimed = sqlx::query!(
+            r#"
+            INSERT INTO fee_invoices (
+                invoice_id, invoice_number, merchant_id, legal_name, gstin, period_start, period_end,
+                transaction_count, taxable_value, cgst, sgst, igst, total
+            )
+            VALUES ($1, 'FEE/' || TO_CHAR($5::DATE, 'YYYYMM') || '/' || LPAD(nextval('fee_invoice_seq')::TEXT, 6, '0'),
+                    $2, $3, $4, $5, $6, 0, 0, 0, 0, 0, 0)
+            ON CONFLICT (merchant_id, period_start) DO NOTHING
+            "#,
+            invoice_id,
+            merchant_id,
+            merchant.legal_name,
+            merchant.gstin,
+            period_start,
+            period_end
+        )
+        .execute(&mut *tx)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            return Ok(None); // invoiced by an earlier run
+        }
+
+        let totals = sqlx::query!(
+            r#"
+            WITH billed AS (
+                UPDATE transaction_fees
+                SET invoice_id = $2
+                WHERE merchant_id = $1 AND invoice_id IS NULL AND posted_at >= $3 AND posted_at < $4
+                RETURNING fee, gst
+            )
+            SELECT COUNT(*)::INT AS "count!", COALESCE(SUM(fee), 0)::BIGINT AS "fees!", COALESCE(SUM(gst), 0)::BIGINT AS "gst!"
+            FROM billed
+            "#,
+            merchant_id,
+            invoice_id,
+            from,
+            until
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if totals.count == 0 {
+            return Ok(None); // nothing left to bill; the rollback drops the claimed number
+        }
+
+        // GST on the invoice is what was actually posted, split by place of supply
+        let (cgst, sgst, igst) = split_gst(totals.gst, merchant.gstin.as_deref());
+        let invoice = sqlx::query_as!(
+            TaxInvoice,
+            r#"
+            UPDATE fee_invoices
+            SET transaction_count = $2, taxable_value = $3, cgst = $4, sgst = $5, igst = $6, total = $7
+            WHERE invoice_id = $1
+            RETURNING invoice_id, invoice_number, merchant_id, legal_name, gstin, period_start, period_end,
+                      transaction_count, taxable_value, cgst, sgst, igst, total, created_at
+            "#,
+            invoice_id,
+            totals.count,
+            totals.fees,
+            cgst,
+            sgst,
+            igst,
+            totals.fees + totals.gst
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        counter!("fee_invoices_total", 1);
+        Ok(Some(invoice))
+    }
+
+    pub async fn list_invoices(&self, owner_user_id: Uuid, merchant_id: Uuid) -> Result<Vec<TaxInvoice>, FeeError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let invoices = sqlx::query_as!(
+            TaxInvoice,
+            r#"
+            SELECT invoice_id, invoice_number, merchant_id, legal_name, gstin, period_start, period_end,
+                   transaction_count, taxable_value, cgst, sgst, igst, total, created_at
+            FROM fee_invoices
+            WHERE merchant_id = $1
+            ORDER BY period_start DESC
+            "#,
+            merchant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(invoices)
+    }
+
+    pub async fn get_invoice(&self, owner_user_id: Uuid, merchant_id: Uuid, invoice_id: Uuid) -> Result<TaxInvoice, FeeError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let invoice = sqlx::query_as!(
+            TaxInvoice,
+            r#"
+            SELECT invoice_id, invoice_number, merchant_id, legal_name, gstin, period_start, period_end,
+                   transaction_count, taxable_value, cgst, sgst, igst, total, created_at
+            FROM fee_invoices
+            WHERE invoice_id = $1 AND merchant_id = $2
+            "#,
+            invoice_id,
+            merchant_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(FeeError::InvoiceNotFound(invoice_id))?;
+
+        Ok(invoice)
+    }
+}
+
+/// First and last day of the month containing `day`.
+pub fn month_bounds(day: chrono::NaiveDate) -> (chrono::NaiveDate, chrono::NaiveDate) {
+    let start = day.with_day(1).unwrap();
+    let next = if start.month() == 12 {
+        chrono::NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+    } else {
+        chrono::NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+    };
+    (start, next.unwrap().pred_opt().unwrap())
+}
+
+fn ist_today() -> chrono::NaiveDate {
+    (chrono::Utc::now() + chrono::Duration::minutes(330)).date_naive()
+}
+
+fn ist_midnight(day: chrono::NaiveDate) -> chrono::DateTime<chrono::Utc> {
+    let ist = chrono::FixedOffset::east_opt(5 * 60 * 60 + 30 * 60).unwrap();
+    day.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(ist).unwrap().with_timezone(&chrono::Utc)
+}
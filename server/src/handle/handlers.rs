@@ -0,0 +1,55 @@
+// src/handle/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+};
+use uuid::Uuid;
+use crate::handle::{HandleService, models::*};
+
+pub async fn get_handle(
+    Extension(handle_service): Extension<std::sync::Arc<HandleService>>,
+    user_id: Uuid, // from JWT middleware
+) -> Result<Json<PaymentHandle>, (http::StatusCode, Json<serde_json::Value>)> {
+    let handle = handle_service.get(user_id)
+        .await
+        .map_err(error_response)?
+        .ok_or_else(|| error_response(HandleError::NoHandle))?;
+
+    Ok(Json(handle))
+}
+
+pub async fn claim_handle(
+    Extension(handle_service): Extension<std::sync::Arc<HandleService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<ClaimHandleRequest>,
+) -> Result<Json<PaymentHandle>, (http::StatusCode, Json<serde_json::Value>)> {
+    let handle = handle_service.claim(user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(handle))
+}
+
+pub async fn change_handle(
+    Extension(handle_service): Extension<std::sync::Arc<HandleService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<ClaimHandleRequest>,
+) -> Result<Json<PaymentHandle>, (http::StatusCode, Json<serde_json::Value>)> {
+    let handle = handle_service.change(user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(handle))
+}
+
+fn error_response(e: HandleError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        HandleError::NoHandle => http::StatusCode::NOT_FOUND,
+        HandleError::Taken(_) | HandleError::AlreadyClaimed(_) => http::StatusCode::CONFLICT,
+        HandleError::DatabaseError(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
@@ -0,0 +1,101 @@
+// src/handle/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use sqlx::types::Uuid;
+use crate::qr::upi::HANDLE_DOMAIN;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ClaimHandleRequest {
+    #[validate(length(min = 3, max = 40))]
+    pub handle: String, // "alice" or "alice@pay"
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct PaymentHandle {
+    pub handle: String, // full handle, e.g. "alice@pay"
+    pub user_id: Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HandleError {
+    #[error("Invalid handle: {0}")]
+    Invalid(String),
+
+    #[error("Handle is reserved: {0}")]
+    Reserved(String),
+
+    #[error("Handle is taken: {0}")]
+    Taken(String),
+
+    #[error("You already have a handle: {0}")]
+    AlreadyClaimed(String),
+
+    #[error("You have not claimed a handle yet")]
+    NoHandle,
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+/// Words nobody may claim: they could pass for the app itself, its staff or
+/// a bank. See `is_reserved` for how names are matched against them.
+pub const RESERVED_HANDLES: &[&str] = &[
+    "admin", "administrator", "support", "help", "helpdesk", "customercare",
+    "care", "official", "security", "fraud", "verify", "verification",
+    "kyc", "refund", "refunds", "cashback", "rewards", "pay", "payment",
+    "payments", "wallet", "upi", "npci", "rbi", "bank", "merchant",
+    "settlement", "system", "root", "null", "undefined", "test", "api",
+];
+
+/// Canonical `name@pay` form of user input. Names are 3–32 characters of
+/// `[a-z0-9._-]`, start with a letter or digit, and may not look like a
+/// mobile number or a user id, which would be confused with other payees.
+pub fn normalize_handle(input: &str) -> Result<String, HandleError> {
+    let input = input.trim().to_ascii_lowercase();
+    let name = match input.split_once('@') {
+        Some((name, domain)) if domain == HANDLE_DOMAIN => name,
+        Some(_) => return Err(HandleError::Invalid(format!("handles end in @{}", HANDLE_DOMAIN))),
+        None => input.as_str(),
+    };
+
+    let valid = (3..=32).contains(&name.len())
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !valid {
+        return Err(HandleError::Invalid(name.to_string()));
+    }
+    if name.chars().all(|c| c.is_ascii_digit()) || Uuid::parse_str(name).is_ok() {
+        return Err(HandleError::Invalid(name.to_string()));
+    }
+
+    if is_reserved(name) {
+        return Err(HandleError::Reserved(name.to_string()));
+    }
+
+    Ok(format!("{}@{}", name, HANDLE_DOMAIN))
+}
+
+/// Shortest reserved word that is also blocked inside a longer name.
+/// Shorter ones (`pay`, `care`, `bank`) begin or occur inside ordinary names
+/// too often (`payal`, `carey`, `bankim`), so they only match whole.
+const MIN_CONTAINED_LEN: usize = 5;
+
+/// A name is reserved if it contains a reserved word of at least
+/// `MIN_CONTAINED_LEN` letters once separators are removed, or if the name
+/// or any part of it between separators is a shorter reserved word. So
+/// `support`, `pay.support`, `xsupportx` and `pay-rahul` are all caught,
+/// while `payal` and `roots` are not.
+fn is_reserved(name: &str) -> bool {
+    let bare: String = name.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let mut parts = std::iter::once(bare.as_str()).chain(name.split(['.', '_', '-']));
+    RESERVED_HANDLES
+        .iter()
+        .any(|word| word.len() >= MIN_CONTAINED_LEN && bare.contains(word))
+        || parts.any(|part| RESERVED_HANDLES.contains(&part))
+}
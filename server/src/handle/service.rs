@@ -0,0 +1,153 @@
+// src/handle/service.rs
+
+use crate::handle::models::*;
+use sqlx::PgPool;
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+/// How long a handle keeps paying its previous owner after a change, so
+/// saved contacts and printed QR codes do not reach a stranger.
+pub const RELEASED_HOLD_DAYS: i32 = 30;
+
+/// Vanity payment handles (`alice@pay`). Every user can always be paid at
+/// `<user_id>@pay`; a claimed handle is an alias on top of that.
+pub struct HandleService {
+    db: PgPool,
+}
+
+impl HandleService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn get(&self, user_id: Uuid) -> Result<Option<PaymentHandle>, HandleError> {
+        let handle = sqlx::query_as!(
+            PaymentHandle,
+            "SELECT handle, user_id, created_at, updated_at FROM payment_handles WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(handle)
+    }
+
+    /// First handle for a user; use `change` once one exists.
+    #[instrument(skip(self, req), fields(user_id = %user_id))]
+    pub async fn claim(&self, user_id: Uuid, req: ClaimHandleRequest) -> Result<PaymentHandle, HandleError> {
+        req.validate()?;
+        let handle = normalize_handle(&req.handle)?;
+
+        if let Some(existing) = self.get(user_id).await? {
+            return Err(HandleError::AlreadyClaimed(existing.handle));
+        }
+
+        let mut tx = self.db.begin().await?;
+        let claimed = sqlx::query_as!(
+            PaymentHandle,
+            r#"
+            INSERT INTO payment_handles (handle, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            RETURNING handle, user_id, created_at, updated_at
+            "#,
+            handle,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| HandleError::Taken(handle.clone()))?;
+        Self::ensure_not_held(&mut tx, &handle, user_id).await?;
+        tx.commit().await?;
+
+        counter!("payment_handles_total", 1, "event" => "claimed");
+        info!(handle = %claimed.handle, "Handle claimed");
+        Ok(claimed)
+    }
+
+    /// Swaps the user's handle. The old one is held for the user for
+    /// `RELEASED_HOLD_DAYS` and keeps resolving to them meanwhile.
+    #[instrument(skip(self, req), fields(user_id = %user_id))]
+    pub async fn change(&self, user_id: Uuid, req: ClaimHandleRequest) -> Result<PaymentHandle, HandleError> {
+        req.validate()?;
+        let handle = normalize_handle(&req.handle)?;
+
+        let mut tx = self.db.begin().await?;
+
+        let old = sqlx::query_scalar!(
+            "DELETE FROM payment_handles WHERE user_id = $1 RETURNING handle",
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(HandleError::NoHandle)?;
+
+        let changed = sqlx::query_as!(
+            PaymentHandle,
+            r#"
+            INSERT INTO payment_handles (handle, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            RETURNING handle, user_id, created_at, updated_at
+            "#,
+            handle,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| HandleError::Taken(handle.clone()))?;
+        Self::ensure_not_held(&mut tx, &handle, user_id).await?;
+
+        if old != handle {
+            sqlx::query!(
+                r#"
+                INSERT INTO released_handles (handle, user_id, held_until)
+                VALUES ($1, $2, NOW() + make_interval(days => $3))
+                ON CONFLICT (handle) DO UPDATE SET user_id = $2, held_until = EXCLUDED.held_until
+                "#,
+                old,
+                user_id,
+                RELEASED_HOLD_DAYS
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            // Switching back to a held handle takes it out of the hold
+            sqlx::query!("DELETE FROM released_handles WHERE handle = $1", handle)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        counter!("payment_handles_total", 1, "event" => "changed");
+        info!(from = %old, to = %changed.handle, "Handle changed");
+        Ok(changed)
+    }
+
+    /// Refuses a handle still held for its previous owner. Runs after the
+    /// insert, in the same transaction: if the handle was being given up
+    /// concurrently, the insert waited for that change to commit, so its
+    /// hold is visible here.
+    async fn ensure_not_held(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        handle: &str,
+        user_id: Uuid,
+    ) -> Result<(), HandleError> {
+        let held = sqlx::query_scalar!(
+            "SELECT 1 FROM released_handles WHERE handle = $1 AND user_id <> $2 AND held_until > NOW()",
+            handle,
+            user_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .is_some();
+
+        if held {
+            return Err(HandleError::Taken(handle.to_string()));
+        }
+        Ok(())
+    }
+}
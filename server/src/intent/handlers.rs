@@ -0,0 +1,143 @@
+// src/intent/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::{Path, Query},
+};
+use uuid::Uuid;
+use crate::api_key::models::{
+    ApiKeyContext, ApiKeyError, SCOPE_PAYMENT_INTENTS_READ, SCOPE_PAYMENT_INTENTS_WRITE, SCOPE_REFUNDS_WRITE,
+};
+use crate::intent::{IntentService, models::*};
+use crate::merchant::models::MerchantError;
+use crate::payment::models::{PaymentResponse, RefundRequest};
+
+/// `POST /v1/payment_intents`
+pub async fn create_payment_intent(
+    Extension(intent_service): Extension<std::sync::Arc<IntentService>>,
+    Extension(api_key): Extension<ApiKeyContext>, // from API key middleware
+    Json(payload): Json<CreatePaymentIntentRequest>,
+) -> Result<Json<PaymentIntent>, (http::StatusCode, Json<serde_json::Value>)> {
+    api_key.require(SCOPE_PAYMENT_INTENTS_WRITE).map_err(scope_error)?;
+    if payload.merchant_id != api_key.merchant_id {
+        return Err(error_response(IntentError::MerchantError(MerchantError::NotFound(payload.merchant_id))));
+    }
+
+    let intent = intent_service.create(api_key.owner_user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(intent))
+}
+
+/// `GET /v1/payment_intents/:intent_id`
+pub async fn get_payment_intent(
+    Extension(intent_service): Extension<std::sync::Arc<IntentService>>,
+    Extension(api_key): Extension<ApiKeyContext>, // from API key middleware
+    Path(intent_id): Path<Uuid>,
+) -> Result<Json<PaymentIntent>, (http::StatusCode, Json<serde_json::Value>)> {
+    api_key.require(SCOPE_PAYMENT_INTENTS_READ).map_err(scope_error)?;
+    let intent = keyed_intent(&intent_service, &api_key, intent_id).await?;
+
+    Ok(Json(intent))
+}
+
+/// `POST /v1/payment_intents/:intent_id/cancel`
+pub async fn cancel_payment_intent(
+    Extension(intent_service): Extension<std::sync::Arc<IntentService>>,
+    Extension(api_key): Extension<ApiKeyContext>, // from API key middleware
+    Path(intent_id): Path<Uuid>,
+    Json(payload): Json<CancelPaymentIntentRequest>,
+) -> Result<Json<PaymentIntent>, (http::StatusCode, Json<serde_json::Value>)> {
+    api_key.require(SCOPE_PAYMENT_INTENTS_WRITE).map_err(scope_error)?;
+    keyed_intent(&intent_service, &api_key, intent_id).await?;
+
+    let intent = intent_service.cancel(api_key.owner_user_id, intent_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(intent))
+}
+
+/// `POST /v1/payment_intents/:intent_id/refunds`
+pub async fn refund_payment_intent(
+    Extension(intent_service): Extension<std::sync::Arc<IntentService>>,
+    Extension(api_key): Extension<ApiKeyContext>, // from API key middleware
+    Path(intent_id): Path<Uuid>,
+    Json(payload): Json<RefundRequest>,
+) -> Result<Json<PaymentResponse>, (http::StatusCode, Json<serde_json::Value>)> {
+    api_key.require(SCOPE_REFUNDS_WRITE).map_err(scope_error)?;
+    keyed_intent(&intent_service, &api_key, intent_id).await?;
+
+    let refund = intent_service.refund(api_key.owner_user_id, intent_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(refund))
+}
+
+/// Customer side: what the checkout page shows before paying.
+pub async fn get_checkout(
+    Extension(intent_service): Extension<std::sync::Arc<IntentService>>,
+    _user_id: Uuid, // from JWT middleware
+    Path(intent_id): Path<Uuid>,
+    Query(query): Query<ClientSecretQuery>,
+) -> Result<Json<CheckoutView>, (http::StatusCode, Json<serde_json::Value>)> {
+    let view = intent_service.checkout_view(intent_id, &query.client_secret)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(view))
+}
+
+/// Customer side: pay the intent from the wallet.
+pub async fn confirm_checkout(
+    Extension(intent_service): Extension<std::sync::Arc<IntentService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(intent_id): Path<Uuid>,
+    Json(payload): Json<ConfirmPaymentIntentRequest>,
+) -> Result<Json<CheckoutView>, (http::StatusCode, Json<serde_json::Value>)> {
+    let view = intent_service.confirm(user_id, intent_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(view))
+}
+
+/// The intent, if it belongs to the key's merchant. A key only sees its own
+/// merchant's intents, even when the owner has several merchants.
+async fn keyed_intent(
+    intent_service: &IntentService,
+    api_key: &ApiKeyContext,
+    intent_id: Uuid,
+) -> Result<PaymentIntent, (http::StatusCode, Json<serde_json::Value>)> {
+    let intent = intent_service.get(api_key.owner_user_id, intent_id)
+        .await
+        .map_err(error_response)?;
+    if intent.merchant_id != api_key.merchant_id {
+        return Err(error_response(IntentError::NotFound(intent_id)));
+    }
+    Ok(intent)
+}
+
+fn scope_error(e: ApiKeyError) -> (http::StatusCode, Json<serde_json::Value>) {
+    (http::StatusCode::FORBIDDEN, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+fn error_response(e: IntentError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        IntentError::NotFound(_) | IntentError::MerchantError(MerchantError::NotFound(_)) => http::StatusCode::NOT_FOUND,
+        IntentError::InvalidStatus(_)
+        | IntentError::DuplicateOrder(_)
+        | IntentError::MerchantError(MerchantError::InvalidStatus(_)) => http::StatusCode::CONFLICT,
+        IntentError::DatabaseError(_)
+        | IntentError::WebhookError(_)
+        | IntentError::MerchantError(MerchantError::DatabaseError(_)) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        IntentError::MerchantError(MerchantError::WalletError(ref w)) if w.is_internal() => http::StatusCode::INTERNAL_SERVER_ERROR,
+        IntentError::PaymentError(ref p) if p.is_internal() => http::StatusCode::INTERNAL_SERVER_ERROR,
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
@@ -0,0 +1,183 @@
+// src/intent/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use sqlx::types::Uuid;
+use crate::merchant::models::MerchantError;
+use crate::payment::models::PaymentError;
+use crate::webhook::models::WebhookError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreatePaymentIntentRequest {
+    pub merchant_id: Uuid,
+
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64, // in paise
+
+    #[validate(regex = "CURRENCY_REGEX")]
+    pub currency: String, // only "INR" for now
+
+    #[validate(length(min = 1, max = 64))]
+    pub order_id: String, // merchant's own reference, unique per merchant
+
+    #[validate(url, length(max = 2048))]
+    pub return_url: String, // where the customer is sent back to afterwards; HTTPS outside development
+
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CancelPaymentIntentRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub reason: Option<String>,
+}
+
+/// Sent by the customer's app; `client_secret` comes from the merchant's
+/// checkout page and proves the customer was handed this intent.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ConfirmPaymentIntentRequest {
+    #[validate(length(min = 1, max = 128))]
+    pub client_secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientSecretQuery {
+    pub client_secret: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentStatus {
+    RequiresConfirmation,
+    Processing,
+    Succeeded,
+    Canceled,
+}
+
+impl IntentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntentStatus::RequiresConfirmation => "REQUIRES_CONFIRMATION",
+            IntentStatus::Processing => "PROCESSING",
+            IntentStatus::Succeeded => "SUCCEEDED",
+            IntentStatus::Canceled => "CANCELED",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "REQUIRES_CONFIRMATION" => Some(IntentStatus::RequiresConfirmation),
+            "PROCESSING" => Some(IntentStatus::Processing),
+            "SUCCEEDED" => Some(IntentStatus::Succeeded),
+            "CANCELED" => Some(IntentStatus::Canceled),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PaymentIntentRow {
+    pub intent_id: Uuid,
+    pub merchant_id: Uuid,
+    pub amount: i64,
+    pub currency: String,
+    pub order_id: String,
+    pub description: Option<String>,
+    pub return_url: String,
+    pub client_secret: String,
+    pub status: String, // 'REQUIRES_CONFIRMATION', 'PROCESSING', 'SUCCEEDED', 'CANCELED'
+    pub customer_user_id: Option<Uuid>,
+    pub tx_id: Option<Uuid>,
+    pub attempts: i32,
+    pub last_payment_error: Option<String>,
+    pub cancellation_reason: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A payment intent as the v1 merchant API returns it. Field names and the
+/// lowercase status are part of the versioned contract.
+#[derive(Debug, Serialize)]
+pub struct PaymentIntent {
+    pub object: &'static str, // always "payment_intent"
+    pub id: Uuid,
+    pub merchant_id: Uuid,
+    pub amount: i64,
+    pub currency: String,
+    pub order_id: String,
+    pub description: Option<String>,
+    pub return_url: String,
+    pub client_secret: String,
+    pub status: IntentStatus,
+    pub tx_id: Option<Uuid>,
+    pub last_payment_error: Option<String>,
+    pub cancellation_reason: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<PaymentIntentRow> for PaymentIntent {
+    fn from(row: PaymentIntentRow) -> Self {
+        Self {
+            object: "payment_intent",
+            id: row.intent_id,
+            merchant_id: row.merchant_id,
+            amount: row.amount,
+            currency: row.currency,
+            order_id: row.order_id,
+            description: row.description,
+            return_url: row.return_url,
+            client_secret: row.client_secret,
+            status: IntentStatus::from_db(&row.status).unwrap_or(IntentStatus::Processing),
+            tx_id: row.tx_id,
+            last_payment_error: row.last_payment_error,
+            cancellation_reason: row.cancellation_reason,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// What the customer sees before and after confirming: no client secret,
+/// plus where to send them back to.
+#[derive(Debug, Serialize)]
+pub struct CheckoutView {
+    pub intent_id: Uuid,
+    pub merchant_name: String,
+    pub amount: i64,
+    pub currency: String,
+    pub description: Option<String>,
+    pub status: IntentStatus,
+    pub last_payment_error: Option<String>,
+    pub redirect_url: Option<String>, // set once the intent has succeeded or been canceled
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IntentError {
+    #[error("Payment intent not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Payment intent is {0}")]
+    InvalidStatus(String),
+
+    #[error("A payment intent for order {0} already exists")]
+    DuplicateOrder(String),
+
+    #[error("Merchant error: {0}")]
+    MerchantError(#[from] MerchantError),
+
+    #[error("Payment failed: {0}")]
+    PaymentError(#[from] PaymentError),
+
+    #[error("Webhook error: {0}")]
+    WebhookError(#[from] WebhookError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+const CURRENCY_REGEX: &str = r"^INR$";
@@ -0,0 +1,443 @@
+// src/intent/service.rs
+
+use crate::intent::models::*;
+use crate::merchant::MerchantService;
+use crate::payment::{PaymentService, models::{PaymentResponse, PaymentStatus, RefundRequest}};
+use crate::webhook::WebhookService;
+use rand::Rng;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+/// Merchant checkout. An intent is created by the merchant's server,
+/// confirmed by the customer from their wallet, and paid through
+/// `PaymentService`:
+/// REQUIRES_CONFIRMATION → PROCESSING → SUCCEEDED, or → CANCELED by the
+/// merchant before it is confirmed, or once a confirmation has been
+/// abandoned without paying. A failed payment returns the intent to
+/// REQUIRES_CONFIRMATION so the customer can try again.
+pub struct IntentService {
+    db: PgPool,
+    merchant_service: Arc<MerchantService>,
+    payment_service: Arc<PaymentService>,
+    webhook_service: Option<Arc<WebhookService>>,
+    allow_http: bool, // plain-HTTP return URLs, for local development
+}
+
+/// A confirmation that has not recorded its outcome for this long is
+/// treated as abandoned. Longer than the payment sweeper's timeout, so its
+/// payment has either gone through or been failed by then.
+const CONFIRM_LEASE: Duration = Duration::from_secs(10 * 60);
+
+impl IntentService {
+    pub fn new(db: PgPool, merchant_service: Arc<MerchantService>, payment_service: Arc<PaymentService>) -> Self {
+        Self {
+            db,
+            merchant_service,
+            payment_service,
+            webhook_service: None,
+            allow_http: false,
+        }
+    }
+
+    /// Accept `http://` return URLs. Only for local development.
+    pub fn allow_http_return_urls(mut self, allow: bool) -> Self {
+        self.allow_http = allow;
+        self
+    }
+
+    /// Tell the merchant about intent and refund outcomes.
+    pub fn with_webhooks(mut self, webhook_service: Arc<WebhookService>) -> Self {
+        self.webhook_service = Some(webhook_service);
+        self
+    }
+
+    #[instrument(skip(self, req), fields(merchant_id = %req.merchant_id, amount = req.amount))]
+    pub async fn create(&self, owner_user_id: Uuid, req: CreatePaymentIntentRequest) -> Result<PaymentIntent, IntentError> {
+        req.validate()?;
+        validate_return_url(&req.return_url, self.allow_http)?;
+        self.merchant_service.get_active(owner_user_id, req.merchant_id).await?;
+
+        let intent_id = Uuid::new_v4();
+        let client_secret = format!("{}_secret_{}", intent_id.simple(), hex::encode(rand::thread_rng().gen::<[u8; 16]>()));
+
+        let row = sqlx::query_as!(
+            PaymentIntentRow,
+            r#"
+            INSERT INTO payment_intents (intent_id, merchant_id, amount, currency, order_id, description, return_url, client_secret)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (merchant_id, order_id) DO NOTHING
+            RETURNING intent_id, merchant_id, amount, currency, order_id, description, return_url, client_secret,
+                      status, customer_user_id, tx_id, attempts, last_payment_error, cancellation_reason,
+                      created_at, updated_at
+            "#,
+            intent_id,
+            req.merchant_id,
+            req.amount as i64,
+            req.currency,
+            req.order_id,
+            req.description,
+            req.return_url,
+            client_secret
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| IntentError::DuplicateOrder(req.order_id.clone()))?;
+
+        counter!("payment_intents_total", 1, "status" => "REQUIRES_CONFIRMATION");
+        info!(intent_id = %intent_id, order_id = %row.order_id, "Payment intent created");
+        Ok(row.into())
+    }
+
+    /// An intent as seen by the merchant that created it.
+    pub async fn get(&self, owner_user_id: Uuid, intent_id: Uuid) -> Result<PaymentIntent, IntentError> {
+        let row = self.load(intent_id).await?;
+        self.owned(owner_user_id, &row).await?;
+        Ok(row.into())
+    }
+
+    /// Cancels an unconfirmed intent, or a PROCESSING one whose confirmation
+    /// was abandoned without paying.
+    pub async fn cancel(
+        &self,
+        owner_user_id: Uuid,
+        intent_id: Uuid,
+        req: CancelPaymentIntentRequest,
+    ) -> Result<PaymentIntent, IntentError> {
+        req.validate()?;
+        let row = self.load(intent_id).await?;
+        self.owned(owner_user_id, &row).await?;
+        if row.status == "PROCESSING" {
+            self.ensure_abandoned(&row).await?;
+        }
+
+        let mut tx = self.db.begin().await?;
+        let row = sqlx::query_as!(
+            PaymentIntentRow,
+            r#"
+            UPDATE payment_intents
+            SET status = 'CANCELED', cancellation_reason = $2, updated_at = NOW()
+            WHERE intent_id = $1
+              AND (status = 'REQUIRES_CONFIRMATION'
+                   OR (status = 'PROCESSING' AND attempts = $3 AND updated_at < NOW() - make_interval(secs => $4)))
+            RETURNING intent_id, merchant_id, amount, currency, order_id, description, return_url, client_secret,
+                      status, customer_user_id, tx_id, attempts, last_payment_error, cancellation_reason,
+                      created_at, updated_at
+            "#,
+            intent_id,
+            req.reason,
+            row.attempts,
+            CONFIRM_LEASE.as_secs_f64()
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            drop(tx);
+            return Err(self.not_actionable(intent_id).await);
+        };
+
+        let intent = PaymentIntent::from(row);
+        self.notify_in(&mut tx, intent.merchant_id, "payment_intent.canceled", &intent).await?;
+        tx.commit().await?;
+
+        counter!("payment_intents_total", 1, "status" => "CANCELED");
+        info!(intent_id = %intent_id, "Payment intent canceled");
+        Ok(intent)
+    }
+
+    /// Gives some or all of a succeeded intent back to the customer. The
+    /// refund commits inside `PaymentService`, so its event is emitted once
+    /// per refund afterwards; if that fails the merchant's retry replays
+    /// the refund and emits it then.
+    #[instrument(skip(self, req), fields(intent_id = %intent_id))]
+    pub async fn refund(
+        &self,
+        owner_user_id: Uuid,
+        intent_id: Uuid,
+        req: RefundRequest,
+    ) -> Result<PaymentResponse, IntentError> {
+        let row = self.load(intent_id).await?;
+        self.owned(owner_user_id, &row).await?;
+        let Some(tx_id) = row.tx_id.filter(|_| row.status == "SUCCEEDED") else {
+            return Err(IntentError::InvalidStatus(row.status));
+        };
+
+        let refund = self.payment_service.refund(row.merchant_id, tx_id, req).await?;
+        if let (PaymentStatus::Success, Some(webhooks)) = (&refund.status, &self.webhook_service) {
+            webhooks.emit_once(row.merchant_id, &format!("refund:{}", refund.tx_id), "refund.succeeded", serde_json::json!({
+                "object": "refund",
+                "id": refund.tx_id,
+                "payment_intent": intent_id,
+                "order_id": row.order_id,
+                "amount": refund.amount,
+                "currency": row.currency,
+                "created": refund.timestamp.timestamp(),
+            })).await?;
+        }
+        Ok(refund)
+    }
+
+    /// What the customer is about to pay, looked up with the client secret.
+    pub async fn checkout_view(&self, intent_id: Uuid, client_secret: &str) -> Result<CheckoutView, IntentError> {
+        let row = self.load(intent_id).await?;
+        if row.client_secret != client_secret {
+            return Err(IntentError::NotFound(intent_id));
+        }
+        self.view(row).await
+    }
+
+    /// Pays the intent from the customer's wallet. Retrying while it is
+    /// PROCESSING resumes the same payment instead of starting another, and
+    /// is refused as a conflict while that payment is still running.
+    #[instrument(skip(self, req), fields(intent_id = %intent_id, customer_user_id = %customer_user_id))]
+    pub async fn confirm(
+        &self,
+        customer_user_id: Uuid,
+        intent_id: Uuid,
+        req: ConfirmPaymentIntentRequest,
+    ) -> Result<CheckoutView, IntentError> {
+        req.validate()?;
+
+        // Step 1: Claim the intent for this customer
+        let intent = sqlx::query_as!(
+            PaymentIntentRow,
+            r#"
+            UPDATE payment_intents
+            SET status = 'PROCESSING',
+                customer_user_id = $2,
+                attempts = CASE WHEN status = 'REQUIRES_CONFIRMATION' THEN attempts + 1 ELSE attempts END,
+                updated_at = NOW()
+            WHERE intent_id = $1 AND client_secret = $3
+              AND (status = 'REQUIRES_CONFIRMATION' OR (status = 'PROCESSING' AND customer_user_id = $2))
+            RETURNING intent_id, merchant_id, amount, currency, order_id, description, return_url, client_secret,
+                      status, customer_user_id, tx_id, attempts, last_payment_error, cancellation_reason,
+                      created_at, updated_at
+            "#,
+            intent_id,
+            customer_user_id,
+            req.client_secret
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let Some(intent) = intent else {
+            let row = self.load(intent_id).await?;
+            if row.client_secret != req.client_secret {
+                return Err(IntentError::NotFound(intent_id));
+            }
+            return Err(IntentError::InvalidStatus(row.status));
+        };
+
+        // Step 2: Pay — one key per attempt, so a retry after a failure is a new payment
+        let result = self.payment_service.pay_user(
+            customer_user_id,
+            intent.merchant_id,
+            intent.amount as u64,
+            &payment_key(intent_id, intent.attempts),
+            "payment_intent",
+            &intent_id.to_string(),
+        ).await;
+
+        // Step 3: Record the outcome. A pending payment is another
+        // confirmation of this attempt still paying, and an internal error
+        // leaves the outcome unknown; both leave the intent PROCESSING so
+        // the payment is resumed, never started again.
+        let (tx_id, error) = match result {
+            Ok(resp) if resp.status == PaymentStatus::Success => (Some(resp.tx_id), None),
+            Ok(resp) if resp.status == PaymentStatus::Pending => return Err(IntentError::InvalidStatus(intent.status)),
+            Ok(resp) => (None, Some(format!("Payment {:?}", resp.status).to_lowercase())),
+            Err(e) if e.is_internal() => return Err(e.into()),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        let mut tx = self.db.begin().await?;
+        let row = sqlx::query_as!(
+            PaymentIntentRow,
+            r#"
+            UPDATE payment_intents
+            SET status = CASE WHEN $2::UUID IS NULL THEN 'REQUIRES_CONFIRMATION' ELSE 'SUCCEEDED' END,
+                tx_id = $2,
+                last_payment_error = $3,
+                updated_at = NOW()
+            WHERE intent_id = $1 AND status = 'PROCESSING'
+            RETURNING intent_id, merchant_id, amount, currency, order_id, description, return_url, client_secret,
+                      status, customer_user_id, tx_id, attempts, last_payment_error, cancellation_reason,
+                      created_at, updated_at
+            "#,
+            intent_id,
+            tx_id,
+            error
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            drop(tx);
+            return Err(self.not_actionable(intent_id).await);
+        };
+
+        // A failure to queue the event leaves the intent PROCESSING; the
+        // customer's retry resumes the same payment and records it again
+        let event_type = if tx_id.is_some() { "payment_intent.succeeded" } else { "payment_intent.payment_failed" };
+        self.notify_in(&mut tx, row.merchant_id, event_type, &PaymentIntent::from(row.clone())).await?;
+        tx.commit().await?;
+
+        counter!("payment_intents_total", 1, "status" => if tx_id.is_some() { "SUCCEEDED" } else { "PAYMENT_FAILED" });
+        info!(status = %row.status, attempt = row.attempts, "Payment intent confirmed");
+        self.view(row).await
+    }
+
+    /// A PROCESSING intent may only be canceled once its confirmation has
+    /// stopped answering and its payment is known not to have gone through.
+    /// One that did go through is recorded as SUCCEEDED instead.
+    async fn ensure_abandoned(&self, row: &PaymentIntentRow) -> Result<(), IntentError> {
+        let lease = chrono::Duration::from_std(CONFIRM_LEASE).unwrap_or_else(|_| chrono::Duration::zero());
+        if row.updated_at > chrono::Utc::now() - lease {
+            return Err(IntentError::InvalidStatus(row.status.clone()));
+        }
+
+        let payment = sqlx::query!(
+            "SELECT tx_id, status FROM transaction_journal WHERE idempotency_key = $1",
+            payment_key(row.intent_id, row.attempts)
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        match payment {
+            Some(p) if p.status == "SUCCESS" => {
+                let mut tx = self.db.begin().await?;
+                let succeeded = sqlx::query_as!(
+                    PaymentIntentRow,
+                    r#"
+                    UPDATE payment_intents
+                    SET status = 'SUCCEEDED', tx_id = $3, last_payment_error = NULL, updated_at = NOW()
+                    WHERE intent_id = $1 AND status = 'PROCESSING' AND attempts = $2
+                    RETURNING intent_id, merchant_id, amount, currency, order_id, description, return_url, client_secret,
+                              status, customer_user_id, tx_id, attempts, last_payment_error, cancellation_reason,
+                              created_at, updated_at
+                    "#,
+                    row.intent_id,
+                    row.attempts,
+                    p.tx_id
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+
+                if let Some(succeeded) = succeeded {
+                    self.notify_in(&mut tx, succeeded.merchant_id, "payment_intent.succeeded", &PaymentIntent::from(succeeded)).await?;
+                    tx.commit().await?;
+                    info!(intent_id = %row.intent_id, tx_id = %p.tx_id, "Abandoned payment intent had been paid");
+                }
+                Err(self.not_actionable(row.intent_id).await)
+            }
+            // Still in flight; the sweeper fails it if it never completes
+            Some(p) if p.status == "INITIATED" => Err(IntentError::InvalidStatus(row.status.clone())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Queues the webhook in the transaction that records the change, so
+    /// the merchant hears about exactly the changes that were committed.
+    async fn notify_in(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        merchant_id: Uuid,
+        event_type: &str,
+        data: &impl serde::Serialize,
+    ) -> Result<(), IntentError> {
+        let Some(webhooks) = &self.webhook_service else {
+            return Ok(());
+        };
+        let data = serde_json::to_value(data).unwrap_or_default();
+        webhooks.emit_in(tx, merchant_id, event_type, data).await?;
+        Ok(())
+    }
+
+    async fn view(&self, row: PaymentIntentRow) -> Result<CheckoutView, IntentError> {
+        let merchant_name = sqlx::query_scalar!("SELECT display_name FROM merchants WHERE merchant_id = $1", row.merchant_id)
+            .fetch_one(&self.db)
+            .await?;
+
+        let status = IntentStatus::from_db(&row.status).unwrap_or(IntentStatus::Processing);
+        let redirect_url = matches!(status, IntentStatus::Succeeded | IntentStatus::Canceled)
+            .then(|| redirect_url(&row.return_url, row.intent_id, status))
+            .flatten();
+
+        Ok(CheckoutView {
+            intent_id: row.intent_id,
+            merchant_name,
+            amount: row.amount,
+            currency: row.currency,
+            description: row.description,
+            status,
+            last_payment_error: row.last_payment_error,
+            redirect_url,
+        })
+    }
+
+    /// Other users' intents are reported as missing.
+    async fn owned(&self, owner_user_id: Uuid, row: &PaymentIntentRow) -> Result<(), IntentError> {
+        self.merchant_service
+            .get(owner_user_id, row.merchant_id)
+            .await
+            .map(|_| ())
+            .map_err(|_| IntentError::NotFound(row.intent_id))
+    }
+
+    async fn load(&self, intent_id: Uuid) -> Result<PaymentIntentRow, IntentError> {
+        sqlx::query_as!(
+            PaymentIntentRow,
+            r#"
+            SELECT intent_id, merchant_id, amount, currency, order_id, description, return_url, client_secret,
+                   status, customer_user_id, tx_id, attempts, last_payment_error, cancellation_reason,
+                   created_at, updated_at
+            FROM payment_intents
+            WHERE intent_id = $1
+            "#,
+            intent_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(IntentError::NotFound(intent_id))
+    }
+
+    /// Explains why a guarded status update matched no row.
+    async fn not_actionable(&self, intent_id: Uuid) -> IntentError {
+        match self.load(intent_id).await {
+            Ok(row) => IntentError::InvalidStatus(row.status),
+            Err(e) => e,
+        }
+    }
+}
+
+/// Idempotency key of the payment made by a confirmation attempt.
+fn payment_key(intent_id: Uuid, attempt: i32) -> String {
+    format!("intent:{}:{}", intent_id, attempt)
+}
+
+/// HTTPS only, unless plain HTTP is allowed for local development.
+fn validate_return_url(return_url: &str, allow_http: bool) -> Result<(), validator::ValidationErrors> {
+    let scheme = url::Url::parse(return_url).map(|url| url.scheme().to_string()).unwrap_or_default();
+    if scheme == "https" || (allow_http && scheme == "http") {
+        return Ok(());
+    }
+    let mut errors = validator::ValidationErrors::new();
+    errors.add("return_url", validator::ValidationError::new("https_required"));
+    Err(errors)
+}
+
+/// `return_url` with `payment_intent` and `status` appended, the way the
+/// merchant's page expects the customer to come back.
+fn redirect_url(return_url: &str, intent_id: Uuid, status: IntentStatus) -> Option<String> {
+    let mut url = url::Url::parse(return_url).ok()?;
+    let status = serde_json::to_value(status).ok()?;
+    url.query_pairs_mut()
+        .append_pair("payment_intent", &intent_id.to_string())
+        .append_pair("status", status.as_str()?);
+    Some(url.into())
+}
@@ -0,0 +1,71 @@
+// src/ledger/models.rs
+
+use serde::Serialize;
+use sqlx::types::Uuid;
+
+/// Fixed accounts owned by the platform itself. Every wallet movement is
+/// posted against one of these (or another wallet) so the books always net
+/// to zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemAccount {
+    Float,    // money held at the partner bank on behalf of all wallets
+    Fees,     // platform revenue
+    Suspense, // legs of a payment that are in flight
+}
+
+impl SystemAccount {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SystemAccount::Float => "system:float",
+            SystemAccount::Fees => "system:fees",
+            SystemAccount::Suspense => "system:suspense",
+        }
+    }
+}
+
+/// Ledger account code for a user's wallet.
+pub fn wallet_account(user_id: &Uuid) -> String {
+    format!("wallet:{}", user_id)
+}
+
+/// One leg of a ledger entry. Credits are positive, debits negative.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posting {
+    pub account_code: String,
+    pub amount: i64, // in paise
+}
+
+impl Posting {
+    pub fn credit(account_code: &str, amount: i64) -> Self {
+        Self {
+            account_code: account_code.to_string(),
+            amount,
+        }
+    }
+
+    pub fn debit(account_code: &str, amount: i64) -> Self {
+        Self {
+            account_code: account_code.to_string(),
+            amount: -amount,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct AccountBalance {
+    pub account_code: String,
+    pub account_type: String, // 'USER_WALLET', 'SYSTEM_FLOAT', 'FEES', 'SUSPENSE'
+    pub balance: i64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LedgerError {
+    #[error("Unbalanced entry: postings sum to {0}")]
+    Unbalanced(i64),
+
+    #[error("Entry needs at least two postings")]
+    TooFewPostings,
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+}
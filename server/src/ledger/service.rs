@@ -0,0 +1,160 @@
+// src/ledger/service.rs
+
+use crate::ledger::models::*;
+use sqlx::PgPool;
+use tracing::{info, instrument};
+use uuid::Uuid;
+use metrics::counter;
+
+pub struct LedgerService {
+    db: PgPool,
+}
+
+impl LedgerService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Writes one balanced entry inside the caller's transaction, so the
+    /// postings commit or roll back together with the balance change they
+    /// describe. The database re-checks the balance at commit time.
+    #[instrument(skip(self, tx, postings), fields(reference = %reference))]
+    pub async fn post_entry(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        reference: &str,
+        description: &str,
+        postings: &[Posting],
+    ) -> Result<Uuid, LedgerError> {
+        if postings.len() < 2 {
+            return Err(LedgerError::TooFewPostings);
+        }
+
+        let sum: i64 = postings.iter().map(|p| p.amount).sum();
+        if sum != 0 {
+            return Err(LedgerError::Unbalanced(sum));
+        }
+
+        let entry_id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO ledger_entries (entry_id, reference, description)
+            VALUES ($1, $2, $3)
+            "#,
+            entry_id,
+            reference,
+            description
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        for posting in postings {
+            sqlx::query!(
+                r#"
+                INSERT INTO ledger_postings (entry_id, account_code, amount)
+                VALUES ($1, $2, $3)
+                "#,
+                entry_id,
+                &posting.account_code,
+                posting.amount
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        counter!("ledger_entries_total", 1, "description" => description.to_string());
+        info!(entry_id = %entry_id, legs = postings.len(), "Ledger entry posted");
+
+        Ok(entry_id)
+    }
+
+    /// Posts the mirror image of the entry recorded under `reference` and
+    /// returns the new postings. Returns nothing if no entry was posted.
+    pub async fn reverse_entry(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        reference: &str,
+        reversal_reference: &str,
+    ) -> Result<Vec<Posting>, LedgerError> {
+        let original = sqlx::query_as!(
+            Posting,
+            r#"
+            SELECT p.account_code, p.amount
+            FROM ledger_postings p
+            JOIN ledger_entries e ON e.entry_id = p.entry_id
+            WHERE e.reference = $1
+            ORDER BY p.id
+            "#,
+            reference
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        if original.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mirror: Vec<Posting> = original
+            .into_iter()
+            .map(|p| Posting { account_code: p.account_code, amount: -p.amount })
+            .collect();
+        self.post_entry(tx, reversal_reference, "reversal", &mirror).await?;
+
+        Ok(mirror)
+    }
+
+    /// Opens the wallet account for a user if it does not exist yet.
+    pub async fn open_wallet_account(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &Uuid,
+    ) -> Result<(), LedgerError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO ledger_accounts (account_code, account_type, user_id)
+            VALUES ($1, 'USER_WALLET', $2)
+            ON CONFLICT (account_code) DO NOTHING
+            "#,
+            wallet_account(user_id),
+            user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn balance(&self, account_code: &str) -> Result<i64, LedgerError> {
+        let balance = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(amount), 0)::BIGINT AS "balance!" FROM ledger_postings WHERE account_code = $1"#,
+            account_code
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(balance)
+    }
+
+    pub async fn balances(&self) -> Result<Vec<AccountBalance>, LedgerError> {
+        let rows = sqlx::query_as!(
+            AccountBalance,
+            r#"SELECT account_code AS "account_code!", account_type AS "account_type!", balance AS "balance!" FROM ledger_balances ORDER BY account_code"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Sum of every posting ever made. Anything other than zero means money
+    /// was created or destroyed.
+    pub async fn trial_balance(&self) -> Result<i64, LedgerError> {
+        let total = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(amount), 0)::BIGINT AS "total!" FROM ledger_postings"#
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(total)
+    }
+}
@@ -2,7 +2,7 @@
 // src/main.rs
 
 use axum::{
-    routing::{post, get},
+    routing::{post, get, put, delete},
     Router,
     Extension,
     http::Request,
@@ -18,9 +18,23 @@ use opentelemetry::KeyValue;
 use opentelemetry::trace::Tracer;
 
 
+mod api_key;
 mod auth;
 mod wallet;
 mod payment;
+mod ledger;
+mod transaction;
+mod collect;
+mod fee;
+mod handle;
+mod intent;
+mod mandate;
+mod merchant;
+mod report;
+mod settlement;
+mod split;
+mod webhook;
+mod ws;
 mod middleware;
 
 #[tokio::main]
@@ -38,6 +52,9 @@ async fn main() {
 
     let redis_client = redis::Client::open(std::env::var("REDIS_URL").unwrap()).unwrap();
 
+    // Local development: allows plain-HTTP merchant URLs
+    let dev_mode = std::env::var("DEV_MODE").map(|v| v == "1" || v == "true").unwrap_or(false);
+
     let wallet_service = std::sync::Arc::new(wallet::WalletService::new(pool.clone()));
     let auth_service = std::sync::Arc::new(auth::AuthService::new(
         pool.clone(),
@@ -49,24 +66,284 @@ async fn main() {
         pool.clone(),
         wallet_service.clone(),
         std::env::var("OTP_SECRET").unwrap(),
+        std::env::var("QR_SIGNING_SECRET").unwrap(),
         std::sync::Arc::new(payment::MockNatsClient {}),
     ));
 
+    let ws_server = Arc::new(ws::server::WsServer::new());
+
+    // Fail payments stuck in INITIATED
+    let sweeper = payment::sweeper::PaymentSweeper::new(
+        pool.clone(),
+        ws_server.clone(),
+        std::time::Duration::from_secs(
+            std::env::var("PAYMENT_SWEEP_TIMEOUT_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(300),
+        ),
+        std::time::Duration::from_secs(30),
+    );
+    tokio::spawn(async move { sweeper.run().await });
+
+    // Release wallet holds past their expiry
+    let hold_wallets = wallet_service.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            if let Err(e) = hold_wallets.expire_holds().await {
+                tracing::error!(error = %e, "Hold expiry failed");
+            }
+        }
+    });
+
+    let collect_service = Arc::new(collect::CollectService::new(
+        pool.clone(),
+        payment_service.clone(),
+        ws_server.clone(),
+    ));
+
+    // Expire unanswered collect requests
+    let expiring_collects = collect_service.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            if let Err(e) = expiring_collects.expire_pending().await {
+                tracing::error!(error = %e, "Collect request expiry failed");
+            }
+        }
+    });
+
+    let split_service = Arc::new(split::SplitService::new(
+        pool.clone(),
+        collect_service.clone(),
+        payment_service.clone(),
+    ));
+
+    let mandate_service = Arc::new(mandate::MandateService::new(pool.clone(), payment_service.clone()));
+
+    // Run due autopay mandates; payers hear about each debit a day ahead
+    let mandate_scheduler = mandate::scheduler::MandateScheduler::new(
+        pool.clone(),
+        payment_service.clone(),
+        ws_server.clone(),
+        mandate::scheduler::RetryPolicy::default(),
+        std::time::Duration::from_secs(24 * 60 * 60),
+        std::time::Duration::from_secs(60),
+    );
+    tokio::spawn(async move { mandate_scheduler.run().await });
+
+    // Ops-only routes — admin token instead of user JWT
+    let merchant_service = std::sync::Arc::new(merchant::MerchantService::new(pool.clone(), wallet_service.clone()));
+
+    // Signed merchant webhooks, retried with backoff until acknowledged
+    let webhook_service = std::sync::Arc::new(webhook::WebhookService::new(
+        pool.clone(),
+        merchant_service.clone(),
+        webhook::service::RetryPolicy::default(),
+    ).allow_loopback_endpoints(dev_mode));
+    let delivering_webhooks = webhook_service.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            if let Err(e) = delivering_webhooks.deliver_due().await {
+                tracing::error!(error = %e, "Webhook delivery run failed");
+            }
+        }
+    });
+
+    let fee_service = std::sync::Arc::new(fee::FeeService::new(pool.clone(), merchant_service.clone()));
+
+    // Invoice last month's fees; runs are idempotent, so retrying all month is harmless
+    let invoicing_fees = fee_service.clone();
+    tokio::spawn(async move {
+        loop {
+            let (this_month, _) = fee::service::month_bounds(chrono::Utc::now().date_naive());
+            let last_month = this_month.pred_opt().unwrap();
+            if let Err(e) = invoicing_fees.generate_invoices(last_month).await {
+                tracing::error!(error = %e, "Fee invoice run failed");
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(6 * 60 * 60)).await;
+        }
+    });
+
+    let settlement_service = std::sync::Arc::new(settlement::SettlementService::new(
+        pool.clone(),
+        wallet_service.clone(),
+        merchant_service.clone(),
+        fee_service.clone(),
+        std::sync::Arc::new(settlement::service::BankPayoutClient::new(
+            std::env::var("BANK_URL").unwrap_or_else(|_| "http://localhost:3002".to_string()),
+            std::env::var("SETTLEMENT_FROM_ACCOUNT").unwrap_or_else(|_| "1234567890".to_string()),
+        )),
+        settlement::service::RetryPolicy::default(),
+    ).with_webhooks(webhook_service.clone()));
+
+    let intent_service = std::sync::Arc::new(intent::IntentService::new(
+        pool.clone(),
+        merchant_service.clone(),
+        payment_service.clone(),
+    )
+    .with_webhooks(webhook_service.clone())
+    .allow_http_return_urls(dev_mode));
+
+    let api_key_service = std::sync::Arc::new(api_key::ApiKeyService::new(
+        pool.clone(),
+        merchant_service.clone(),
+        std::env::var("API_KEY_ENCRYPTION_SECRET").unwrap(),
+    ));
+
+    // Forget request nonces once their timestamps have expired
+    let purging_api_keys = api_key_service.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(10 * 60)).await;
+            if let Err(e) = purging_api_keys.purge_nonces().await {
+                tracing::error!(error = %e, "API nonce purge failed");
+            }
+        }
+    });
+
+    let report_service = std::sync::Arc::new(report::ReportService::new(pool.clone(), merchant_service.clone()));
+
+    // Bring merchant report rollups up to date with the hours queued since the last run
+    let refreshing_rollups = report_service.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            loop {
+                match refreshing_rollups.refresh_rollups(500).await {
+                    Ok(run) if run.hours > 0 => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Rollup refresh failed");
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // Batch merchant balances and pay them out; retries failed payouts
+    let settlement_scheduler = settlement::scheduler::SettlementScheduler::new(
+        pool.clone(),
+        settlement_service.clone(),
+        std::time::Duration::from_secs(60 * 60),
+    );
+    tokio::spawn(async move { settlement_scheduler.run().await });
+
+    let admin_routes = Router::new()
+        .route("/admin/transactions/:tx_id/reverse", post(payment::handlers::admin_reverse))
+        .route("/admin/merchants/:merchant_id/status", post(merchant::handlers::admin_set_status))
+        .route("/admin/merchants/:merchant_id/fee-plan", post(merchant::handlers::admin_set_fee_plan))
+        .route("/admin/merchants/:merchant_id/settlement-cycle", post(merchant::handlers::admin_set_settlement_cycle))
+        .route("/admin/settlements/:batch_id/retry", post(settlement::handlers::admin_retry_settlement))
+        .route("/admin/fee-plans", get(fee::handlers::admin_list_fee_plans))
+        .route("/admin/fee-plans/:plan_code", put(fee::handlers::admin_upsert_fee_plan))
+        .route("/admin/fee-invoices/generate", post(fee::handlers::admin_generate_invoices))
+        .route("/admin/reports/rebuild", post(report::handlers::admin_rebuild_rollups))
+        .route_layer(axum::middleware::from_fn(middleware::admin::admin_middleware))
+        .layer(Extension(payment_service.clone()))
+        .layer(Extension(merchant_service.clone()))
+        .layer(Extension(settlement_service.clone()))
+        .layer(Extension(fee_service.clone()))
+        .layer(Extension(report_service.clone()));
+
     // Build app
+    let mut qr_service = qr::service::QrService::new(std::env::var("QR_SIGNING_SECRET").unwrap());
+    if let Ok(path) = std::env::var("QR_LOGO_PATH") {
+        let logo = std::fs::read(&path).expect("QR_LOGO_PATH is not readable");
+        qr_service = qr_service.with_logo(&logo).expect("QR_LOGO_PATH is not a PNG or JPEG");
+    }
+    let qr_service = std::sync::Arc::new(qr_service);
+
+    // Versioned server-to-server API for merchants — signed with an API key instead of user JWT
+    let merchant_api_v1 = Router::new()
+        .route("/payment_intents", post(intent::handlers::create_payment_intent))
+        .route("/payment_intents/:intent_id", get(intent::handlers::get_payment_intent))
+        .route("/payment_intents/:intent_id/cancel", post(intent::handlers::cancel_payment_intent))
+        .route("/payment_intents/:intent_id/refunds", post(intent::handlers::refund_payment_intent))
+        .route("/fees/quote", get(fee::handlers::quote_fees))
+        .route_layer(axum::middleware::from_fn(middleware::api_key::api_key_middleware))
+        .layer(
+            ServiceBuilder::new()
+                .layer(TraceLayer::new_for_http())
+                .layer(middleware::rate_limit::RateLimitLayer::new(
+                    redis_client.clone(),
+                    100, // 100 requests per minute per IP
+                ))
+                .layer(middleware::request_id::RequestIdLayer)
+                .layer(Extension(intent_service.clone()))
+                .layer(Extension(fee_service.clone()))
+                .layer(Extension(api_key_service.clone()))
+        );
+
     let app = Router::new()
         .route("/auth/register", post(auth::handlers::register))
         .route("/auth/verify-otp", post(auth::handlers::verify_otp))
         .route("/auth/refresh", post(auth::handlers::refresh))
         .route("/auth/logout", post(auth::handlers::logout))
         .route("/wallet/balance", get(wallet::handlers::get_balance))
+        .route("/wallet/holds", post(wallet::handlers::authorize_hold))
+        .route("/wallet/holds/:hold_id/capture", post(wallet::handlers::capture_hold))
+        .route("/wallet/holds/:hold_id/void", post(wallet::handlers::void_hold))
+        .route("/pay", post(payment::handlers::pay))
+        .route(
+            "/pay/lookup",
+            post(payment::handlers::lookup_payee)
+                // Own budget so the lookup cannot enumerate registered numbers
+                .layer(middleware::rate_limit::RateLimitLayer::scoped(redis_client.clone(), "payee_lookup_day", 100, 24 * 60 * 60))
+                .layer(middleware::rate_limit::RateLimitLayer::scoped(redis_client.clone(), "payee_lookup", 10, 60)),
+        )
         .route("/pay/phone", post(payment::handlers::pay_by_phone))
         .route("/pay/qr", post(payment::handlers::pay_by_qr))
+        .route("/pay/handle", post(payment::handlers::pay_by_handle))
+        .route(
+            "/handle",
+            get(handle::handlers::get_handle)
+                .post(handle::handlers::claim_handle)
+                .put(handle::handlers::change_handle),
+        )
         .route("/health", get(health))
         .route("/metrics", get(metrics_handler))
 
         .route("/qr/:user_id.png", get(qr::handlers::get_qr_png))
 .route("/qr/:user_id.svg", get(qr::handlers::get_qr_svg))
+.route("/qr/dynamic", post(qr::handlers::create_dynamic_qr))
+.route(
+    "/pay/qr/scan",
+    post(qr::handlers::scan_qr).layer(axum::extract::DefaultBodyLimit::max(5 * 1024 * 1024)),
+)
 .route("/transactions", get(transaction::handlers::get_transactions))
+.route("/transactions/:tx_id", get(transaction::handlers::get_transaction))
+.route("/transactions/:tx_id/refund", post(payment::handlers::refund))
+.route("/collect", get(collect::handlers::list_collects).post(collect::handlers::create_collect))
+.route("/collect/:request_id/approve", post(collect::handlers::approve_collect))
+.route("/collect/:request_id/decline", post(collect::handlers::decline_collect))
+.route("/splits", get(split::handlers::list_splits).post(split::handlers::create_split))
+.route("/splits/:split_id", get(split::handlers::get_split))
+.route("/splits/:split_id/remind", post(split::handlers::remind_split))
+.route("/mandates", get(mandate::handlers::list_mandates).post(mandate::handlers::create_mandate))
+.route("/mandates/:mandate_id/pause", post(mandate::handlers::pause_mandate))
+.route("/mandates/:mandate_id/resume", post(mandate::handlers::resume_mandate))
+.route("/mandates/:mandate_id/revoke", post(mandate::handlers::revoke_mandate))
+.route("/mandates/:mandate_id/amount", post(mandate::handlers::present_amount))
+.route("/merchants", get(merchant::handlers::list_merchants).post(merchant::handlers::onboard_merchant))
+.route("/merchants/:merchant_id", get(merchant::handlers::get_merchant))
+.route("/merchants/:merchant_id/qr", post(merchant::handlers::create_merchant_qr))
+.route("/merchants/:merchant_id/api-keys", get(api_key::handlers::list_api_keys).post(api_key::handlers::create_api_key))
+.route("/merchants/:merchant_id/api-keys/:key_id/rotate", post(api_key::handlers::rotate_api_key))
+.route("/merchants/:merchant_id/api-keys/:key_id/revoke", post(api_key::handlers::revoke_api_key))
+.route("/merchants/:merchant_id/invoices", get(fee::handlers::list_invoices))
+.route("/merchants/:merchant_id/invoices/:invoice_id", get(fee::handlers::get_invoice))
+.route("/merchants/:merchant_id/settlements", get(settlement::handlers::list_settlements))
+.route("/merchants/:merchant_id/settlements/:batch_id", get(settlement::handlers::get_settlement_report))
+.route("/merchants/:merchant_id/reports/sales", get(report::handlers::get_sales_report))
+.route("/merchants/:merchant_id/reports/sales.csv", get(report::handlers::export_sales_report))
+.route("/merchants/:merchant_id/webhooks", get(webhook::handlers::list_webhooks).post(webhook::handlers::register_webhook))
+.route("/merchants/:merchant_id/webhooks/:endpoint_id", delete(webhook::handlers::disable_webhook))
+.route("/merchants/:merchant_id/webhook-deliveries", get(webhook::handlers::list_deliveries))
+.route("/merchants/:merchant_id/webhook-deliveries/:delivery_id", get(webhook::handlers::get_delivery))
+.route("/merchants/:merchant_id/webhook-deliveries/:delivery_id/redeliver", post(webhook::handlers::redeliver))
+.route("/checkout/:intent_id", get(intent::handlers::get_checkout))
+.route("/checkout/:intent_id/confirm", post(intent::handlers::confirm_checkout))
 .route("/contacts", get(contact::handlers::get_contacts))
 .route("/user/profile", get(user::handlers::get_profile))
 
@@ -81,10 +358,25 @@ async fn main() {
                     100, // 100 requests per minute per IP
                 ))
                 .layer(middleware::request_id::RequestIdLayer)
+                .layer(middleware::idempotency::IdempotencyLayer::new(
+                    redis_client.clone(),
+                    24 * 60 * 60, // replay window for Idempotency-Key
+                ))
                 .layer(Extension(auth_service))
                 .layer(Extension(wallet_service))
                 .layer(Extension(payment_service))
-                .layer(Extension(std::sync::Arc::new(qr::service::QrService::new())))
+                .layer(Extension(collect_service))
+                .layer(Extension(mandate_service))
+                .layer(Extension(split_service))
+                .layer(Extension(merchant_service))
+                .layer(Extension(settlement_service))
+                .layer(Extension(intent_service))
+                .layer(Extension(webhook_service))
+                .layer(Extension(api_key_service))
+                .layer(Extension(fee_service))
+                .layer(Extension(report_service))
+                .layer(Extension(std::sync::Arc::new(handle::HandleService::new(pool.clone()))))
+                .layer(Extension(qr_service))
                 .layer(Extension(Arc::new(TransactionService::new(pool.clone()))))
                 .layer(Extension(Arc::new(ContactService::new(pool.clone()))))
         )
@@ -96,6 +388,8 @@ async fn main() {
                 middleware::jwt::jwt_middleware,
             ),
         )
+        .merge(admin_routes)
+        .nest("/v1", merchant_api_v1)
         .with_state(redis_client);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
@@ -108,7 +402,6 @@ async fn main() {
 
         //ws server
         // Add to main.rs
-let ws_server = Arc::new(WsServer::new());
 let ws_server_clone = ws_server.clone();
 
 // Start WebSocket server
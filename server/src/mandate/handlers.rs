@@ -0,0 +1,94 @@
+// src/mandate/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::Path,
+};
+use uuid::Uuid;
+use crate::mandate::{MandateService, models::*};
+use crate::payment::models::PaymentError;
+
+pub async fn create_mandate(
+    Extension(mandate_service): Extension<std::sync::Arc<MandateService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<CreateMandateRequest>,
+) -> Result<Json<Mandate>, (http::StatusCode, Json<serde_json::Value>)> {
+    let mandate = mandate_service.create(user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(mandate))
+}
+
+pub async fn list_mandates(
+    Extension(mandate_service): Extension<std::sync::Arc<MandateService>>,
+    user_id: Uuid, // from JWT middleware
+) -> Result<Json<Vec<Mandate>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let mandates = mandate_service.list(user_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(mandates))
+}
+
+pub async fn pause_mandate(
+    Extension(mandate_service): Extension<std::sync::Arc<MandateService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(mandate_id): Path<Uuid>,
+) -> Result<Json<Mandate>, (http::StatusCode, Json<serde_json::Value>)> {
+    let mandate = mandate_service.pause(user_id, mandate_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(mandate))
+}
+
+pub async fn resume_mandate(
+    Extension(mandate_service): Extension<std::sync::Arc<MandateService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(mandate_id): Path<Uuid>,
+) -> Result<Json<Mandate>, (http::StatusCode, Json<serde_json::Value>)> {
+    let mandate = mandate_service.resume(user_id, mandate_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(mandate))
+}
+
+pub async fn revoke_mandate(
+    Extension(mandate_service): Extension<std::sync::Arc<MandateService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(mandate_id): Path<Uuid>,
+) -> Result<Json<Mandate>, (http::StatusCode, Json<serde_json::Value>)> {
+    let mandate = mandate_service.revoke(user_id, mandate_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(mandate))
+}
+
+pub async fn present_amount(
+    Extension(mandate_service): Extension<std::sync::Arc<MandateService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(mandate_id): Path<Uuid>,
+    Json(payload): Json<PresentAmountRequest>,
+) -> Result<Json<Mandate>, (http::StatusCode, Json<serde_json::Value>)> {
+    let mandate = mandate_service.present_amount(user_id, mandate_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(mandate))
+}
+
+fn error_response(e: MandateError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        MandateError::NotFound(_) | MandateError::PaymentError(PaymentError::UserNotFound(_)) => http::StatusCode::NOT_FOUND,
+        MandateError::InvalidStatus(_) => http::StatusCode::CONFLICT,
+        MandateError::DatabaseError(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        MandateError::PaymentError(ref p) if p.is_internal() => http::StatusCode::INTERNAL_SERVER_ERROR,
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
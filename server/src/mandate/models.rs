@@ -0,0 +1,125 @@
+// src/mandate/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use sqlx::types::Uuid;
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use crate::payment::models::PaymentError;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        }
+    }
+
+    pub fn from_db(frequency: &str) -> Option<Self> {
+        match frequency {
+            "DAILY" => Some(Frequency::Daily),
+            "WEEKLY" => Some(Frequency::Weekly),
+            "MONTHLY" => Some(Frequency::Monthly),
+            _ => None,
+        }
+    }
+
+    /// First debit strictly after `at` in the schedule that starts at
+    /// `anchor`. Every date is worked out from the anchor, so monthly debits
+    /// on the 29th–31st fall back to the last day of shorter months and
+    /// return to the anchor's day after, rather than drifting.
+    pub fn next_after(&self, anchor: DateTime<Utc>, at: DateTime<Utc>) -> DateTime<Utc> {
+        if at < anchor {
+            return anchor;
+        }
+        let period = match self {
+            Frequency::Daily => Duration::days(1),
+            Frequency::Weekly => Duration::weeks(1),
+            Frequency::Monthly => {
+                let mut months = (at.year() - anchor.year()) * 12 + at.month() as i32 - anchor.month() as i32;
+                loop {
+                    match anchor.checked_add_months(Months::new(months as u32)) {
+                        Some(next) if next > at => return next,
+                        Some(_) => months += 1,
+                        None => return at + Duration::days(30),
+                    }
+                }
+            }
+        };
+        let cycles = (at - anchor).num_seconds() / period.num_seconds() + 1;
+        anchor + Duration::seconds(period.num_seconds() * cycles)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateMandateRequest {
+    #[validate(regex = "MOBILE_REGEX")]
+    pub payee_mobile: String,
+
+    #[validate(range(min = 1, max = 500_000))]
+    pub max_amount: u64, // per debit, in paise
+
+    pub frequency: Frequency,
+    pub first_debit_at: Option<DateTime<Utc>>, // defaults to one day from now
+    pub end_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PresentAmountRequest {
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64, // to debit on the next cycle, at most the mandate's max_amount
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct Mandate {
+    pub mandate_id: Uuid,
+    pub payer_id: Uuid,
+    pub payee_id: Uuid,
+    pub max_amount: i64,
+    pub next_amount: Option<i64>, // presented by the payee; None = max_amount
+    pub frequency: String, // 'DAILY', 'WEEKLY', 'MONTHLY'
+    pub status: String, // 'ACTIVE', 'PAUSED', 'REVOKED', 'COMPLETED'
+    pub first_debit_at: DateTime<Utc>, // anchors the schedule
+    pub next_debit_at: DateTime<Utc>,
+    pub retry_at: Option<DateTime<Utc>>, // set while a failed debit waits for retry
+    pub attempts: i32, // failed attempts in the current cycle
+    pub end_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MandateError {
+    #[error("Mandate not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Mandate is {0}")]
+    InvalidStatus(String),
+
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(String),
+
+    #[error("Amount exceeds mandate limit of {max}")]
+    AmountExceedsMandate { max: i64 },
+
+    #[error("Cannot create a mandate to yourself")]
+    SelfMandate,
+
+    #[error("Payment failed: {0}")]
+    PaymentError(#[from] PaymentError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+const MOBILE_REGEX: &str = r"^\+91[6-9]\d{9}$";
@@ -0,0 +1,348 @@
+// src/mandate/scheduler.rs
+
+use crate::mandate::models::{Frequency, Mandate};
+use crate::payment::{PaymentService, models::PaymentError};
+use crate::wallet::WalletError;
+use crate::ws::server::WsServer;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+use metrics::counter;
+
+/// How often, and how far apart, a debit that failed on insufficient balance
+/// is retried within the same cycle. Other failures are not retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_secs(4 * 60 * 60),
+        }
+    }
+}
+
+/// How long a claimed debit attempt may run before another worker may take
+/// it over.
+const CLAIM_LEASE: Duration = Duration::from_secs(10 * 60);
+
+/// Background task that sends pre-debit notices and runs due mandates
+/// through `PaymentService`.
+pub struct MandateScheduler {
+    db: PgPool,
+    payment_service: Arc<PaymentService>,
+    ws_server: Arc<WsServer>,
+    retry: RetryPolicy,
+    notice: Duration, // how long before a debit the payer is told about it
+    interval: Duration,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct MandateRunReport {
+    pub notified: u32,
+    pub succeeded: u32,
+    pub retrying: u32,
+    pub failed: u32,
+    pub errors: u32,
+}
+
+/// What happened to one due debit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Success,
+    RetryScheduled,
+    Failed,
+}
+
+impl Outcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Success => "SUCCESS",
+            Outcome::RetryScheduled => "RETRY_SCHEDULED",
+            Outcome::Failed => "FAILED",
+        }
+    }
+}
+
+impl MandateScheduler {
+    pub fn new(
+        db: PgPool,
+        payment_service: Arc<PaymentService>,
+        ws_server: Arc<WsServer>,
+        retry: RetryPolicy,
+        notice: Duration,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            db,
+            payment_service,
+            ws_server,
+            retry,
+            notice,
+            interval,
+        }
+    }
+
+    pub async fn run(&self) {
+        info!(interval_secs = self.interval.as_secs(), "Mandate scheduler started");
+        loop {
+            tokio::time::sleep(self.interval).await;
+            if let Err(e) = self.run_once().await {
+                error!(error = %e, "Mandate run failed");
+            }
+        }
+    }
+
+    pub async fn run_once(&self) -> Result<MandateRunReport, sqlx::Error> {
+        let mut report = MandateRunReport {
+            notified: self.send_notices().await?,
+            ..Default::default()
+        };
+
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT mandate_id
+            FROM mandates
+            WHERE status = 'ACTIVE' AND COALESCE(retry_at, next_debit_at) <= NOW()
+            ORDER BY COALESCE(retry_at, next_debit_at)
+            LIMIT 100
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for mandate_id in due {
+            match self.debit(mandate_id).await {
+                Ok(Some(Outcome::Success)) => report.succeeded += 1,
+                Ok(Some(Outcome::RetryScheduled)) => report.retrying += 1,
+                Ok(Some(Outcome::Failed)) => report.failed += 1,
+                Ok(None) => {} // paused, revoked or picked up elsewhere meanwhile
+                Err(e) => {
+                    report.errors += 1;
+                    counter!("mandate_scheduler_errors_total", 1);
+                    warn!(mandate_id = %mandate_id, error = %e, "Could not run mandate");
+                }
+            }
+        }
+
+        if report != MandateRunReport::default() {
+            info!(?report, "Mandate run finished");
+        }
+        Ok(report)
+    }
+
+    /// Tells payers about debits coming up within the notice window, once
+    /// per cycle.
+    async fn send_notices(&self) -> Result<u32, sqlx::Error> {
+        let upcoming = sqlx::query!(
+            r#"
+            UPDATE mandates
+            SET notified_for = next_debit_at
+            WHERE status = 'ACTIVE'
+              AND retry_at IS NULL
+              AND next_debit_at <= NOW() + make_interval(secs => $1)
+              AND notified_for IS DISTINCT FROM next_debit_at
+            RETURNING mandate_id, payer_id, COALESCE(next_amount, max_amount) AS "amount!", next_debit_at
+            "#,
+            self.notice.as_secs_f64()
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for m in &upcoming {
+            let notification = format!(
+                r#"{{"type":"mandate_upcoming","mandate_id":"{}","amount":{},"debit_at":"{}"}}"#,
+                m.mandate_id, m.amount, m.next_debit_at.to_rfc3339()
+            );
+            self.ws_server.send_notification(&m.payer_id.to_string(), &notification).await;
+        }
+        Ok(upcoming.len() as u32)
+    }
+
+    /// Runs one due debit. The attempt is claimed and committed first, so
+    /// one claimed by a worker that died is taken over once its lease expires
+    /// and replays the same payment. The mandate row is then locked again,
+    /// checked to be still ACTIVE and held until the outcome is recorded: a
+    /// pause or revoke either lands before the payment and the attempt is
+    /// dropped, or waits for the payment to finish; it is never debited after
+    /// the payer has stopped it.
+    async fn debit(&self, mandate_id: Uuid) -> Result<Option<Outcome>, Box<dyn std::error::Error + Send + Sync>> {
+        // Step 1: Claim the attempt
+        let mut tx = self.db.begin().await?;
+        let mandate = sqlx::query_as!(
+            Mandate,
+            r#"
+            SELECT mandate_id, payer_id, payee_id, max_amount, next_amount, frequency, status,
+                   first_debit_at, next_debit_at, retry_at, attempts, end_at, created_at
+            FROM mandates
+            WHERE mandate_id = $1 AND status = 'ACTIVE' AND COALESCE(retry_at, next_debit_at) <= NOW()
+            FOR UPDATE SKIP LOCKED
+            "#,
+            mandate_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(mandate) = mandate else {
+            return Ok(None);
+        };
+        let frequency = Frequency::from_db(&mandate.frequency)
+            .ok_or_else(|| format!("unknown frequency {}", mandate.frequency))?;
+
+        let attempt = mandate.attempts + 1;
+        let amount = mandate.next_amount.unwrap_or(mandate.max_amount);
+        let claimed = sqlx::query_scalar!(
+            r#"
+            INSERT INTO mandate_executions (mandate_id, due_at, attempt, amount, status)
+            VALUES ($1, $2, $3, $4, 'PROCESSING')
+            ON CONFLICT (mandate_id, due_at, attempt) DO UPDATE SET created_at = NOW()
+            WHERE mandate_executions.status = 'PROCESSING'
+              AND mandate_executions.created_at < NOW() - make_interval(secs => $5)
+            RETURNING amount
+            "#,
+            mandate_id,
+            mandate.next_debit_at,
+            attempt,
+            amount,
+            CLAIM_LEASE.as_secs_f64()
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        // Another worker is running this attempt; a takeover keeps its amount
+        let Some(amount) = claimed else {
+            return Ok(None);
+        };
+
+        // Step 2: Re-check that the mandate is still ACTIVE and in the claimed
+        // cycle, under a row lock kept until the outcome is recorded
+        let mut tx = self.db.begin().await?;
+        let still_due = sqlx::query_scalar!(
+            r#"SELECT status = 'ACTIVE' AND next_debit_at = $2 AS "still_due!" FROM mandates WHERE mandate_id = $1 FOR UPDATE"#,
+            mandate_id,
+            mandate.next_debit_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if !still_due {
+            sqlx::query!(
+                "DELETE FROM mandate_executions WHERE mandate_id = $1 AND due_at = $2 AND attempt = $3",
+                mandate_id,
+                mandate.next_debit_at,
+                attempt
+            )
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(None);
+        }
+
+        // Step 3: Pay — one idempotency key per cycle and attempt
+        let key = format!("mandate:{}:{}:{}", mandate_id, mandate.next_debit_at.timestamp(), attempt);
+        let result = self.payment_service.pay_user(
+            mandate.payer_id,
+            mandate.payee_id,
+            amount as u64,
+            &key,
+            "mandate",
+            &mandate_id.to_string(),
+        ).await;
+
+        let (outcome, tx_id, reason) = match &result {
+            Ok(resp) => (Outcome::Success, Some(resp.tx_id), None),
+            Err(e) if is_insufficient_balance(e) && attempt < self.retry.max_attempts => {
+                (Outcome::RetryScheduled, None, Some(e.to_string()))
+            }
+            Err(e) => (Outcome::Failed, None, Some(e.to_string())),
+        };
+
+        // Step 4: Record the outcome and decide what comes next
+        sqlx::query!(
+            r#"
+            UPDATE mandate_executions
+            SET tx_id = $1, status = $2, reason = $3
+            WHERE mandate_id = $4 AND due_at = $5 AND attempt = $6
+            "#,
+            tx_id,
+            outcome.as_str(),
+            reason,
+            mandate_id,
+            mandate.next_debit_at,
+            attempt
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if outcome == Outcome::RetryScheduled {
+            let retry_at = chrono::Utc::now() + chrono::Duration::from_std(self.retry.backoff)?;
+            sqlx::query!(
+                "UPDATE mandates SET attempts = $1, retry_at = $2, updated_at = NOW() WHERE mandate_id = $3",
+                attempt,
+                retry_at,
+                mandate_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            // Cycle is over either way — move to the next one still ahead,
+            // so a backlog of missed cycles is debited once, not in a burst
+            let now = chrono::Utc::now();
+            let next_debit_at = frequency.next_after(mandate.first_debit_at, now.max(mandate.next_debit_at));
+            let status = if next_debit_at > mandate.end_at { "COMPLETED" } else { "ACTIVE" };
+            // An amount the payee presented during the payment is for the next cycle
+            sqlx::query!(
+                r#"
+                UPDATE mandates
+                SET next_debit_at = $1,
+                    status = $2,
+                    attempts = 0,
+                    retry_at = NULL,
+                    next_amount = CASE WHEN next_amount IS NOT DISTINCT FROM $3 THEN NULL ELSE next_amount END,
+                    updated_at = NOW()
+                WHERE mandate_id = $4
+                "#,
+                next_debit_at,
+                status,
+                mandate.next_amount,
+                mandate_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        counter!("mandate_debits_total", 1, "outcome" => outcome.as_str());
+        info!(mandate_id = %mandate_id, attempt, outcome = outcome.as_str(), "Mandate debit processed");
+        self.notify(&mandate, amount, outcome, tx_id).await;
+        Ok(Some(outcome))
+    }
+
+    async fn notify(&self, mandate: &Mandate, amount: i64, outcome: Outcome, tx_id: Option<Uuid>) {
+        let notification = format!(
+            r#"{{"type":"mandate_debit","mandate_id":"{}","amount":{},"status":"{}","tx_id":{}}}"#,
+            mandate.mandate_id,
+            amount,
+            outcome.as_str(),
+            tx_id.map(|id| format!(r#""{}""#, id)).unwrap_or_else(|| "null".to_string())
+        );
+        self.ws_server.send_notification(&mandate.payer_id.to_string(), &notification).await;
+        if outcome == Outcome::Success {
+            self.ws_server.send_notification(&mandate.payee_id.to_string(), &notification).await;
+        }
+    }
+}
+
+fn is_insufficient_balance(e: &PaymentError) -> bool {
+    matches!(
+        e,
+        PaymentError::InsufficientBalance | PaymentError::WalletError(WalletError::InsufficientBalance)
+    )
+}
@@ -0,0 +1,258 @@
+// src/mandate/service.rs
+
+use crate::mandate::models::*;
+use crate::payment::PaymentService;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+/// Standing instructions: the payer authorizes a payee to debit up to
+/// `max_amount` on a schedule. Debits themselves are run by
+/// `MandateScheduler`.
+pub struct MandateService {
+    db: PgPool,
+    payment_service: Arc<PaymentService>,
+}
+
+impl MandateService {
+    pub fn new(db: PgPool, payment_service: Arc<PaymentService>) -> Self {
+        Self { db, payment_service }
+    }
+
+    #[instrument(skip(self, req), fields(payer_id = %payer_id, max_amount = req.max_amount))]
+    pub async fn create(&self, payer_id: Uuid, req: CreateMandateRequest) -> Result<Mandate, MandateError> {
+        req.validate()?;
+
+        let now = chrono::Utc::now();
+        let first_debit_at = req.first_debit_at.unwrap_or(now + chrono::Duration::days(1));
+        if first_debit_at <= now {
+            return Err(MandateError::InvalidSchedule("first debit must be in the future".to_string()));
+        }
+        if req.end_at < first_debit_at {
+            return Err(MandateError::InvalidSchedule("end date is before the first debit".to_string()));
+        }
+
+        let payee_id = self.payment_service.resolve_mobile(&req.payee_mobile).await?;
+        if payee_id == payer_id {
+            return Err(MandateError::SelfMandate);
+        }
+
+        let mandate = sqlx::query_as!(
+            Mandate,
+            r#"
+            INSERT INTO mandates (payer_id, payee_id, max_amount, frequency, first_debit_at, next_debit_at, end_at)
+            VALUES ($1, $2, $3, $4, $5, $5, $6)
+            RETURNING mandate_id, payer_id, payee_id, max_amount, next_amount, frequency, status,
+                      first_debit_at, next_debit_at, retry_at, attempts, end_at, created_at
+            "#,
+            payer_id,
+            payee_id,
+            req.max_amount as i64,
+            req.frequency.as_str(),
+            first_debit_at,
+            req.end_at
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        counter!("mandates_total", 1, "event" => "created");
+        info!(mandate_id = %mandate.mandate_id, "Mandate created");
+        Ok(mandate)
+    }
+
+    /// Mandates where the user is payer or payee.
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<Mandate>, MandateError> {
+        let mandates = sqlx::query_as!(
+            Mandate,
+            r#"
+            SELECT mandate_id, payer_id, payee_id, max_amount, next_amount, frequency, status,
+                   first_debit_at, next_debit_at, retry_at, attempts, end_at, created_at
+            FROM mandates
+            WHERE payer_id = $1 OR payee_id = $1
+            ORDER BY created_at DESC
+            LIMIT 100
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(mandates)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn pause(&self, payer_id: Uuid, mandate_id: Uuid) -> Result<Mandate, MandateError> {
+        let paused = sqlx::query_as!(
+            Mandate,
+            r#"
+            UPDATE mandates
+            SET status = 'PAUSED', updated_at = NOW()
+            WHERE mandate_id = $1 AND payer_id = $2 AND status = 'ACTIVE'
+            RETURNING mandate_id, payer_id, payee_id, max_amount, next_amount, frequency, status,
+                      first_debit_at, next_debit_at, retry_at, attempts, end_at, created_at
+            "#,
+            mandate_id,
+            payer_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        match paused {
+            Some(m) => {
+                counter!("mandates_total", 1, "event" => "paused");
+                info!(mandate_id = %mandate_id, "Mandate paused");
+                Ok(m)
+            }
+            None => Err(self.not_actionable(payer_id, mandate_id).await),
+        }
+    }
+
+    /// Resumes a paused mandate. Cycles missed while paused are skipped, not
+    /// debited in a burst.
+    #[instrument(skip(self))]
+    pub async fn resume(&self, payer_id: Uuid, mandate_id: Uuid) -> Result<Mandate, MandateError> {
+        let mut tx = self.db.begin().await?;
+        let mandate = sqlx::query_as!(
+            Mandate,
+            r#"
+            SELECT mandate_id, payer_id, payee_id, max_amount, next_amount, frequency, status,
+                   first_debit_at, next_debit_at, retry_at, attempts, end_at, created_at
+            FROM mandates
+            WHERE mandate_id = $1 AND payer_id = $2
+            FOR UPDATE
+            "#,
+            mandate_id,
+            payer_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(MandateError::NotFound(mandate_id))?;
+
+        if mandate.status != "PAUSED" {
+            return Err(MandateError::InvalidStatus(mandate.status));
+        }
+
+        let frequency = Frequency::from_db(&mandate.frequency)
+            .ok_or_else(|| MandateError::InvalidSchedule(mandate.frequency.clone()))?;
+        let now = chrono::Utc::now();
+        let next_debit_at = if mandate.next_debit_at > now {
+            mandate.next_debit_at
+        } else {
+            frequency.next_after(mandate.first_debit_at, now)
+        };
+        let status = if next_debit_at > mandate.end_at { "COMPLETED" } else { "ACTIVE" };
+
+        let mandate = sqlx::query_as!(
+            Mandate,
+            r#"
+            UPDATE mandates
+            SET status = $1, next_debit_at = $2, retry_at = NULL, attempts = 0, updated_at = NOW()
+            WHERE mandate_id = $3
+            RETURNING mandate_id, payer_id, payee_id, max_amount, next_amount, frequency, status,
+                      first_debit_at, next_debit_at, retry_at, attempts, end_at, created_at
+            "#,
+            status,
+            next_debit_at,
+            mandate_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        counter!("mandates_total", 1, "event" => "resumed");
+        Ok(mandate)
+    }
+
+    /// Either party can revoke; the payee's way of ending a subscription.
+    #[instrument(skip(self))]
+    pub async fn revoke(&self, user_id: Uuid, mandate_id: Uuid) -> Result<Mandate, MandateError> {
+        let revoked = sqlx::query_as!(
+            Mandate,
+            r#"
+            UPDATE mandates
+            SET status = 'REVOKED', retry_at = NULL, updated_at = NOW()
+            WHERE mandate_id = $1 AND (payer_id = $2 OR payee_id = $2) AND status IN ('ACTIVE', 'PAUSED')
+            RETURNING mandate_id, payer_id, payee_id, max_amount, next_amount, frequency, status,
+                      first_debit_at, next_debit_at, retry_at, attempts, end_at, created_at
+            "#,
+            mandate_id,
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        match revoked {
+            Some(m) => {
+                counter!("mandates_total", 1, "event" => "revoked");
+                info!(mandate_id = %mandate_id, "Mandate revoked");
+                Ok(m)
+            }
+            None => Err(self.not_actionable(user_id, mandate_id).await),
+        }
+    }
+
+    /// Payee sets the amount for the next debit, within the mandate limit.
+    /// The payer gets a fresh pre-debit notice with the new amount.
+    #[instrument(skip(self, req))]
+    pub async fn present_amount(
+        &self,
+        payee_id: Uuid,
+        mandate_id: Uuid,
+        req: PresentAmountRequest,
+    ) -> Result<Mandate, MandateError> {
+        req.validate()?;
+
+        let mandate = sqlx::query_as!(
+            Mandate,
+            r#"
+            UPDATE mandates
+            SET next_amount = $1, notified_for = NULL, updated_at = NOW()
+            WHERE mandate_id = $2 AND payee_id = $3 AND status IN ('ACTIVE', 'PAUSED') AND max_amount >= $1
+            RETURNING mandate_id, payer_id, payee_id, max_amount, next_amount, frequency, status,
+                      first_debit_at, next_debit_at, retry_at, attempts, end_at, created_at
+            "#,
+            req.amount as i64,
+            mandate_id,
+            payee_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        if let Some(m) = mandate {
+            return Ok(m);
+        }
+
+        let max = sqlx::query_scalar!(
+            "SELECT max_amount FROM mandates WHERE mandate_id = $1 AND payee_id = $2 AND status IN ('ACTIVE', 'PAUSED')",
+            mandate_id,
+            payee_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        match max {
+            Some(max) => Err(MandateError::AmountExceedsMandate { max }),
+            None => Err(self.not_actionable(payee_id, mandate_id).await),
+        }
+    }
+
+    async fn not_actionable(&self, user_id: Uuid, mandate_id: Uuid) -> MandateError {
+        let status = sqlx::query_scalar!(
+            "SELECT status FROM mandates WHERE mandate_id = $1 AND (payer_id = $2 OR payee_id = $2)",
+            mandate_id,
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await;
+
+        match status {
+            Ok(Some(status)) => MandateError::InvalidStatus(status),
+            Ok(None) => MandateError::NotFound(mandate_id),
+            Err(e) => e.into(),
+        }
+    }
+}
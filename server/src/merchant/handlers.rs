@@ -0,0 +1,133 @@
+// src/merchant/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::Path,
+};
+use uuid::Uuid;
+use validator::Validate;
+use crate::merchant::{MerchantService, models::*};
+use crate::middleware::admin::AdminOperator;
+use crate::qr::service::QrService;
+use crate::qr::models::{CreateDynamicQrRequest, DynamicQrResponse};
+
+pub async fn onboard_merchant(
+    Extension(merchant_service): Extension<std::sync::Arc<MerchantService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<OnboardMerchantRequest>,
+) -> Result<Json<Merchant>, (http::StatusCode, Json<serde_json::Value>)> {
+    let merchant = merchant_service.onboard(user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(merchant))
+}
+
+pub async fn list_merchants(
+    Extension(merchant_service): Extension<std::sync::Arc<MerchantService>>,
+    user_id: Uuid, // from JWT middleware
+) -> Result<Json<Vec<Merchant>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let merchants = merchant_service.list(user_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(merchants))
+}
+
+pub async fn get_merchant(
+    Extension(merchant_service): Extension<std::sync::Arc<MerchantService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+) -> Result<Json<Merchant>, (http::StatusCode, Json<serde_json::Value>)> {
+    let merchant = merchant_service.get(user_id, merchant_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(merchant))
+}
+
+/// Per-order code that pays the merchant's wallet, not the owner's.
+pub async fn create_merchant_qr(
+    Extension(merchant_service): Extension<std::sync::Arc<MerchantService>>,
+    Extension(qr_service): Extension<std::sync::Arc<QrService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+    Json(payload): Json<CreateDynamicQrRequest>,
+) -> Result<Json<DynamicQrResponse>, (http::StatusCode, Json<serde_json::Value>)> {
+    payload.validate().map_err(|e| error_response(e.into()))?;
+    let merchant = merchant_service.get_active(user_id, merchant_id)
+        .await
+        .map_err(error_response)?;
+
+    let (qr, qr_code) = qr_service.generate_dynamic(
+        merchant.merchant_id,
+        payload.amount,
+        &payload.reference,
+        std::time::Duration::from_secs(payload.expires_in_secs),
+    )
+    .map_err(|e| (
+        http::StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({ "error": format!("QR gen failed: {}", e) })),
+    ))?;
+
+    Ok(Json(DynamicQrResponse {
+        qr_id: qr.qr_id,
+        qr_code,
+        amount: qr.amount,
+        reference: qr.reference,
+        expires_at: qr.expires_at,
+    }))
+}
+
+pub async fn admin_set_status(
+    Extension(merchant_service): Extension<std::sync::Arc<MerchantService>>,
+    Extension(operator): Extension<AdminOperator>, // from admin middleware
+    Path(merchant_id): Path<Uuid>,
+    Json(payload): Json<UpdateMerchantStatusRequest>,
+) -> Result<Json<Merchant>, (http::StatusCode, Json<serde_json::Value>)> {
+    let merchant = merchant_service.set_status(merchant_id, payload, &operator.0)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(merchant))
+}
+
+pub async fn admin_set_fee_plan(
+    Extension(merchant_service): Extension<std::sync::Arc<MerchantService>>,
+    Extension(_operator): Extension<AdminOperator>, // from admin middleware
+    Path(merchant_id): Path<Uuid>,
+    Json(payload): Json<SetFeePlanRequest>,
+) -> Result<Json<Merchant>, (http::StatusCode, Json<serde_json::Value>)> {
+    let merchant = merchant_service.set_fee_plan(merchant_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(merchant))
+}
+
+/// T+0 is a risk decision, so only operators can move a merchant onto it.
+pub async fn admin_set_settlement_cycle(
+    Extension(merchant_service): Extension<std::sync::Arc<MerchantService>>,
+    Extension(_operator): Extension<AdminOperator>, // from admin middleware
+    Path(merchant_id): Path<Uuid>,
+    Json(payload): Json<SetSettlementCycleRequest>,
+) -> Result<Json<Merchant>, (http::StatusCode, Json<serde_json::Value>)> {
+    let merchant = merchant_service.set_settlement_cycle(merchant_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(merchant))
+}
+
+fn error_response(e: MerchantError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        MerchantError::NotFound(_) => http::StatusCode::NOT_FOUND,
+        MerchantError::InvalidStatus(_) | MerchantError::DuplicateGstin => http::StatusCode::CONFLICT,
+        MerchantError::DatabaseError(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        MerchantError::WalletError(ref w) if w.is_internal() => http::StatusCode::INTERNAL_SERVER_ERROR,
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
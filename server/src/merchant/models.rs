@@ -0,0 +1,176 @@
+// src/merchant/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use sqlx::types::Uuid;
+use crate::wallet::WalletError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct OnboardMerchantRequest {
+    #[validate(length(min = 1, max = 200))]
+    pub legal_name: String,
+
+    #[validate(length(min = 1, max = 100))]
+    pub display_name: String, // shown to payers
+
+    #[validate(regex = "GSTIN_REGEX")]
+    pub gstin: Option<String>, // small businesses may be unregistered
+
+    #[validate(regex = "PAN_REGEX")]
+    pub pan: String,
+
+    #[validate(regex = "MCC_REGEX")]
+    pub category_code: String, // ISO 18245 merchant category code
+
+    #[validate(regex = "ACCOUNT_REGEX")]
+    pub settlement_account_number: String,
+
+    #[validate(regex = "IFSC_REGEX")]
+    pub settlement_ifsc: String,
+
+    #[validate(length(min = 1, max = 200))]
+    pub settlement_account_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct UpdateMerchantStatusRequest {
+    pub status: MerchantStatus,
+
+    #[validate(length(min = 1, max = 255))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetFeePlanRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub fee_plan: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetSettlementCycleRequest {
+    pub settlement_cycle: SettlementCycle,
+}
+
+/// When money paid to a merchant reaches its bank account: T0 settles
+/// everything received so far on each run, T1 everything received before
+/// the start of the current day (IST).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SettlementCycle {
+    T0,
+    T1,
+}
+
+impl SettlementCycle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettlementCycle::T0 => "T0",
+            SettlementCycle::T1 => "T1",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "T0" => Some(SettlementCycle::T0),
+            "T1" => Some(SettlementCycle::T1),
+            _ => None,
+        }
+    }
+
+    /// Payments made before this instant belong in a batch run at `now`.
+    pub fn cutoff(&self, now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+        match self {
+            SettlementCycle::T0 => now,
+            SettlementCycle::T1 => {
+                let ist = chrono::FixedOffset::east_opt(5 * 60 * 60 + 30 * 60).unwrap();
+                let midnight = now.with_timezone(&ist).date_naive().and_hms_opt(0, 0, 0).unwrap();
+                midnight.and_local_timezone(ist).unwrap().with_timezone(&chrono::Utc)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MerchantStatus {
+    Pending,
+    Active,
+    Suspended,
+    Rejected,
+}
+
+impl MerchantStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MerchantStatus::Pending => "PENDING",
+            MerchantStatus::Active => "ACTIVE",
+            MerchantStatus::Suspended => "SUSPENDED",
+            MerchantStatus::Rejected => "REJECTED",
+        }
+    }
+
+    /// States a merchant may move to this one from.
+    pub fn allowed_from(&self) -> &'static [&'static str] {
+        match self {
+            MerchantStatus::Pending => &[],
+            MerchantStatus::Active => &["PENDING", "SUSPENDED"],
+            MerchantStatus::Suspended => &["ACTIVE"],
+            MerchantStatus::Rejected => &["PENDING"],
+        }
+    }
+}
+
+/// A business that accepts payments. `merchant_id` doubles as the id of the
+/// merchant's own wallet, separate from the owner's personal wallet.
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct Merchant {
+    pub merchant_id: Uuid,
+    pub owner_user_id: Uuid,
+    pub legal_name: String,
+    pub display_name: String,
+    pub gstin: Option<String>,
+    pub pan: String,
+    pub category_code: String,
+    pub settlement_account_number: String,
+    pub settlement_ifsc: String,
+    pub settlement_account_name: String,
+    pub fee_plan: String,
+    pub settlement_cycle: String, // 'T0', 'T1'
+    pub status: String, // 'PENDING', 'ACTIVE', 'SUSPENDED', 'REJECTED'
+    pub status_reason: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MerchantError {
+    #[error("Merchant not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Merchant is {0}")]
+    InvalidStatus(String),
+
+    #[error("GSTIN does not belong to PAN {0}")]
+    GstinPanMismatch(String),
+
+    #[error("A merchant with this GSTIN already exists")]
+    DuplicateGstin,
+
+    #[error("Unknown fee plan: {0}")]
+    UnknownFeePlan(String),
+
+    #[error("Wallet error: {0}")]
+    WalletError(#[from] WalletError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+/// 2-digit state code, the PAN, entity number, 'Z', checksum character.
+const GSTIN_REGEX: &str = r"^\d{2}[A-Z]{5}\d{4}[A-Z][1-9A-Z]Z[0-9A-Z]$";
+const PAN_REGEX: &str = r"^[A-Z]{5}\d{4}[A-Z]$";
+const MCC_REGEX: &str = r"^\d{4}$";
+const ACCOUNT_REGEX: &str = r"^\d{9,18}$";
+const IFSC_REGEX: &str = r"^[A-Z]{4}0[A-Z0-9]{6}$";
@@ -0,0 +1,236 @@
+// src/merchant/service.rs
+
+use crate::merchant::models::*;
+use crate::wallet::WalletService;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+/// Merchant onboarding. A merchant starts PENDING while its business KYC is
+/// reviewed; activation creates its wallet, and only ACTIVE merchants can be
+/// paid.
+pub struct MerchantService {
+    db: PgPool,
+    wallet_service: Arc<WalletService>,
+}
+
+impl MerchantService {
+    pub fn new(db: PgPool, wallet_service: Arc<WalletService>) -> Self {
+        Self { db, wallet_service }
+    }
+
+    #[instrument(skip(self, req), fields(owner_user_id = %owner_user_id))]
+    pub async fn onboard(&self, owner_user_id: Uuid, req: OnboardMerchantRequest) -> Result<Merchant, MerchantError> {
+        req.validate()?;
+
+        // Characters 3-12 of a GSTIN are the holder's PAN
+        if let Some(gstin) = &req.gstin {
+            if gstin.get(2..12) != Some(req.pan.as_str()) {
+                return Err(MerchantError::GstinPanMismatch(req.pan.clone()));
+            }
+        }
+
+        let merchant = sqlx::query_as!(
+            Merchant,
+            r#"
+            INSERT INTO merchants (
+                owner_user_id, legal_name, display_name, gstin, pan, category_code,
+                settlement_account_number, settlement_ifsc, settlement_account_name
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (gstin) DO NOTHING
+            RETURNING merchant_id, owner_user_id, legal_name, display_name, gstin, pan, category_code,
+                      settlement_account_number, settlement_ifsc, settlement_account_name,
+                      fee_plan, settlement_cycle, status, status_reason, created_at, updated_at
+            "#,
+            owner_user_id,
+            req.legal_name,
+            req.display_name,
+            req.gstin,
+            req.pan,
+            req.category_code,
+            req.settlement_account_number,
+            req.settlement_ifsc,
+            req.settlement_account_name
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(MerchantError::DuplicateGstin)?;
+
+        counter!("merchants_total", 1, "event" => "onboarded");
+        info!(merchant_id = %merchant.merchant_id, "Merchant onboarded");
+        Ok(merchant)
+    }
+
+    pub async fn list(&self, owner_user_id: Uuid) -> Result<Vec<Merchant>, MerchantError> {
+        let merchants = sqlx::query_as!(
+            Merchant,
+            r#"
+            SELECT merchant_id, owner_user_id, legal_name, display_name, gstin, pan, category_code,
+                   settlement_account_number, settlement_ifsc, settlement_account_name,
+                   fee_plan, settlement_cycle, status, status_reason, created_at, updated_at
+            FROM merchants
+            WHERE owner_user_id = $1
+            ORDER BY created_at
+            "#,
+            owner_user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(merchants)
+    }
+
+    /// A merchant as seen by its owner; other users get `NotFound`.
+    pub async fn get(&self, owner_user_id: Uuid, merchant_id: Uuid) -> Result<Merchant, MerchantError> {
+        let merchant = self.load(merchant_id).await?;
+        if merchant.owner_user_id != owner_user_id {
+            return Err(MerchantError::NotFound(merchant_id));
+        }
+        Ok(merchant)
+    }
+
+    /// Same as `get`, and the merchant must be ACTIVE.
+    pub async fn get_active(&self, owner_user_id: Uuid, merchant_id: Uuid) -> Result<Merchant, MerchantError> {
+        let merchant = self.get(owner_user_id, merchant_id).await?;
+        if merchant.status != "ACTIVE" {
+            return Err(MerchantError::InvalidStatus(merchant.status));
+        }
+        Ok(merchant)
+    }
+
+    /// Review decision by an operator. The first activation creates the
+    /// merchant's wallet.
+    #[instrument(skip(self, req), fields(merchant_id = %merchant_id, operator))]
+    pub async fn set_status(
+        &self,
+        merchant_id: Uuid,
+        req: UpdateMerchantStatusRequest,
+        operator: &str,
+    ) -> Result<Merchant, MerchantError> {
+        req.validate()?;
+
+        let mut tx = self.db.begin().await?;
+        let allowed: Vec<String> = req.status.allowed_from().iter().map(|s| s.to_string()).collect();
+        let merchant = sqlx::query_as!(
+            Merchant,
+            r#"
+            UPDATE merchants
+            SET status = $2, status_reason = $3, updated_at = NOW()
+            WHERE merchant_id = $1 AND status = ANY($4)
+            RETURNING merchant_id, owner_user_id, legal_name, display_name, gstin, pan, category_code,
+                      settlement_account_number, settlement_ifsc, settlement_account_name,
+                      fee_plan, settlement_cycle, status, status_reason, created_at, updated_at
+            "#,
+            merchant_id,
+            req.status.as_str(),
+            req.reason,
+            &allowed
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(merchant) = merchant else {
+            drop(tx);
+            return Err(self.not_actionable(merchant_id).await);
+        };
+
+        // Only once the transition is allowed, and committed with it
+        if req.status == MerchantStatus::Active {
+            self.wallet_service.create_wallet_in(&mut tx, &merchant_id).await?;
+        }
+        tx.commit().await?;
+
+        counter!("merchants_total", 1, "event" => req.status.as_str());
+        info!(status = %merchant.status, operator, "Merchant status changed");
+        Ok(merchant)
+    }
+
+    pub async fn set_fee_plan(&self, merchant_id: Uuid, req: SetFeePlanRequest) -> Result<Merchant, MerchantError> {
+        req.validate()?;
+
+        let plan_exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM fee_plans WHERE plan_code = $1) AS "exists!""#,
+            req.fee_plan
+        )
+        .fetch_one(&self.db)
+        .await?;
+        if !plan_exists {
+            return Err(MerchantError::UnknownFeePlan(req.fee_plan));
+        }
+
+        let merchant = sqlx::query_as!(
+            Merchant,
+            r#"
+            UPDATE merchants
+            SET fee_plan = $2, updated_at = NOW()
+            WHERE merchant_id = $1
+            RETURNING merchant_id, owner_user_id, legal_name, display_name, gstin, pan, category_code,
+                      settlement_account_number, settlement_ifsc, settlement_account_name,
+                      fee_plan, settlement_cycle, status, status_reason, created_at, updated_at
+            "#,
+            merchant_id,
+            req.fee_plan
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(MerchantError::NotFound(merchant_id))?;
+
+        info!(merchant_id = %merchant_id, fee_plan = %merchant.fee_plan, "Fee plan changed");
+        Ok(merchant)
+    }
+
+    pub async fn set_settlement_cycle(
+        &self,
+        merchant_id: Uuid,
+        req: SetSettlementCycleRequest,
+    ) -> Result<Merchant, MerchantError> {
+        let merchant = sqlx::query_as!(
+            Merchant,
+            r#"
+            UPDATE merchants
+            SET settlement_cycle = $2, updated_at = NOW()
+            WHERE merchant_id = $1
+            RETURNING merchant_id, owner_user_id, legal_name, display_name, gstin, pan, category_code,
+                      settlement_account_number, settlement_ifsc, settlement_account_name,
+                      fee_plan, settlement_cycle, status, status_reason, created_at, updated_at
+            "#,
+            merchant_id,
+            req.settlement_cycle.as_str()
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(MerchantError::NotFound(merchant_id))?;
+
+        info!(merchant_id = %merchant_id, settlement_cycle = %merchant.settlement_cycle, "Settlement cycle changed");
+        Ok(merchant)
+    }
+
+    async fn load(&self, merchant_id: Uuid) -> Result<Merchant, MerchantError> {
+        sqlx::query_as!(
+            Merchant,
+            r#"
+            SELECT merchant_id, owner_user_id, legal_name, display_name, gstin, pan, category_code,
+                   settlement_account_number, settlement_ifsc, settlement_account_name,
+                   fee_plan, settlement_cycle, status, status_reason, created_at, updated_at
+            FROM merchants
+            WHERE merchant_id = $1
+            "#,
+            merchant_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(MerchantError::NotFound(merchant_id))
+    }
+
+    /// Explains why a guarded status update matched no row.
+    async fn not_actionable(&self, merchant_id: Uuid) -> MerchantError {
+        match self.load(merchant_id).await {
+            Ok(merchant) => MerchantError::InvalidStatus(merchant.status),
+            Err(e) => e,
+        }
+    }
+}
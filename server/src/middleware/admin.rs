@@ -0,0 +1,42 @@
+// src/middleware/admin.rs
+
+use axum::{
+    extract::Request,
+    middleware::Next,
+    response::Response,
+    http::{StatusCode, HeaderMap},
+};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// Name of the operator calling an admin route, taken from `X-Operator` and
+/// recorded in audit reasons.
+#[derive(Debug, Clone)]
+pub struct AdminOperator(pub String);
+
+pub async fn admin_middleware(
+    headers: HeaderMap,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, &'static str)> {
+    let expected = std::env::var("ADMIN_API_TOKEN")
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Server misconfigured"))?;
+
+    let provided = headers.get("X-Admin-Token")
+        .and_then(|h| h.to_str().ok())
+        .ok_or((StatusCode::UNAUTHORIZED, "Missing X-Admin-Token header"))?;
+
+    // Compare digests so the check takes the same time for any input
+    if Sha256::digest(provided.as_bytes()) != Sha256::digest(expected.as_bytes()) {
+        warn!(path = %req.uri().path(), "Rejected admin request");
+        return Err((StatusCode::FORBIDDEN, "Invalid admin token"));
+    }
+
+    let operator = headers.get("X-Operator")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("unknown")
+        .to_string();
+    req.extensions_mut().insert(AdminOperator(operator));
+
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,233 @@
+// src/middleware/idempotency.rs
+
+use axum::{
+    body::Body,
+    response::{IntoResponse, Response},
+    http::{Request, StatusCode, HeaderValue, Method, header},
+};
+use redis::{AsyncCommands, Client};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use metrics::counter;
+
+const IN_FLIGHT_TTL_SECS: u64 = 60; // lock expires if the first request dies mid-way
+const IN_FLIGHT_RENEW_SECS: u64 = 20; // renewed while the first request is still running
+const MAX_STORED_BODY: usize = 1024 * 1024; // 1 MB
+const MAX_REQUEST_BODY: usize = 5 * 1024 * 1024; // largest body any route accepts (QR scan)
+
+/// Replays the stored response for a repeated `Idempotency-Key` on mutating
+/// requests. Keys are scoped to the authenticated user (set by the JWT
+/// middleware, which must run first), method and path, and kept in Redis
+/// for `ttl_secs`. A key reused with a different body is refused with 422.
+/// Requests without a user (e.g. `/auth/register`) share one anonymous scope
+/// per route, so a response is only ever replayed for the identical body.
+pub struct IdempotencyLayer {
+    redis: Client,
+    ttl_secs: u64,
+}
+
+impl IdempotencyLayer {
+    pub fn new(redis: Client, ttl_secs: u64) -> Self {
+        Self { redis, ttl_secs }
+    }
+}
+
+impl<S> tower::Layer<S> for IdempotencyLayer {
+    type Service = IdempotencyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IdempotencyService {
+            inner,
+            redis: self.redis.clone(),
+            ttl_secs: self.ttl_secs,
+        }
+    }
+}
+
+pub struct IdempotencyService<S> {
+    inner: S,
+    redis: Client,
+    ttl_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredResponse {
+    status: u16,
+    content_type: Option<String>,
+    body: Vec<u8>,
+    request_hash: String, // SHA-256 of the request body
+}
+
+impl<S> tower::Service<Request<Body>> for IdempotencyService<S>
+where
+    S: tower::Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let redis = self.redis.clone();
+        let ttl_secs = self.ttl_secs;
+
+        Box::pin(async move {
+            let is_mutating = matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+            let key = req.headers().get("Idempotency-Key")
+                .and_then(|v| v.to_str().ok())
+                .map(|k| k.to_string());
+
+            let key = match key {
+                Some(k) if is_mutating => k,
+                _ => return inner.call(req).await,
+            };
+
+            if key.is_empty() || key.len() > 255 {
+                return Ok((StatusCode::BAD_REQUEST, "Invalid Idempotency-Key header").into_response());
+            }
+
+            // Scoped by user rather than token, so a refreshed token still replays
+            let scope = match req.extensions().get::<uuid::Uuid>() {
+                Some(user_id) => user_id.to_string(),
+                None => "anonymous".to_string(),
+            };
+            let resp_key = format!("idempotency:resp:{}:{}:{}:{}", scope, req.method(), req.uri().path(), key);
+            let lock_key = format!("idempotency:lock:{}:{}:{}:{}", scope, req.method(), req.uri().path(), key);
+
+            let (parts, body) = req.into_parts();
+            let body = match axum::body::to_bytes(body, MAX_REQUEST_BODY).await {
+                Ok(b) => b,
+                Err(_) => return Ok((StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response()),
+            };
+            let request_hash = hex::encode(Sha256::digest(&body));
+            let req = Request::from_parts(parts, Body::from(body));
+
+            // Degrade gracefully — without Redis we cannot dedupe, but must not block payments
+            let mut conn = match redis.get_async_connection().await {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!(error = %e, "Failed to connect to Redis for idempotency");
+                    return inner.call(req).await;
+                }
+            };
+
+            // Step 1: Replay a completed response
+            if let Some(stored) = load(&mut conn, &resp_key).await {
+                return Ok(replay(stored, &request_hash));
+            }
+
+            // Step 2: Claim the key; losing the race means the first request is still running
+            let claimed: bool = redis::cmd("SET")
+                .arg(&lock_key)
+                .arg(&request_hash)
+                .arg("NX")
+                .arg("EX")
+                .arg(IN_FLIGHT_TTL_SECS)
+                .query_async::<_, Option<String>>(&mut conn)
+                .await
+                .map(|r| r.is_some())
+                .unwrap_or(true);
+
+            if !claimed {
+                let running: Option<String> = conn.get(&lock_key).await.unwrap_or(None);
+                if running.is_some_and(|hash| hash != request_hash) {
+                    return Ok(mismatch());
+                }
+                counter!("idempotency_requests_total", 1, "result" => "in_flight");
+                return Ok((StatusCode::CONFLICT, "Request with this Idempotency-Key is in progress").into_response());
+            }
+
+            // The first request may have finished between Step 1 and Step 2
+            if let Some(stored) = load(&mut conn, &resp_key).await {
+                let _: () = conn.del(&lock_key).await.unwrap_or(());
+                return Ok(replay(stored, &request_hash));
+            }
+
+            // Step 3: Run the request, keeping the claim alive however long it takes
+            let call = inner.call(req);
+            tokio::pin!(call);
+            let mut renew = tokio::time::interval(std::time::Duration::from_secs(IN_FLIGHT_RENEW_SECS));
+            renew.tick().await; // the first tick is immediate
+            let response = loop {
+                tokio::select! {
+                    result = &mut call => break result?,
+                    _ = renew.tick() => {
+                        let _: () = redis::cmd("EXPIRE")
+                            .arg(&lock_key)
+                            .arg(IN_FLIGHT_TTL_SECS)
+                            .query_async(&mut conn)
+                            .await
+                            .unwrap_or(());
+                    }
+                }
+            };
+
+            // Step 4: Remember the outcome
+            let (parts, body) = response.into_parts();
+            let bytes = match axum::body::to_bytes(body, MAX_STORED_BODY).await {
+                Ok(b) => b,
+                Err(e) => {
+                    warn!(error = %e, "Response too large to store for idempotency");
+                    let _: () = conn.del(&lock_key).await.unwrap_or(());
+                    return Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+                }
+            };
+
+            // Server errors are not final — let the client retry them
+            if !parts.status.is_server_error() {
+                let stored = StoredResponse {
+                    status: parts.status.as_u16(),
+                    content_type: parts.headers.get(header::CONTENT_TYPE)
+                        .and_then(|v| v.to_str().ok())
+                        .map(|s| s.to_string()),
+                    body: bytes.to_vec(),
+                    request_hash,
+                };
+                if let Ok(json) = serde_json::to_string(&stored) {
+                    let _: () = conn.set_ex(&resp_key, json, ttl_secs).await.unwrap_or(());
+                }
+            }
+            let _: () = conn.del(&lock_key).await.unwrap_or(());
+
+            counter!("idempotency_requests_total", 1, "result" => "stored");
+            Ok(Response::from_parts(parts, Body::from(bytes)))
+        })
+    }
+}
+
+async fn load(conn: &mut redis::aio::Connection, resp_key: &str) -> Option<StoredResponse> {
+    let raw: Option<String> = conn.get(resp_key).await.ok()?;
+    raw.and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// The stored response, unless the key was first used with another body.
+fn replay(stored: StoredResponse, request_hash: &str) -> Response {
+    if stored.request_hash != request_hash {
+        return mismatch();
+    }
+    counter!("idempotency_requests_total", 1, "result" => "replayed");
+
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    if let Some(ct) = stored.content_type.and_then(|ct| HeaderValue::from_str(&ct).ok()) {
+        response.headers_mut().insert(header::CONTENT_TYPE, ct);
+    }
+    response.headers_mut().insert("Idempotent-Replayed", HeaderValue::from_static("true"));
+    response
+}
+
+fn mismatch() -> Response {
+    counter!("idempotency_requests_total", 1, "result" => "mismatch");
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "Idempotency-Key was already used with a different request body",
+    ).into_response()
+}
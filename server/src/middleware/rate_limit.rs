@@ -12,11 +12,20 @@ use tracing::warn;
 pub struct RateLimitLayer {
     redis: Client,
     max_requests: i32,
+    scope: Option<&'static str>, // separate budget, counted per user
+    window_secs: u64,
 }
 
 impl RateLimitLayer {
+    /// Global limit: `max_requests` per minute per client IP.
     pub fn new(redis: Client, max_requests: i32) -> Self {
-        Self { redis, max_requests }
+        Self { redis, max_requests, scope: None, window_secs: 60 }
+    }
+
+    /// Limit with its own budget for sensitive routes, on top of the global
+    /// one. Counted per authenticated user, falling back to the client IP.
+    pub fn scoped(redis: Client, scope: &'static str, max_requests: i32, window_secs: u64) -> Self {
+        Self { redis, max_requests, scope: Some(scope), window_secs }
     }
 }
 
@@ -28,6 +37,8 @@ impl<S> tower::Layer<S> for RateLimitLayer {
             inner,
             redis: self.redis.clone(),
             max_requests: self.max_requests,
+            scope: self.scope,
+            window_secs: self.window_secs,
         }
     }
 }
@@ -36,6 +47,8 @@ pub struct RateLimitService<S> {
     inner: S,
     redis: Client,
     max_requests: i32,
+    scope: Option<&'static str>,
+    window_secs: u64,
 }
 
 impl<S, B> tower::Service<Request<B>> for RateLimitService<S>
@@ -58,13 +71,21 @@ where
 
         let redis = self.redis.clone();
         let max_requests = self.max_requests;
+        let scope = self.scope;
+        let window_secs = self.window_secs;
 
         Box::pin(async move {
             let ip = req.headers().get("X-Forwarded-For")
                 .and_then(|v| v.to_str().ok())
                 .unwrap_or("127.0.0.1");
 
-            let key = format!("rate_limit:{}", ip);
+            let key = match scope {
+                None => format!("rate_limit:{}", ip),
+                Some(scope) => match req.extensions().get::<uuid::Uuid>() {
+                    Some(user_id) => format!("rate_limit:{}:user:{}", scope, user_id),
+                    None => format!("rate_limit:{}:ip:{}", scope, ip),
+                },
+            };
             let mut conn = redis.get_async_connection().await.map_err(|e| {
                 warn!(error = %e, "Failed to connect to Redis for rate limiting");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -72,10 +93,24 @@ where
 
             let count: i32 = conn.get(&key).await.unwrap_or(0);
             if count >= max_requests {
+                if let Some(scope) = scope {
+                    metrics::counter!("rate_limit_rejected_total", 1, "scope" => scope);
+                }
                 return Err((StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded").into());
             }
 
-            let _: () = conn.set_ex(&key, count + 1, 60).await.unwrap_or(());
+            if scope.is_some() {
+                // Strict budgets count atomically and keep a fixed window
+                let count: i32 = conn.incr(&key, 1).await.unwrap_or(max_requests);
+                if count == 1 {
+                    let _: () = conn.expire(&key, window_secs as usize).await.unwrap_or(());
+                }
+                if count > max_requests {
+                    return Err((StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded").into());
+                }
+            } else {
+                let _: () = conn.set_ex(&key, count + 1, window_secs as usize).await.unwrap_or(());
+            }
 
             inner.call(req).await
         })
@@ -3,10 +3,44 @@
 use axum::{
     Extension,
     Json,
-    extract::Extension as Ext,
+    extract::{Extension as Ext, Path},
 };
 use uuid::Uuid;
+use validator::Validate;
 use crate::payment::{PaymentService, models::*};
+use crate::middleware::admin::AdminOperator;
+
+/// Pays any payee address: `{"payee": {"type": "mobile", "mobile": ...}, ...}`.
+pub async fn pay(
+    Extension(payment_service): Extension<std::sync::Arc<PaymentService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<PayRequest>,
+) -> Result<Json<PaymentResponse>, (http::StatusCode, Json<serde_json::Value>)> {
+    let resp = payment_service.pay(user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(resp))
+}
+
+/// Masked name and KYC badge for a payee, shown before the payer confirms.
+pub async fn lookup_payee(
+    Extension(payment_service): Extension<std::sync::Arc<PaymentService>>,
+    _user_id: Uuid, // from JWT middleware
+    Json(payload): Json<PayeeLookupRequest>,
+) -> Result<Json<PayeeLookup>, (http::StatusCode, Json<serde_json::Value>)> {
+    let lookup = payment_service.lookup_payee(&payload.payee)
+        .await
+        .map_err(|e| match e {
+            PaymentError::UserNotFound(_) => (
+                http::StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "No payee found" })),
+            ),
+            e => error_response(e),
+        })?;
+
+    Ok(Json(lookup))
+}
 
 pub async fn pay_by_phone(
     Extension(payment_service): Extension<std::sync::Arc<PaymentService>>,
@@ -15,12 +49,74 @@ pub async fn pay_by_phone(
 ) -> Result<Json<PaymentResponse>, (http::StatusCode, Json<serde_json::Value>)> {
     let resp = payment_service.pay_by_phone(user_id, payload)
         .await
-        .map_err(|e| {
-            (
-                http::StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": e.to_string() })),
-            )
-        })?;
+        .map_err(error_response)?;
+
+    Ok(Json(resp))
+}
+
+pub async fn pay_by_handle(
+    Extension(payment_service): Extension<std::sync::Arc<PaymentService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<PayByHandleRequest>,
+) -> Result<Json<PaymentResponse>, (http::StatusCode, Json<serde_json::Value>)> {
+    let resp = payment_service.pay_by_handle(user_id, payload)
+        .await
+        .map_err(error_response)?;
 
     Ok(Json(resp))
-}
\ No newline at end of file
+}
+
+pub async fn pay_by_qr(
+    Extension(payment_service): Extension<std::sync::Arc<PaymentService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<PayByQrRequest>,
+) -> Result<Json<PaymentResponse>, (http::StatusCode, Json<serde_json::Value>)> {
+    let resp = payment_service.pay_by_qr(user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(resp))
+}
+
+pub async fn refund(
+    Extension(payment_service): Extension<std::sync::Arc<PaymentService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(tx_id): Path<Uuid>,
+    Json(payload): Json<RefundRequest>,
+) -> Result<Json<PaymentResponse>, (http::StatusCode, Json<serde_json::Value>)> {
+    let resp = payment_service.refund(user_id, tx_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(resp))
+}
+
+pub async fn admin_reverse(
+    Extension(payment_service): Extension<std::sync::Arc<PaymentService>>,
+    Extension(operator): Extension<AdminOperator>, // from admin middleware
+    Path(tx_id): Path<Uuid>,
+    Json(payload): Json<AdminReverseRequest>,
+) -> Result<Json<serde_json::Value>, (http::StatusCode, Json<serde_json::Value>)> {
+    payload.validate().map_err(|e| error_response(e.into()))?;
+
+    payment_service.admin_reverse(tx_id, &operator.0, &payload.reason)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(serde_json::json!({ "tx_id": tx_id, "status": "REVERSED" })))
+}
+
+fn error_response(e: PaymentError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        PaymentError::IdempotencyConflict => http::StatusCode::CONFLICT,
+        PaymentError::AttemptFailed(_) => http::StatusCode::CONFLICT,
+        PaymentError::TransactionNotFound(_) => http::StatusCode::NOT_FOUND,
+        PaymentError::QrAlreadyUsed => http::StatusCode::CONFLICT,
+        PaymentError::QrExpired => http::StatusCode::GONE,
+        PaymentError::MerchantNotActive(_) => http::StatusCode::CONFLICT,
+        ref e if e.is_internal() => http::StatusCode::INTERNAL_SERVER_ERROR,
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
@@ -4,8 +4,21 @@ use serde::{Deserialize, Serialize};
 use validator::Validate;
 use sqlx::types::Uuid;
 use crate::auth::crypto::hash_mobile; // reuse from Auth
+use crate::payment::payee::PayeeAddress;
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+/// Body of `/pay`; the payee is checked by its resolver, not here.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PayRequest {
+    pub payee: PayeeAddress,
+
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64, // in paise
+
+    #[validate(length(equal = 36))]
+    pub idempotency_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct PayByPhoneRequest {
     #[validate(regex = "MOBILE_REGEX")]
     pub to_mobile: String,
@@ -17,7 +30,19 @@ pub struct PayByPhoneRequest {
     pub idempotency_key: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PayByHandleRequest {
+    #[validate(length(min = 3, max = 300))]
+    pub to_handle: String, // e.g. "alice@pay"
+
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64, // in paise
+
+    #[validate(length(equal = 36))]
+    pub idempotency_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct PayByQrRequest {
     #[validate(length(min = 1))]
     pub qr_code: String, // e.g., "payment://user/550e8400-e29b-41d4-a716-446655440000"
@@ -29,6 +54,58 @@ pub struct PayByQrRequest {
     pub idempotency_key: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayeeLookupRequest {
+    pub payee: PayeeAddress,
+}
+
+/// Enough to recognise a payee, not to identify them: the name is masked
+/// and no user id is returned.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PayeeLookup {
+    pub display_name: Option<String>, // e.g. "Rahul K. S."; None until KYC
+    pub kyc_badge: KycBadge,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum KycBadge {
+    Verified,
+    Pending,
+    Unverified,
+}
+
+/// What a scanned code would pay, shown to the payer before they confirm.
+#[derive(Debug, Serialize, Clone)]
+pub struct QrPreview {
+    pub qr_code: String, // decoded payload, to send back to /pay/qr
+    pub kind: &'static str, // "static" | "dynamic" | "upi", else the resolver's method
+    pub payee_user_id: Uuid,
+    pub payee_name: Option<String>, // as printed on UPI codes
+    pub amount: Option<u64>, // in paise; None = payer enters it
+    pub amount_editable: bool,
+    pub reference: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RefundRequest {
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: Option<u64>, // None = refund whatever is left
+
+    #[validate(length(max = 255))]
+    pub reason: Option<String>,
+
+    #[validate(length(equal = 36))]
+    pub idempotency_key: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AdminReverseRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub reason: String,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct PaymentResponse {
     pub tx_id: Uuid,
@@ -44,6 +121,20 @@ pub enum PaymentStatus {
     Success,
     Failed,
     Pending, // if async fraud check
+    Reversed,
+}
+
+impl PaymentStatus {
+    /// Maps `transaction_journal.status` back to the API enum. Intermediate
+    /// states are reported as `Pending`.
+    pub fn from_db(status: &str) -> Self {
+        match status {
+            "SUCCESS" => PaymentStatus::Success,
+            "REVERSED" => PaymentStatus::Reversed,
+            "FAILED" => PaymentStatus::Failed,
+            _ => PaymentStatus::Pending,
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -60,8 +151,26 @@ pub enum PaymentError {
     #[error("Invalid QR code")]
     InvalidQrCode,
 
-    #[error("Idempotency key already used")]
-    DuplicateIdempotencyKey,
+    #[error("Invalid payee: {0}")]
+    InvalidPayee(String),
+
+    #[error("Merchant is not accepting payments ({0})")]
+    MerchantNotActive(String),
+
+    #[error("QR code has expired")]
+    QrExpired,
+
+    #[error("QR code has already been paid")]
+    QrAlreadyUsed,
+
+    #[error("Amount does not match QR code — expected {expected} paise")]
+    QrAmountMismatch { expected: u64 },
+
+    #[error("Idempotency key already used with a different request")]
+    IdempotencyConflict,
+
+    #[error("Payment with this idempotency key failed ({0}); retry with a new key")]
+    AttemptFailed(String),
 
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
@@ -74,6 +183,29 @@ pub enum PaymentError {
 
     #[error("Fraud check failed: {0}")]
     FraudCheckFailed(String),
+
+    #[error("State error: {0}")]
+    StateError(#[from] crate::transaction::state::TransitionError),
+
+    #[error("Transaction not found: {0}")]
+    TransactionNotFound(Uuid),
+
+    #[error("Refund not allowed: {0}")]
+    RefundNotAllowed(String),
+
+    #[error("Refund exceeds original payment — {remaining} paise left to refund")]
+    RefundExceedsOriginal { remaining: i64 },
+}
+
+impl PaymentError {
+    /// Failures of the server rather than of the request; worth retrying.
+    pub fn is_internal(&self) -> bool {
+        match self {
+            PaymentError::DatabaseError(_) | PaymentError::StateError(_) => true,
+            PaymentError::WalletError(e) => e.is_internal(),
+            _ => false,
+        }
+    }
 }
 
 // Regex for Indian mobile
@@ -0,0 +1,329 @@
+// src/payment/payee.rs
+
+use crate::payment::models::PaymentError;
+use crate::auth::crypto::hash_mobile;
+use crate::qr::payload::{self as qr_payload, QrPayload, DynamicQr};
+use crate::qr::upi;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+use metrics::counter;
+
+/// Every way a payer can name a payee, tagged by `type`:
+/// `{"type": "handle", "handle": "alice@pay"}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PayeeAddress {
+    Mobile { mobile: String },
+    Handle { handle: String },
+    Qr { qr_code: String },
+    UserId { user_id: Uuid },
+    BankAccount { account_number: String, ifsc: String },
+    Merchant { merchant_id: Uuid },
+}
+
+impl PayeeAddress {
+    /// Entry point label, used in the idempotency fingerprint. Existing
+    /// fingerprints depend on these values, so they must not change.
+    pub fn method(&self) -> &'static str {
+        match self {
+            PayeeAddress::Mobile { .. } => "phone",
+            PayeeAddress::Handle { .. } => "handle",
+            PayeeAddress::Qr { .. } => "qr",
+            PayeeAddress::UserId { .. } => "user_id",
+            PayeeAddress::BankAccount { .. } => "bank_account",
+            PayeeAddress::Merchant { .. } => "merchant",
+        }
+    }
+
+    /// The address as it enters the idempotency fingerprint.
+    pub fn canonical(&self) -> String {
+        match self {
+            PayeeAddress::Mobile { mobile } => mobile.clone(),
+            PayeeAddress::Handle { handle } => handle.trim().to_ascii_lowercase(),
+            PayeeAddress::Qr { qr_code } => qr_code.clone(),
+            PayeeAddress::UserId { user_id } => user_id.to_string(),
+            PayeeAddress::BankAccount { account_number, ifsc } => {
+                format!("{}|{}", account_number.trim(), ifsc.trim().to_ascii_uppercase())
+            }
+            PayeeAddress::Merchant { merchant_id } => merchant_id.to_string(),
+        }
+    }
+}
+
+/// Who an address pays, plus any terms the address itself imposes.
+#[derive(Debug, Clone)]
+pub struct ResolvedPayee {
+    pub user_id: Uuid,
+    pub method: &'static str, // journal/metrics label, e.g. "dynamic_qr"
+    pub fixed_amount: Option<u64>, // the payer must send exactly this
+    pub single_use: Option<DynamicQr>, // claimed in qr_redemptions before paying
+}
+
+impl ResolvedPayee {
+    pub fn user(user_id: Uuid, method: &'static str) -> Self {
+        Self { user_id, method, fixed_amount: None, single_use: None }
+    }
+}
+
+/// One way of turning a `PayeeAddress` into a user. `PaymentService` asks
+/// each registered resolver in turn; a resolver returns `Ok(None)` for
+/// address kinds it does not handle.
+#[async_trait::async_trait]
+pub trait PayeeResolver: Send + Sync {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError>;
+}
+
+/// The resolvers `PaymentService` starts with.
+pub fn default_resolvers(db: &PgPool, otp_secret: &str, qr_secret: &str) -> Vec<std::sync::Arc<dyn PayeeResolver>> {
+    vec![
+        std::sync::Arc::new(MobileResolver { db: db.clone(), otp_secret: otp_secret.to_string() }),
+        std::sync::Arc::new(HandleResolver { db: db.clone() }),
+        std::sync::Arc::new(QrResolver { qr_secret: qr_secret.to_string(), handles: HandleResolver { db: db.clone() } }),
+        std::sync::Arc::new(UserIdResolver { db: db.clone() }),
+        std::sync::Arc::new(BankAccountResolver { db: db.clone() }),
+        std::sync::Arc::new(MerchantResolver { db: db.clone() }),
+    ]
+}
+
+pub struct MobileResolver {
+    db: PgPool,
+    otp_secret: String, // mobiles are stored as HMACs
+}
+
+#[async_trait::async_trait]
+impl PayeeResolver for MobileResolver {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError> {
+        let PayeeAddress::Mobile { mobile } = address else {
+            return Ok(None);
+        };
+        if !is_valid_mobile(mobile) {
+            return Err(PaymentError::InvalidPayee(format!("invalid mobile: {}", mobile)));
+        }
+
+        let mobile_hash = hash_mobile(mobile, &self.otp_secret);
+        let user_id = sqlx::query_scalar!(
+            "SELECT id FROM users WHERE mobile_hash = $1",
+            &mobile_hash
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| PaymentError::UserNotFound(mobile.to_string()))?;
+
+        Ok(Some(ResolvedPayee::user(user_id, "phone")))
+    }
+}
+
+pub struct HandleResolver {
+    db: PgPool,
+}
+
+impl HandleResolver {
+    /// `name@pay` to its user. Only our own domain resolves; handles at
+    /// other PSPs are not payable from a wallet.
+    async fn user_for(&self, handle: &str) -> Result<Uuid, PaymentError> {
+        let handle = handle.trim().to_ascii_lowercase();
+        let not_found = || PaymentError::UserNotFound(handle.clone());
+        let (name, domain) = handle.split_once('@').ok_or_else(not_found)?;
+        if domain != upi::HANDLE_DOMAIN {
+            return Err(not_found());
+        }
+
+        // Every user can be paid at `<user_id>@pay`, claimed handle or not
+        if let Ok(user_id) = Uuid::parse_str(name) {
+            return existing_user(&self.db, user_id, &handle).await;
+        }
+
+        // A changed handle keeps paying its previous owner while it is held;
+        // the current owner wins if both rows exist
+        let user_id = sqlx::query_scalar!(
+            r#"
+            SELECT user_id AS "user_id!"
+            FROM (
+                SELECT user_id, 0 AS priority FROM payment_handles WHERE handle = $1
+                UNION ALL
+                SELECT user_id, 1 FROM released_handles WHERE handle = $1 AND held_until > NOW()
+            ) h
+            ORDER BY priority
+            LIMIT 1
+            "#,
+            handle
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(not_found)?;
+
+        Ok(user_id)
+    }
+}
+
+#[async_trait::async_trait]
+impl PayeeResolver for HandleResolver {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError> {
+        let PayeeAddress::Handle { handle } = address else {
+            return Ok(None);
+        };
+        Ok(Some(ResolvedPayee::user(self.user_for(handle).await?, "handle")))
+    }
+}
+
+pub struct QrResolver {
+    qr_secret: String, // verifies dynamic QR signatures
+    handles: HandleResolver, // UPI codes name a handle
+}
+
+#[async_trait::async_trait]
+impl PayeeResolver for QrResolver {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError> {
+        let PayeeAddress::Qr { qr_code } = address else {
+            return Ok(None);
+        };
+
+        let payee = match decode_qr(qr_code, &self.qr_secret)? {
+            QrPayload::Static { user_id } => {
+                ResolvedPayee::user(existing_user(&self.handles.db, user_id, qr_code).await?, "qr")
+            }
+            // Dynamic codes fix the amount and can be paid once
+            QrPayload::Dynamic(qr) => {
+                if qr.is_expired() {
+                    return Err(PaymentError::QrExpired);
+                }
+                ResolvedPayee {
+                    user_id: qr.user_id,
+                    method: "dynamic_qr",
+                    fixed_amount: Some(qr.amount),
+                    single_use: Some(qr),
+                }
+            }
+            // UPI codes are unsigned, so `am` is only a prefill we hold the
+            // payer to; it cannot make the code single-use
+            QrPayload::Upi(qr) => ResolvedPayee {
+                user_id: self.handles.user_for(&qr.handle).await?,
+                method: "upi_qr",
+                fixed_amount: qr.amount,
+                single_use: None,
+            },
+        };
+        Ok(Some(payee))
+    }
+}
+
+pub struct UserIdResolver {
+    db: PgPool,
+}
+
+#[async_trait::async_trait]
+impl PayeeResolver for UserIdResolver {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError> {
+        let PayeeAddress::UserId { user_id } = address else {
+            return Ok(None);
+        };
+        let user_id = existing_user(&self.db, *user_id, &user_id.to_string()).await?;
+        Ok(Some(ResolvedPayee::user(user_id, "user_id")))
+    }
+}
+
+/// Pays the wallet linked to a bank account. Accounts that are not linked
+/// to any wallet are not reachable yet.
+pub struct BankAccountResolver {
+    db: PgPool,
+}
+
+#[async_trait::async_trait]
+impl PayeeResolver for BankAccountResolver {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError> {
+        let PayeeAddress::BankAccount { account_number, ifsc } = address else {
+            return Ok(None);
+        };
+        let account_number = account_number.trim();
+        let ifsc = ifsc.trim().to_ascii_uppercase();
+        if !(9..=18).contains(&account_number.len()) || !account_number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(PaymentError::InvalidPayee("account number must be 9-18 digits".to_string()));
+        }
+        if !is_valid_ifsc(&ifsc) {
+            return Err(PaymentError::InvalidPayee(format!("invalid IFSC: {}", ifsc)));
+        }
+
+        let owners = sqlx::query_scalar!(
+            "SELECT user_id FROM fake_bank_accounts WHERE account_number = $1 AND ifsc = $2",
+            account_number,
+            ifsc
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        match owners.as_slice() {
+            [user_id] => Ok(Some(ResolvedPayee::user(*user_id, "bank_account"))),
+            [] => Err(PaymentError::UserNotFound(format!("XXXX{}", &account_number[account_number.len() - 4..]))),
+            _ => Err(PaymentError::InvalidPayee("account is linked to more than one wallet".to_string())),
+        }
+    }
+}
+
+/// Pays the merchant's own wallet, whose id is the merchant id.
+pub struct MerchantResolver {
+    db: PgPool,
+}
+
+#[async_trait::async_trait]
+impl PayeeResolver for MerchantResolver {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError> {
+        let PayeeAddress::Merchant { merchant_id } = address else {
+            return Ok(None);
+        };
+
+        let status = sqlx::query_scalar!("SELECT status FROM merchants WHERE merchant_id = $1", merchant_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| PaymentError::UserNotFound(merchant_id.to_string()))?;
+        if status != "ACTIVE" {
+            return Err(PaymentError::MerchantNotActive(status));
+        }
+
+        Ok(Some(ResolvedPayee::user(*merchant_id, "merchant")))
+    }
+}
+
+/// Static `payment://user/<uuid>`, signed dynamic `payment://pay?...`, or
+/// a standard `upi://pay?...` code.
+pub fn decode_qr(qr: &str, qr_secret: &str) -> Result<QrPayload, PaymentError> {
+    qr_payload::parse(qr, qr_secret).map_err(|e| {
+        counter!("payment_qr_rejected_total", 1, "reason" => "invalid");
+        tracing::warn!(error = %e, "Rejected QR code");
+        PaymentError::InvalidQrCode
+    })
+}
+
+pub async fn existing_user(db: &PgPool, user_id: Uuid, shown_as: &str) -> Result<Uuid, PaymentError> {
+    sqlx::query_scalar!("SELECT id FROM users WHERE id = $1", user_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| PaymentError::UserNotFound(shown_as.to_string()))
+}
+
+/// First name in full, every other name as an initial: "Rahul Kumar
+/// Sharma" → "Rahul K. S.".
+pub fn mask_name(name: &str) -> String {
+    let mut words = name.split_whitespace();
+    let first = words.next().unwrap_or_default().to_string();
+    words.fold(first, |masked, word| {
+        let initial: String = word.chars().take(1).flat_map(char::to_uppercase).collect();
+        format!("{} {}.", masked, initial)
+    })
+}
+
+/// `+91` followed by a 10-digit number starting 6-9, as `MOBILE_REGEX`.
+fn is_valid_mobile(mobile: &str) -> bool {
+    mobile
+        .strip_prefix("+91")
+        .is_some_and(|n| n.len() == 10 && n.starts_with(['6', '7', '8', '9']) && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Four bank letters, a zero, then six branch characters.
+fn is_valid_ifsc(ifsc: &str) -> bool {
+    let b = ifsc.as_bytes();
+    b.len() == 11
+        && b[..4].iter().all(u8::is_ascii_uppercase)
+        && b[4] == b'0'
+        && b[5..].iter().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
@@ -1,17 +1,23 @@
 // src/payment/service.rs
 
 use crate::payment::models::*;
-use crate::wallet::WalletService;
+use crate::wallet::{WalletService, WalletError, TransferRequest};
+use crate::transaction::state::{self, PaymentState, TransitionError};
+use crate::payment::payee::{self, PayeeAddress, PayeeResolver, ResolvedPayee};
+use crate::qr::payload::{QrPayload, DynamicQr};
 use sqlx::{PgPool, Executor};
 use std::sync::Arc;
 use tracing::{info, instrument};
 use uuid::Uuid;
+use sha2::{Digest, Sha256};
+use metrics::{counter, histogram};
 
 pub struct PaymentService {
     db: PgPool,
     wallet_service: Arc<WalletService>,
-    otp_secret: String, // for hashing mobile
+    qr_secret: String, // verifies dynamic QR signatures
     nats_client: Arc<dyn NatsClient>, // for fraud events
+    resolvers: Vec<Arc<dyn PayeeResolver>>, // tried in order by `resolve`
 }
 
 #[async_trait::async_trait]
@@ -35,93 +41,269 @@ impl PaymentService {
         db: PgPool,
         wallet_service: Arc<WalletService>,
         otp_secret: String,
+        qr_secret: String,
         nats_client: Arc<dyn NatsClient>,
     ) -> Self {
+        let resolvers = payee::default_resolvers(&db, &otp_secret, &qr_secret);
         Self {
             db,
             wallet_service,
-            otp_secret,
+            qr_secret,
             nats_client,
+            resolvers,
+        }
+    }
+
+    /// Registers another way to pay. It is asked before the built-in
+    /// resolvers, so it can also override one of them.
+    pub fn with_resolver(mut self, resolver: Arc<dyn PayeeResolver>) -> Self {
+        self.resolvers.insert(0, resolver);
+        self
+    }
+
+    /// Single entry point for every payee address. Resolving the address is
+    /// the only step that differs between ways to pay; see `PayeeResolver`.
+    #[instrument(skip(self, req), fields(from_user_id = %from_user_id, amount = req.amount, method = req.payee.method()))]
+    pub async fn pay(
+        &self,
+        from_user_id: Uuid,
+        req: PayRequest,
+    ) -> Result<PaymentResponse, PaymentError> {
+        req.validate()?;
+
+        // Step 1: Replay the original result if this key was already used
+        let fingerprint = request_fingerprint(from_user_id, req.payee.method(), &req.payee.canonical(), req.amount);
+        if let Some(resp) = self.replay(&req.idempotency_key, &fingerprint).await? {
+            return Ok(resp);
+        }
+
+        // Step 2: Resolve the address and hold the payer to its terms
+        let payee = self.resolve(&req.payee).await?;
+        if let Some(expected) = payee.fixed_amount.filter(|am| *am != req.amount) {
+            return Err(PaymentError::QrAmountMismatch { expected });
+        }
+
+        // Step 3: Run the payment
+        match &payee.single_use {
+            Some(qr) => self.pay_single_use(from_user_id, qr, &req.idempotency_key, &fingerprint).await,
+            None => self.execute(from_user_id, payee.user_id, req.amount, &req.idempotency_key, &fingerprint, payee.method).await,
         }
     }
 
-    #[instrument(skip(self), fields(from_user_id = %from_user_id, amount = req.amount))]
     pub async fn pay_by_phone(
         &self,
         from_user_id: Uuid,
         req: PayByPhoneRequest,
     ) -> Result<PaymentResponse, PaymentError> {
-        let start = std::time::Instant::now();
         req.validate()?;
+        self.pay(from_user_id, PayRequest {
+            payee: PayeeAddress::Mobile { mobile: req.to_mobile },
+            amount: req.amount,
+            idempotency_key: req.idempotency_key,
+        }).await
+    }
 
-        // Step 1: Check idempotency
-        if self.is_idempotent(&req.idempotency_key).await? {
-            return Err(PaymentError::DuplicateIdempotencyKey);
+    pub async fn pay_by_handle(
+        &self,
+        from_user_id: Uuid,
+        req: PayByHandleRequest,
+    ) -> Result<PaymentResponse, PaymentError> {
+        req.validate()?;
+        self.pay(from_user_id, PayRequest {
+            payee: PayeeAddress::Handle { handle: req.to_handle },
+            amount: req.amount,
+            idempotency_key: req.idempotency_key,
+        }).await
+    }
+
+    pub async fn pay_by_qr(
+        &self,
+        from_user_id: Uuid,
+        req: PayByQrRequest,
+    ) -> Result<PaymentResponse, PaymentError> {
+        req.validate()?;
+        self.pay(from_user_id, PayRequest {
+            payee: PayeeAddress::Qr { qr_code: req.qr_code },
+            amount: req.amount,
+            idempotency_key: req.idempotency_key,
+        }).await
+    }
+
+    /// Asks each resolver in turn; the first that understands the address
+    /// answers for it.
+    pub async fn resolve(&self, address: &PayeeAddress) -> Result<ResolvedPayee, PaymentError> {
+        for resolver in &self.resolvers {
+            if let Some(payee) = resolver.resolve(address).await? {
+                return Ok(payee);
+            }
         }
+        Err(PaymentError::InvalidPayee(format!("no resolver for {} addresses", address.method())))
+    }
 
-        // Step 2: Resolve to_mobile → to_user_id
-        let to_user_id = self.resolve_mobile(&req.to_mobile).await?;
+    /// Single-use codes are claimed before paying and released again if the
+    /// payment fails, so a failed attempt does not burn them.
+    async fn pay_single_use(
+        &self,
+        from_user_id: Uuid,
+        qr: &DynamicQr,
+        idempotency_key: &str,
+        fingerprint: &str,
+    ) -> Result<PaymentResponse, PaymentError> {
+        let claimed = sqlx::query!(
+            r#"
+            INSERT INTO qr_redemptions (qr_id, payee_id, reference, idempotency_key)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (qr_id) DO NOTHING
+            "#,
+            qr.qr_id,
+            qr.user_id,
+            qr.reference,
+            idempotency_key
+        )
+        .execute(&self.db)
+        .await?
+        .rows_affected() == 1;
 
-        // Step 3: Validate same user
-        if from_user_id == to_user_id {
-            return Err(PaymentError::UserNotFound("Cannot send to self".to_string()));
+        if !claimed {
+            counter!("payment_qr_rejected_total", 1, "reason" => "reused");
+            return Err(PaymentError::QrAlreadyUsed);
         }
 
-        // Step 4: Check daily limit
-        self.check_daily_limit(from_user_id, req.amount).await?;
+        let result = self.execute(from_user_id, qr.user_id, qr.amount, idempotency_key, fingerprint, "dynamic_qr").await;
 
-        // Step 5: Begin payment — use DB transaction for atomicity
-        let mut tx = self.db.begin().await?;
+        match &result {
+            Ok(resp) => {
+                sqlx::query!("UPDATE qr_redemptions SET tx_id = $1 WHERE qr_id = $2", resp.tx_id, qr.qr_id)
+                    .execute(&self.db)
+                    .await?;
+            }
+            Err(_) => {
+                sqlx::query!(
+                    "DELETE FROM qr_redemptions WHERE qr_id = $1 AND idempotency_key = $2",
+                    qr.qr_id,
+                    idempotency_key
+                )
+                .execute(&self.db)
+                .await?;
+            }
+        }
+        result
+    }
 
-        // Step 6: Debit sender
-        let debit_req = crate::wallet::CreditDebitRequest {
-            user_id: from_user_id,
-            amount: req.amount,
-            idempotency_key: format!("debit_{}", req.idempotency_key),
-        };
-        self.wallet_service.debit(&debit_req).await?;
+    /// Pays a payee that the caller has already resolved, e.g. an approved
+    /// collect request. `reference` identifies the originating object and is
+    /// part of the idempotency fingerprint.
+    #[instrument(skip(self), fields(from_user_id = %from_user_id, amount))]
+    pub async fn pay_user(
+        &self,
+        from_user_id: Uuid,
+        to_user_id: Uuid,
+        amount: u64,
+        idempotency_key: &str,
+        method: &'static str,
+        reference: &str,
+    ) -> Result<PaymentResponse, PaymentError> {
+        let fingerprint = request_fingerprint(from_user_id, method, reference, amount);
+        if let Some(resp) = self.replay(idempotency_key, &fingerprint).await? {
+            return Ok(resp);
+        }
 
-        // Step 7: Credit receiver
-        let credit_req = crate::wallet::CreditDebitRequest {
-            user_id: to_user_id,
-            amount: req.amount,
-            idempotency_key: format!("credit_{}", req.idempotency_key),
+        self.execute(from_user_id, to_user_id, amount, idempotency_key, &fingerprint, method).await
+    }
+
+    async fn execute(
+        &self,
+        from_user_id: Uuid,
+        to_user_id: Uuid,
+        amount: u64,
+        idempotency_key: &str,
+        fingerprint: &str,
+        method: &'static str,
+    ) -> Result<PaymentResponse, PaymentError> {
+        let start = std::time::Instant::now();
+        let result = self.settle(from_user_id, to_user_id, amount, idempotency_key, fingerprint, method).await;
+
+        let status = match &result {
+            Ok(_) => "success",
+            Err(PaymentError::InsufficientBalance)
+            | Err(PaymentError::WalletError(WalletError::InsufficientBalance)) => "insufficient_balance",
+            Err(PaymentError::DailyLimitExceeded) => "daily_limit_exceeded",
+            Err(_) => "error",
         };
-        self.wallet_service.credit(&credit_req).await?;
 
-        // Step 8: Record in journal
+        histogram!("payment_duration_seconds", start.elapsed().as_secs_f64());
+        counter!("payment_total", 1, "status" => status, "method" => method);
+
+        result
+    }
+
+    /// Shared pipeline once the payee is known. The journal row is written
+    /// first so that a payment failing at any later step stays visible as
+    /// FAILED, with the error as the transition reason.
+    async fn settle(
+        &self,
+        from_user_id: Uuid,
+        to_user_id: Uuid,
+        amount: u64,
+        idempotency_key: &str,
+        fingerprint: &str,
+        method: &str,
+    ) -> Result<PaymentResponse, PaymentError> {
+        // Step 1: Validate same user
+        if from_user_id == to_user_id {
+            return Err(PaymentError::UserNotFound("Cannot send to self".to_string()));
+        }
+
+        // Merchant wallets only accept payments while the merchant is ACTIVE
+        let merchant_status = sqlx::query_scalar!("SELECT status FROM merchants WHERE merchant_id = $1", to_user_id)
+            .fetch_optional(&self.db)
+            .await?;
+        if let Some(status) = merchant_status.filter(|s| s != "ACTIVE") {
+            return Err(PaymentError::MerchantNotActive(status));
+        }
+
+        // Step 2: Journal row in INITIATED — duplicate keys collide here
         let tx_id = Uuid::new_v4();
-        sqlx::query!(
-            r#"
-            INSERT INTO transaction_journal (tx_id, from_user_id, to_user_id, amount, status, idempotency_key)
-            VALUES ($1, $2, $3, $4, 'SUCCESS', $5)
-            "#,
+        let mut tx = self.db.begin().await?;
+        match state::initiate(&mut tx, tx_id, from_user_id, to_user_id, amount as i64, idempotency_key, Some(fingerprint), method).await {
+            Ok(_) => tx.commit().await?,
+            // A concurrent request with the same key won the race
+            Err(e) if is_duplicate_key(&e) => {
+                drop(tx);
+                return self.replay(idempotency_key, fingerprint).await?
+                    .ok_or(PaymentError::StateError(e));
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        // Step 3: Debit, credit, ledger and daily limit usage in one DB transaction
+        let receipt = match self.wallet_service.transfer(&TransferRequest {
             tx_id,
             from_user_id,
             to_user_id,
-            req.amount as i64,
-            &req.idempotency_key
-        )
-        .execute(&mut *tx)
-        .await?;
-
-        // Step 9: Update daily limit
-        self.update_daily_limit(&mut tx, from_user_id, req.amount).await?;
+            amount,
+            idempotency_key: idempotency_key.to_string(),
+        }).await {
+            Ok(r) => r,
+            Err(e) => {
+                let e = transfer_error(e);
+                self.mark_failed(tx_id, &e).await;
+                return Err(e);
+            }
+        };
 
-        // Step 10: Commit
-        tx.commit().await?;
         let notification = format!(
     r#"{{"type":"payment","tx_id":"{}","amount":{},"status":"Success"}}"#,
-    tx_id, req.amount
+    tx_id, amount
 );
 ws_server.send_notification(&from_user_id.to_string(), &notification).await;
 
-        // Step 11: Emit fraud event (async, fire-and-forget)
+        // Step 4: Emit fraud event (async, fire-and-forget)
         let event = FraudEvent {
             tx_id,
             from_user_id,
             to_user_id,
-            amount: req.amount,
+            amount,
             device_fingerprint: None, // get from request context
             ip_address: None,         // get from request context
             timestamp: chrono::Utc::now(),
@@ -134,203 +316,355 @@ ws_server.send_notification(&from_user_id.to_string(), &notification).await;
         });
 
         info!(tx_id = %tx_id, "Payment completed");
-         let status = match &result {
-        Ok(_) => "success",
-        Err(PaymentError::InsufficientBalance) => "insufficient_balance",
-        Err(PaymentError::DailyLimitExceeded) => "daily_limit_exceeded",
-        Err(_) => "error",
-    };
-
-    histogram!("payment_duration_seconds", start.elapsed().as_secs_f64());
-    counter!("payment_total", 1, "status" => status, "method" => "phone");
         Ok(PaymentResponse {
             tx_id,
             from_user_id,
             to_user_id,
-            amount: req.amount,
+            amount,
             status: PaymentStatus::Success,
-            timestamp: chrono::Utc::now(),
+            timestamp: receipt.created_at,
         })
     }
 
-    #[instrument(skip(self), fields(from_user_id = %from_user_id, amount = req.amount))]
-    pub async fn pay_by_qr(
+    /// Refunds all or part of a successful payment back to the payer. Only the
+    /// original payee can refund, and refunds never add up to more than the
+    /// original amount.
+    #[instrument(skip(self, req), fields(user_id = %user_id, original_tx_id = %original_tx_id))]
+    pub async fn refund(
         &self,
-        from_user_id: Uuid,
-        req: PayByQrRequest,
+        user_id: Uuid,
+        original_tx_id: Uuid,
+        req: RefundRequest,
     ) -> Result<PaymentResponse, PaymentError> {
         req.validate()?;
 
-        // Decode QR: "payment://user/<uuid>"
-        let to_user_id = self.decode_qr(&req.qr_code)?;
-
-        // Same logic as pay_by_phone — reuse
-        let phone_req = PayByPhoneRequest {
-            to_mobile: "QR_PAYMENT".to_string(), // dummy — we have user_id
-            amount: req.amount,
-            idempotency_key: req.idempotency_key,
-        };
+        // Step 1: Replay (amount 0 in the fingerprint means "full refund")
+        let fingerprint = request_fingerprint(user_id, "refund", &original_tx_id.to_string(), req.amount.unwrap_or(0));
+        if let Some(resp) = self.replay(&req.idempotency_key, &fingerprint).await? {
+            return Ok(resp);
+        }
 
-        // Override resolved user_id
+        // Step 2: Lock the original payment so concurrent refunds serialize
         let mut tx = self.db.begin().await?;
-        if self.is_idempotent(&req.idempotency_key).await? {
-            return Err(PaymentError::DuplicateIdempotencyKey);
+        let original = sqlx::query!(
+            r#"
+            SELECT from_user_id, to_user_id, amount, status, kind
+            FROM transaction_journal
+            WHERE tx_id = $1
+            FOR UPDATE
+            "#,
+            original_tx_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .filter(|o| o.to_user_id == user_id) // payers and strangers see "not found"
+        .ok_or(PaymentError::TransactionNotFound(original_tx_id))?;
+
+        if original.kind != "PAYMENT" || original.status != "SUCCESS" {
+            return Err(PaymentError::RefundNotAllowed(format!("{} is {}", original.kind, original.status)));
         }
-        self.check_daily_limit(from_user_id, req.amount).await?;
-        let debit_req = crate::wallet::CreditDebitRequest {
-            user_id: from_user_id,
-            amount: req.amount,
-            idempotency_key: format!("debit_{}", req.idempotency_key),
-        };
-        self.wallet_service.debit(&debit_req).await?;
-        let credit_req = crate::wallet::CreditDebitRequest {
-            user_id: to_user_id,
-            amount: req.amount,
-            idempotency_key: format!("credit_{}", req.idempotency_key),
-        };
-        self.wallet_service.credit(&credit_req).await?;
-        let tx_id = Uuid::new_v4();
-        sqlx::query!(
+
+        // Step 3: Work out what is left; in-flight refunds count
+        let refunded = sqlx::query_scalar!(
             r#"
-            INSERT INTO transaction_journal (tx_id, from_user_id, to_user_id, amount, status, idempotency_key)
-            VALUES ($1, $2, $3, $4, 'SUCCESS', $5)
+            SELECT COALESCE(SUM(amount), 0)::BIGINT AS "refunded!"
+            FROM transaction_journal
+            WHERE original_tx_id = $1 AND kind = 'REFUND' AND status NOT IN ('FAILED', 'REVERSED')
             "#,
-            tx_id,
-            from_user_id,
-            to_user_id,
-            req.amount as i64,
-            &req.idempotency_key
+            original_tx_id
         )
-        .execute(&mut *tx)
+        .fetch_one(&mut *tx)
         .await?;
-        self.update_daily_limit(&mut tx, from_user_id, req.amount).await?;
-        tx.commit().await?;
-        let notification = format!(
-    r#"{{"type":"payment","tx_id":"{}","amount":{},"status":"Success"}}"#,
-    tx_id, req.amount
-);
-ws_server.send_notification(&from_user_id.to_string(), &notification).await;
 
-        let event = FraudEvent {
+        let remaining = original.amount - refunded;
+        let amount = req.amount.map(|a| a as i64).unwrap_or(remaining);
+        if amount <= 0 || amount > remaining {
+            return Err(PaymentError::RefundExceedsOriginal { remaining });
+        }
+
+        // Step 4: Refund journal row, committed while the original is locked
+        let tx_id = Uuid::new_v4();
+        match state::initiate_refund(
+            &mut tx,
             tx_id,
-            from_user_id,
-            to_user_id,
-            amount: req.amount,
-            device_fingerprint: None,
-            ip_address: None,
-            timestamp: chrono::Utc::now(),
-        };
-        let nc = self.nats_client.clone();
-        tokio::spawn(async move {
-            if let Err(e) = nc.publish_fraud_event(&event).await {
-                tracing::error!(error = %e, "Failed to publish fraud event");
+            original_tx_id,
+            user_id,
+            original.from_user_id,
+            amount,
+            &req.idempotency_key,
+            Some(&fingerprint),
+            req.reason.as_deref(),
+        ).await {
+            Ok(_) => tx.commit().await?,
+            Err(e) if is_duplicate_key(&e) => {
+                drop(tx);
+                return self.replay(&req.idempotency_key, &fingerprint).await?
+                    .ok_or(PaymentError::StateError(e));
             }
-        });
+            Err(e) => return Err(e.into()),
+        }
+
+        // Step 5: Move the money back. Refunds are money out of the payee's
+        // wallet and count against their daily limit like any other payment
+        let receipt = match self.wallet_service.transfer(&TransferRequest {
+            tx_id,
+            from_user_id: user_id,
+            to_user_id: original.from_user_id,
+            amount: amount as u64,
+            idempotency_key: req.idempotency_key.clone(),
+        }).await {
+            Ok(r) => r,
+            Err(e) => {
+                let e = transfer_error(e);
+                self.mark_failed(tx_id, &e).await;
+                return Err(e);
+            }
+        };
+
+        let refund_type = if amount == original.amount { "full" } else { "partial" };
+        counter!("payment_refunds_total", 1, "type" => refund_type);
+        info!(tx_id = %tx_id, amount, "Refund completed");
 
         Ok(PaymentResponse {
             tx_id,
-            from_user_id,
-            to_user_id,
-            amount: req.amount,
+            from_user_id: user_id,
+            to_user_id: original.from_user_id,
+            amount: amount as u64,
             status: PaymentStatus::Success,
-            timestamp: chrono::Utc::now(),
+            timestamp: receipt.created_at,
         })
     }
 
-    async fn resolve_mobile(&self, mobile: &str) -> Result<Uuid, PaymentError> {
-        let mobile_hash = hash_mobile(mobile, &self.otp_secret);
-        let user_id = sqlx::query_scalar!(
-            "SELECT id FROM users WHERE mobile_hash = $1",
-            &mobile_hash
+    /// Ops-initiated reversal of an erroneous payment. Refused once a refund
+    /// exists, since part of the money has already gone back.
+    #[instrument(skip(self))]
+    pub async fn admin_reverse(&self, tx_id: Uuid, operator: &str, reason: &str) -> Result<(), PaymentError> {
+        // The row stays locked until the reversal commits, so a refund or a
+        // second reversal cannot slip in between the checks and the money
+        // moving
+        let mut tx = self.db.begin().await?;
+        let row = sqlx::query!(
+            r#"
+            SELECT status, kind, idempotency_key, from_user_id, to_user_id, amount,
+                   EXISTS(
+                       SELECT 1 FROM transaction_journal r
+                       WHERE r.original_tx_id = tj.tx_id AND r.status NOT IN ('FAILED', 'REVERSED')
+                   ) AS "has_refunds!"
+            FROM transaction_journal tj
+            WHERE tx_id = $1
+            FOR UPDATE
+            "#,
+            tx_id
         )
-        .fetch_optional(&self.db)
+        .fetch_optional(&mut *tx)
         .await?
-        .ok_or_else(|| PaymentError::UserNotFound(mobile.to_string()))?;
+        .ok_or(PaymentError::TransactionNotFound(tx_id))?;
+
+        if row.status != "SUCCESS" {
+            return Err(PaymentError::RefundNotAllowed(format!("{} is {}", row.kind, row.status)));
+        }
+        if row.has_refunds {
+            return Err(PaymentError::RefundNotAllowed("payment already has refunds".to_string()));
+        }
+
+        self.wallet_service.reverse_transfer_in(
+            &mut tx,
+            tx_id,
+            &row.idempotency_key,
+            PaymentState::Success,
+            &format!("admin reversal by {}: {}", operator, reason),
+        ).await?;
+        tx.commit().await?;
+
+        // Money leaves the payee and returns to the payer; tell both
+        let notification = format!(
+            r#"{{"type":"payment","tx_id":"{}","amount":{},"status":"Reversed"}}"#,
+            tx_id, row.amount
+        );
+        for user_id in [row.from_user_id, row.to_user_id] {
+            ws_server.send_notification(&user_id.to_string(), &notification).await;
+        }
 
-        Ok(user_id)
+        counter!("payment_admin_reversals_total", 1);
+        tracing::warn!(tx_id = %tx_id, operator, reason, "Payment reversed by admin");
+        Ok(())
     }
 
-    fn decode_qr(&self, qr: &str) -> Result<Uuid, PaymentError> {
-        // Format: payment://user/<uuid>
-        let parts: Vec<&str> = qr.split('/').collect();
-        if parts.len() < 4 || parts[2] != "user" {
-            return Err(PaymentError::InvalidQrCode);
+    /// Records INITIATED → FAILED. Errors here are logged, not returned, so
+    /// the caller still sees the original failure.
+    async fn mark_failed(&self, tx_id: Uuid, err: &PaymentError) {
+        let reason = err.to_string();
+        let result = match self.db.acquire().await {
+            Ok(mut conn) => {
+                state::record_transition(&mut conn, tx_id, PaymentState::Initiated, PaymentState::Failed, Some(&reason)).await
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            tracing::error!(tx_id = %tx_id, error = %e, "Failed to record payment failure");
         }
-        Uuid::from_str(parts[3])
-            .map_err(|_| PaymentError::InvalidQrCode)
     }
 
-    async fn check_daily_limit(&self, user_id: Uuid, amount: u64) -> Result<(), PaymentError> {
-        let today = chrono::Utc::now().date_naive();
-        let row = sqlx::query!(
-            r#"
-            SELECT amount_used, reset_date, kyc_tier
-            FROM daily_limits
-            WHERE user_id = $1
-            "#,
-            user_id
+    pub async fn resolve_mobile(&self, mobile: &str) -> Result<Uuid, PaymentError> {
+        let address = PayeeAddress::Mobile { mobile: mobile.to_string() };
+        Ok(self.resolve(&address).await?.user_id)
+    }
+
+    /// Maps a `name@pay` handle to its user, the way `resolve_mobile` maps a
+    /// number.
+    pub async fn resolve_handle(&self, handle: &str) -> Result<Uuid, PaymentError> {
+        let address = PayeeAddress::Handle { handle: handle.to_string() };
+        Ok(self.resolve(&address).await?.user_id)
+    }
+
+    /// Name check before paying: resolves the address like `pay` would and
+    /// returns the payee's masked KYC name. Callers must be rate limited;
+    /// otherwise this is an oracle for which numbers are registered.
+    pub async fn lookup_payee(&self, address: &PayeeAddress) -> Result<PayeeLookup, PaymentError> {
+        let payee = match self.resolve(address).await {
+            Ok(payee) => payee,
+            Err(e) => {
+                counter!("payee_lookup_total", 1, "result" => "not_found");
+                return Err(e);
+            }
+        };
+
+        let kyc = sqlx::query!(
+            "SELECT name, status FROM fake_kyc_verifications WHERE user_id = $1",
+            payee.user_id
         )
         .fetch_optional(&self.db)
         .await?;
 
-        let (amount_used, reset_date, kyc_tier) = match row {
-            Some(r) => (r.amount_used.unwrap_or(0), r.reset_date, r.kyc_tier.unwrap_or("basic".to_string())),
-            None => (0, today, "basic".to_string()),
-        };
+        counter!("payee_lookup_total", 1, "result" => "found");
+        Ok(match kyc {
+            Some(kyc) => PayeeLookup {
+                display_name: Some(payee::mask_name(&kyc.name)),
+                kyc_badge: match kyc.status.as_str() {
+                    "approved" => KycBadge::Verified,
+                    "pending" => KycBadge::Pending,
+                    _ => KycBadge::Unverified,
+                },
+            },
+            None => PayeeLookup { display_name: None, kyc_badge: KycBadge::Unverified },
+        })
+    }
 
-        // Reset if new day
-        if reset_date < today {
-            sqlx::query!(
-                "UPDATE daily_limits SET amount_used = 0, reset_date = $1 WHERE user_id = $2",
-                today,
-                user_id
-            )
-            .execute(&self.db)
-            .await?;
-            return self.check_daily_limit(user_id, amount).await; // recurse
+    /// Resolves a code through the same resolvers `pay_by_qr` uses, without
+    /// paying, so the payer can confirm who and how much. Dynamic codes that
+    /// are expired or already paid are rejected up front.
+    pub async fn preview_qr(&self, qr_code: &str) -> Result<QrPreview, PaymentError> {
+        let payee = self.resolve(&PayeeAddress::Qr { qr_code: qr_code.to_string() }).await?;
+
+        if let Some(qr) = &payee.single_use {
+            let redeemed = sqlx::query_scalar!("SELECT 1 FROM qr_redemptions WHERE qr_id = $1", qr.qr_id)
+                .fetch_optional(&self.db)
+                .await?
+                .is_some();
+            if redeemed {
+                return Err(PaymentError::QrAlreadyUsed);
+            }
         }
 
-        let limit = if kyc_tier == "full" { 100_000_00 } else { 10_000_00 }; // in paise
-        if (amount_used + amount as i64) > limit {
-            return Err(PaymentError::DailyLimitExceeded);
-        }
+        // What the code itself carries, for display only. Codes only a
+        // registered resolver understands are labelled with its method.
+        let (kind, payee_name, reference, expires_at) = match self.decode_qr(qr_code) {
+            Ok(QrPayload::Static { .. }) => ("static", None, None, None),
+            Ok(QrPayload::Dynamic(qr)) => ("dynamic", None, Some(qr.reference), Some(qr.expires_at)),
+            Ok(QrPayload::Upi(qr)) => ("upi", qr.payee_name, qr.reference, None),
+            Err(_) => (payee.method, None, None, None),
+        };
 
-        Ok(())
+        Ok(QrPreview {
+            qr_code: qr_code.to_string(),
+            kind,
+            payee_user_id: payee.user_id,
+            payee_name,
+            amount: payee.fixed_amount,
+            amount_editable: payee.fixed_amount.is_none(),
+            reference,
+            expires_at,
+        })
     }
 
-    async fn update_daily_limit(
-        &self,
-        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-        user_id: Uuid,
-        amount: u64,
-    ) -> Result<(), PaymentError> {
-        let today = chrono::Utc::now().date_naive();
-        let rows = sqlx::query!(
+    pub fn decode_qr(&self, qr: &str) -> Result<QrPayload, PaymentError> {
+        payee::decode_qr(qr, &self.qr_secret)
+    }
+
+    /// Looks up a payment already made with `key`. Returns the original
+    /// response if the request matches, or a conflict if the key was used for
+    /// a different payment. A failed attempt is replayed as an error, never
+    /// as a response: the key is spent and nothing moved.
+    async fn replay(&self, key: &str, fingerprint: &str) -> Result<Option<PaymentResponse>, PaymentError> {
+        let row = sqlx::query!(
             r#"
-            INSERT INTO daily_limits (user_id, amount_used, reset_date, kyc_tier)
-            VALUES ($1, $2, $3, 'basic')
-            ON CONFLICT (user_id) DO UPDATE
-            SET amount_used = daily_limits.amount_used + $2
-            WHERE daily_limits.user_id = $1
+            SELECT tx_id, from_user_id, to_user_id, amount, status, request_hash, created_at
+            FROM transaction_journal
+            WHERE idempotency_key = $1
             "#,
-            user_id,
-            amount as i64,
-            today
+            key
         )
-        .execute(&mut *tx)
+        .fetch_optional(&self.db)
         .await?;
 
-        Ok(())
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        if row.request_hash.as_deref() != Some(fingerprint) {
+            counter!("payment_idempotency_total", 1, "result" => "conflict");
+            return Err(PaymentError::IdempotencyConflict);
+        }
+
+        if row.status == PaymentState::Failed.as_str() {
+            let reason = sqlx::query_scalar!(
+                "SELECT reason FROM payment_state_transitions WHERE tx_id = $1 AND to_state = 'FAILED'",
+                row.tx_id
+            )
+            .fetch_optional(&self.db)
+            .await?
+            .flatten();
+
+            counter!("payment_idempotency_total", 1, "result" => "failed");
+            return Err(PaymentError::AttemptFailed(reason.unwrap_or_else(|| "unknown reason".to_string())));
+        }
+
+        counter!("payment_idempotency_total", 1, "result" => "replayed");
+        info!(tx_id = %row.tx_id, "Replaying idempotent payment");
+
+        Ok(Some(PaymentResponse {
+            tx_id: row.tx_id,
+            from_user_id: row.from_user_id,
+            to_user_id: row.to_user_id,
+            amount: row.amount as u64,
+            status: PaymentStatus::from_db(&row.status),
+            timestamp: row.created_at,
+        }))
     }
+}
 
-    async fn is_idempotent(&self, key: &str) -> Result<bool, PaymentError> {
-        let exists = sqlx::query_scalar!(
-            "SELECT EXISTS(SELECT 1 FROM transaction_journal WHERE idempotency_key = $1)",
-            key
-        )
-        .fetch_one(&self.db)
-        .await?;
+/// Stable hash of everything that defines a payment, so a retry can be told
+/// apart from a different payment reusing the same idempotency key.
+fn request_fingerprint(from_user_id: Uuid, method: &str, payee: &str, amount: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}|{}|{}|{}", from_user_id, method, payee, amount).as_bytes());
+    hex::encode(hasher.finalize())
+}
 
-        Ok(exists.unwrap_or(false))
+/// True when the journal insert hit the unique idempotency key, i.e. another
+/// request with the same key got there first.
+fn is_duplicate_key(err: &TransitionError) -> bool {
+    matches!(
+        err,
+        TransitionError::DatabaseError(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23505")
+    )
+}
+/// The daily limit is enforced inside the transfer; it is still reported as
+/// the payment's own error.
+fn transfer_error(e: WalletError) -> PaymentError {
+    match e {
+        WalletError::DailyLimitExceeded => PaymentError::DailyLimitExceeded,
+        other => other.into(),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,120 @@
+// src/payment/sweeper.rs
+
+use crate::transaction::state::{self, PaymentState};
+use crate::ws::server::WsServer;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+use metrics::{counter, gauge};
+
+/// Background task that fails payments stuck in INITIATED. Money only
+/// moves in the transaction that marks a payment SUCCESS, so a payment
+/// still INITIATED after `timeout` has moved nothing.
+pub struct PaymentSweeper {
+    db: PgPool,
+    ws_server: Arc<WsServer>,
+    timeout: Duration, // how long a payment may sit in a non-final state
+    interval: Duration,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct StuckPayment {
+    tx_id: Uuid,
+    from_user_id: Uuid,
+    to_user_id: Uuid,
+    amount: i64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SweepReport {
+    pub failed: u32,
+    pub errors: u32,
+}
+
+impl PaymentSweeper {
+    pub fn new(
+        db: PgPool,
+        ws_server: Arc<WsServer>,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            db,
+            ws_server,
+            timeout,
+            interval,
+        }
+    }
+
+    pub async fn run(&self) {
+        info!(timeout_secs = self.timeout.as_secs(), "Payment sweeper started");
+        loop {
+            tokio::time::sleep(self.interval).await;
+            if let Err(e) = self.sweep_once().await {
+                error!(error = %e, "Payment sweep failed");
+            }
+        }
+    }
+
+    pub async fn sweep_once(&self) -> Result<SweepReport, sqlx::Error> {
+        let stuck = sqlx::query_as!(
+            StuckPayment,
+            r#"
+            SELECT tx_id, from_user_id, to_user_id, amount
+            FROM transaction_journal
+            WHERE status = 'INITIATED'
+              AND created_at < NOW() - make_interval(secs => $1)
+            ORDER BY created_at
+            LIMIT 100
+            "#,
+            self.timeout.as_secs_f64()
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        gauge!("payment_stuck_transactions", stuck.len() as f64);
+
+        let mut report = SweepReport::default();
+        for payment in stuck {
+            match self.fail(&payment).await {
+                Ok(()) => {
+                    report.failed += 1;
+                    counter!("payment_sweeper_resolved_total", 1, "outcome" => PaymentState::Failed.as_str());
+                    self.notify(&payment).await;
+                }
+                Err(e) => {
+                    report.errors += 1;
+                    counter!("payment_sweeper_errors_total", 1);
+                    warn!(tx_id = %payment.tx_id, error = %e, "Could not resolve stuck payment");
+                }
+            }
+        }
+
+        if report != SweepReport::default() {
+            info!(?report, "Payment sweep finished");
+        }
+        Ok(report)
+    }
+
+    async fn fail(&self, payment: &StuckPayment) -> Result<(), state::TransitionError> {
+        let reason = format!("timed out in INITIATED after {}s", self.timeout.as_secs());
+        let mut conn = self.db.acquire().await?;
+        state::record_transition(&mut conn, payment.tx_id, PaymentState::Initiated, PaymentState::Failed, Some(&reason)).await?;
+
+        info!(tx_id = %payment.tx_id, "Stuck payment failed");
+        Ok(())
+    }
+
+    /// Both sides may be showing the payment as pending.
+    async fn notify(&self, payment: &StuckPayment) {
+        let notification = format!(
+            r#"{{"type":"payment","tx_id":"{}","amount":{},"status":"Failed"}}"#,
+            payment.tx_id, payment.amount
+        );
+        for user_id in [payment.from_user_id, payment.to_user_id] {
+            self.ws_server.send_notification(&user_id.to_string(), &notification).await;
+        }
+    }
+}
@@ -1,25 +1,47 @@
 // src/qr/handlers.rs
 
 use axum::{
-    extract::Path,
-    http::StatusCode,
+    Extension,
+    Json,
+    body::Bytes,
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use uuid::Uuid;
+use validator::Validate;
 use crate::qr::service::QrService;
+use crate::qr::models::{CreateDynamicQrRequest, DynamicQrResponse, QrFormat, QrImageQuery, QrImageError, RenderOptions};
+use crate::handle::HandleService;
+use crate::payment::{PaymentService, models::{PaymentError, QrPreview}};
+
+/// Codes are cheap to revalidate via ETag but the payload behind them can
+/// change (format, handle), so they are no longer cached for a year.
+const QR_CACHE_CONTROL: &str = "public, max-age=300, must-revalidate";
 
 pub async fn get_qr_png(
     Path(user_id): Path<Uuid>,
+    Query(query): Query<QrImageQuery>,
+    headers: HeaderMap,
     Extension(qr_service): Extension<std::sync::Arc<QrService>>,
+    Extension(handle_service): Extension<std::sync::Arc<HandleService>>,
 ) -> Result<Response, (StatusCode, String)> {
-    let png_data = qr_service.generate_qr_png(&user_id)
+    let opts = render_options(&query, &qr_service)?;
+    let content = static_content(&qr_service, &handle_service, &user_id, &query).await?;
+    let etag = qr_service.etag(&content, &opts, "png");
+    if is_not_modified(&headers, &etag) {
+        return Ok(not_modified(etag));
+    }
+
+    let png_data = qr_service.render_png(&content, &opts)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("QR gen failed: {}", e)))?;
 
     Ok((
         [
-            ("Content-Type", "image/png"),
-            ("Cache-Control", "public, max-age=31536000"), // 1 year
-            ("X-Content-Type-Options", "nosniff"),
+            ("Content-Type", "image/png".to_string()),
+            ("Cache-Control", QR_CACHE_CONTROL.to_string()),
+            ("ETag", etag),
+            ("X-Content-Type-Options", "nosniff".to_string()),
         ],
         png_data,
     ).into_response())
@@ -27,17 +49,134 @@ pub async fn get_qr_png(
 
 pub async fn get_qr_svg(
     Path(user_id): Path<Uuid>,
+    Query(query): Query<QrImageQuery>,
+    headers: HeaderMap,
     Extension(qr_service): Extension<std::sync::Arc<QrService>>,
+    Extension(handle_service): Extension<std::sync::Arc<HandleService>>,
 ) -> Result<Response, (StatusCode, String)> {
-    let svg_data = qr_service.generate_qr_svg(&user_id)
+    let opts = render_options(&query, &qr_service)?;
+    let content = static_content(&qr_service, &handle_service, &user_id, &query).await?;
+    let etag = qr_service.etag(&content, &opts, "svg");
+    if is_not_modified(&headers, &etag) {
+        return Ok(not_modified(etag));
+    }
+
+    let svg_data = qr_service.render_svg(&content, &opts)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("QR gen failed: {}", e)))?;
 
     Ok((
         [
-            ("Content-Type", "image/svg+xml"),
-            ("Cache-Control", "public, max-age=31536000"),
-            ("X-Content-Type-Options", "nosniff"),
+            ("Content-Type", "image/svg+xml".to_string()),
+            ("Cache-Control", QR_CACHE_CONTROL.to_string()),
+            ("ETag", etag),
+            ("X-Content-Type-Options", "nosniff".to_string()),
         ],
         svg_data,
     ).into_response())
-}
\ No newline at end of file
+}
+
+/// UPI codes carry the user's claimed handle when they have one.
+async fn static_content(
+    qr_service: &QrService,
+    handle_service: &HandleService,
+    user_id: &Uuid,
+    query: &QrImageQuery,
+) -> Result<String, (StatusCode, String)> {
+    let handle = match query.format {
+        QrFormat::Upi => handle_service.get(*user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map(|h| h.handle),
+        QrFormat::Payment => None,
+    };
+    Ok(qr_service.static_content(user_id, query.format, handle.as_deref()))
+}
+
+fn render_options(query: &QrImageQuery, qr_service: &QrService) -> Result<RenderOptions, (StatusCode, String)> {
+    let opts = query.render_options().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if opts.logo && !qr_service.has_logo() {
+        return Err((StatusCode::BAD_REQUEST, "logo is not available".to_string()));
+    }
+    Ok(opts)
+}
+
+/// `If-None-Match` may list several tags, weak ones included, or `*`.
+fn is_not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+fn not_modified(etag: String) -> Response {
+    (
+        StatusCode::NOT_MODIFIED,
+        [("ETag", etag), ("Cache-Control", QR_CACHE_CONTROL.to_string())],
+    ).into_response()
+}
+
+/// Per-order code for the authenticated merchant.
+pub async fn create_dynamic_qr(
+    Extension(qr_service): Extension<std::sync::Arc<QrService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<CreateDynamicQrRequest>,
+) -> Result<Json<DynamicQrResponse>, (StatusCode, String)> {
+    payload.validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let (qr, qr_code) = qr_service.generate_dynamic(
+        user_id,
+        payload.amount,
+        &payload.reference,
+        std::time::Duration::from_secs(payload.expires_in_secs),
+    )
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("QR gen failed: {}", e)))?;
+
+    Ok(Json(DynamicQrResponse {
+        qr_id: qr.qr_id,
+        qr_code,
+        amount: qr.amount,
+        reference: qr.reference,
+        expires_at: qr.expires_at,
+    }))
+}
+
+/// Decodes a photographed or screenshotted QR (raw PNG/JPEG body) and
+/// returns its payee and amount for the payer to confirm via `/pay/qr`.
+pub async fn scan_qr(
+    Extension(qr_service): Extension<std::sync::Arc<QrService>>,
+    Extension(payment_service): Extension<std::sync::Arc<PaymentService>>,
+    _user_id: Uuid, // from JWT middleware
+    body: Bytes,
+) -> Result<Json<QrPreview>, (StatusCode, String)> {
+    // Image decoding and grid detection are CPU-bound; keep them off the
+    // async workers
+    let decoder = qr_service.clone();
+    let qr_code = tokio::task::spawn_blocking(move || decoder.decode_image(&body))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("QR decode failed: {}", e)))?
+        .map_err(|e| {
+            let status = match e {
+                QrImageError::UnsupportedFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                QrImageError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+                QrImageError::Unreadable(_) | QrImageError::NoQrFound => StatusCode::UNPROCESSABLE_ENTITY,
+            };
+            (status, e.to_string())
+        })?;
+
+    let preview = payment_service.preview_qr(&qr_code).await.map_err(|e| {
+        let status = match e {
+            PaymentError::UserNotFound(_) => StatusCode::NOT_FOUND,
+            PaymentError::QrAlreadyUsed => StatusCode::CONFLICT,
+            PaymentError::QrExpired => StatusCode::GONE,
+            PaymentError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, e.to_string())
+    })?;
+
+    Ok(Json(preview))
+}
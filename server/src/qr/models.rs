@@ -0,0 +1,177 @@
+// src/qr/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use uuid::Uuid;
+use qrcode::EcLevel;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateDynamicQrRequest {
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64, // in paise
+
+    #[validate(length(min = 1, max = 64))]
+    pub reference: String, // order reference shown back to the merchant
+
+    #[validate(range(min = 60, max = 86_400))] // 1 minute to 1 day
+    pub expires_in_secs: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DynamicQrResponse {
+    pub qr_id: Uuid,
+    pub qr_code: String, // payload to render or share
+    pub amount: u64,
+    pub reference: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Which scheme a static code is written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    #[default]
+    Payment, // payment://user/<uuid>, this app only
+    Upi,     // upi://pay?pa=<handle>, any UPI app
+}
+
+/// `/qr/:user_id.png` and `.svg` query string. Colors are `RRGGBB` without
+/// the `#`, which would otherwise need escaping in a URL.
+#[derive(Debug, Deserialize, Validate)]
+pub struct QrImageQuery {
+    #[serde(default)]
+    pub format: QrFormat,
+
+    #[validate(range(min = 128, max = 2048))]
+    pub size: Option<u32>, // minimum side in pixels
+
+    pub ec: Option<EcLevelParam>,
+
+    #[validate(range(max = 16))]
+    pub margin: Option<u32>, // quiet zone in modules
+
+    #[validate(regex = "HEX_COLOR_REGEX")]
+    pub fg: Option<String>,
+
+    #[validate(regex = "HEX_COLOR_REGEX")]
+    pub bg: Option<String>,
+
+    #[serde(default)]
+    pub logo: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum EcLevelParam {
+    L,
+    M,
+    Q,
+    H,
+}
+
+/// Validated rendering settings. The defaults match what every code looked
+/// like before these were configurable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub size: u32,
+    pub ec: EcLevel,
+    pub margin: u32,
+    pub dark: [u8; 3],
+    pub light: [u8; 3],
+    pub logo: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            size: 300,
+            ec: EcLevel::M,
+            margin: 4,
+            dark: [0, 0, 0],
+            light: [255, 255, 255],
+            logo: false,
+        }
+    }
+}
+
+/// Below this contrast ratio phone cameras start missing modules.
+const MIN_CONTRAST: f64 = 3.0;
+
+impl QrImageQuery {
+    pub fn render_options(&self) -> Result<RenderOptions, String> {
+        self.validate().map_err(|e| e.to_string())?;
+
+        let defaults = RenderOptions::default();
+        let dark = self.fg.as_deref().map(parse_hex_color).transpose()?.unwrap_or(defaults.dark);
+        let light = self.bg.as_deref().map(parse_hex_color).transpose()?.unwrap_or(defaults.light);
+        if contrast_ratio(dark, light) < MIN_CONTRAST {
+            return Err(format!("fg must be darker than bg with a contrast ratio of at least {}", MIN_CONTRAST));
+        }
+
+        // A logo hides the centre modules; only Q and H can recover them
+        let ec = match (self.ec, self.logo) {
+            (Some(EcLevelParam::L | EcLevelParam::M), true) => {
+                return Err("logo requires error correction level Q or H".to_string());
+            }
+            (None, true) => EcLevel::H,
+            (None, false) => defaults.ec,
+            (Some(EcLevelParam::L), _) => EcLevel::L,
+            (Some(EcLevelParam::M), _) => EcLevel::M,
+            (Some(EcLevelParam::Q), _) => EcLevel::Q,
+            (Some(EcLevelParam::H), _) => EcLevel::H,
+        };
+
+        Ok(RenderOptions {
+            size: self.size.unwrap_or(defaults.size),
+            ec,
+            margin: self.margin.unwrap_or(defaults.margin),
+            dark,
+            light,
+            logo: self.logo,
+        })
+    }
+}
+
+fn parse_hex_color(hex: &str) -> Result<[u8; 3], String> {
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .ok_or_else(|| format!("invalid color: {}", hex))
+    };
+    if hex.len() != 6 {
+        return Err(format!("invalid color: {}", hex));
+    }
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// WCAG contrast ratio; negative when `dark` is the lighter of the two.
+fn contrast_ratio(dark: [u8; 3], light: [u8; 3]) -> f64 {
+    fn luminance(rgb: [u8; 3]) -> f64 {
+        let lin = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        };
+        0.2126 * lin(rgb[0]) + 0.7152 * lin(rgb[1]) + 0.0722 * lin(rgb[2])
+    }
+    let (d, l) = (luminance(dark), luminance(light));
+    if d > l {
+        return -1.0;
+    }
+    (l + 0.05) / (d + 0.05)
+}
+
+const HEX_COLOR_REGEX: &str = r"^[0-9a-fA-F]{6}$";
+
+#[derive(Debug, thiserror::Error)]
+pub enum QrImageError {
+    #[error("Only PNG and JPEG uploads are supported")]
+    UnsupportedFormat,
+
+    #[error("Image exceeds {max}x{max} pixels")]
+    TooLarge { max: u32 },
+
+    #[error("Could not read image: {0}")]
+    Unreadable(String),
+
+    #[error("No QR code found in image")]
+    NoQrFound,
+}
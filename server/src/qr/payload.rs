@@ -0,0 +1,152 @@
+// src/qr/payload.rs
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+use std::str::FromStr;
+use crate::qr::upi::{self, UpiQr};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What a scanned QR code asks the payer to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QrPayload {
+    /// `payment://user/<uuid>` — pay this user any amount
+    Static { user_id: Uuid },
+    /// Signed per-order code with a fixed amount and expiry
+    Dynamic(DynamicQr),
+    /// Standard `upi://pay?` code; the payee is a handle, not a user id
+    Upi(UpiQr),
+}
+
+impl QrPayload {
+    /// Payee, when the code names one directly. UPI codes need a handle lookup.
+    pub fn user_id(&self) -> Option<Uuid> {
+        match self {
+            QrPayload::Static { user_id } => Some(*user_id),
+            QrPayload::Dynamic(qr) => Some(qr.user_id),
+            QrPayload::Upi(_) => None,
+        }
+    }
+}
+
+/// `payment://pay?id=..&to=..&am=..&tr=..&exp=..&sig=..`, where `sig` is an
+/// HMAC-SHA256 over every other field. `id` makes each code single-use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicQr {
+    pub qr_id: Uuid,
+    pub user_id: Uuid,
+    pub amount: u64, // in paise
+    pub reference: String, // merchant's order reference
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum QrError {
+    #[error("Malformed QR payload")]
+    Malformed,
+
+    #[error("QR signature does not match")]
+    BadSignature,
+}
+
+impl DynamicQr {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= chrono::Utc::now()
+    }
+
+    pub fn encode(&self, secret: &str) -> String {
+        format!(
+            "payment://pay?id={}&to={}&am={}&tr={}&exp={}&sig={}",
+            self.qr_id,
+            self.user_id,
+            self.amount,
+            url::form_urlencoded::byte_serialize(self.reference.as_bytes()).collect::<String>(),
+            self.expires_at.timestamp(),
+            self.signature(secret)
+        )
+    }
+
+    fn signature(&self, secret: &str) -> String {
+        hex::encode(self.mac(secret).finalize().into_bytes())
+    }
+
+    fn mac(&self, secret: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(
+            format!(
+                "v1|{}|{}|{}|{}|{}",
+                self.qr_id,
+                self.user_id,
+                self.amount,
+                self.reference,
+                self.expires_at.timestamp()
+            )
+            .as_bytes(),
+        );
+        mac
+    }
+}
+
+/// Parses any QR payload we accept. Dynamic codes must carry a valid
+/// signature; expiry is left to the caller so it can report it distinctly.
+pub fn parse(qr: &str, secret: &str) -> Result<QrPayload, QrError> {
+    if let Some(rest) = qr.strip_prefix("payment://user/") {
+        let user_id = Uuid::from_str(rest.trim_end_matches('/')).map_err(|_| QrError::Malformed)?;
+        return Ok(QrPayload::Static { user_id });
+    }
+
+    if let Some(query) = qr.strip_prefix("payment://pay?") {
+        return parse_dynamic(query, secret).map(QrPayload::Dynamic);
+    }
+
+    // Scheme and host are case-insensitive; some apps print `UPI://PAY?`
+    if qr.get(..10).is_some_and(|scheme| scheme.eq_ignore_ascii_case("upi://pay?")) {
+        return upi::parse(&qr[10..]).map(QrPayload::Upi);
+    }
+
+    Err(QrError::Malformed)
+}
+
+fn parse_dynamic(query: &str, secret: &str) -> Result<DynamicQr, QrError> {
+    let mut id = None;
+    let mut to = None;
+    let mut am = None;
+    let mut tr = None;
+    let mut exp = None;
+    let mut sig = None;
+
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        let slot = match key.as_ref() {
+            "id" => &mut id,
+            "to" => &mut to,
+            "am" => &mut am,
+            "tr" => &mut tr,
+            "exp" => &mut exp,
+            "sig" => &mut sig,
+            _ => return Err(QrError::Malformed),
+        };
+        // Repeated parameters could smuggle a second value past the signature
+        if slot.replace(value.into_owned()).is_some() {
+            return Err(QrError::Malformed);
+        }
+    }
+
+    let expires_at = exp
+        .and_then(|e| e.parse::<i64>().ok())
+        .and_then(|e| chrono::DateTime::from_timestamp(e, 0))
+        .ok_or(QrError::Malformed)?;
+
+    let qr = DynamicQr {
+        qr_id: id.and_then(|v| Uuid::from_str(&v).ok()).ok_or(QrError::Malformed)?,
+        user_id: to.and_then(|v| Uuid::from_str(&v).ok()).ok_or(QrError::Malformed)?,
+        amount: am.and_then(|v| v.parse().ok()).filter(|a| *a > 0).ok_or(QrError::Malformed)?,
+        reference: tr.filter(|r| !r.is_empty() && r.len() <= 64).ok_or(QrError::Malformed)?,
+        expires_at,
+    };
+
+    let sig = sig.and_then(|s| hex::decode(s).ok()).ok_or(QrError::Malformed)?;
+    qr.mac(secret).verify_slice(&sig).map_err(|_| QrError::BadSignature)?;
+
+    Ok(qr)
+}
@@ -1,29 +1,149 @@
 // src/qr/service.rs
 
 use qrcode::QrCode;
-use image::{ImageBuffer, Rgba};
+use image::{ImageBuffer, ImageEncoder, Rgba, RgbaImage};
+use base64::Engine;
+use sha2::{Digest, Sha256};
 use std::io::Cursor;
+use crate::qr::payload::DynamicQr;
+use crate::qr::models::{QrFormat, QrImageError, RenderOptions};
+use crate::qr::upi::{self, UpiQr};
 
-pub struct QrService;
+/// Largest upload we will decode, per side. Phone photos are downscaled by
+/// clients well below this; anything bigger is likely a decompression bomb.
+pub const MAX_SCAN_DIMENSION: u32 = 4096;
+
+pub struct QrService {
+    signing_secret: String, // HMAC key for dynamic codes
+    logo: Option<Logo>, // centre logo, when one is configured
+}
+
+struct Logo {
+    image: RgbaImage,
+    png_base64: String, // embedded as-is in SVGs
+}
 
 impl QrService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(signing_secret: String) -> Self {
+        Self { signing_secret, logo: None }
+    }
+
+    /// Enables `?logo=true`. The logo is re-encoded as PNG so SVGs can embed it.
+    pub fn with_logo(mut self, bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        let mut png = Vec::new();
+        image::codecs::png::PngEncoder::new(Cursor::new(&mut png)).write_image(
+            &image,
+            image.width(),
+            image.height(),
+            image::ColorType::Rgba8,
+        )?;
+        self.logo = Some(Logo {
+            image,
+            png_base64: base64::engine::general_purpose::STANDARD.encode(png),
+        });
+        Ok(self)
+    }
+
+    pub fn generate_qr_png(&self, user_id: &uuid::Uuid, format: QrFormat, opts: &RenderOptions) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.render_png(&self.static_content(user_id, format, None), opts)
+    }
+
+    pub fn generate_qr_svg(&self, user_id: &uuid::Uuid, format: QrFormat, opts: &RenderOptions) -> Result<String, Box<dyn std::error::Error>> {
+        self.render_svg(&self.static_content(user_id, format, None), opts)
+    }
+
+    pub fn has_logo(&self) -> bool {
+        self.logo.is_some()
+    }
+
+    /// `handle` is the user's claimed handle, if any; UPI codes fall back to
+    /// `<user_id>@pay` without one.
+    pub fn static_content(&self, user_id: &uuid::Uuid, format: QrFormat, handle: Option<&str>) -> String {
+        match format {
+            // Format: payment://user/<uuid>
+            QrFormat::Payment => format!("payment://user/{}", user_id),
+            QrFormat::Upi => match handle {
+                Some(handle) => self.generate_upi_for_handle(handle, None, None, None),
+                None => self.generate_upi(user_id, None, None, None),
+            },
+        }
+    }
+
+    /// `upi://pay?...` code readable by any UPI app, addressed to the user's
+    /// default handle. `amount` (paise) and `reference` prefill the payment.
+    pub fn generate_upi(
+        &self,
+        user_id: &uuid::Uuid,
+        payee_name: Option<&str>,
+        amount: Option<u64>,
+        reference: Option<&str>,
+    ) -> String {
+        self.generate_upi_for_handle(&upi::default_handle(user_id), payee_name, amount, reference)
     }
 
-    pub fn generate_qr_png(&self, user_id: &uuid::Uuid) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        // Format: payment://user/<uuid>
-        let content = format!("payment://user/{}", user_id);
+    pub fn generate_upi_for_handle(
+        &self,
+        handle: &str,
+        payee_name: Option<&str>,
+        amount: Option<u64>,
+        reference: Option<&str>,
+    ) -> String {
+        UpiQr {
+            handle: handle.to_string(),
+            payee_name: payee_name.map(str::to_string),
+            amount,
+            reference: reference.map(str::to_string),
+            note: None,
+        }
+        .encode()
+    }
+
+    /// Single-use, signed code for one order of `amount` paise.
+    pub fn generate_dynamic(
+        &self,
+        user_id: uuid::Uuid,
+        amount: u64,
+        reference: &str,
+        ttl: std::time::Duration,
+    ) -> Result<(DynamicQr, String), Box<dyn std::error::Error>> {
+        let qr = DynamicQr {
+            qr_id: uuid::Uuid::new_v4(),
+            user_id,
+            amount,
+            reference: reference.to_string(),
+            expires_at: chrono::Utc::now() + chrono::Duration::from_std(ttl)?,
+        };
+        let content = qr.encode(&self.signing_secret);
+        Ok((qr, content))
+    }
 
-        // Generate QR code
-        let code = QrCode::new(content.as_bytes())?;
+    pub fn render_png(&self, content: &str, opts: &RenderOptions) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let (modules, width) = Self::modules(content, opts)?;
+        let total = width + 2 * opts.margin;
+        let scale = opts.size.div_ceil(total); // pixels per module
+        let side = total * scale;
 
-        // Render to 300x300 PNG
-        let image = code.render::<Rgba<u8>>()
-            .min_dimensions(300, 300)
-            .dark_color(Rgba([0, 0, 0, 255]))   // black
-            .light_color(Rgba([255, 255, 255, 255])) // white
-            .build();
+        let dark = Rgba([opts.dark[0], opts.dark[1], opts.dark[2], 255]);
+        let light = Rgba([opts.light[0], opts.light[1], opts.light[2], 255]);
+        let mut image = ImageBuffer::from_fn(side, side, |px, py| {
+            let (mx, my) = (px / scale, py / scale);
+            let inside = (opts.margin..opts.margin + width).contains(&mx)
+                && (opts.margin..opts.margin + width).contains(&my);
+            let is_dark = inside && modules[((my - opts.margin) * width + (mx - opts.margin)) as usize];
+            if is_dark { dark } else { light }
+        });
+
+        if opts.logo {
+            let logo = self.logo.as_ref().ok_or("no logo configured")?;
+            let (logo_side, pad) = Self::logo_box(width);
+            let logo_px = logo_side * scale;
+            let origin = (side - logo_px) / 2;
+            let backing = ImageBuffer::from_pixel(logo_px + 2 * pad * scale, logo_px + 2 * pad * scale, light);
+            image::imageops::overlay(&mut image, &backing, (origin - pad * scale) as i64, (origin - pad * scale) as i64);
+            let resized = image::imageops::resize(&logo.image, logo_px, logo_px, image::imageops::FilterType::Lanczos3);
+            image::imageops::overlay(&mut image, &resized, origin as i64, origin as i64);
+        }
 
         // Encode to PNG
         let mut buf = Vec::new();
@@ -38,10 +158,90 @@ impl QrService {
         Ok(buf)
     }
 
-    pub fn generate_qr_svg(&self, user_id: &uuid::Uuid) -> Result<String, Box<dyn std::error::Error>> {
-        let content = format!("payment://user/{}", user_id);
-        let code = QrCode::new(content.as_bytes())?;
-        let svg = code.render().min_dimensions(300, 300).to_string();
+    pub fn render_svg(&self, content: &str, opts: &RenderOptions) -> Result<String, Box<dyn std::error::Error>> {
+        let (modules, width) = Self::modules(content, opts)?;
+        let total = width + 2 * opts.margin;
+        let side = opts.size.div_ceil(total) * total;
+
+        // One path of 1x1 squares in module units; the viewBox scales it
+        let mut path = String::new();
+        for (i, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+            let (x, y) = (i as u32 % width + opts.margin, i as u32 / width + opts.margin);
+            path.push_str(&format!("M{} {}h1v1h-1z", x, y));
+        }
+
+        let mut svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {total} {total}" width="{side}" height="{side}" shape-rendering="crispEdges"><rect width="{total}" height="{total}" fill="#{light}"/><path fill="#{dark}" d="{path}"/>"##,
+            light = hex::encode(opts.light),
+            dark = hex::encode(opts.dark),
+        );
+
+        if opts.logo {
+            let logo = self.logo.as_ref().ok_or("no logo configured")?;
+            let (logo_side, pad) = Self::logo_box(width);
+            let origin = (total - logo_side) / 2;
+            svg.push_str(&format!(
+                r##"<rect x="{backing}" y="{backing}" width="{backing_side}" height="{backing_side}" fill="#{light}"/><image href="data:image/png;base64,{data}" x="{origin}" y="{origin}" width="{logo_side}" height="{logo_side}"/>"##,
+                backing = origin - pad,
+                backing_side = logo_side + 2 * pad,
+                light = hex::encode(opts.light),
+                data = logo.png_base64,
+            ));
+        }
+
+        svg.push_str("</svg>");
         Ok(svg)
     }
-}
\ No newline at end of file
+
+    /// Strong validator for a rendered image: changes whenever the payload,
+    /// the options, or the configured logo change.
+    pub fn etag(&self, content: &str, opts: &RenderOptions, kind: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}|{}|{:?}", kind, content, opts));
+        if opts.logo {
+            hasher.update(self.logo.as_ref().map(|l| l.png_base64.as_str()).unwrap_or(""));
+        }
+        format!("\"{}\"", &hex::encode(hasher.finalize())[..32])
+    }
+
+    fn modules(content: &str, opts: &RenderOptions) -> Result<(Vec<bool>, u32), Box<dyn std::error::Error>> {
+        let code = QrCode::with_error_correction_level(content.as_bytes(), opts.ec)?;
+        let modules = code.to_colors().into_iter().map(|c| c == qrcode::Color::Dark).collect();
+        Ok((modules, code.width() as u32))
+    }
+
+    /// Logo side and its light padding, in modules: about a fifth of the
+    /// code, which level H (and Q, barely) can still recover from.
+    fn logo_box(width: u32) -> (u32, u32) {
+        ((width / 5) | 1, 1)
+    }
+
+    /// Reads the first QR code in an uploaded PNG or JPEG. The format is
+    /// sniffed from the bytes rather than trusted from the upload's headers.
+    /// CPU-bound; call it from `spawn_blocking` in async code.
+    pub fn decode_image(&self, bytes: &[u8]) -> Result<String, QrImageError> {
+        let mut reader = image::io::Reader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|e| QrImageError::Unreadable(e.to_string()))?;
+        if !matches!(reader.format(), Some(image::ImageFormat::Png | image::ImageFormat::Jpeg)) {
+            return Err(QrImageError::UnsupportedFormat);
+        }
+
+        let mut limits = image::io::Limits::default();
+        limits.max_image_width = Some(MAX_SCAN_DIMENSION);
+        limits.max_image_height = Some(MAX_SCAN_DIMENSION);
+        reader.limits(limits);
+
+        let image = reader.decode().map_err(|e| match e {
+            image::ImageError::Limits(_) => QrImageError::TooLarge { max: MAX_SCAN_DIMENSION },
+            e => QrImageError::Unreadable(e.to_string()),
+        })?;
+
+        let mut prepared = rqrr::PreparedImage::prepare(image.to_luma8());
+        prepared
+            .detect_grids()
+            .iter()
+            .find_map(|grid| grid.decode().ok().map(|(_, content)| content))
+            .ok_or(QrImageError::NoQrFound)
+    }
+}
@@ -0,0 +1,134 @@
+// src/qr/upi.rs
+
+use crate::qr::payload::QrError;
+
+/// Domain of the handles we issue, as in `<name>@pay`.
+pub const HANDLE_DOMAIN: &str = "pay";
+
+const MAX_NAME_LEN: usize = 99;
+const MAX_REF_LEN: usize = 35;
+const MAX_NOTE_LEN: usize = 80;
+
+/// `upi://pay?pa=...&pn=...&am=...&tr=...&cu=INR`, as used by every UPI app.
+/// Only `pa` is mandatory; `am` is in rupees on the wire and paise here.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpiQr {
+    pub handle: String, // payee address (`pa`)
+    pub payee_name: Option<String>,
+    pub amount: Option<u64>, // in paise
+    pub reference: Option<String>,
+    pub note: Option<String>,
+}
+
+impl UpiQr {
+    pub fn encode(&self) -> String {
+        let mut uri = format!("upi://pay?pa={}", encode_component(&self.handle));
+        if let Some(name) = &self.payee_name {
+            uri.push_str(&format!("&pn={}", encode_component(name)));
+        }
+        if let Some(amount) = self.amount {
+            uri.push_str(&format!("&am={}.{:02}", amount / 100, amount % 100));
+        }
+        if let Some(reference) = &self.reference {
+            uri.push_str(&format!("&tr={}", encode_component(reference)));
+        }
+        if let Some(note) = &self.note {
+            uri.push_str(&format!("&tn={}", encode_component(note)));
+        }
+        uri.push_str("&cu=INR");
+        uri
+    }
+}
+
+/// Default handle for users who have not picked one.
+pub fn default_handle(user_id: &uuid::Uuid) -> String {
+    format!("{}@{}", user_id, HANDLE_DOMAIN)
+}
+
+/// Parses the query part of a `upi://pay?` URI. Parameters we do not use
+/// (`mc`, `mode`, `orgid`, ...) are accepted and ignored so that QRs from
+/// other apps still scan; the ones we do use are validated.
+pub fn parse(query: &str) -> Result<UpiQr, QrError> {
+    let mut pa = None;
+    let mut pn = None;
+    let mut am = None;
+    let mut tr = None;
+    let mut tn = None;
+    let mut cu = None;
+
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        let slot = match key.as_ref() {
+            "pa" => &mut pa,
+            "pn" => &mut pn,
+            "am" => &mut am,
+            "tr" => &mut tr,
+            "tn" => &mut tn,
+            "cu" => &mut cu,
+            _ => continue,
+        };
+        if slot.replace(value.into_owned()).is_some() {
+            return Err(QrError::Malformed);
+        }
+    }
+
+    if cu.as_deref().is_some_and(|c| c != "INR") {
+        return Err(QrError::Malformed);
+    }
+
+    let handle = pa.filter(|h| is_valid_handle(h)).ok_or(QrError::Malformed)?;
+    let amount = am.map(|a| parse_rupees(&a).ok_or(QrError::Malformed)).transpose()?;
+
+    Ok(UpiQr {
+        handle: handle.to_lowercase(),
+        payee_name: pn.map(|n| bounded(n, MAX_NAME_LEN)).transpose()?,
+        amount,
+        reference: tr.map(parse_reference).transpose()?,
+        note: tn.map(|n| bounded(n, MAX_NOTE_LEN)).transpose()?,
+    })
+}
+
+/// `name@psp`: 2–256 characters of `[a-zA-Z0-9._-]`, then a letters-only PSP.
+pub fn is_valid_handle(handle: &str) -> bool {
+    let Some((name, psp)) = handle.split_once('@') else {
+        return false;
+    };
+    (2..=256).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        && (2..=64).contains(&psp.len())
+        && psp.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Merchant order reference: up to 35 characters of `[a-zA-Z0-9_-]`. A
+/// reference we cannot carry is an error, not dropped, since the merchant
+/// matches the payment to the order by it.
+fn parse_reference(tr: String) -> Result<String, QrError> {
+    if !tr.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(QrError::Malformed);
+    }
+    bounded(tr, MAX_REF_LEN)
+}
+
+/// "49.99" → 4999, "50" → 5000. At most two decimals, strictly positive.
+fn parse_rupees(am: &str) -> Option<u64> {
+    let (rupees, paise) = am.split_once('.').unwrap_or((am, ""));
+    if rupees.is_empty() || paise.len() > 2 || !rupees.chars().chain(paise.chars()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let rupees: u64 = rupees.parse().ok()?;
+    let paise: u64 = format!("{:0<2}", paise).parse().ok()?;
+    rupees.checked_mul(100)?.checked_add(paise).filter(|p| *p > 0)
+}
+
+fn bounded(value: String, max: usize) -> Result<String, QrError> {
+    if value.is_empty() || value.chars().count() > max {
+        return Err(QrError::Malformed);
+    }
+    Ok(value)
+}
+
+/// Percent-encodes with `%20` for spaces; some UPI apps show a literal `+`.
+fn encode_component(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
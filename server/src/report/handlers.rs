@@ -0,0 +1,73 @@
+// src/report/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::{Path, Query},
+    response::{IntoResponse, Response},
+};
+use uuid::Uuid;
+use crate::merchant::models::MerchantError;
+use crate::middleware::admin::AdminOperator;
+use crate::report::{ReportService, models::*};
+
+/// `GET /merchants/:merchant_id/reports/sales?from=&to=&granularity=&by_method=`
+pub async fn get_sales_report(
+    Extension(report_service): Extension<std::sync::Arc<ReportService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+    Query(query): Query<SalesReportQuery>,
+) -> Result<Json<SalesReport>, (http::StatusCode, Json<serde_json::Value>)> {
+    let report = report_service.sales_report(user_id, merchant_id, query)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(report))
+}
+
+/// Same report as a CSV download.
+pub async fn export_sales_report(
+    Extension(report_service): Extension<std::sync::Arc<ReportService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+    Query(query): Query<SalesReportQuery>,
+) -> Result<Response, (http::StatusCode, Json<serde_json::Value>)> {
+    let report = report_service.sales_report(user_id, merchant_id, query)
+        .await
+        .map_err(error_response)?;
+
+    let filename = format!("sales-{}-{}-{}.csv", report.from, report.to, report.granularity.as_str());
+    Ok((
+        [
+            ("Content-Type", "text/csv; charset=utf-8".to_string()),
+            ("Content-Disposition", format!("attachment; filename=\"{}\"", filename)),
+            ("X-Content-Type-Options", "nosniff".to_string()),
+        ],
+        report.to_csv(),
+    ).into_response())
+}
+
+/// Recomputes a merchant's rollups from the journal, e.g. to backfill
+/// history or after a manual data fix.
+pub async fn admin_rebuild_rollups(
+    Extension(report_service): Extension<std::sync::Arc<ReportService>>,
+    Extension(operator): Extension<AdminOperator>, // from admin middleware
+    Json(payload): Json<RebuildRollupsRequest>,
+) -> Result<Json<serde_json::Value>, (http::StatusCode, Json<serde_json::Value>)> {
+    tracing::info!(merchant_id = %payload.merchant_id, operator = %operator.0, "Rollup rebuild requested");
+    let queued = report_service.rebuild(payload.merchant_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(serde_json::json!({ "queued_hours": queued })))
+}
+
+fn error_response(e: ReportError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        ReportError::MerchantError(MerchantError::NotFound(_)) => http::StatusCode::NOT_FOUND,
+        ReportError::ValidationError(_) => http::StatusCode::BAD_REQUEST,
+        _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
@@ -0,0 +1,181 @@
+// src/report/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+use sqlx::types::Uuid;
+use crate::merchant::models::MerchantError;
+
+/// Longest range a report may cover, by granularity.
+pub const MAX_HOURLY_DAYS: i64 = 31;
+pub const MAX_DAILY_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    Hour,
+    #[default]
+    Day,
+    Total, // one row for the whole range (per method, if split)
+}
+
+impl Granularity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Granularity::Hour => "hour",
+            Granularity::Day => "day",
+            Granularity::Total => "total",
+        }
+    }
+}
+
+/// `/merchants/:merchant_id/reports/sales` query string. Dates are IST
+/// calendar days, both inclusive.
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[validate(schema(function = "validate_range"))]
+pub struct SalesReportQuery {
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+
+    #[serde(default)]
+    pub granularity: Granularity,
+
+    #[serde(default)]
+    pub by_method: bool, // one row per payment method within each period
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RebuildRollupsRequest {
+    pub merchant_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SalesReportRow {
+    pub period_start: chrono::NaiveDateTime, // IST
+    pub method: Option<String>, // None unless split by method
+    pub payment_count: i64,
+    pub gross: i64,
+    pub refund_count: i64,
+    pub refunds: i64,
+    pub fees: i64,
+    pub gst: i64,
+    pub net: i64,
+    pub unsettled: i64,
+    pub settling: i64,
+    pub settled: i64,
+    pub settlement_status: SettlementStatus,
+}
+
+/// Where a period's net amount is on its way to the merchant's bank.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SettlementStatus {
+    Unsettled,
+    Partial, // some of it batched or paid
+    Settling,
+    Settled,
+}
+
+impl SettlementStatus {
+    pub fn of(unsettled: i64, settling: i64, settled: i64) -> Self {
+        match (unsettled != 0, settling != 0, settled != 0) {
+            (false, false, _) => SettlementStatus::Settled,
+            (false, true, false) => SettlementStatus::Settling,
+            (true, false, false) => SettlementStatus::Unsettled,
+            _ => SettlementStatus::Partial,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettlementStatus::Unsettled => "UNSETTLED",
+            SettlementStatus::Partial => "PARTIAL",
+            SettlementStatus::Settling => "SETTLING",
+            SettlementStatus::Settled => "SETTLED",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SalesReport {
+    pub merchant_id: Uuid,
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    pub granularity: Granularity,
+    pub rows: Vec<SalesReportRow>,
+    pub totals: SalesReportRow, // period_start is `from`; method is None
+    pub refreshing: bool, // some hours in the range are queued for a rollup refresh
+}
+
+const CSV_HEADER: &str = "period_start,method,payment_count,gross,refund_count,refunds,fees,gst,net,unsettled,settling,settled,settlement_status";
+
+impl SalesReport {
+    /// One line per row, amounts in paise, timestamps in IST.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                row.period_start.format("%Y-%m-%d %H:%M"),
+                csv_field(row.method.as_deref().unwrap_or("")),
+                row.payment_count,
+                row.gross,
+                row.refund_count,
+                row.refunds,
+                row.fees,
+                row.gst,
+                row.net,
+                row.unsettled,
+                row.settling,
+                row.settled,
+                row.settlement_status.as_str(),
+            ));
+        }
+        csv
+    }
+}
+
+/// Quotes a field containing a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RollupRunReport {
+    pub hours: usize,
+    pub rows: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReportError {
+    #[error("Merchant error: {0}")]
+    MerchantError(#[from] MerchantError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+fn validate_range(query: &SalesReportQuery) -> Result<(), ValidationError> {
+    let days = (query.to - query.from).num_days() + 1;
+    let max = match query.granularity {
+        Granularity::Hour => MAX_HOURLY_DAYS,
+        Granularity::Day | Granularity::Total => MAX_DAILY_DAYS,
+    };
+
+    if query.to.succ_opt().is_none() {
+        Err(ValidationError::new("to_out_of_range")) // the report runs until midnight after `to`
+    } else if days < 1 {
+        Err(ValidationError::new("to_before_from"))
+    } else if days > max {
+        Err(ValidationError::new("range_too_long"))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+// src/report/service.rs
+
+use crate::report::models::*;
+use crate::merchant::MerchantService;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+/// Merchant sales and settlement reports. Reports read
+/// `merchant_hourly_rollups` only; triggers queue the hours a payment,
+/// settlement item or batch status change touches, and `refresh_rollups`
+/// recomputes those hours from the journal.
+pub struct ReportService {
+    db: PgPool,
+    merchant_service: Arc<MerchantService>,
+}
+
+impl ReportService {
+    pub fn new(db: PgPool, merchant_service: Arc<MerchantService>) -> Self {
+        Self { db, merchant_service }
+    }
+
+    pub async fn sales_report(
+        &self,
+        owner_user_id: Uuid,
+        merchant_id: Uuid,
+        query: SalesReportQuery,
+    ) -> Result<SalesReport, ReportError> {
+        query.validate()?;
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let from = query.from.and_hms_opt(0, 0, 0).unwrap();
+        let until = query.to.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                CASE $4::TEXT WHEN 'hour' THEN hour WHEN 'day' THEN date_trunc('day', hour) ELSE $2 END AS "period_start!",
+                CASE WHEN $5::BOOL THEN method END AS method,
+                SUM(payment_count)::BIGINT AS "payment_count!",
+                SUM(gross)::BIGINT AS "gross!",
+                SUM(refund_count)::BIGINT AS "refund_count!",
+                SUM(refunds)::BIGINT AS "refunds!",
+                SUM(fees)::BIGINT AS "fees!",
+                SUM(gst)::BIGINT AS "gst!",
+                SUM(net)::BIGINT AS "net!",
+                SUM(unsettled)::BIGINT AS "unsettled!",
+                SUM(settling)::BIGINT AS "settling!",
+                SUM(settled)::BIGINT AS "settled!"
+            FROM merchant_hourly_rollups
+            WHERE merchant_id = $1 AND hour >= $2 AND hour < $3
+            GROUP BY 1, 2
+            ORDER BY 1, 2
+            "#,
+            merchant_id,
+            from,
+            until,
+            query.granularity.as_str(),
+            query.by_method
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let rows: Vec<SalesReportRow> = rows
+            .into_iter()
+            .map(|r| SalesReportRow {
+                period_start: r.period_start,
+                method: r.method,
+                payment_count: r.payment_count,
+                gross: r.gross,
+                refund_count: r.refund_count,
+                refunds: r.refunds,
+                fees: r.fees,
+                gst: r.gst,
+                net: r.net,
+                unsettled: r.unsettled,
+                settling: r.settling,
+                settled: r.settled,
+                settlement_status: SettlementStatus::of(r.unsettled, r.settling, r.settled),
+            })
+            .collect();
+
+        let refreshing = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM merchant_rollup_queue
+                WHERE merchant_id = $1 AND hour >= $2 AND hour < $3
+            ) AS "refreshing!"
+            "#,
+            merchant_id,
+            from,
+            until
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(SalesReport {
+            merchant_id,
+            from: query.from,
+            to: query.to,
+            granularity: query.granularity,
+            totals: totals(&rows, from),
+            rows,
+            refreshing,
+        })
+    }
+
+    /// Recomputes up to `limit` queued hours, oldest first. Hours are taken
+    /// off the queue in the same transaction. The queue triggers lock an
+    /// hour's row until the change that queued it commits, and locked rows
+    /// are skipped here, so an hour is only recomputed once every change
+    /// queued for it is visible; one committed after this run queues it again.
+    #[instrument(skip(self))]
+    pub async fn refresh_rollups(&self, limit: i64) -> Result<RollupRunReport, ReportError> {
+        let mut tx = self.db.begin().await?;
+
+        let queued = sqlx::query!(
+            r#"
+            DELETE FROM merchant_rollup_queue
+            WHERE (merchant_id, hour) IN (
+                SELECT merchant_id, hour
+                FROM merchant_rollup_queue
+                ORDER BY queued_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING merchant_id, hour
+            "#,
+            limit
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if queued.is_empty() {
+            return Ok(RollupRunReport::default());
+        }
+        let (merchant_ids, hours): (Vec<Uuid>, Vec<chrono::NaiveDateTime>) =
+            queued.into_iter().map(|q| (q.merchant_id, q.hour)).unzip();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM merchant_hourly_rollups r
+            USING UNNEST($1::UUID[], $2::TIMESTAMP[]) AS q(merchant_id, hour)
+            WHERE r.merchant_id = q.merchant_id AND r.hour = q.hour
+            "#,
+            &merchant_ids,
+            &hours
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Same rows a settlement batch picks up: successful payments into
+        // the merchant wallet and refunds out of it
+        let inserted = sqlx::query!(
+            r#"
+            WITH moves AS (
+                SELECT
+                    q.merchant_id,
+                    q.hour,
+                    COALESCE(tj.method, 'unknown') AS method,
+                    tj.kind,
+                    tj.amount,
+                    COALESCE(tf.fee, 0) AS fee,
+                    COALESCE(tf.gst, 0) AS gst,
+                    CASE WHEN tj.kind = 'PAYMENT' THEN tj.amount ELSE -tj.amount END
+                        - COALESCE(tf.fee, 0) - COALESCE(tf.gst, 0) AS net,
+                    sb.status AS batch_status
+                FROM UNNEST($1::UUID[], $2::TIMESTAMP[]) AS q(merchant_id, hour)
+                JOIN transaction_journal tj
+                  ON tj.merchant_id = q.merchant_id
+                 AND tj.created_at >= q.hour AT TIME ZONE 'Asia/Kolkata'
+                 AND tj.created_at < (q.hour + INTERVAL '1 hour') AT TIME ZONE 'Asia/Kolkata'
+                LEFT JOIN transaction_fees tf ON tf.tx_id = tj.tx_id
+                LEFT JOIN settlement_items si ON si.tx_id = tj.tx_id
+                LEFT JOIN settlement_batches sb ON sb.batch_id = si.batch_id
+                WHERE tj.status = 'SUCCESS'
+                  AND ((tj.kind = 'PAYMENT' AND tj.to_user_id = q.merchant_id) OR (tj.kind = 'REFUND' AND tj.from_user_id = q.merchant_id))
+            )
+            INSERT INTO merchant_hourly_rollups (
+                merchant_id, hour, method, payment_count, gross, refund_count, refunds,
+                fees, gst, net, unsettled, settling, settled
+            )
+            SELECT
+                merchant_id,
+                hour,
+                method,
+                COUNT(*) FILTER (WHERE kind = 'PAYMENT')::INT,
+                COALESCE(SUM(amount) FILTER (WHERE kind = 'PAYMENT'), 0)::BIGINT,
+                COUNT(*) FILTER (WHERE kind = 'REFUND')::INT,
+                COALESCE(SUM(amount) FILTER (WHERE kind = 'REFUND'), 0)::BIGINT,
+                SUM(fee)::BIGINT,
+                SUM(gst)::BIGINT,
+                SUM(net)::BIGINT,
+                COALESCE(SUM(net) FILTER (WHERE batch_status IS NULL), 0)::BIGINT,
+                COALESCE(SUM(net) FILTER (WHERE batch_status <> 'PAID'), 0)::BIGINT,
+                COALESCE(SUM(net) FILTER (WHERE batch_status = 'PAID'), 0)::BIGINT
+            FROM moves
+            GROUP BY merchant_id, hour, method
+            "#,
+            &merchant_ids,
+            &hours
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+
+        counter!("report_rollup_hours_total", hours.len() as u64);
+        Ok(RollupRunReport { hours: hours.len(), rows: inserted })
+    }
+
+    /// Queues every hour the merchant has journal rows in, e.g. to backfill
+    /// history recorded before rollups existed. Returns the hours queued.
+    #[instrument(skip(self))]
+    pub async fn rebuild(&self, merchant_id: Uuid) -> Result<u64, ReportError> {
+        let queued = sqlx::query!(
+            r#"
+            INSERT INTO merchant_rollup_queue (merchant_id, hour)
+            SELECT DISTINCT merchant_id, date_trunc('hour', created_at AT TIME ZONE 'Asia/Kolkata')
+            FROM transaction_journal
+            WHERE merchant_id = $1
+            ON CONFLICT DO NOTHING
+            "#,
+            merchant_id
+        )
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+
+        info!(queued, "Merchant rollups queued for rebuild");
+        Ok(queued)
+    }
+}
+
+fn totals(rows: &[SalesReportRow], period_start: chrono::NaiveDateTime) -> SalesReportRow {
+    let sum = |f: fn(&SalesReportRow) -> i64| rows.iter().map(f).sum::<i64>();
+    let (unsettled, settling, settled) = (sum(|r| r.unsettled), sum(|r| r.settling), sum(|r| r.settled));
+
+    SalesReportRow {
+        period_start,
+        method: None,
+        payment_count: sum(|r| r.payment_count),
+        gross: sum(|r| r.gross),
+        refund_count: sum(|r| r.refund_count),
+        refunds: sum(|r| r.refunds),
+        fees: sum(|r| r.fees),
+        gst: sum(|r| r.gst),
+        net: sum(|r| r.net),
+        unsettled,
+        settling,
+        settled,
+        settlement_status: SettlementStatus::of(unsettled, settling, settled),
+    }
+}
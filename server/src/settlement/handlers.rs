@@ -0,0 +1,60 @@
+// src/settlement/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::Path,
+};
+use uuid::Uuid;
+use crate::merchant::models::MerchantError;
+use crate::middleware::admin::AdminOperator;
+use crate::settlement::{SettlementService, models::*};
+
+pub async fn list_settlements(
+    Extension(settlement_service): Extension<std::sync::Arc<SettlementService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+) -> Result<Json<Vec<SettlementBatch>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let batches = settlement_service.list(user_id, merchant_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(batches))
+}
+
+pub async fn get_settlement_report(
+    Extension(settlement_service): Extension<std::sync::Arc<SettlementService>>,
+    user_id: Uuid, // from JWT middleware
+    Path((merchant_id, batch_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<SettlementReport>, (http::StatusCode, Json<serde_json::Value>)> {
+    let report = settlement_service.report(user_id, merchant_id, batch_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(report))
+}
+
+/// Runs a FAILED batch again, including one whose automatic retries are
+/// exhausted (e.g. after the merchant fixed their bank account).
+pub async fn admin_retry_settlement(
+    Extension(settlement_service): Extension<std::sync::Arc<SettlementService>>,
+    Extension(operator): Extension<AdminOperator>, // from admin middleware
+    Path(batch_id): Path<Uuid>,
+) -> Result<Json<SettlementBatch>, (http::StatusCode, Json<serde_json::Value>)> {
+    tracing::info!(batch_id = %batch_id, operator = %operator.0, "Settlement retry requested");
+    let batch = settlement_service.pay_out(batch_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(batch))
+}
+
+fn error_response(e: SettlementError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        SettlementError::NotFound(_) | SettlementError::MerchantError(MerchantError::NotFound(_)) => http::StatusCode::NOT_FOUND,
+        SettlementError::InvalidStatus(_) => http::StatusCode::CONFLICT,
+        _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
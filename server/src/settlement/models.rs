@@ -0,0 +1,120 @@
+// src/settlement/models.rs
+
+use serde::Serialize;
+use sqlx::types::Uuid;
+use crate::fee::models::{FeeBreakdown, FeeError};
+use crate::merchant::models::MerchantError;
+use crate::wallet::WalletError;
+use crate::webhook::models::WebhookError;
+
+/// What a batch pays out. Fees and GST are charged per payment under the
+/// merchant's fee plan; refunds do not earn the fee back.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SettlementAmounts {
+    pub gross: i64,
+    pub refunds: i64,
+    pub fees: i64,
+    pub gst: i64,
+    pub net: i64,
+}
+
+impl SettlementAmounts {
+    pub fn compute(gross: i64, refunds: i64, payment_fees: &[FeeBreakdown]) -> Self {
+        let fees = payment_fees.iter().map(|f| f.fee).sum();
+        let gst = payment_fees.iter().map(|f| f.gst).sum();
+        Self {
+            gross,
+            refunds,
+            fees,
+            gst,
+            net: gross - refunds - fees - gst,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct SettlementBatch {
+    pub batch_id: Uuid,
+    pub merchant_id: Uuid,
+    pub cycle: String, // 'T0', 'T1'
+    pub cutoff_at: chrono::DateTime<chrono::Utc>,
+    pub payment_count: i32,
+    pub refund_count: i32,
+    pub gross: i64,
+    pub refunds: i64,
+    pub fees: i64,
+    pub gst: i64,
+    pub net: i64,
+    pub account_number: String,
+    pub ifsc: String,
+    pub status: String, // 'PENDING', 'PROCESSING', 'PAID', 'FAILED'
+    pub attempts: i32,
+    pub next_retry_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
+    pub utr: Option<String>,
+    pub paid_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct SettlementItem {
+    pub tx_id: Uuid,
+    pub kind: String, // 'PAYMENT' or 'REFUND'
+    pub amount: i64, // negative for refunds
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct SettlementAttempt {
+    pub attempt: i32,
+    pub status: String, // 'PAID', 'FAILED'
+    pub utr: Option<String>,
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Everything a merchant needs to reconcile one payout.
+#[derive(Debug, Serialize)]
+pub struct SettlementReport {
+    pub batch: SettlementBatch,
+    pub items: Vec<SettlementItem>,
+    pub attempts: Vec<SettlementAttempt>,
+}
+
+/// One payout request to the bank.
+#[derive(Debug, Clone, Serialize)]
+pub struct Payout {
+    pub reference: Uuid, // batch id
+    pub merchant_id: Uuid,
+    pub account_number: String,
+    pub ifsc: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SettlementError {
+    #[error("Settlement batch not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Settlement batch is {0}")]
+    InvalidStatus(String),
+
+    #[error("Merchant error: {0}")]
+    MerchantError(#[from] MerchantError),
+
+    #[error("Wallet error: {0}")]
+    WalletError(#[from] WalletError),
+
+    #[error("Fee error: {0}")]
+    FeeError(#[from] FeeError),
+
+    #[error("Ledger error: {0}")]
+    LedgerError(#[from] crate::ledger::LedgerError),
+
+    #[error("Webhook error: {0}")]
+    WebhookError(#[from] WebhookError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+}
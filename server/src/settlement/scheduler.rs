@@ -0,0 +1,91 @@
+// src/settlement/scheduler.rs
+
+use crate::settlement::SettlementService;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn, error};
+use metrics::counter;
+
+/// Background task that batches every active merchant's settled-but-unpaid
+/// money and pushes pending, due-for-retry and stale PROCESSING batches to
+/// the bank. Running it more often than once a day is harmless for T1
+/// merchants: nothing new falls before their cutoff until the day turns.
+pub struct SettlementScheduler {
+    db: PgPool,
+    settlement_service: Arc<SettlementService>,
+    interval: Duration,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SettlementRunReport {
+    pub batches: u32,
+    pub paid: u32,
+    pub failed: u32,
+    pub errors: u32,
+}
+
+impl SettlementScheduler {
+    pub fn new(db: PgPool, settlement_service: Arc<SettlementService>, interval: Duration) -> Self {
+        Self {
+            db,
+            settlement_service,
+            interval,
+        }
+    }
+
+    pub async fn run(&self) {
+        info!(interval_secs = self.interval.as_secs(), "Settlement scheduler started");
+        loop {
+            tokio::time::sleep(self.interval).await;
+            if let Err(e) = self.run_once().await {
+                error!(error = %e, "Settlement run failed");
+            }
+        }
+    }
+
+    pub async fn run_once(&self) -> Result<SettlementRunReport, sqlx::Error> {
+        let mut report = SettlementRunReport::default();
+        let now = chrono::Utc::now();
+
+        let merchants = sqlx::query_scalar!("SELECT merchant_id FROM merchants WHERE status = 'ACTIVE' ORDER BY merchant_id")
+            .fetch_all(&self.db)
+            .await?;
+
+        for merchant_id in merchants {
+            match self.settlement_service.create_batch(merchant_id, now).await {
+                Ok(Some(_)) => report.batches += 1,
+                Ok(None) => {}
+                Err(e) => {
+                    report.errors += 1;
+                    counter!("settlement_scheduler_errors_total", 1);
+                    warn!(merchant_id = %merchant_id, error = %e, "Could not create settlement batch");
+                }
+            }
+        }
+
+        let due = match self.settlement_service.due_payouts().await {
+            Ok(due) => due,
+            Err(e) => {
+                error!(error = %e, "Could not list due settlement payouts");
+                Vec::new()
+            }
+        };
+        for batch_id in due {
+            match self.settlement_service.pay_out(batch_id).await {
+                Ok(batch) if batch.status == "PAID" => report.paid += 1,
+                Ok(_) => report.failed += 1,
+                Err(e) => {
+                    report.errors += 1;
+                    counter!("settlement_scheduler_errors_total", 1);
+                    warn!(batch_id = %batch_id, error = %e, "Could not pay out settlement batch");
+                }
+            }
+        }
+
+        if report != SettlementRunReport::default() {
+            info!(?report, "Settlement run finished");
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,540 @@
+// src/settlement/service.rs
+
+use crate::fee::{FeeService, models::FeeBreakdown};
+use crate::ledger::{LedgerService, models::{Posting, SystemAccount}};
+use crate::merchant::{MerchantService, models::SettlementCycle};
+use crate::settlement::models::*;
+use crate::wallet::WalletService;
+use crate::webhook::WebhookService;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn, instrument};
+use uuid::Uuid;
+use metrics::counter;
+
+/// Sends a payout to the merchant's bank account.
+#[async_trait::async_trait]
+pub trait PayoutClient: Send + Sync {
+    /// Returns the bank's UTR, or the bank's reason for rejecting the payout.
+    async fn transfer(&self, payout: &Payout) -> Result<String, String>;
+}
+
+/// A PROCESSING batch whose worker has not recorded an outcome for this long
+/// is claimed again. Longer than `BANK_TIMEOUT`, so a live call has ended.
+const CLAIM_LEASE: Duration = Duration::from_secs(5 * 60);
+const BANK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pays out through the bank's `/bank/transfer` endpoint.
+pub struct BankPayoutClient {
+    http: reqwest::Client,
+    base_url: String, // e.g. http://localhost:3002
+    from_account: String, // platform's nodal account
+}
+
+impl BankPayoutClient {
+    pub fn new(base_url: String, from_account: String) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(BANK_TIMEOUT)
+                .build()
+                .expect("static reqwest config"),
+            base_url,
+            from_account,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PayoutClient for BankPayoutClient {
+    async fn transfer(&self, payout: &Payout) -> Result<String, String> {
+        let resp = self.http
+            .post(format!("{}/bank/transfer", self.base_url))
+            .json(&serde_json::json!({
+                "from_account": self.from_account,
+                "to_user_id": payout.merchant_id,
+                "to_account": payout.account_number,
+                "ifsc": payout.ifsc,
+                "amount": payout.amount,
+                "reference": payout.reference,
+            }))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !resp.status().is_success() {
+            return Err(format!("bank returned {}", resp.status()));
+        }
+        let body: crate::bank::models::BankTransferResponse = resp.json().await.map_err(|e| e.to_string())?;
+        if body.status != "success" || body.utr.is_empty() {
+            return Err(body.message);
+        }
+        Ok(body.utr)
+    }
+}
+
+/// How often a payout the bank rejected is retried. The delay doubles on
+/// every attempt; after `max_attempts` the batch waits for an operator.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub backoff: Duration, // before the first retry
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            backoff: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn delay_after(&self, attempt: i32) -> Duration {
+        self.backoff * 2u32.saturating_pow((attempt - 1).max(0) as u32)
+    }
+}
+
+/// Moves money merchants have been paid from their wallets to their bank
+/// accounts. A batch first debits the merchant wallet (net to suspense,
+/// fees and GST to the fee account) and is then paid out; the suspense leg
+/// moves to the bank float once the bank returns a UTR.
+pub struct SettlementService {
+    db: PgPool,
+    wallet_service: Arc<WalletService>,
+    merchant_service: Arc<MerchantService>,
+    fee_service: Arc<FeeService>,
+    ledger: LedgerService,
+    bank: Arc<dyn PayoutClient>,
+    retry: RetryPolicy,
+    webhook_service: Option<Arc<WebhookService>>,
+}
+
+impl SettlementService {
+    pub fn new(
+        db: PgPool,
+        wallet_service: Arc<WalletService>,
+        merchant_service: Arc<MerchantService>,
+        fee_service: Arc<FeeService>,
+        bank: Arc<dyn PayoutClient>,
+        retry: RetryPolicy,
+    ) -> Self {
+        Self {
+            ledger: LedgerService::new(db.clone()),
+            db,
+            wallet_service,
+            merchant_service,
+            fee_service,
+            bank,
+            retry,
+            webhook_service: None,
+        }
+    }
+
+    /// Tell merchants when a payout lands, or finally gives up.
+    pub fn with_webhooks(mut self, webhook_service: Arc<WebhookService>) -> Self {
+        self.webhook_service = Some(webhook_service);
+        self
+    }
+
+    /// Collects the merchant's unsettled payments and refunds made before
+    /// its cycle's cutoff into a new batch. Returns nothing when there is
+    /// nothing to pay out; refunds exceeding payments carry over.
+    #[instrument(skip(self), fields(merchant_id = %merchant_id))]
+    pub async fn create_batch(
+        &self,
+        merchant_id: Uuid,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<SettlementBatch>, SettlementError> {
+        let mut tx = self.db.begin().await?;
+
+        // Step 1: Lock the merchant so two runs cannot batch the same payments
+        let merchant = sqlx::query!(
+            r#"
+            SELECT settlement_cycle, settlement_account_number, settlement_ifsc
+            FROM merchants
+            WHERE merchant_id = $1 AND status = 'ACTIVE'
+            FOR UPDATE
+            "#,
+            merchant_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(merchant) = merchant else {
+            return Ok(None);
+        };
+        let cycle = SettlementCycle::from_db(&merchant.settlement_cycle).unwrap_or(SettlementCycle::T1);
+        let cutoff = cycle.cutoff(now);
+
+        // Step 2: Unsettled money in and out of the merchant wallet
+        let items = sqlx::query!(
+            r#"
+            SELECT tj.tx_id,
+                   CASE WHEN tj.kind = 'REFUND' THEN -tj.amount ELSE tj.amount END AS "amount!"
+            FROM transaction_journal tj
+            WHERE tj.merchant_id = $1
+              AND tj.status = 'SUCCESS'
+              AND tj.created_at < $2
+              AND ((tj.kind = 'PAYMENT' AND tj.to_user_id = $1) OR (tj.kind = 'REFUND' AND tj.from_user_id = $1))
+              AND NOT EXISTS (SELECT 1 FROM settlement_items si WHERE si.tx_id = tj.tx_id)
+            "#,
+            merchant_id,
+            cutoff
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let (payments, refunds): (Vec<_>, Vec<_>) = items.iter().partition(|i| i.amount > 0);
+        let gross: i64 = payments.iter().map(|i| i.amount).sum();
+        let refunded: i64 = refunds.iter().map(|i| -i.amount).sum();
+        let schedule = self.fee_service.schedule_for(merchant_id).await?;
+        let payment_fees: Vec<FeeBreakdown> = payments.iter().map(|p| schedule.quote(p.amount)).collect();
+        let amounts = SettlementAmounts::compute(gross, refunded, &payment_fees);
+        if amounts.net <= 0 {
+            return Ok(None);
+        }
+
+        // Step 3: Batch and its items
+        let batch = sqlx::query_as!(
+            SettlementBatch,
+            r#"
+            INSERT INTO settlement_batches (
+                merchant_id, cycle, cutoff_at, payment_count, refund_count,
+                gross, refunds, fees, gst, net, account_number, ifsc
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING batch_id, merchant_id, cycle, cutoff_at, payment_count, refund_count,
+                      gross, refunds, fees, gst, net, account_number, ifsc, status, attempts,
+                      next_retry_at, last_error, utr, paid_at, created_at, updated_at
+            "#,
+            merchant_id,
+            cycle.as_str(),
+            cutoff,
+            payments.len() as i32,
+            refunds.len() as i32,
+            amounts.gross,
+            amounts.refunds,
+            amounts.fees,
+            amounts.gst,
+            amounts.net,
+            merchant.settlement_account_number,
+            merchant.settlement_ifsc
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let tx_ids: Vec<Uuid> = items.iter().map(|i| i.tx_id).collect();
+        let amounts_by_tx: Vec<i64> = items.iter().map(|i| i.amount).collect();
+        sqlx::query!(
+            r#"
+            INSERT INTO settlement_items (tx_id, batch_id, amount)
+            SELECT tx_id, $1, amount FROM UNNEST($2::UUID[], $3::BIGINT[]) AS t (tx_id, amount)
+            "#,
+            batch.batch_id,
+            &tx_ids,
+            &amounts_by_tx
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Fees are posted with the batch; monthly invoices are built from these rows
+        let fee_tx_ids: Vec<Uuid> = payments.iter().map(|p| p.tx_id).collect();
+        let fee_rule_ids: Vec<Option<i64>> = payment_fees.iter().map(|f| f.rule_id).collect();
+        let fee_amounts: Vec<i64> = payment_fees.iter().map(|f| f.fee).collect();
+        let fee_gst: Vec<i64> = payment_fees.iter().map(|f| f.gst).collect();
+        sqlx::query!(
+            r#"
+            INSERT INTO transaction_fees (tx_id, merchant_id, batch_id, plan_code, rule_id, fee, gst)
+            SELECT tx_id, $1, $2, $3, rule_id, fee, gst
+            FROM UNNEST($4::UUID[], $5::BIGINT[], $6::BIGINT[], $7::BIGINT[]) AS t (tx_id, rule_id, fee, gst)
+            "#,
+            merchant_id,
+            batch.batch_id,
+            schedule.plan_code,
+            &fee_tx_ids,
+            &fee_rule_ids,
+            &fee_amounts,
+            &fee_gst
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Step 4: Take the money out of the wallet. Refunds already left it.
+        self.wallet_service.debit_in(
+            &mut tx,
+            &merchant_id,
+            amounts.gross - amounts.refunds,
+            &format!("settlement:{}", batch.batch_id),
+            "settlement",
+            &[
+                Posting::credit(SystemAccount::Suspense.code(), amounts.net),
+                Posting::credit(SystemAccount::Fees.code(), amounts.fees + amounts.gst),
+            ],
+        ).await?;
+
+        tx.commit().await?;
+
+        counter!("settlement_batches_total", 1, "cycle" => cycle.as_str());
+        info!(batch_id = %batch.batch_id, net = batch.net, items = tx_ids.len(), "Settlement batch created");
+        Ok(Some(batch))
+    }
+
+    /// Sends a PENDING or FAILED batch to the bank and records the outcome.
+    /// A batch left PROCESSING by a worker that died mid-call is sent again
+    /// once its claim is older than `CLAIM_LEASE`.
+    #[instrument(skip(self), fields(batch_id = %batch_id))]
+    pub async fn pay_out(&self, batch_id: Uuid) -> Result<SettlementBatch, SettlementError> {
+        // Step 1: Claim the batch so only one worker talks to the bank
+        let batch = sqlx::query_as!(
+            SettlementBatch,
+            r#"
+            UPDATE settlement_batches
+            SET status = 'PROCESSING', attempts = attempts + 1, next_retry_at = NULL, updated_at = NOW()
+            WHERE batch_id = $1
+              AND (status IN ('PENDING', 'FAILED')
+                   OR (status = 'PROCESSING' AND updated_at < NOW() - make_interval(secs => $2)))
+            RETURNING batch_id, merchant_id, cycle, cutoff_at, payment_count, refund_count,
+                      gross, refunds, fees, gst, net, account_number, ifsc, status, attempts,
+                      next_retry_at, last_error, utr, paid_at, created_at, updated_at
+            "#,
+            batch_id,
+            CLAIM_LEASE.as_secs_f64()
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let Some(batch) = batch else {
+            return Err(self.not_actionable(batch_id).await);
+        };
+
+        // Step 2: Bank call. The batch id goes along as the reference; the
+        // bank pays a reference once and answers a repeat with the original
+        // UTR, so re-sending after a lost response does not pay twice.
+        let result = self.bank.transfer(&Payout {
+            reference: batch.batch_id,
+            merchant_id: batch.merchant_id,
+            account_number: batch.account_number.clone(),
+            ifsc: batch.ifsc.clone(),
+            amount: batch.net,
+        }).await;
+
+        // Step 3: Record the attempt, unless the claim expired and another
+        // worker has since taken the batch over
+        let mut tx = self.db.begin().await?;
+        let (status, utr, error) = match &result {
+            Ok(utr) => ("PAID", Some(utr.as_str()), None),
+            Err(e) => ("FAILED", None, Some(e.as_str())),
+        };
+        sqlx::query!(
+            r#"
+            INSERT INTO settlement_attempts (batch_id, attempt, status, utr, error)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            batch_id,
+            batch.attempts,
+            status,
+            utr,
+            error
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let updated = match &result {
+            Ok(utr) => {
+                let updated = sqlx::query_as!(
+                    SettlementBatch,
+                    r#"
+                    UPDATE settlement_batches
+                    SET status = 'PAID', utr = $3, last_error = NULL, paid_at = NOW(), updated_at = NOW()
+                    WHERE batch_id = $1 AND status = 'PROCESSING' AND attempts = $2
+                    RETURNING batch_id, merchant_id, cycle, cutoff_at, payment_count, refund_count,
+                              gross, refunds, fees, gst, net, account_number, ifsc, status, attempts,
+                              next_retry_at, last_error, utr, paid_at, created_at, updated_at
+                    "#,
+                    batch_id,
+                    batch.attempts,
+                    utr
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+
+                if updated.is_some() {
+                    self.ledger.post_entry(
+                        &mut tx,
+                        &format!("settlement:{}:paid", batch_id),
+                        "settlement_payout",
+                        &[
+                            Posting::debit(SystemAccount::Suspense.code(), batch.net),
+                            Posting::credit(SystemAccount::Float.code(), batch.net),
+                        ],
+                    ).await?;
+                }
+                updated
+            }
+            Err(e) => {
+                let next_retry_at = (batch.attempts < self.retry.max_attempts)
+                    .then(|| chrono::Utc::now() + chrono::Duration::seconds(self.retry.delay_after(batch.attempts).as_secs() as i64));
+
+                sqlx::query_as!(
+                    SettlementBatch,
+                    r#"
+                    UPDATE settlement_batches
+                    SET status = 'FAILED', last_error = $3, next_retry_at = $4, updated_at = NOW()
+                    WHERE batch_id = $1 AND status = 'PROCESSING' AND attempts = $2
+                    RETURNING batch_id, merchant_id, cycle, cutoff_at, payment_count, refund_count,
+                              gross, refunds, fees, gst, net, account_number, ifsc, status, attempts,
+                              next_retry_at, last_error, utr, paid_at, created_at, updated_at
+                    "#,
+                    batch_id,
+                    batch.attempts,
+                    e,
+                    next_retry_at
+                )
+                .fetch_optional(&mut *tx)
+                .await?
+            }
+        };
+
+        let Some(updated) = updated else {
+            drop(tx);
+            warn!(attempt = batch.attempts, "Settlement claim expired before the outcome was recorded");
+            return Err(self.not_actionable(batch_id).await);
+        };
+
+        // Retries in between are ours to worry about; merchants hear the
+        // outcome, queued with it so one is never recorded without the other
+        let event_type = match (&result, updated.next_retry_at) {
+            (Ok(_), _) => Some("settlement.paid"),
+            (Err(_), None) => Some("settlement.failed"),
+            (Err(_), Some(_)) => None,
+        };
+        if let (Some(event_type), Some(webhooks)) = (event_type, &self.webhook_service) {
+            let data = serde_json::to_value(&updated).unwrap_or_default();
+            webhooks.emit_in(&mut tx, updated.merchant_id, event_type, data).await?;
+        }
+
+        tx.commit().await?;
+
+        counter!("settlement_payouts_total", 1, "status" => status);
+        match &result {
+            Ok(utr) => info!(attempt = batch.attempts, utr = %utr, "Settlement paid out"),
+            Err(e) => warn!(attempt = batch.attempts, error = %e, retry_at = ?updated.next_retry_at, "Settlement payout failed"),
+        }
+        Ok(updated)
+    }
+
+    /// Batches still waiting for the bank: new ones, FAILED ones whose
+    /// retry is due, and PROCESSING ones whose claim has expired.
+    pub async fn due_payouts(&self) -> Result<Vec<Uuid>, SettlementError> {
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT batch_id
+            FROM settlement_batches
+            WHERE status = 'PENDING'
+               OR (status = 'FAILED' AND next_retry_at <= NOW())
+               OR (status = 'PROCESSING' AND updated_at < NOW() - make_interval(secs => $1))
+            ORDER BY created_at
+            LIMIT 100
+            "#,
+            CLAIM_LEASE.as_secs_f64()
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(due)
+    }
+
+    pub async fn list(&self, owner_user_id: Uuid, merchant_id: Uuid) -> Result<Vec<SettlementBatch>, SettlementError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let batches = sqlx::query_as!(
+            SettlementBatch,
+            r#"
+            SELECT batch_id, merchant_id, cycle, cutoff_at, payment_count, refund_count,
+                   gross, refunds, fees, gst, net, account_number, ifsc, status, attempts,
+                   next_retry_at, last_error, utr, paid_at, created_at, updated_at
+            FROM settlement_batches
+            WHERE merchant_id = $1
+            ORDER BY created_at DESC
+            LIMIT 100
+            "#,
+            merchant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(batches)
+    }
+
+    /// One batch with the payments and refunds it covers and every payout
+    /// attempt, for the merchant's reconciliation.
+    pub async fn report(
+        &self,
+        owner_user_id: Uuid,
+        merchant_id: Uuid,
+        batch_id: Uuid,
+    ) -> Result<SettlementReport, SettlementError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let batch = self.load(batch_id).await?;
+        if batch.merchant_id != merchant_id {
+            return Err(SettlementError::NotFound(batch_id));
+        }
+
+        let items = sqlx::query_as!(
+            SettlementItem,
+            r#"
+            SELECT si.tx_id, tj.kind, si.amount, tj.created_at
+            FROM settlement_items si
+            JOIN transaction_journal tj ON tj.tx_id = si.tx_id
+            WHERE si.batch_id = $1
+            ORDER BY tj.created_at
+            "#,
+            batch_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let attempts = sqlx::query_as!(
+            SettlementAttempt,
+            r#"
+            SELECT attempt, status, utr, error, created_at
+            FROM settlement_attempts
+            WHERE batch_id = $1
+            ORDER BY attempt
+            "#,
+            batch_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(SettlementReport { batch, items, attempts })
+    }
+
+    async fn load(&self, batch_id: Uuid) -> Result<SettlementBatch, SettlementError> {
+        sqlx::query_as!(
+            SettlementBatch,
+            r#"
+            SELECT batch_id, merchant_id, cycle, cutoff_at, payment_count, refund_count,
+                   gross, refunds, fees, gst, net, account_number, ifsc, status, attempts,
+                   next_retry_at, last_error, utr, paid_at, created_at, updated_at
+            FROM settlement_batches
+            WHERE batch_id = $1
+            "#,
+            batch_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(SettlementError::NotFound(batch_id))
+    }
+
+    /// Explains why a guarded status update matched no row.
+    async fn not_actionable(&self, batch_id: Uuid) -> SettlementError {
+        match self.load(batch_id).await {
+            Ok(batch) => SettlementError::InvalidStatus(batch.status),
+            Err(e) => e,
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// src/split/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::Path,
+};
+use uuid::Uuid;
+use crate::split::{SplitService, models::*};
+use crate::collect::models::CollectError;
+use crate::payment::models::PaymentError;
+
+pub async fn create_split(
+    Extension(split_service): Extension<std::sync::Arc<SplitService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<CreateSplitRequest>,
+) -> Result<Json<SplitDetail>, (http::StatusCode, Json<serde_json::Value>)> {
+    let split = split_service.create(user_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(split))
+}
+
+pub async fn list_splits(
+    Extension(split_service): Extension<std::sync::Arc<SplitService>>,
+    user_id: Uuid, // from JWT middleware
+) -> Result<Json<Vec<SplitDetail>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let splits = split_service.list(user_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(splits))
+}
+
+pub async fn get_split(
+    Extension(split_service): Extension<std::sync::Arc<SplitService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(split_id): Path<Uuid>,
+) -> Result<Json<SplitDetail>, (http::StatusCode, Json<serde_json::Value>)> {
+    let split = split_service.get(user_id, split_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(split))
+}
+
+pub async fn remind_split(
+    Extension(split_service): Extension<std::sync::Arc<SplitService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(split_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (http::StatusCode, Json<serde_json::Value>)> {
+    let reminded = split_service.remind(user_id, split_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(serde_json::json!({ "split_id": split_id, "reminded": reminded })))
+}
+
+fn error_response(e: SplitError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        SplitError::NotFound(_) | SplitError::PaymentError(PaymentError::UserNotFound(_)) => http::StatusCode::NOT_FOUND,
+        SplitError::DatabaseError(_) | SplitError::CollectError(CollectError::DatabaseError(_)) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        SplitError::PaymentError(ref p) | SplitError::CollectError(CollectError::PaymentError(ref p)) if p.is_internal() => {
+            http::StatusCode::INTERNAL_SERVER_ERROR
+        }
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
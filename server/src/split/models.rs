@@ -0,0 +1,106 @@
+// src/split/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use sqlx::types::Uuid;
+use crate::collect::models::CollectError;
+use crate::payment::models::PaymentError;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SplitType {
+    Equal,
+    Amount,
+    Percentage,
+}
+
+impl SplitType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SplitType::Equal => "EQUAL",
+            SplitType::Amount => "AMOUNT",
+            SplitType::Percentage => "PERCENTAGE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SplitParticipantInput {
+    #[validate(regex = "MOBILE_REGEX")]
+    pub mobile: String,
+
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: Option<u64>, // required for AMOUNT splits
+
+    #[validate(range(min = 1, max = 10_000))]
+    pub percent_bps: Option<u32>, // required for PERCENTAGE splits; 100% = 10_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateSplitRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub description: String,
+
+    #[validate(range(min = 1, max = 10_000_000))]
+    pub total_amount: u64, // whole bill in paise, including the creator's part
+
+    pub split_type: SplitType,
+
+    pub include_self: Option<bool>, // EQUAL only: count the creator as a share; defaults to true
+
+    #[validate(length(min = 1, max = 20))]
+    pub participants: Vec<SplitParticipantInput>,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct Split {
+    pub split_id: Uuid,
+    pub creator_id: Uuid,
+    pub description: String,
+    pub total_amount: i64,
+    pub split_type: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct SplitShare {
+    pub user_id: Uuid,
+    pub share: i64,
+    pub collect_request_id: Option<Uuid>,
+    pub status: String, // collect request status, or 'UNSENT'
+    pub tx_id: Option<Uuid>,
+    pub reminder_count: i32,
+    pub last_reminded_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SplitDetail {
+    #[serde(flatten)]
+    pub split: Split,
+    pub status: String, // 'OPEN' until every share is paid, then 'SETTLED'
+    pub paid_amount: i64,
+    pub participants: Vec<SplitShare>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SplitError {
+    #[error("Split not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Invalid shares: {0}")]
+    InvalidShares(String),
+
+    #[error("Collect request failed: {0}")]
+    CollectError(#[from] CollectError),
+
+    #[error("Payment error: {0}")]
+    PaymentError(#[from] PaymentError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+const MOBILE_REGEX: &str = r"^\+91[6-9]\d{9}$";
@@ -0,0 +1,315 @@
+// src/split/service.rs
+
+use crate::split::models::*;
+use crate::collect::CollectService;
+use crate::payment::PaymentService;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{info, warn, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::counter;
+
+const MAX_SHARE: i64 = 500_000; // a share is paid as one payment
+const REMINDER_COOLDOWN_SECS: i64 = 60 * 60;
+
+/// Splits a bill across participants. Each share is raised as a collect
+/// request, so paying a share is an ordinary approved payment; the split
+/// reads its state from those requests rather than keeping its own copy.
+pub struct SplitService {
+    db: PgPool,
+    collect_service: Arc<CollectService>,
+    payment_service: Arc<PaymentService>,
+}
+
+impl SplitService {
+    pub fn new(db: PgPool, collect_service: Arc<CollectService>, payment_service: Arc<PaymentService>) -> Self {
+        Self {
+            db,
+            collect_service,
+            payment_service,
+        }
+    }
+
+    #[instrument(skip(self, req), fields(creator_id = %creator_id, total = req.total_amount))]
+    pub async fn create(&self, creator_id: Uuid, req: CreateSplitRequest) -> Result<SplitDetail, SplitError> {
+        req.validate()?;
+        for p in &req.participants {
+            p.validate()?;
+        }
+
+        // Step 1: Work out the shares and who they belong to
+        let shares = compute_shares(
+            req.total_amount as i64,
+            req.split_type,
+            req.include_self.unwrap_or(true),
+            &req.participants,
+        )?;
+
+        let mut participants = Vec::with_capacity(req.participants.len());
+        for (p, share) in req.participants.iter().zip(shares) {
+            let user_id = self.payment_service.resolve_mobile(&p.mobile).await?;
+            if user_id == creator_id {
+                return Err(SplitError::InvalidShares("creator cannot be a participant".to_string()));
+            }
+            if participants.iter().any(|(id, _)| *id == user_id) {
+                return Err(SplitError::InvalidShares(format!("{} listed twice", p.mobile)));
+            }
+            participants.push((user_id, share));
+        }
+
+        // Step 2: Store the split
+        let mut tx = self.db.begin().await?;
+        let split = sqlx::query_as!(
+            Split,
+            r#"
+            INSERT INTO splits (creator_id, description, total_amount, split_type)
+            VALUES ($1, $2, $3, $4)
+            RETURNING split_id, creator_id, description, total_amount, split_type, created_at
+            "#,
+            creator_id,
+            req.description,
+            req.total_amount as i64,
+            req.split_type.as_str()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (user_id, share) in &participants {
+            sqlx::query!(
+                "INSERT INTO split_participants (split_id, user_id, share) VALUES ($1, $2, $3)",
+                split.split_id,
+                user_id,
+                share
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        // Step 3: Ask everyone for their share. A failure here leaves the share
+        // UNSENT; the next reminder raises it.
+        for (user_id, share) in &participants {
+            if let Err(e) = self.request_share(&split, *user_id, *share).await {
+                warn!(split_id = %split.split_id, user_id = %user_id, error = %e, "Could not raise split share");
+            }
+        }
+
+        counter!("splits_total", 1, "type" => req.split_type.as_str());
+        info!(split_id = %split.split_id, participants = participants.len(), "Split created");
+        self.detail(split).await
+    }
+
+    /// Split as seen by its creator or one of its participants.
+    pub async fn get(&self, user_id: Uuid, split_id: Uuid) -> Result<SplitDetail, SplitError> {
+        let split = sqlx::query_as!(
+            Split,
+            r#"
+            SELECT s.split_id, s.creator_id, s.description, s.total_amount, s.split_type, s.created_at
+            FROM splits s
+            WHERE s.split_id = $1
+              AND (s.creator_id = $2 OR EXISTS (
+                  SELECT 1 FROM split_participants sp WHERE sp.split_id = s.split_id AND sp.user_id = $2
+              ))
+            "#,
+            split_id,
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(SplitError::NotFound(split_id))?;
+
+        self.detail(split).await
+    }
+
+    /// Splits the user created or takes part in, newest first.
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<SplitDetail>, SplitError> {
+        let splits = sqlx::query_as!(
+            Split,
+            r#"
+            SELECT s.split_id, s.creator_id, s.description, s.total_amount, s.split_type, s.created_at
+            FROM splits s
+            WHERE s.creator_id = $1
+               OR EXISTS (SELECT 1 FROM split_participants sp WHERE sp.split_id = s.split_id AND sp.user_id = $1)
+            ORDER BY s.created_at DESC
+            LIMIT 50
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut details = Vec::with_capacity(splits.len());
+        for split in splits {
+            details.push(self.detail(split).await?);
+        }
+        Ok(details)
+    }
+
+    /// Nudges everyone who has not paid yet. Pending requests are re-sent;
+    /// declined, expired or never-sent shares get a fresh collect request.
+    /// Each participant is reminded at most once an hour. Returns how many
+    /// were reminded.
+    #[instrument(skip(self))]
+    pub async fn remind(&self, creator_id: Uuid, split_id: Uuid) -> Result<u32, SplitError> {
+        let split = sqlx::query_as!(
+            Split,
+            r#"
+            SELECT split_id, creator_id, description, total_amount, split_type, created_at
+            FROM splits
+            WHERE split_id = $1 AND creator_id = $2
+            "#,
+            split_id,
+            creator_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(SplitError::NotFound(split_id))?;
+
+        let due = self.shares(split_id).await?.into_iter().filter(|s| {
+            s.status != "PAID"
+                && s.status != "APPROVED" // payment in flight
+                && s.last_reminded_at.map_or(true, |at| {
+                    chrono::Utc::now() - at > chrono::Duration::seconds(REMINDER_COOLDOWN_SECS)
+                })
+        });
+
+        let mut reminded = 0;
+        for share in due {
+            match (share.status.as_str(), share.collect_request_id) {
+                // Still open: only re-send it. If it was answered meanwhile,
+                // the next reminder sees the new status.
+                ("PENDING", Some(request_id)) => {
+                    if let Err(e) = self.collect_service.remind(creator_id, request_id).await {
+                        warn!(split_id = %split_id, user_id = %share.user_id, error = %e, "Could not remind split share");
+                        continue;
+                    }
+                }
+                // Declined, expired or never sent: ask again
+                ("DECLINED" | "EXPIRED" | "UNSENT", _) => {
+                    if let Err(e) = self.request_share(&split, share.user_id, share.share).await {
+                        warn!(split_id = %split_id, user_id = %share.user_id, error = %e, "Could not re-raise split share");
+                        continue;
+                    }
+                }
+                _ => continue,
+            }
+
+            sqlx::query!(
+                r#"
+                UPDATE split_participants
+                SET reminder_count = reminder_count + 1, last_reminded_at = NOW()
+                WHERE split_id = $1 AND user_id = $2
+                "#,
+                split_id,
+                share.user_id
+            )
+            .execute(&self.db)
+            .await?;
+            reminded += 1;
+        }
+
+        counter!("split_reminders_total", reminded as u64);
+        Ok(reminded)
+    }
+
+    /// Raises a collect request for one share and links it to the participant.
+    async fn request_share(&self, split: &Split, user_id: Uuid, share: i64) -> Result<(), SplitError> {
+        let note: String = format!("Split: {}", split.description).chars().take(140).collect();
+        let request = self.collect_service
+            .create_for(split.creator_id, user_id, share as u64, Some(&note), None)
+            .await?;
+
+        sqlx::query!(
+            "UPDATE split_participants SET collect_request_id = $1 WHERE split_id = $2 AND user_id = $3",
+            request.request_id,
+            split.split_id,
+            user_id
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn shares(&self, split_id: Uuid) -> Result<Vec<SplitShare>, SplitError> {
+        let shares = sqlx::query_as!(
+            SplitShare,
+            r#"
+            SELECT sp.user_id, sp.share, sp.collect_request_id,
+                   CASE WHEN c.status = 'PENDING' AND c.expires_at <= NOW() THEN 'EXPIRED'
+                        ELSE COALESCE(c.status, 'UNSENT') END AS "status!",
+                   c.tx_id AS "tx_id?",
+                   sp.reminder_count, sp.last_reminded_at
+            FROM split_participants sp
+            LEFT JOIN collect_requests c ON c.request_id = sp.collect_request_id
+            WHERE sp.split_id = $1
+            ORDER BY sp.user_id
+            "#,
+            split_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(shares)
+    }
+
+    async fn detail(&self, split: Split) -> Result<SplitDetail, SplitError> {
+        let participants = self.shares(split.split_id).await?;
+        let paid_amount = participants.iter().filter(|s| s.status == "PAID").map(|s| s.share).sum();
+        let status = if participants.iter().all(|s| s.status == "PAID") { "SETTLED" } else { "OPEN" };
+
+        Ok(SplitDetail {
+            split,
+            status: status.to_string(),
+            paid_amount,
+            participants,
+        })
+    }
+}
+
+/// Shares owed by each participant, in input order. An EQUAL split gives
+/// its rounding leftover to the first participants, one paisa each, so the
+/// shares (plus the creator's own, if included) add up to the total. For
+/// PERCENTAGE splits the leftover stays with the creator.
+pub fn compute_shares(
+    total: i64,
+    split_type: SplitType,
+    include_self: bool,
+    participants: &[SplitParticipantInput],
+) -> Result<Vec<i64>, SplitError> {
+    let n = participants.len() as i64;
+    if n == 0 {
+        return Err(SplitError::InvalidShares("no participants".to_string()));
+    }
+
+    let shares: Vec<i64> = match split_type {
+        SplitType::Equal => {
+            // The creator's share is never larger than anyone else's, so
+            // at most `n` paise are left over
+            let parts = n + i64::from(include_self);
+            let (base, extra) = (total / parts, total % parts);
+            (0..n).map(|i| base + i64::from(i < extra)).collect()
+        }
+        SplitType::Amount => {
+            let amounts: Option<Vec<i64>> = participants.iter().map(|p| p.amount.map(|a| a as i64)).collect();
+            let amounts = amounts.ok_or_else(|| SplitError::InvalidShares("every participant needs an amount".to_string()))?;
+            if amounts.iter().sum::<i64>() > total {
+                return Err(SplitError::InvalidShares("shares add up to more than the total".to_string()));
+            }
+            amounts
+        }
+        SplitType::Percentage => {
+            let bps: Option<Vec<i64>> = participants.iter().map(|p| p.percent_bps.map(i64::from)).collect();
+            let bps = bps.ok_or_else(|| SplitError::InvalidShares("every participant needs a percentage".to_string()))?;
+            if bps.iter().sum::<i64>() > 10_000 {
+                return Err(SplitError::InvalidShares("percentages add up to more than 100%".to_string()));
+            }
+            bps.iter().map(|b| total * b / 10_000).collect()
+        }
+    };
+
+    if let Some(bad) = shares.iter().find(|s| **s < 1 || **s > MAX_SHARE) {
+        return Err(SplitError::InvalidShares(format!("share of {} paise is out of range", bad)));
+    }
+    Ok(shares)
+}
@@ -22,4 +22,17 @@ pub async fn get_transactions(
     }).collect();
 
     Ok(Json(masked))
-}
\ No newline at end of file
+}
+
+pub async fn get_transaction(
+    Extension(tx_service): Extension<Arc<TransactionService>>,
+    user_id: Uuid,
+    Path(tx_id): Path<Uuid>,
+) -> Result<Json<TransactionTimeline>, (StatusCode, Json<Value>)> {
+    let timeline = tx_service.get_timeline(user_id, tx_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({ "error": "Transaction not found" }))))?;
+
+    Ok(Json(timeline))
+}
@@ -6,6 +6,29 @@ pub struct TransactionItem {
     pub amount: i64,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub status: String,
+    pub kind: String, // "PAYMENT" or "REFUND"
+    pub original_tx_id: Option<Uuid>, // payment a refund belongs to
+    pub payment_type: String, // "P2P" or "MERCHANT"
+    pub merchant_id: Option<Uuid>,
     pub counterparty_mobile: Option<String>, // masked
     pub transaction_type: String, // "sent" or "received"
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TimelineEvent {
+    pub from_state: Option<String>, // None for the initial INITIATED event
+    pub to_state: String,
+    pub reason: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransactionTimeline {
+    pub tx_id: Uuid,
+    pub from_user_id: Uuid,
+    pub to_user_id: Uuid,
+    pub amount: i64,
+    pub status: String, // current state
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub events: Vec<TimelineEvent>,
+}
@@ -0,0 +1,175 @@
+// src/transaction/state.rs
+
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+/// Lifecycle of a payment as stored in `transaction_journal.status`. Every
+/// change is also appended to `payment_state_transitions`.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PaymentState {
+    Initiated,
+    Success,
+    Failed,
+    Reversed,
+}
+
+impl PaymentState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentState::Initiated => "INITIATED",
+            PaymentState::Success => "SUCCESS",
+            PaymentState::Failed => "FAILED",
+            PaymentState::Reversed => "REVERSED",
+        }
+    }
+
+    pub fn can_transition_to(&self, next: PaymentState) -> bool {
+        use PaymentState::*;
+        matches!(
+            (self, next),
+            (Initiated, Success)
+                | (Initiated, Failed)
+                | (Success, Reversed)
+        )
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransitionError {
+    #[error("Invalid payment transition {0:?} -> {1:?}")]
+    Invalid(PaymentState, PaymentState),
+
+    #[error("Payment {0} is not in the expected state")]
+    Stale(Uuid),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+}
+
+/// Creates the journal row for a new payment in INITIATED state. The unique
+/// `idempotency_key` makes this the point where duplicate requests collide.
+pub async fn initiate(
+    conn: &mut PgConnection,
+    tx_id: Uuid,
+    from_user_id: Uuid,
+    to_user_id: Uuid,
+    amount: i64,
+    idempotency_key: &str,
+    request_hash: Option<&str>,
+    method: &str,
+) -> Result<chrono::DateTime<chrono::Utc>, TransitionError> {
+    insert_initiated(conn, tx_id, from_user_id, to_user_id, amount, idempotency_key, request_hash, "PAYMENT", Some(method), None, None).await
+}
+
+/// Same as `initiate`, for a refund of `original_tx_id`. The refund flows
+/// from the original payee back to the original payer, and is reported
+/// under the original payment's method.
+pub async fn initiate_refund(
+    conn: &mut PgConnection,
+    tx_id: Uuid,
+    original_tx_id: Uuid,
+    from_user_id: Uuid,
+    to_user_id: Uuid,
+    amount: i64,
+    idempotency_key: &str,
+    request_hash: Option<&str>,
+    reason: Option<&str>,
+) -> Result<chrono::DateTime<chrono::Utc>, TransitionError> {
+    insert_initiated(conn, tx_id, from_user_id, to_user_id, amount, idempotency_key, request_hash, "REFUND", None, Some(original_tx_id), reason).await
+}
+
+async fn insert_initiated(
+    conn: &mut PgConnection,
+    tx_id: Uuid,
+    from_user_id: Uuid,
+    to_user_id: Uuid,
+    amount: i64,
+    idempotency_key: &str,
+    request_hash: Option<&str>,
+    kind: &str,
+    method: Option<&str>,
+    original_tx_id: Option<Uuid>,
+    reason: Option<&str>,
+) -> Result<chrono::DateTime<chrono::Utc>, TransitionError> {
+    let created_at = sqlx::query_scalar!(
+        r#"
+        INSERT INTO transaction_journal (tx_id, from_user_id, to_user_id, amount, status, idempotency_key, request_hash, kind, original_tx_id, method, merchant_id)
+        VALUES (
+            $1, $2, $3, $4, 'INITIATED', $5, $6, $7, $8,
+            COALESCE($9, (SELECT method FROM transaction_journal WHERE tx_id = $8)),
+            -- Tags merchant payments (and refunds out of a merchant wallet) for reporting
+            (SELECT merchant_id FROM merchants WHERE merchant_id IN ($2, $3) ORDER BY merchant_id = $3 DESC LIMIT 1)
+        )
+        RETURNING created_at
+        "#,
+        tx_id,
+        from_user_id,
+        to_user_id,
+        amount,
+        idempotency_key,
+        request_hash,
+        kind,
+        original_tx_id,
+        method
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO payment_state_transitions (tx_id, from_state, to_state, reason)
+        VALUES ($1, NULL, 'INITIATED', $2)
+        "#,
+        tx_id,
+        reason
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(created_at)
+}
+
+/// Moves a payment from `from` to `to`, failing if the journal row is no
+/// longer in `from` (another worker got there first).
+pub async fn record_transition(
+    conn: &mut PgConnection,
+    tx_id: Uuid,
+    from: PaymentState,
+    to: PaymentState,
+    reason: Option<&str>,
+) -> Result<(), TransitionError> {
+    if !from.can_transition_to(to) {
+        return Err(TransitionError::Invalid(from, to));
+    }
+
+    let updated = sqlx::query!(
+        "UPDATE transaction_journal SET status = $1 WHERE tx_id = $2 AND status = $3",
+        to.as_str(),
+        tx_id,
+        from.as_str()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(TransitionError::Stale(tx_id));
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO payment_state_transitions (tx_id, from_state, to_state, reason)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        tx_id,
+        from.as_str(),
+        to.as_str(),
+        reason
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    metrics::counter!("payment_state_transitions_total", 1, "to" => to.as_str());
+    Ok(())
+}
@@ -28,9 +28,11 @@ impl UserService {
         .await?
         .ok_or(sqlx::Error::RowNotFound)?;
 
+        // Usage recorded on an earlier day no longer counts
         let limits = sqlx::query!(
-            "SELECT kyc_tier, amount_used FROM daily_limits WHERE user_id = $1",
-            user_id
+            "SELECT kyc_tier, CASE WHEN reset_date < $2 THEN 0 ELSE amount_used END AS amount_used FROM daily_limits WHERE user_id = $1",
+            user_id,
+            chrono::Utc::now().date_naive()
         )
         .fetch_optional(&self.db)
         .await?;
@@ -7,12 +7,13 @@ use axum::{
 };
 use uuid::Uuid;
 use crate::wallet::{WalletService, WalletError};
+use crate::wallet::models::{AuthorizeHoldRequest, CaptureHoldRequest, Hold, BalanceResponse, TransferReceipt};
 
 pub async fn get_balance(
     Extension(wallet_service): Extension<std::sync::Arc<WalletService>>,
     user_id: Uuid, // from JWT middleware
-) -> Result<Json<i64>, (http::StatusCode, Json<serde_json::Value>)> {
-    let balance = wallet_service.get_balance_cached(&user_id)
+) -> Result<Json<BalanceResponse>, (http::StatusCode, Json<serde_json::Value>)> {
+    let balance = wallet_service.get_balance_details(&user_id)
         .await
         .map_err(|e| {
             (
@@ -22,4 +23,52 @@ pub async fn get_balance(
         })?;
 
     Ok(Json(balance))
-}
\ No newline at end of file
+}
+
+pub async fn authorize_hold(
+    Extension(wallet_service): Extension<std::sync::Arc<WalletService>>,
+    user_id: Uuid, // from JWT middleware
+    Json(payload): Json<AuthorizeHoldRequest>,
+) -> Result<Json<Hold>, (http::StatusCode, Json<serde_json::Value>)> {
+    let hold = wallet_service.authorize_hold(user_id, &payload)
+        .await
+        .map_err(hold_error_response)?;
+
+    Ok(Json(hold))
+}
+
+pub async fn capture_hold(
+    Extension(wallet_service): Extension<std::sync::Arc<WalletService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(hold_id): Path<Uuid>,
+    Json(payload): Json<CaptureHoldRequest>,
+) -> Result<Json<TransferReceipt>, (http::StatusCode, Json<serde_json::Value>)> {
+    let receipt = wallet_service.capture_hold(user_id, hold_id, &payload)
+        .await
+        .map_err(hold_error_response)?;
+
+    Ok(Json(receipt))
+}
+
+pub async fn void_hold(
+    Extension(wallet_service): Extension<std::sync::Arc<WalletService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(hold_id): Path<Uuid>,
+) -> Result<Json<Hold>, (http::StatusCode, Json<serde_json::Value>)> {
+    let hold = wallet_service.void_hold(user_id, hold_id)
+        .await
+        .map_err(hold_error_response)?;
+
+    Ok(Json(hold))
+}
+
+fn hold_error_response(e: WalletError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        WalletError::HoldNotFound(_) | WalletError::WalletNotFound(_) => http::StatusCode::NOT_FOUND,
+        WalletError::HoldNotActive(_) | WalletError::ConcurrencyConflict => http::StatusCode::CONFLICT,
+        ref e if e.is_internal() => http::StatusCode::INTERNAL_SERVER_ERROR,
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
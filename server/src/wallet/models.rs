@@ -22,10 +22,75 @@ pub struct CreditDebitRequest {
     pub idempotency_key: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct TransferRequest {
+    pub tx_id: Uuid, // journal row, already INITIATED
+    pub from_user_id: Uuid,
+    pub to_user_id: Uuid,
+
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64, // in paise
+
+    #[validate(length(min = 1))]
+    pub idempotency_key: String, // reference for the ledger entry
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TransferReceipt {
+    pub tx_id: Uuid,
+    pub from_balance: i64,
+    pub to_balance: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AuthorizeHoldRequest {
+    pub payee_user_id: Uuid, // the only party allowed to capture
+
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: u64, // in paise
+
+    #[validate(range(min = 60, max = 604_800))] // 1 minute to 7 days
+    pub expires_in_secs: u64,
+
+    #[validate(length(min = 1, max = 255))]
+    pub idempotency_key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CaptureHoldRequest {
+    #[validate(range(min = 1, max = 500_000))]
+    pub amount: Option<u64>, // None = capture the full hold
+
+    #[validate(length(min = 1, max = 255))]
+    pub idempotency_key: String,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct Hold {
+    pub hold_id: Uuid,
+    pub user_id: Uuid,
+    pub payee_user_id: Uuid,
+    pub amount: i64,
+    pub captured: i64,
+    pub status: String, // 'ACTIVE', 'CAPTURED', 'VOIDED', 'EXPIRED'
+    pub captured_tx_id: Option<Uuid>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct BalanceResponse {
+    pub balance: i64,   // ledger balance
+    pub available: i64, // balance minus active holds
+    pub held: i64,
+}
+
 #[derive(Debug, Serialize, Clone, sqlx::FromRow)]
 pub struct Wallet {
     pub user_id: Uuid,
     pub balance: i64,          // in paise (₹1 = 100 paise) — avoids float
+    pub held: i64,             // reserved by active holds; not spendable
     pub version: i32,          // for optimistic concurrency
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
@@ -42,6 +107,21 @@ pub enum WalletError {
     #[error("Wallet not found for user: {0}")]
     WalletNotFound(Uuid),
 
+    #[error("Cannot transfer to the same wallet")]
+    SelfTransfer,
+
+    #[error("Hold not found: {0}")]
+    HoldNotFound(Uuid),
+
+    #[error("Hold is {0}")]
+    HoldNotActive(String),
+
+    #[error("Capture exceeds held amount of {held}")]
+    CaptureExceedsHold { held: i64 },
+
+    #[error("Daily limit exceeded")]
+    DailyLimitExceeded,
+
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
 
@@ -50,4 +130,20 @@ pub enum WalletError {
 
     #[error("Concurrency conflict - retry")]
     ConcurrencyConflict,
-}
\ No newline at end of file
+
+    #[error("Ledger error: {0}")]
+    LedgerError(#[from] crate::ledger::LedgerError),
+
+    #[error("State error: {0}")]
+    StateError(#[from] crate::transaction::state::TransitionError),
+}
+
+impl WalletError {
+    /// Failures of the server rather than of the request; worth retrying.
+    pub fn is_internal(&self) -> bool {
+        matches!(
+            self,
+            WalletError::DatabaseError(_) | WalletError::LedgerError(_) | WalletError::StateError(_)
+        )
+    }
+}
@@ -1,20 +1,33 @@
 // src/wallet_service.rs
 
-use crate::models::{Wallet, CreditDebitRequest, WalletError, CreateWalletRequest};
+use crate::models::{
+    Wallet, CreditDebitRequest, WalletError, CreateWalletRequest, TransferRequest, TransferReceipt,
+    AuthorizeHoldRequest, CaptureHoldRequest, Hold, BalanceResponse,
+};
+use crate::ledger::{LedgerService, models::{Posting, SystemAccount, wallet_account}};
+use crate::transaction::state::{self, PaymentState, record_transition};
 use sqlx::{PgPool, Executor};
 use std::sync::Arc;
 use tracing::{info, error, instrument};
 use opentelemetry::trace::TraceContextExt;
 use metrics::{counter, histogram};
 
+/// Daily payment limits by KYC tier, in paise.
+const BASIC_DAILY_LIMIT: i64 = 10_000_00;
+const FULL_DAILY_LIMIT: i64 = 100_000_00;
+
 pub struct WalletService {
     db: PgPool,
+    ledger: LedgerService,
     // Optional: Redis client for caching
 }
 
 impl WalletService {
     pub fn new(db: PgPool) -> Self {
-        Self { db }
+        Self {
+            ledger: LedgerService::new(db.clone()),
+            db,
+        }
     }
 
     #[instrument(skip(self), fields(user_id = %req.user_id))]
@@ -27,7 +40,7 @@ impl WalletService {
             INSERT INTO wallets (user_id, balance, version)
             VALUES ($1, 0, 0)
             ON CONFLICT (user_id) DO NOTHING
-            RETURNING user_id, balance, version, created_at, updated_at
+            RETURNING user_id, balance, held, version, created_at, updated_at
             "#,
             req.user_id
         )
@@ -38,10 +51,39 @@ impl WalletService {
         Ok(wallet)
     }
 
+    /// Creates the user's wallet inside the caller's transaction, if it does
+    /// not exist yet.
+    pub async fn create_wallet_in(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &Uuid,
+    ) -> Result<(), WalletError> {
+        let created = sqlx::query!(
+            "INSERT INTO wallets (user_id, balance, version) VALUES ($1, 0, 0) ON CONFLICT (user_id) DO NOTHING",
+            user_id
+        )
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+
+        if created > 0 {
+            info!("Wallet created for user {}", user_id);
+        }
+        Ok(())
+    }
+
+    /// Balance as computed from ledger postings. `wallets.balance` is only a
+    /// running projection used for row locking and the overdraft check.
     #[instrument(skip(self), fields(user_id = %user_id))]
     pub async fn get_balance(&self, user_id: &Uuid) -> Result<i64, WalletError> {
         let balance = sqlx::query_scalar!(
-            "SELECT balance FROM wallets WHERE user_id = $1",
+            r#"
+            SELECT COALESCE(SUM(p.amount), 0)::BIGINT AS "balance!"
+            FROM wallets w
+            LEFT JOIN ledger_postings p ON p.account_code = 'wallet:' || w.user_id::text
+            WHERE w.user_id = $1
+            GROUP BY w.user_id
+            "#,
             user_id
         )
         .fetch_optional(&self.db)
@@ -51,10 +93,25 @@ impl WalletService {
         Ok(balance)
     }
 
+    /// Ledger balance alongside what active holds leave spendable.
+    #[instrument(skip(self), fields(user_id = %user_id))]
+    pub async fn get_balance_details(&self, user_id: &Uuid) -> Result<BalanceResponse, WalletError> {
+        let balance = self.get_balance(user_id).await?;
+        let held = sqlx::query_scalar!("SELECT held FROM wallets WHERE user_id = $1", user_id)
+            .fetch_one(&self.db)
+            .await?;
+
+        Ok(BalanceResponse {
+            balance,
+            available: balance - held,
+            held,
+        })
+    }
+
     #[instrument(skip(self), fields(user_id = %req.user_id, amount = req.amount))]
 pub async fn credit(&self, req: &CreditDebitRequest) -> Result<Wallet, WalletError> {
     let start = std::time::Instant::now();
-    let result = self.process_transaction(req, true).await;
+    let result = self.process_transaction(req, true, SystemAccount::Suspense).await;
 
     let status = match &result {
         Ok(_) => "success",
@@ -71,13 +128,49 @@ pub async fn credit(&self, req: &CreditDebitRequest) -> Result<Wallet, WalletErr
 
     #[instrument(skip(self), fields(user_id = %req.user_id, amount = req.amount))]
     pub async fn debit(&self, req: &CreditDebitRequest) -> Result<Wallet, WalletError> {
-        self.process_transaction(req, false).await
+        self.process_transaction(req, false, SystemAccount::Suspense).await
     }
 
+    /// Debits `amount` from a wallet inside the caller's transaction and
+    /// spreads it over `credits`, which must add up to `amount`. Settlement
+    /// uses this so the batch and the debit commit together.
+    pub async fn debit_in(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &Uuid,
+        amount: i64,
+        reference: &str,
+        description: &str,
+        credits: &[Posting],
+    ) -> Result<(), WalletError> {
+        let wallet = self.lock_wallet(tx, user_id).await?;
+        if wallet.balance - wallet.held < amount {
+            return Err(WalletError::InsufficientBalance);
+        }
+
+        sqlx::query!(
+            "UPDATE wallets SET balance = balance - $1, version = version + 1, updated_at = NOW() WHERE user_id = $2",
+            amount,
+            user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        let mut postings = vec![Posting::debit(&wallet_account(user_id), amount)];
+        postings.extend_from_slice(credits);
+        self.ledger.post_entry(tx, reference, description, &postings).await?;
+
+        info!(user_id = %user_id, amount, reference, "Wallet debited");
+        Ok(())
+    }
+
+    /// Moves `req.amount` between the user's wallet and `contra`, recording
+    /// both legs as one balanced ledger entry.
     async fn process_transaction(
         &self,
         req: &CreditDebitRequest,
         is_credit: bool,
+        contra: SystemAccount,
     ) -> Result<Wallet, WalletError> {
         req.validate()?;
 
@@ -93,7 +186,7 @@ pub async fn credit(&self, req: &CreditDebitRequest) -> Result<Wallet, WalletErr
         let mut wallet = sqlx::query_as!(
             Wallet,
             r#"
-            SELECT user_id, balance, version, created_at, updated_at
+            SELECT user_id, balance, held, version, created_at, updated_at
             FROM wallets
             WHERE user_id = $1
             FOR UPDATE
@@ -104,8 +197,8 @@ pub async fn credit(&self, req: &CreditDebitRequest) -> Result<Wallet, WalletErr
         .await?
         .ok_or_else(|| WalletError::WalletNotFound(req.user_id))?;
 
-        // Step 4: Validate balance for debit
-        if !is_credit && wallet.balance < req.amount as i64 {
+        // Step 4: Validate available balance (held funds cannot be spent) for debit
+        if !is_credit && wallet.balance - wallet.held < req.amount as i64 {
             return Err(WalletError::InsufficientBalance);
         }
 
@@ -149,13 +242,23 @@ pub async fn credit(&self, req: &CreditDebitRequest) -> Result<Wallet, WalletErr
         .execute(&mut *tx)
         .await?;
 
-        // Step 8: Commit
+        // Step 8: Post balanced ledger entry against the contra account
+        let wallet_code = wallet_account(&req.user_id);
+        self.ledger.open_wallet_account(&mut tx, &req.user_id).await?;
+        let (description, postings) = if is_credit {
+            ("wallet_credit", [Posting::debit(contra.code(), amount_i64), Posting::credit(&wallet_code, amount_i64)])
+        } else {
+            ("wallet_debit", [Posting::debit(&wallet_code, amount_i64), Posting::credit(contra.code(), amount_i64)])
+        };
+        self.ledger.post_entry(&mut tx, &req.idempotency_key, description, &postings).await?;
+
+        // Step 9: Commit
         tx.commit().await?;
 
-        // Step 9: Invalidate cache (if using Redis) — async fire-and-forget
+        // Step 10: Invalidate cache (if using Redis) — async fire-and-forget
         // self.invalidate_cache(req.user_id).await;
 
-        // Step 10: Return updated wallet
+        // Step 11: Return updated wallet
         wallet.balance = new_balance;
         wallet.version = new_version;
         wallet.updated_at = chrono::Utc::now();
@@ -170,6 +273,532 @@ pub async fn credit(&self, req: &CreditDebitRequest) -> Result<Wallet, WalletErr
         Ok(wallet)
     }
 
+    /// Wallet-to-wallet payment in a single database transaction: both rows
+    /// are locked in user_id order (so two opposite transfers cannot
+    /// deadlock), then debit, credit, ledger entry, journal state changes and
+    /// the sender's daily-limit usage commit or roll back together. The
+    /// journal row for `req.tx_id` must already exist in INITIATED state.
+    #[instrument(skip(self), fields(from_user_id = %req.from_user_id, to_user_id = %req.to_user_id, amount = req.amount))]
+    pub async fn transfer(&self, req: &TransferRequest) -> Result<TransferReceipt, WalletError> {
+        let start = std::time::Instant::now();
+        let result = self.process_transfer(req).await;
+
+        let status = match &result {
+            Ok(_) => "success",
+            Err(WalletError::InsufficientBalance) => "insufficient_balance",
+            Err(WalletError::ConcurrencyConflict) => "concurrency_conflict",
+            Err(_) => "error",
+        };
+
+        histogram!("wallet_transfer_duration_seconds", start.elapsed().as_secs_f64());
+        counter!("wallet_transfer_total", 1, "status" => status);
+
+        result
+    }
+
+    async fn process_transfer(&self, req: &TransferRequest) -> Result<TransferReceipt, WalletError> {
+        req.validate()?;
+
+        if req.from_user_id == req.to_user_id {
+            return Err(WalletError::SelfTransfer);
+        }
+
+        let mut tx = self.db.begin().await?;
+        let receipt = self.transfer_in(&mut tx, req, 0).await?;
+        tx.commit().await?;
+
+        info!(tx_id = %req.tx_id, "Transfer committed");
+        Ok(receipt)
+    }
+
+    /// Body of `transfer`, run inside the caller's transaction. `released_hold`
+    /// is the part of the sender's held funds this transfer consumes (for
+    /// hold captures); it is released in the same row update as the debit.
+    async fn transfer_in(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        req: &TransferRequest,
+        released_hold: i64,
+    ) -> Result<TransferReceipt, WalletError> {
+        let amount_i64 = req.amount as i64;
+
+        // Step 1: Lock both wallets in a deterministic order
+        let (first, second) = if req.from_user_id < req.to_user_id {
+            (req.from_user_id, req.to_user_id)
+        } else {
+            (req.to_user_id, req.from_user_id)
+        };
+        let first_wallet = self.lock_wallet(tx, &first).await?;
+        let second_wallet = self.lock_wallet(tx, &second).await?;
+        let (from_wallet, to_wallet) = if first == req.from_user_id {
+            (first_wallet, second_wallet)
+        } else {
+            (second_wallet, first_wallet)
+        };
+
+        // Step 2: Validate available balance and record the sender's daily
+        // limit usage; holds still outstanding are already promised today
+        let still_held = from_wallet.held - released_hold;
+        if from_wallet.balance - still_held < amount_i64 {
+            return Err(WalletError::InsufficientBalance);
+        }
+        self.use_daily_limit(tx, req.from_user_id, amount_i64, still_held).await?;
+
+        // Step 3: Debit sender, credit receiver
+        let from_balance = from_wallet.balance - amount_i64;
+        let to_balance = to_wallet.balance + amount_i64;
+        sqlx::query!(
+            "UPDATE wallets SET balance = $1, held = held - $2, version = version + 1, updated_at = NOW() WHERE user_id = $3",
+            from_balance,
+            released_hold,
+            req.from_user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE wallets SET balance = $1, version = version + 1, updated_at = NOW() WHERE user_id = $2",
+            to_balance,
+            req.to_user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        // Step 4: Ledger entry straight from payer to payee
+        self.ledger.open_wallet_account(tx, &req.from_user_id).await?;
+        self.ledger.open_wallet_account(tx, &req.to_user_id).await?;
+        self.ledger.post_entry(
+            tx,
+            &req.idempotency_key,
+            "wallet_transfer",
+            &[
+                Posting::debit(&wallet_account(&req.from_user_id), amount_i64),
+                Posting::credit(&wallet_account(&req.to_user_id), amount_i64),
+            ],
+        ).await?;
+
+        // Step 6: Mark the journal row final; both legs commit together, so
+        // there is no partly-moved state in between
+        record_transition(tx, req.tx_id, PaymentState::Initiated, PaymentState::Success, None).await?;
+        let created_at = sqlx::query_scalar!(
+            "SELECT created_at FROM transaction_journal WHERE tx_id = $1",
+            req.tx_id
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(TransferReceipt {
+            tx_id: req.tx_id,
+            from_balance,
+            to_balance,
+            created_at,
+        })
+    }
+
+    /// Undoes whatever a payment moved: posts the mirror of its ledger entry,
+    /// restores the affected wallet balances and moves the journal row from
+    /// `from` to REVERSED, all in one database transaction.
+    #[instrument(skip(self))]
+    pub async fn reverse_transfer(
+        &self,
+        tx_id: Uuid,
+        idempotency_key: &str,
+        from: PaymentState,
+        reason: &str,
+    ) -> Result<(), WalletError> {
+        let mut tx = self.db.begin().await?;
+        self.reverse_transfer_in(&mut tx, tx_id, idempotency_key, from, reason).await?;
+        tx.commit().await?;
+
+        counter!("wallet_reversal_total", 1);
+        info!(tx_id = %tx_id, "Transfer reversed");
+        Ok(())
+    }
+
+    /// Body of `reverse_transfer`, run inside the caller's transaction so
+    /// the caller can check and lock the journal row first.
+    pub async fn reverse_transfer_in(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        tx_id: Uuid,
+        idempotency_key: &str,
+        from: PaymentState,
+        reason: &str,
+    ) -> Result<(), WalletError> {
+        // Step 1: Compensating ledger entry
+        let mirror = self.ledger.reverse_entry(
+            tx,
+            idempotency_key,
+            &format!("reversal:{}", idempotency_key),
+        ).await?;
+
+        // Step 2: Apply it to the wallet rows, locked in user_id order
+        let mut changes: Vec<(Uuid, i64)> = mirror
+            .iter()
+            .filter_map(|p| {
+                let user_id = p.account_code.strip_prefix("wallet:")?;
+                Uuid::parse_str(user_id).ok().map(|id| (id, p.amount))
+            })
+            .collect();
+        changes.sort_by_key(|(user_id, _)| *user_id);
+
+        for (user_id, delta) in changes {
+            let wallet = self.lock_wallet(tx, &user_id).await?;
+            if wallet.balance - wallet.held + delta < 0 {
+                return Err(WalletError::InsufficientBalance); // payee already spent it
+            }
+            sqlx::query!(
+                "UPDATE wallets SET balance = balance + $1, version = version + 1, updated_at = NOW() WHERE user_id = $2",
+                delta,
+                user_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        // Step 3: Journal state
+        record_transition(tx, tx_id, from, PaymentState::Reversed, Some(reason)).await?;
+        Ok(())
+    }
+
+    /// Reserves `req.amount` of the user's available balance for the payee.
+    /// The ledger is untouched until capture. Retrying with the same
+    /// idempotency key returns the original hold.
+    #[instrument(skip(self, req), fields(amount = req.amount))]
+    pub async fn authorize_hold(&self, user_id: Uuid, req: &AuthorizeHoldRequest) -> Result<Hold, WalletError> {
+        req.validate()?;
+        if user_id == req.payee_user_id {
+            return Err(WalletError::SelfTransfer);
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        // Step 1: Lock the wallet first so retries of the same key serialize here
+        let wallet = self.lock_wallet(&mut tx, &user_id).await?;
+
+        if let Some(existing) = sqlx::query_as!(
+            Hold,
+            r#"
+            SELECT hold_id, user_id, payee_user_id, amount, captured, status, captured_tx_id, expires_at, created_at
+            FROM wallet_holds
+            WHERE reference = $1 AND user_id = $2
+            "#,
+            req.idempotency_key,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        {
+            return Ok(existing);
+        }
+
+        // Step 2: A hold is a promise to pay, so it and the user's other
+        // active holds are held to the same daily limit; then reserve against
+        // the available balance
+        let amount = req.amount as i64;
+        self.use_daily_limit(&mut tx, user_id, 0, wallet.held + amount).await?;
+        if wallet.balance - wallet.held < amount {
+            return Err(WalletError::InsufficientBalance);
+        }
+        sqlx::query!(
+            "UPDATE wallets SET held = held + $1, version = version + 1, updated_at = NOW() WHERE user_id = $2",
+            amount,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let hold = sqlx::query_as!(
+            Hold,
+            r#"
+            INSERT INTO wallet_holds (user_id, payee_user_id, amount, reference, expires_at)
+            VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+            RETURNING hold_id, user_id, payee_user_id, amount, captured, status, captured_tx_id, expires_at, created_at
+            "#,
+            user_id,
+            req.payee_user_id,
+            amount,
+            req.idempotency_key,
+            req.expires_in_secs as f64
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        counter!("wallet_holds_total", 1, "event" => "authorized");
+        info!(hold_id = %hold.hold_id, "Hold authorized");
+        Ok(hold)
+    }
+
+    /// Captures all or part of an active hold as a regular payment from the
+    /// holder to the payee. Whatever is not captured is released. Retrying
+    /// with the same idempotency key returns the original capture.
+    #[instrument(skip(self, req))]
+    pub async fn capture_hold(
+        &self,
+        payee_user_id: Uuid,
+        hold_id: Uuid,
+        req: &CaptureHoldRequest,
+    ) -> Result<TransferReceipt, WalletError> {
+        req.validate()?;
+
+        let mut tx = self.db.begin().await?;
+        let hold = self.lock_hold(&mut tx, hold_id).await?;
+        if hold.payee_user_id != payee_user_id {
+            return Err(WalletError::HoldNotFound(hold_id));
+        }
+        if let Some(receipt) = self.captured_receipt(&mut tx, &hold, &req.idempotency_key).await? {
+            return Ok(receipt);
+        }
+        if hold.status != "ACTIVE" {
+            return Err(WalletError::HoldNotActive(hold.status));
+        }
+        if hold.expires_at <= chrono::Utc::now() {
+            return Err(WalletError::HoldNotActive("EXPIRED".to_string()));
+        }
+
+        let amount = req.amount.map(|a| a as i64).unwrap_or(hold.amount);
+        if amount > hold.amount {
+            return Err(WalletError::CaptureExceedsHold { held: hold.amount });
+        }
+
+        // Step 1: Journal row for the captured payment
+        let tx_id = Uuid::new_v4();
+        state::initiate(&mut tx, tx_id, hold.user_id, payee_user_id, amount, &req.idempotency_key, None, "hold_capture").await?;
+
+        // Step 2: Move the money, releasing the whole hold in the same row update
+        let receipt = self.transfer_in(&mut tx, &TransferRequest {
+            tx_id,
+            from_user_id: hold.user_id,
+            to_user_id: payee_user_id,
+            amount: amount as u64,
+            idempotency_key: req.idempotency_key.clone(),
+        }, hold.amount).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE wallet_holds
+            SET status = 'CAPTURED', captured = $1, captured_tx_id = $2,
+                captured_from_balance = $3, captured_to_balance = $4, updated_at = NOW()
+            WHERE hold_id = $5
+            "#,
+            amount,
+            tx_id,
+            receipt.from_balance,
+            receipt.to_balance,
+            hold_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        counter!("wallet_holds_total", 1, "event" => "captured");
+        info!(hold_id = %hold_id, tx_id = %tx_id, amount, "Hold captured");
+        Ok(receipt)
+    }
+
+    /// The receipt of an earlier capture of `hold` made with `idempotency_key`,
+    /// with the balances as they were right after it.
+    async fn captured_receipt(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        hold: &Hold,
+        idempotency_key: &str,
+    ) -> Result<Option<TransferReceipt>, WalletError> {
+        let Some(captured_tx_id) = hold.captured_tx_id else {
+            return Ok(None);
+        };
+
+        let receipt = sqlx::query!(
+            r#"
+            SELECT tj.created_at, wh.captured_from_balance AS "from_balance!", wh.captured_to_balance AS "to_balance!"
+            FROM transaction_journal tj
+            JOIN wallet_holds wh ON wh.captured_tx_id = tj.tx_id
+            WHERE tj.tx_id = $1 AND tj.idempotency_key = $2
+            "#,
+            captured_tx_id,
+            idempotency_key
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .map(|r| TransferReceipt {
+            tx_id: captured_tx_id,
+            from_balance: r.from_balance,
+            to_balance: r.to_balance,
+            created_at: r.created_at,
+        });
+
+        Ok(receipt)
+    }
+
+    /// Releases an active hold. Either the holder or the payee may void it.
+    #[instrument(skip(self))]
+    pub async fn void_hold(&self, user_id: Uuid, hold_id: Uuid) -> Result<Hold, WalletError> {
+        let mut tx = self.db.begin().await?;
+        let hold = self.lock_hold(&mut tx, hold_id).await?;
+        if hold.user_id != user_id && hold.payee_user_id != user_id {
+            return Err(WalletError::HoldNotFound(hold_id));
+        }
+        if hold.status != "ACTIVE" {
+            return Err(WalletError::HoldNotActive(hold.status));
+        }
+
+        let hold = self.release_hold(&mut tx, &hold, "VOIDED").await?;
+        tx.commit().await?;
+
+        counter!("wallet_holds_total", 1, "event" => "voided");
+        info!(hold_id = %hold_id, "Hold voided");
+        Ok(hold)
+    }
+
+    /// Releases every active hold past its expiry. Returns how many expired.
+    pub async fn expire_holds(&self) -> Result<u32, WalletError> {
+        let due = sqlx::query_scalar!(
+            "SELECT hold_id FROM wallet_holds WHERE status = 'ACTIVE' AND expires_at <= NOW() ORDER BY expires_at LIMIT 100"
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut expired = 0;
+        for hold_id in due {
+            let mut tx = self.db.begin().await?;
+            let hold = self.lock_hold(&mut tx, hold_id).await?;
+            // Captured or voided since the scan
+            if hold.status != "ACTIVE" {
+                continue;
+            }
+            self.release_hold(&mut tx, &hold, "EXPIRED").await?;
+            tx.commit().await?;
+            expired += 1;
+        }
+
+        if expired > 0 {
+            counter!("wallet_holds_total", expired as u64, "event" => "expired");
+            info!(expired, "Expired wallet holds");
+        }
+        Ok(expired)
+    }
+
+    /// Adds `amount` to the user's usage for today on their locked
+    /// daily_limits row, starting the count over if the row is from an
+    /// earlier day. `reserved` counts against the limit as well but is not
+    /// recorded. Fails without recording anything if the total would take
+    /// the user past the limit for their KYC tier.
+    async fn use_daily_limit(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        amount: i64,
+        reserved: i64,
+    ) -> Result<(), WalletError> {
+        let today = chrono::Utc::now().date_naive();
+        let used = sqlx::query_scalar!(
+            r#"
+            INSERT INTO daily_limits (user_id, amount_used, reset_date, kyc_tier)
+            SELECT $1, $2, $3, 'basic'
+            WHERE $2 + $6 <= $4
+            ON CONFLICT (user_id) DO UPDATE
+            SET amount_used = (CASE WHEN daily_limits.reset_date < $3 THEN 0 ELSE daily_limits.amount_used END) + $2,
+                reset_date = $3
+            WHERE (CASE WHEN daily_limits.reset_date < $3 THEN 0 ELSE daily_limits.amount_used END) + $2 + $6
+                  <= CASE WHEN daily_limits.kyc_tier = 'full' THEN $5 ELSE $4 END
+            RETURNING amount_used
+            "#,
+            user_id,
+            amount,
+            today,
+            BASIC_DAILY_LIMIT,
+            FULL_DAILY_LIMIT,
+            reserved
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        if used.is_none() {
+            return Err(WalletError::DailyLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Gives a hold's amount back to the holder's available balance and moves
+    /// it to `status`.
+    async fn release_hold(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        hold: &Hold,
+        status: &str,
+    ) -> Result<Hold, WalletError> {
+        self.lock_wallet(tx, &hold.user_id).await?;
+        sqlx::query!(
+            "UPDATE wallets SET held = held - $1, version = version + 1, updated_at = NOW() WHERE user_id = $2",
+            hold.amount,
+            hold.user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        let hold = sqlx::query_as!(
+            Hold,
+            r#"
+            UPDATE wallet_holds SET status = $1, updated_at = NOW()
+            WHERE hold_id = $2
+            RETURNING hold_id, user_id, payee_user_id, amount, captured, status, captured_tx_id, expires_at, created_at
+            "#,
+            status,
+            hold.hold_id
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(hold)
+    }
+
+    async fn lock_hold(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        hold_id: Uuid,
+    ) -> Result<Hold, WalletError> {
+        let hold = sqlx::query_as!(
+            Hold,
+            r#"
+            SELECT hold_id, user_id, payee_user_id, amount, captured, status, captured_tx_id, expires_at, created_at
+            FROM wallet_holds
+            WHERE hold_id = $1
+            FOR UPDATE
+            "#,
+            hold_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or(WalletError::HoldNotFound(hold_id))?;
+
+        Ok(hold)
+    }
+
+    async fn lock_wallet(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &Uuid,
+    ) -> Result<Wallet, WalletError> {
+        let wallet = sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT user_id, balance, held, version, created_at, updated_at
+            FROM wallets
+            WHERE user_id = $1
+            FOR UPDATE
+            "#,
+            user_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| WalletError::WalletNotFound(*user_id))?;
+
+        Ok(wallet)
+    }
+
     async fn is_idempotent(&self, key: &str, user_id: &Uuid) -> Result<bool, WalletError> {
         let exists = sqlx::query_scalar!(
             "SELECT EXISTS(SELECT 1 FROM idempotency_keys WHERE idempotency_key = $1 AND user_id = $2)",
@@ -229,12 +858,12 @@ pub async fn credit(&self, req: &CreditDebitRequest) -> Result<Wallet, WalletErr
         .json()
         .await?;
 
-    // Credit wallet
+    // Credit wallet — money now sits in the bank float
     let req = CreditDebitRequest {
         user_id,
         amount,
         idempotency_key: Uuid::new_v4().to_string(),
     };
-    self.credit(&req).await
+    self.process_transaction(&req, true, SystemAccount::Float).await
 }
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+// src/webhook/destination.rs
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Where a webhook may be sent. Merchants choose the URL, so every address
+/// it resolves to must be on the public internet; otherwise the server
+/// could be pointed at its own network.
+#[derive(Debug, Clone, Copy)]
+pub struct DestinationPolicy {
+    pub allow_loopback: bool, // local development only: plain HTTP to this machine
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DestinationError {
+    #[error("URL is not valid")]
+    InvalidUrl,
+
+    #[error("HTTPS is required")]
+    HttpsRequired,
+
+    #[error("Host {0} could not be resolved")]
+    Unresolvable(String),
+
+    #[error("Host {0} resolves to a non-public address")]
+    NonPublicAddress(String),
+}
+
+impl DestinationError {
+    /// Validation code reported back to the merchant.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DestinationError::InvalidUrl => "invalid_url",
+            DestinationError::HttpsRequired => "https_required",
+            DestinationError::Unresolvable(_) => "unresolvable_host",
+            DestinationError::NonPublicAddress(_) => "non_public_address",
+        }
+    }
+}
+
+impl DestinationPolicy {
+    /// Resolves the URL's host and checks every address it resolves to.
+    /// Returns the host and the addresses, so the caller can connect to
+    /// exactly what was checked rather than resolving again.
+    pub async fn resolve(&self, url: &str) -> Result<(String, Vec<SocketAddr>), DestinationError> {
+        let parsed = url::Url::parse(url).map_err(|_| DestinationError::InvalidUrl)?;
+        let host = parsed.host_str().ok_or(DestinationError::InvalidUrl)?.to_string();
+        let port = parsed.port_or_known_default().ok_or(DestinationError::InvalidUrl)?;
+
+        let secure = parsed.scheme() == "https";
+        if !secure && !(self.allow_loopback && parsed.scheme() == "http") {
+            return Err(DestinationError::HttpsRequired);
+        }
+
+        let lookup = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup, port))
+            .await
+            .map_err(|_| DestinationError::Unresolvable(host.clone()))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(DestinationError::Unresolvable(host));
+        }
+
+        let allowed = |ip: IpAddr| is_public(ip) || (self.allow_loopback && ip.is_loopback());
+        if !addrs.iter().all(|addr| allowed(addr.ip())) {
+            return Err(DestinationError::NonPublicAddress(host));
+        }
+        // Plain HTTP only ever goes to this machine
+        if !secure && !addrs.iter().all(|addr| addr.ip().is_loopback()) {
+            return Err(DestinationError::HttpsRequired);
+        }
+        Ok((host, addrs))
+    }
+}
+
+/// Not private, loopback, link-local, shared (CGNAT), multicast or
+/// otherwise reserved.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(v6),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // 100.64.0.0/10, carrier-grade NAT
+        || (a == 192 && b == 0 && c == 0) // 192.0.0.0/24, protocol assignments
+        || (a == 198 && (b == 18 || b == 19)) // 198.18.0.0/15, benchmarking
+        || a >= 240) // reserved
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00 // fc00::/7, unique local
+        || (first & 0xffc0) == 0xfe80 // fe80::/10, link-local
+        || (first & 0xffc0) == 0xfec0 // fec0::/10, old site-local
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8) // documentation
+        || (first == 0x0064 && ip.segments()[1] == 0xff9b)) // 64:ff9b::/96, NAT64 onto IPv4
+}
@@ -0,0 +1,99 @@
+// src/webhook/handlers.rs
+
+use axum::{
+    Extension,
+    Json,
+    extract::Path,
+};
+use uuid::Uuid;
+use crate::merchant::models::MerchantError;
+use crate::webhook::{WebhookService, models::*};
+
+/// Registers an endpoint; the response carries the signing secret, which is
+/// not shown again.
+pub async fn register_webhook(
+    Extension(webhook_service): Extension<std::sync::Arc<WebhookService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+    Json(payload): Json<RegisterEndpointRequest>,
+) -> Result<Json<RegisteredEndpoint>, (http::StatusCode, Json<serde_json::Value>)> {
+    let endpoint = webhook_service.register_endpoint(user_id, merchant_id, payload)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(endpoint))
+}
+
+pub async fn list_webhooks(
+    Extension(webhook_service): Extension<std::sync::Arc<WebhookService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+) -> Result<Json<Vec<WebhookEndpoint>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let endpoints = webhook_service.list_endpoints(user_id, merchant_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(endpoints))
+}
+
+pub async fn disable_webhook(
+    Extension(webhook_service): Extension<std::sync::Arc<WebhookService>>,
+    user_id: Uuid, // from JWT middleware
+    Path((merchant_id, endpoint_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<WebhookEndpoint>, (http::StatusCode, Json<serde_json::Value>)> {
+    let endpoint = webhook_service.disable_endpoint(user_id, merchant_id, endpoint_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(endpoint))
+}
+
+pub async fn list_deliveries(
+    Extension(webhook_service): Extension<std::sync::Arc<WebhookService>>,
+    user_id: Uuid, // from JWT middleware
+    Path(merchant_id): Path<Uuid>,
+) -> Result<Json<Vec<WebhookDelivery>>, (http::StatusCode, Json<serde_json::Value>)> {
+    let deliveries = webhook_service.list_deliveries(user_id, merchant_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(deliveries))
+}
+
+pub async fn get_delivery(
+    Extension(webhook_service): Extension<std::sync::Arc<WebhookService>>,
+    user_id: Uuid, // from JWT middleware
+    Path((merchant_id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<DeliveryLog>, (http::StatusCode, Json<serde_json::Value>)> {
+    let log = webhook_service.delivery_log(user_id, merchant_id, delivery_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(log))
+}
+
+/// Sends the delivery again now, even one that already succeeded or gave up.
+pub async fn redeliver(
+    Extension(webhook_service): Extension<std::sync::Arc<WebhookService>>,
+    user_id: Uuid, // from JWT middleware
+    Path((merchant_id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<DeliveryLog>, (http::StatusCode, Json<serde_json::Value>)> {
+    let log = webhook_service.redeliver(user_id, merchant_id, delivery_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(log))
+}
+
+fn error_response(e: WebhookError) -> (http::StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        WebhookError::EndpointNotFound(_)
+        | WebhookError::DeliveryNotFound(_)
+        | WebhookError::MerchantError(MerchantError::NotFound(_)) => http::StatusCode::NOT_FOUND,
+        WebhookError::EndpointDisabled(_) => http::StatusCode::CONFLICT,
+        WebhookError::ValidationError(_) => http::StatusCode::BAD_REQUEST,
+        _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
@@ -0,0 +1,126 @@
+// src/webhook/models.rs
+
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+use sqlx::types::Uuid;
+use crate::merchant::models::MerchantError;
+
+/// Every event type a merchant can subscribe to.
+pub const EVENT_TYPES: &[&str] = &[
+    "payment_intent.succeeded",
+    "payment_intent.payment_failed",
+    "payment_intent.canceled",
+    "refund.succeeded",
+    "settlement.paid",
+    "settlement.failed",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RegisterEndpointRequest {
+    #[validate(length(max = 2048), custom = "validate_endpoint_url")]
+    pub url: String,
+
+    #[validate(length(min = 1, max = 16), custom = "validate_event_types")]
+    pub events: Vec<String>, // "*" for everything
+}
+
+/// A merchant's receiver. The secret is only ever returned once, when the
+/// endpoint is registered.
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct WebhookEndpoint {
+    pub endpoint_id: Uuid,
+    pub merchant_id: Uuid,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<String>,
+    pub status: String, // 'ACTIVE', 'DISABLED'
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegisteredEndpoint {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    pub secret: String, // used to verify `Webhook-Signature`
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub delivery_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub endpoint_id: Uuid,
+    pub url: String,
+    pub status: String, // 'PENDING', 'SUCCEEDED', 'FAILED'
+    pub attempts: i32,
+    pub next_attempt_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct WebhookAttempt {
+    pub attempt: i32,
+    pub status_code: Option<i32>, // None when no response came back
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeliveryLog {
+    pub delivery: WebhookDelivery,
+    pub payload: String, // the exact body that was signed
+    pub attempts: Vec<WebhookAttempt>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DeliveryRunReport {
+    pub delivered: u32,
+    pub retrying: u32,
+    pub failed: u32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("Webhook endpoint not found: {0}")]
+    EndpointNotFound(Uuid),
+
+    #[error("Webhook delivery not found: {0}")]
+    DeliveryNotFound(Uuid),
+
+    #[error("Webhook endpoint is disabled: {0}")]
+    EndpointDisabled(Uuid),
+
+    #[error("Merchant error: {0}")]
+    MerchantError(#[from] MerchantError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    ValidationError(#[from] validator::ValidationErrors),
+}
+
+/// An HTTP(S) URL with a host. Where it may point is checked by
+/// `DestinationPolicy`, which resolves the host.
+fn validate_endpoint_url(url: &str) -> Result<(), ValidationError> {
+    let parsed = url::Url::parse(url).map_err(|_| ValidationError::new("invalid_url"))?;
+    match parsed.scheme() {
+        "https" | "http" if parsed.host_str().is_some() => Ok(()),
+        "https" | "http" => Err(ValidationError::new("invalid_url")),
+        _ => Err(ValidationError::new("https_required")),
+    }
+}
+
+fn validate_event_types(events: &[String]) -> Result<(), ValidationError> {
+    if events.iter().all(|e| e == "*" || EVENT_TYPES.contains(&e.as_str())) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_event_type"))
+    }
+}
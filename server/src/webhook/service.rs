@@ -0,0 +1,554 @@
+// src/webhook/service.rs
+
+use crate::merchant::MerchantService;
+use crate::webhook::destination::DestinationPolicy;
+use crate::webhook::models::*;
+use crate::webhook::signature::{self, SIGNATURE_HEADER};
+use rand::Rng;
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use metrics::{counter, histogram};
+
+/// Delivery attempts per event and endpoint, and the delay before the first
+/// retry. The delay doubles after every failure, up to `max_backoff`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            backoff: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(6 * 60 * 60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn delay_after(&self, attempt: i32) -> Duration {
+        let factor = 2u32.saturating_pow((attempt - 1).max(0) as u32);
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// A claimed delivery is not picked up again for this long, so a worker
+/// that dies mid-request does not strand it.
+const DELIVERY_LEASE: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outbound, signed merchant webhooks. `emit_in` stores the event and one
+/// delivery per subscribed endpoint in the caller's transaction, so an
+/// event exists exactly when the change it reports was committed;
+/// `deliver_due` sends them, retrying with exponential backoff until the
+/// endpoint answers 2xx or attempts run out.
+pub struct WebhookService {
+    db: PgPool,
+    merchant_service: Arc<MerchantService>,
+    retry: RetryPolicy,
+    destinations: DestinationPolicy,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ClaimedDelivery {
+    delivery_id: Uuid,
+    event_id: Uuid,
+    attempts: i32,
+    url: String,
+    secret: String,
+    payload: String,
+}
+
+impl WebhookService {
+    pub fn new(db: PgPool, merchant_service: Arc<MerchantService>, retry: RetryPolicy) -> Self {
+        Self {
+            db,
+            merchant_service,
+            retry,
+            destinations: DestinationPolicy { allow_loopback: false },
+        }
+    }
+
+    /// Accept endpoints on this machine, over plain HTTP too. Only for
+    /// local development.
+    pub fn allow_loopback_endpoints(mut self, allow: bool) -> Self {
+        self.destinations.allow_loopback = allow;
+        self
+    }
+
+    pub async fn register_endpoint(
+        &self,
+        owner_user_id: Uuid,
+        merchant_id: Uuid,
+        req: RegisterEndpointRequest,
+    ) -> Result<RegisteredEndpoint, WebhookError> {
+        req.validate()?;
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+        if let Err(e) = self.destinations.resolve(&req.url).await {
+            let mut errors = validator::ValidationErrors::new();
+            errors.add("url", validator::ValidationError::new(e.code()));
+            return Err(errors.into());
+        }
+
+        let secret = format!("whsec_{}", hex::encode(rand::thread_rng().gen::<[u8; 24]>()));
+        let endpoint = sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            INSERT INTO webhook_endpoints (merchant_id, url, secret, events)
+            VALUES ($1, $2, $3, $4)
+            RETURNING endpoint_id, merchant_id, url, secret, events, status, created_at, updated_at
+            "#,
+            merchant_id,
+            req.url,
+            secret,
+            &req.events
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        info!(merchant_id = %merchant_id, endpoint_id = %endpoint.endpoint_id, "Webhook endpoint registered");
+        Ok(RegisteredEndpoint { endpoint, secret })
+    }
+
+    pub async fn list_endpoints(&self, owner_user_id: Uuid, merchant_id: Uuid) -> Result<Vec<WebhookEndpoint>, WebhookError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let endpoints = sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            SELECT endpoint_id, merchant_id, url, secret, events, status, created_at, updated_at
+            FROM webhook_endpoints
+            WHERE merchant_id = $1
+            ORDER BY created_at
+            "#,
+            merchant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(endpoints)
+    }
+
+    /// Stops new deliveries to the endpoint; pending ones are dropped.
+    pub async fn disable_endpoint(
+        &self,
+        owner_user_id: Uuid,
+        merchant_id: Uuid,
+        endpoint_id: Uuid,
+    ) -> Result<WebhookEndpoint, WebhookError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let mut tx = self.db.begin().await?;
+        let endpoint = sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            UPDATE webhook_endpoints
+            SET status = 'DISABLED', updated_at = NOW()
+            WHERE endpoint_id = $1 AND merchant_id = $2
+            RETURNING endpoint_id, merchant_id, url, secret, events, status, created_at, updated_at
+            "#,
+            endpoint_id,
+            merchant_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(WebhookError::EndpointNotFound(endpoint_id))?;
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'FAILED', next_attempt_at = NULL, last_error = 'endpoint disabled', updated_at = NOW()
+            WHERE endpoint_id = $1 AND status = 'PENDING'
+            "#,
+            endpoint_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        info!(endpoint_id = %endpoint_id, "Webhook endpoint disabled");
+        Ok(endpoint)
+    }
+
+    /// `emit_in` on its own, for events not tied to a change of ours.
+    pub async fn emit(&self, merchant_id: Uuid, event_type: &str, data: serde_json::Value) -> Result<Uuid, WebhookError> {
+        let mut tx = self.db.begin().await?;
+        let event_id = self.emit_in(&mut tx, merchant_id, event_type, data).await?;
+        tx.commit().await?;
+        Ok(event_id)
+    }
+
+    /// Emits an event for a change committed elsewhere, at most once per
+    /// `source_key`. The caller emits again whenever it replays the change,
+    /// so an event lost to a failure here is sent on the retry.
+    pub async fn emit_once(
+        &self,
+        merchant_id: Uuid,
+        source_key: &str,
+        event_type: &str,
+        data: serde_json::Value,
+    ) -> Result<Option<Uuid>, WebhookError> {
+        let mut tx = self.db.begin().await?;
+        let emitted = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM webhook_events WHERE source_key = $1) AS "exists!""#,
+            source_key
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if emitted {
+            return Ok(None);
+        }
+
+        let event_id = self.emit_in(&mut tx, merchant_id, event_type, data).await?;
+        sqlx::query!("UPDATE webhook_events SET source_key = $2 WHERE event_id = $1", event_id, source_key)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(Some(event_id))
+    }
+
+    /// Records `event_type` for the merchant and queues a delivery to every
+    /// active endpoint subscribed to it. The body is fixed here, so retries
+    /// send (and sign) exactly the same bytes.
+    #[instrument(skip(self, tx, data), fields(merchant_id = %merchant_id, event_type))]
+    pub async fn emit_in(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        merchant_id: Uuid,
+        event_type: &str,
+        data: serde_json::Value,
+    ) -> Result<Uuid, WebhookError> {
+        let event_id = Uuid::new_v4();
+        let created_at = chrono::Utc::now();
+        let payload = serde_json::json!({
+            "id": event_id,
+            "type": event_type,
+            "api_version": "v1",
+            "created": created_at.timestamp(),
+            "data": data,
+        })
+        .to_string();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_events (event_id, merchant_id, event_type, payload, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            event_id,
+            merchant_id,
+            event_type,
+            payload,
+            created_at
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        let queued = sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries (event_id, endpoint_id, next_attempt_at)
+            SELECT $1, endpoint_id, NOW()
+            FROM webhook_endpoints
+            WHERE merchant_id = $2 AND status = 'ACTIVE' AND ($3 = ANY(events) OR '*' = ANY(events))
+            "#,
+            event_id,
+            merchant_id,
+            event_type
+        )
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+
+        counter!("webhook_events_total", 1, "type" => event_type.to_string());
+        info!(event_id = %event_id, deliveries = queued, "Webhook event queued");
+        Ok(event_id)
+    }
+
+    /// Sends every delivery that is due.
+    pub async fn deliver_due(&self) -> Result<DeliveryRunReport, WebhookError> {
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT delivery_id
+            FROM webhook_deliveries
+            WHERE status = 'PENDING' AND next_attempt_at <= NOW()
+            ORDER BY next_attempt_at
+            LIMIT 100
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut report = DeliveryRunReport::default();
+        for delivery_id in due {
+            match self.deliver(delivery_id).await {
+                Ok(Some(status)) if status == "SUCCEEDED" => report.delivered += 1,
+                Ok(Some(status)) if status == "FAILED" => report.failed += 1,
+                Ok(Some(_)) => report.retrying += 1,
+                Ok(None) => {} // claimed by another worker
+                Err(e) => warn!(delivery_id = %delivery_id, error = %e, "Webhook delivery errored"),
+            }
+        }
+        Ok(report)
+    }
+
+    /// One attempt at one delivery. Returns the delivery's new status, or
+    /// nothing if it was not due (or someone else holds it).
+    pub async fn deliver(&self, delivery_id: Uuid) -> Result<Option<String>, WebhookError> {
+        // Step 1: Claim with a lease
+        let claimed = sqlx::query_as!(
+            ClaimedDelivery,
+            r#"
+            WITH claimed AS (
+                UPDATE webhook_deliveries
+                SET attempts = attempts + 1, next_attempt_at = NOW() + make_interval(secs => $2), updated_at = NOW()
+                WHERE delivery_id = $1 AND status = 'PENDING' AND next_attempt_at <= NOW()
+                RETURNING delivery_id, event_id, endpoint_id, attempts
+            )
+            SELECT c.delivery_id, c.event_id, c.attempts, e.url, e.secret, ev.payload
+            FROM claimed c
+            JOIN webhook_endpoints e ON e.endpoint_id = c.endpoint_id
+            JOIN webhook_events ev ON ev.event_id = c.event_id
+            "#,
+            delivery_id,
+            DELIVERY_LEASE.as_secs_f64()
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let Some(delivery) = claimed else {
+            return Ok(None);
+        };
+
+        // Step 2: POST the signed body. The host is resolved and checked
+        // again, since its DNS may have changed since registration.
+        let started = std::time::Instant::now();
+        let result = match self.destinations.resolve(&delivery.url).await {
+            Ok((host, addrs)) => self.post(&delivery, &host, &addrs).await,
+            Err(e) => Err(e.to_string()),
+        };
+        let elapsed = started.elapsed();
+        histogram!("webhook_delivery_duration_seconds", elapsed.as_secs_f64());
+
+        let (status_code, error) = match &result {
+            Ok(resp) if resp.status().is_success() => (Some(resp.status().as_u16() as i32), None),
+            Ok(resp) => (Some(resp.status().as_u16() as i32), Some(format!("endpoint returned {}", resp.status()))),
+            Err(e) => (None, Some(e.clone())),
+        };
+
+        // Step 3: Log the attempt and schedule what comes next
+        let status = match (&error, delivery.attempts < self.retry.max_attempts) {
+            (None, _) => "SUCCEEDED",
+            (Some(_), true) => "PENDING",
+            (Some(_), false) => "FAILED",
+        };
+        let next_attempt_at = (status == "PENDING").then(|| {
+            chrono::Utc::now() + chrono::Duration::seconds(self.retry.delay_after(delivery.attempts).as_secs() as i64)
+        });
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_attempts (delivery_id, attempt, status_code, error, duration_ms)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            delivery_id,
+            delivery.attempts,
+            status_code,
+            error,
+            elapsed.as_millis() as i32
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2,
+                next_attempt_at = $3,
+                last_status_code = $4,
+                last_error = $5,
+                delivered_at = CASE WHEN $2 = 'SUCCEEDED' THEN NOW() ELSE delivered_at END,
+                updated_at = NOW()
+            WHERE delivery_id = $1
+            "#,
+            delivery_id,
+            status,
+            next_attempt_at,
+            status_code,
+            error
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        counter!("webhook_deliveries_total", 1, "status" => status);
+        match &error {
+            None => info!(delivery_id = %delivery_id, attempt = delivery.attempts, "Webhook delivered"),
+            Some(e) => warn!(delivery_id = %delivery_id, attempt = delivery.attempts, error = %e, status, "Webhook delivery failed"),
+        }
+        Ok(Some(status.to_string()))
+    }
+
+    /// Connects only to the addresses that passed the destination check,
+    /// and never follows redirects.
+    async fn post(&self, delivery: &ClaimedDelivery, host: &str, addrs: &[SocketAddr]) -> Result<reqwest::Response, String> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve_to_addrs(host, addrs)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        client
+            .post(&delivery.url)
+            .header("Content-Type", "application/json")
+            .header("Webhook-Id", delivery.event_id.to_string())
+            .header(SIGNATURE_HEADER, signature::sign(&delivery.secret, timestamp, &delivery.payload))
+            .body(delivery.payload.clone())
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Sends a delivery again right away, whatever its state, e.g. after
+    /// the merchant fixed their endpoint or lost the original. Not to a
+    /// disabled endpoint.
+    pub async fn redeliver(
+        &self,
+        owner_user_id: Uuid,
+        merchant_id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<DeliveryLog, WebhookError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let reset = sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries d
+            SET status = 'PENDING', next_attempt_at = NOW(), updated_at = NOW()
+            FROM webhook_events ev, webhook_endpoints e
+            WHERE d.delivery_id = $1 AND ev.event_id = d.event_id AND ev.merchant_id = $2
+              AND e.endpoint_id = d.endpoint_id AND e.status = 'ACTIVE'
+            "#,
+            delivery_id,
+            merchant_id
+        )
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+
+        if reset == 0 {
+            let endpoint_id = sqlx::query_scalar!(
+                r#"
+                SELECT d.endpoint_id
+                FROM webhook_deliveries d
+                JOIN webhook_events ev ON ev.event_id = d.event_id
+                WHERE d.delivery_id = $1 AND ev.merchant_id = $2
+                "#,
+                delivery_id,
+                merchant_id
+            )
+            .fetch_optional(&self.db)
+            .await?;
+
+            return Err(match endpoint_id {
+                Some(endpoint_id) => WebhookError::EndpointDisabled(endpoint_id),
+                None => WebhookError::DeliveryNotFound(delivery_id),
+            });
+        }
+
+        counter!("webhook_redeliveries_total", 1);
+        self.deliver(delivery_id).await?;
+        self.delivery_log(owner_user_id, merchant_id, delivery_id).await
+    }
+
+    /// Most recent deliveries first.
+    pub async fn list_deliveries(&self, owner_user_id: Uuid, merchant_id: Uuid) -> Result<Vec<WebhookDelivery>, WebhookError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let deliveries = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT d.delivery_id, d.event_id, ev.event_type, d.endpoint_id, e.url, d.status, d.attempts,
+                   d.next_attempt_at, d.last_status_code, d.last_error, d.delivered_at, d.created_at
+            FROM webhook_deliveries d
+            JOIN webhook_events ev ON ev.event_id = d.event_id
+            JOIN webhook_endpoints e ON e.endpoint_id = d.endpoint_id
+            WHERE ev.merchant_id = $1
+            ORDER BY d.created_at DESC
+            LIMIT 100
+            "#,
+            merchant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    pub async fn delivery_log(
+        &self,
+        owner_user_id: Uuid,
+        merchant_id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<DeliveryLog, WebhookError> {
+        self.merchant_service.get(owner_user_id, merchant_id).await?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT d.delivery_id, d.event_id, ev.event_type, d.endpoint_id, e.url, d.status, d.attempts,
+                   d.next_attempt_at, d.last_status_code, d.last_error, d.delivered_at, d.created_at,
+                   ev.payload
+            FROM webhook_deliveries d
+            JOIN webhook_events ev ON ev.event_id = d.event_id
+            JOIN webhook_endpoints e ON e.endpoint_id = d.endpoint_id
+            WHERE d.delivery_id = $1 AND ev.merchant_id = $2
+            "#,
+            delivery_id,
+            merchant_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(WebhookError::DeliveryNotFound(delivery_id))?;
+
+        let attempts = sqlx::query_as!(
+            WebhookAttempt,
+            r#"
+            SELECT attempt, status_code, error, duration_ms, created_at
+            FROM webhook_attempts
+            WHERE delivery_id = $1
+            ORDER BY id
+            "#,
+            delivery_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(DeliveryLog {
+            delivery: WebhookDelivery {
+                delivery_id: row.delivery_id,
+                event_id: row.event_id,
+                event_type: row.event_type,
+                endpoint_id: row.endpoint_id,
+                url: row.url,
+                status: row.status,
+                attempts: row.attempts,
+                next_attempt_at: row.next_attempt_at,
+                last_status_code: row.last_status_code,
+                last_error: row.last_error,
+                delivered_at: row.delivered_at,
+                created_at: row.created_at,
+            },
+            payload: row.payload,
+            attempts,
+        })
+    }
+}
@@ -1,8 +1,26 @@
 // tests/common/mod.rs
 use sqlx::{PgPool, Postgres, Pool};
 use redis::{Client, AsyncCommands};
+use payment_system::merchant::{MerchantService, models::*};
+use payment_system::payment::PaymentService;
+use payment_system::payment::service::NatsClient;
+use payment_system::wallet::{WalletService, models::*};
+use mockall::mock;
+use async_trait::async_trait;
 use std::sync::Arc;
 
+mock! {
+    pub NatsClient {}
+    #[async_trait]
+    impl NatsClient for NatsClient {
+        async fn publish_fraud_event(&self, event: &payment_system::payment::models::FraudEvent) -> Result<(), Box<dyn std::error::Error>>;
+    }
+}
+
+pub const PAYEE_MOBILE: &str = "+919876543210";
+pub const PAYER_MOBILE: &str = "+919876543211";
+pub const OWNER_MOBILE: &str = "+919812345678";
+
 pub struct TestContext {
     pub db: PgPool,
     pub redis_client: Client,
@@ -25,7 +43,7 @@ impl TestContext {
 
     pub async fn cleanup(&self) {
         // Truncate all tables
-        sqlx::query!("TRUNCATE TABLE users, wallets, transaction_journal, daily_limits, idempotency_keys, refresh_tokens, fraud_flags, otp_store RESTART IDENTITY")
+        sqlx::query!("TRUNCATE TABLE merchant_rollup_queue, merchant_hourly_rollups, api_key_nonces, merchant_api_keys, webhook_attempts, webhook_deliveries, webhook_events, webhook_endpoints, transaction_fees, fee_invoices, settlement_attempts, settlement_items, settlement_batches, payment_intents, merchants, users, wallets, transaction_journal, daily_limits, idempotency_keys, refresh_tokens, fraud_flags, otp_store, ledger_postings, ledger_entries, payment_state_transitions, wallet_holds, split_participants, splits, collect_requests, mandate_executions, mandates, qr_redemptions, payment_handles, released_handles, fake_kyc_verifications, fake_bank_accounts, fake_bank_transfers RESTART IDENTITY")
             .execute(&self.db)
             .await
             .unwrap();
@@ -40,4 +58,115 @@ pub fn new_uuid() -> uuid::Uuid {
 // Helper to generate JWT
 pub fn generate_jwt(user_id: &uuid::Uuid, secret: &str) -> String {
     crate::auth::crypto::create_jwt(user_id, None, secret, 3600).unwrap()
-}
\ No newline at end of file
+}
+
+// Helper to build a PaymentService with the test secrets and a mocked NATS client
+pub fn payment_service(db: &PgPool, wallets: Arc<WalletService>) -> PaymentService {
+    PaymentService::new(
+        db.clone(),
+        wallets,
+        "otp_secret".to_string(),
+        "qr_secret".to_string(),
+        Arc::new(MockNatsClient::new()),
+    )
+}
+
+// Helper to register a user reachable by `mobile`
+pub async fn create_user(db: &PgPool, mobile: &str) -> uuid::Uuid {
+    let user_id = new_uuid();
+    let mobile_hash = payment_system::auth::crypto::hash_mobile(mobile, "otp_secret");
+    sqlx::query!("INSERT INTO users (id, mobile_hash) VALUES ($1, $2)", user_id, mobile_hash)
+        .execute(db)
+        .await
+        .unwrap();
+    user_id
+}
+
+// Helper to open a wallet holding `balance` paise
+pub async fn create_wallet(wallets: &WalletService, user_id: uuid::Uuid, balance: u64) {
+    wallets.create_wallet(CreateWalletRequest { user_id }).await.unwrap();
+    if balance > 0 {
+        credit(wallets, user_id, balance).await;
+    }
+}
+
+// Helper to top up a wallet
+pub async fn credit(wallets: &WalletService, user_id: uuid::Uuid, amount: u64) {
+    wallets.credit(&CreditDebitRequest {
+        user_id,
+        amount,
+        idempotency_key: new_uuid().to_string(),
+    }).await.unwrap();
+}
+
+/// A payer holding `balance` paise and an empty payee, registered on
+/// `PAYER_MOBILE` and `PAYEE_MOBILE`, both with wallets.
+pub struct Parties {
+    pub wallets: Arc<WalletService>,
+    pub payments: Arc<PaymentService>,
+    pub payer: uuid::Uuid,
+    pub payee: uuid::Uuid,
+}
+
+impl Parties {
+    pub async fn new(ctx: &TestContext, balance: u64) -> Self {
+        let wallets = Arc::new(WalletService::new(ctx.db.clone()));
+        let payments = Arc::new(payment_service(&ctx.db, wallets.clone()));
+
+        let payer = create_user(&ctx.db, PAYER_MOBILE).await;
+        create_wallet(&wallets, payer, balance).await;
+        let payee = create_user(&ctx.db, PAYEE_MOBILE).await;
+        create_wallet(&wallets, payee, 0).await;
+
+        Self { wallets, payments, payer, payee }
+    }
+}
+
+// Helper to describe Chai Point, optionally registered for GST
+pub fn onboarding(gstin: Option<&str>) -> OnboardMerchantRequest {
+    OnboardMerchantRequest {
+        legal_name: "Chai Point Private Limited".to_string(),
+        display_name: "Chai Point".to_string(),
+        gstin: gstin.map(str::to_string),
+        pan: "AABCC1234D".to_string(),
+        category_code: "5812".to_string(),
+        settlement_account_number: "123456789012".to_string(),
+        settlement_ifsc: "HDFC0001234".to_string(),
+        settlement_account_name: "Chai Point Pvt Ltd".to_string(),
+    }
+}
+
+/// A merchant owner registered on `OWNER_MOBILE` and a payer holding
+/// `balance` paise, with the services merchant tests build on.
+pub struct Shop {
+    pub wallets: Arc<WalletService>,
+    pub merchants: Arc<MerchantService>,
+    pub payments: Arc<PaymentService>,
+    pub owner: uuid::Uuid,
+    pub payer: uuid::Uuid,
+}
+
+impl Shop {
+    pub async fn new(ctx: &TestContext, balance: u64) -> Self {
+        let wallets = Arc::new(WalletService::new(ctx.db.clone()));
+        let merchants = Arc::new(MerchantService::new(ctx.db.clone(), wallets.clone()));
+        let payments = Arc::new(payment_service(&ctx.db, wallets.clone()));
+
+        let owner = create_user(&ctx.db, OWNER_MOBILE).await;
+        let payer = new_uuid();
+        create_wallet(&wallets, payer, balance).await;
+
+        Self { wallets, merchants, payments, owner, payer }
+    }
+
+    /// Onboards the owner's merchant and activates it; returns its id.
+    pub async fn open(&self, req: OnboardMerchantRequest) -> uuid::Uuid {
+        let merchant = self.merchants.onboard(self.owner, req).await.unwrap();
+        self.merchants.set_status(
+            merchant.merchant_id,
+            UpdateMerchantStatusRequest { status: MerchantStatus::Active, reason: None },
+            "ops@test",
+        ).await.unwrap();
+        merchant.merchant_id
+    }
+}
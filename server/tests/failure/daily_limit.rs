@@ -9,6 +9,7 @@ async fn test_daily_limit_blocks_payments() {
         ctx.db.clone(),
         wallet_service.clone(),
         "otp_secret".to_string(),
+        "qr_secret".to_string(),
         Arc::new(MockNatsClient::new()),
     );
 
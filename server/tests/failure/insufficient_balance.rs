@@ -10,6 +10,7 @@ async fn test_payment_fails_with_insufficient_balance() {
         ctx.db.clone(),
         wallet_service.clone(),
         "otp_secret".to_string(),
+        "qr_secret".to_string(),
         Arc::new(MockNatsClient::new()),
     );
 
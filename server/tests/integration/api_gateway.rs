@@ -1,6 +1,7 @@
 // tests/integration/api_gateway.rs
 use crate::common::{TestContext, new_uuid, generate_jwt};
 use payment_system::main;
+use payment_system::wallet::models::BalanceResponse;
 use axum::{
     body::Body,
     http::{Request, StatusCode},
@@ -50,6 +51,6 @@ async fn test_get_balance_with_valid_jwt() {
 
     assert_eq!(res.status(), StatusCode::OK);
     let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
-    let balance: i64 = serde_json::from_slice(&body).unwrap();
-    assert_eq!(balance, 25000);
+    let balance: BalanceResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(balance, BalanceResponse { balance: 25000, available: 25000, held: 0 });
 }
\ No newline at end of file
@@ -0,0 +1,130 @@
+// tests/integration/idempotency.rs
+use crate::common::TestContext;
+use payment_system::middleware::idempotency::IdempotencyLayer;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware::{self, Next},
+    routing::post,
+    Router,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tower::ServiceExt;
+
+fn app(ctx: &TestContext, calls: Arc<AtomicUsize>) -> Router {
+    Router::new()
+        .route("/echo", post(move || {
+            let calls = calls.clone();
+            async move {
+                let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                format!("call {}", n)
+            }
+        }))
+        .route("/slow", post(|| async {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            "done"
+        }))
+        .layer(IdempotencyLayer::new(ctx.redis_client.clone(), 60))
+        .layer(middleware::from_fn(authenticate))
+}
+
+/// Stand-in for the JWT middleware: `X-User` carries the user id.
+async fn authenticate(mut req: Request<Body>, next: Next) -> axum::response::Response {
+    let user_id = req.headers().get("X-User")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| uuid::Uuid::parse_str(v).ok());
+    if let Some(user_id) = user_id {
+        req.extensions_mut().insert(user_id);
+    }
+    next.run(req).await
+}
+
+const USER: &str = "6f1c2a7e-3b7d-4a51-9a53-0c5b3f0f6d11";
+
+fn request(uri: &str, key: &str) -> Request<Body> {
+    request_as(Some(USER), uri, key, "")
+}
+
+fn request_as(user: Option<&str>, uri: &str, key: &str, body: &str) -> Request<Body> {
+    let mut builder = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("Idempotency-Key", key);
+    if let Some(user) = user {
+        builder = builder.header("X-User", user);
+    }
+    builder.body(Body::from(body.to_string())).unwrap()
+}
+
+#[tokio::test]
+async fn test_retry_replays_stored_response() {
+    let ctx = TestContext::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = app(&ctx, calls.clone());
+
+    let first = app.clone().oneshot(request("/echo", "key-1")).await.unwrap();
+    let second = app.clone().oneshot(request("/echo", "key-1")).await.unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::OK);
+    assert_eq!(second.headers()["Idempotent-Replayed"], "true");
+    let body = hyper::body::to_bytes(second.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"call 1");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_concurrent_request_gets_conflict() {
+    let ctx = TestContext::new().await;
+    let app = app(&ctx, Arc::new(AtomicUsize::new(0)));
+
+    let first = tokio::spawn(app.clone().oneshot(request("/slow", "key-2")));
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let second = app.clone().oneshot(request("/slow", "key-2")).await.unwrap();
+
+    assert_eq!(second.status(), StatusCode::CONFLICT);
+    assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_key_reused_with_another_body_is_refused() {
+    let ctx = TestContext::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = app(&ctx, calls.clone());
+
+    let first = app.clone().oneshot(request_as(Some(USER), "/echo", "key-3", r#"{"amount":100}"#)).await.unwrap();
+    let changed = app.clone().oneshot(request_as(Some(USER), "/echo", "key-3", r#"{"amount":900}"#)).await.unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(changed.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_keys_are_scoped_per_user() {
+    let ctx = TestContext::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = app(&ctx, calls.clone());
+    let other = "0b8e7f5c-52a4-4d1e-8f0e-7d0f3c6a2b90";
+
+    app.clone().oneshot(request_as(Some(USER), "/echo", "key-4", "")).await.unwrap();
+    let theirs = app.clone().oneshot(request_as(Some(other), "/echo", "key-4", "")).await.unwrap();
+    assert!(theirs.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_anonymous_requests_share_one_scope() {
+    let ctx = TestContext::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = app(&ctx, calls.clone());
+
+    app.clone().oneshot(request_as(None, "/echo", "key-5", r#"{"mobile":"+919812345678"}"#)).await.unwrap();
+    let again = app.clone().oneshot(request_as(None, "/echo", "key-5", r#"{"mobile":"+919812345678"}"#)).await.unwrap();
+    let changed = app.clone().oneshot(request_as(None, "/echo", "key-5", r#"{"mobile":"+919876543210"}"#)).await.unwrap();
+
+    assert_eq!(again.headers()["Idempotent-Replayed"], "true");
+    assert_eq!(changed.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
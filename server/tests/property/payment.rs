@@ -14,6 +14,7 @@ proptest! {
             ctx.db.clone(),
             wallet_service.clone(),
             "otp_secret".to_string(),
+            "qr_secret".to_string(),
             Arc::new(MockNatsClient::new()),
         );
 
@@ -49,11 +50,19 @@ proptest! {
 
         let first_result = service.pay_by_phone(sender_id, req.clone()).await;
 
-        // Second payment with same idempotency key
-        let second_result = service.pay_by_phone(sender_id, req).await;
+        // Second payment with same idempotency key and payload
+        let second_result = service.pay_by_phone(sender_id, req.clone()).await;
 
-        // Property: First succeeds, second fails with DuplicateIdempotencyKey
-        prop_assert!(first_result.is_ok());
-        prop_assert!(matches!(second_result, Err(PaymentError::DuplicateIdempotencyKey)));
+        // Property: retry replays the original result without moving money again
+        let first = first_result.unwrap();
+        let second = second_result.unwrap();
+        prop_assert_eq!(first.tx_id, second.tx_id);
+        prop_assert_eq!(first.amount, second.amount);
+        prop_assert_eq!(wallet_service.get_balance(&receiver_id).await.unwrap(), amount as i64);
+
+        // Property: same key with a different payload is a conflict
+        let conflicting = PayByPhoneRequest { amount: amount + 1, ..req };
+        let third_result = service.pay_by_phone(sender_id, conflicting).await;
+        prop_assert!(matches!(third_result, Err(PaymentError::IdempotencyConflict)));
     }
 }
\ No newline at end of file
@@ -0,0 +1,204 @@
+// tests/unit/api_key.rs
+use crate::common::{Shop, TestContext, new_uuid, onboarding};
+use payment_system::api_key::{ApiKeyService, models::*, signature};
+use payment_system::merchant::{MerchantService, models::*};
+use payment_system::wallet::WalletService;
+use std::sync::Arc;
+use uuid::Uuid;
+
+struct Fixture {
+    api_keys: ApiKeyService,
+    owner: Uuid,
+    merchant_id: Uuid,
+}
+
+/// A merchant onboarded by its owner, not yet activated.
+async fn setup(ctx: &TestContext) -> Fixture {
+    let shop = Shop::new(ctx, 0).await;
+    let merchant = shop.merchants.onboard(shop.owner, onboarding(None)).await.unwrap();
+
+    Fixture {
+        api_keys: ApiKeyService::new(ctx.db.clone(), shop.merchants.clone(), "api_key_secret".to_string()),
+        owner: shop.owner,
+        merchant_id: merchant.merchant_id,
+    }
+}
+
+fn create(scopes: &[&str]) -> CreateApiKeyRequest {
+    CreateApiKeyRequest {
+        name: Some("checkout-server".to_string()),
+        scopes: scopes.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+/// A request as a merchant's server would sign it.
+struct Call {
+    publishable_key: String,
+    timestamp: i64,
+    nonce: String,
+    signature: String,
+    body: Vec<u8>,
+}
+
+const PATH: &str = "/v1/payment_intents";
+
+fn signed(key: &IssuedApiKey, body: &str) -> Call {
+    let timestamp = chrono::Utc::now().timestamp();
+    let nonce = Uuid::new_v4().simple().to_string();
+    Call {
+        publishable_key: key.publishable_key.clone(),
+        timestamp,
+        signature: signature::sign(&key.secret_key, "POST", PATH, timestamp, &nonce, body.as_bytes()),
+        nonce,
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+async fn send(f: &Fixture, call: &Call) -> Result<ApiKeyContext, ApiKeyError> {
+    f.api_keys.authenticate(&SignedRequest {
+        publishable_key: &call.publishable_key,
+        timestamp: call.timestamp,
+        nonce: &call.nonce,
+        signature: &call.signature,
+        method: "POST",
+        path: PATH,
+        body: &call.body,
+    }, chrono::Utc::now()).await
+}
+
+#[tokio::test]
+async fn test_signed_request_authenticates_once() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let key = f.api_keys.create(f.owner, f.merchant_id, create(&[SCOPE_PAYMENT_INTENTS_WRITE])).await.unwrap();
+    assert!(key.publishable_key.starts_with("pk_live_") && key.secret_key.starts_with("sk_live_"));
+
+    let call = signed(&key, r#"{"amount":12000}"#);
+    let context = send(&f, &call).await.unwrap();
+    assert_eq!((context.merchant_id, context.owner_user_id), (f.merchant_id, f.owner));
+    assert!(context.require(SCOPE_PAYMENT_INTENTS_WRITE).is_ok());
+    assert!(matches!(context.require(SCOPE_REFUNDS_WRITE), Err(ApiKeyError::MissingScope(_))));
+
+    // The same request again is a replay
+    assert!(matches!(send(&f, &call).await, Err(ApiKeyError::ReplayedNonce)));
+
+    // A changed body no longer matches its signature
+    let mut tampered = signed(&key, r#"{"amount":12000}"#);
+    tampered.body = br#"{"amount":1}"#.to_vec();
+    assert!(matches!(send(&f, &tampered).await, Err(ApiKeyError::InvalidSignature)));
+
+    // Nor does one signed with someone else's secret
+    let mut forged = signed(&key, "{}");
+    forged.signature = signature::sign("sk_live_guess", "POST", PATH, forged.timestamp, &forged.nonce, b"{}");
+    assert!(matches!(send(&f, &forged).await, Err(ApiKeyError::InvalidSignature)));
+
+    // A request signed ten minutes ago is refused even with a fresh nonce
+    let mut stale = signed(&key, "{}");
+    stale.timestamp -= 600;
+    stale.signature = signature::sign(&key.secret_key, "POST", PATH, stale.timestamp, &stale.nonce, b"{}");
+    assert!(matches!(send(&f, &stale).await, Err(ApiKeyError::StaleTimestamp)));
+
+    let mut unknown = signed(&key, "{}");
+    unknown.publishable_key = "pk_live_unknown".to_string();
+    assert!(matches!(send(&f, &unknown).await, Err(ApiKeyError::InvalidKey)));
+
+    let listed = f.api_keys.list(f.owner, f.merchant_id).await.unwrap();
+    assert!(listed[0].last_used_at.is_some());
+}
+
+#[tokio::test]
+async fn test_secrets_are_not_stored_in_the_clear() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let key = f.api_keys.create(f.owner, f.merchant_id, create(&[SCOPE_PAYMENT_INTENTS_READ])).await.unwrap();
+
+    let row = sqlx::query!("SELECT publishable_hash, publishable_prefix, secret_ciphertext, secret_last4 FROM merchant_api_keys")
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert!(!row.publishable_hash.contains(&key.publishable_key[8..]));
+    assert!(!row.secret_ciphertext.contains(&key.secret_key[8..]));
+    // Nor anything a request could be signed with
+    assert!(!row.secret_ciphertext.contains(&signature::signing_key(&key.secret_key)));
+    assert!(key.publishable_key.starts_with(&row.publishable_prefix));
+    assert!(key.secret_key.ends_with(&row.secret_last4));
+
+    let listed = serde_json::to_string(&f.api_keys.list(f.owner, f.merchant_id).await.unwrap()).unwrap();
+    assert!(!listed.contains(&key.secret_key) && !listed.contains(&key.publishable_key));
+
+    let err = f.api_keys.create(f.owner, f.merchant_id, create(&["admin"])).await.unwrap_err();
+    assert!(matches!(err, ApiKeyError::ValidationError(_)));
+    let err = f.api_keys.create(new_uuid(), f.merchant_id, create(&[SCOPE_PAYMENT_INTENTS_READ])).await.unwrap_err();
+    assert!(matches!(err, ApiKeyError::MerchantError(MerchantError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_stored_secret_is_bound_to_its_key() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let first = f.api_keys.create(f.owner, f.merchant_id, create(&[SCOPE_PAYMENT_INTENTS_READ])).await.unwrap();
+    let second = f.api_keys.create(f.owner, f.merchant_id, create(&[SCOPE_PAYMENT_INTENTS_READ])).await.unwrap();
+
+    // Copying one key's sealed secret onto another does not let it sign as that key
+    sqlx::query!(
+        r#"
+        UPDATE merchant_api_keys
+        SET secret_ciphertext = (SELECT secret_ciphertext FROM merchant_api_keys WHERE key_id = $1)
+        WHERE key_id = $2
+        "#,
+        first.key.key_id,
+        second.key.key_id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+    let mut forged = signed(&first, "{}");
+    forged.publishable_key = second.publishable_key.clone();
+    assert!(matches!(send(&f, &forged).await, Err(ApiKeyError::InvalidKey)));
+
+    // Nor does the database alone, without the server's encryption secret
+    let wallets = Arc::new(WalletService::new(ctx.db.clone()));
+    let elsewhere = Fixture {
+        api_keys: ApiKeyService::new(ctx.db.clone(), Arc::new(MerchantService::new(ctx.db.clone(), wallets)), "other".to_string()),
+        owner: f.owner,
+        merchant_id: f.merchant_id,
+    };
+    assert!(matches!(send(&elsewhere, &signed(&first, "{}")).await, Err(ApiKeyError::InvalidKey)));
+    assert!(send(&f, &signed(&first, "{}")).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_rotation_keeps_old_key_for_grace_period_and_revocation_is_immediate() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let scopes = [SCOPE_PAYMENT_INTENTS_READ, SCOPE_PAYMENT_INTENTS_WRITE];
+    let old = f.api_keys.create(f.owner, f.merchant_id, create(&scopes)).await.unwrap();
+
+    let new = f.api_keys
+        .rotate(f.owner, f.merchant_id, old.key.key_id, RotateApiKeyRequest { grace_hours: None })
+        .await
+        .unwrap();
+    assert_eq!(new.key.scopes, old.key.scopes);
+    // Both work during the grace period
+    assert!(send(&f, &signed(&old, "{}")).await.is_ok());
+    assert!(send(&f, &signed(&new, "{}")).await.is_ok());
+    // A key is only rotated once
+    let err = f.api_keys
+        .rotate(f.owner, f.merchant_id, old.key.key_id, RotateApiKeyRequest { grace_hours: None })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ApiKeyError::InvalidStatus(s) if s == "ROTATED"));
+
+    // No grace: the replaced key stops at once
+    let newest = f.api_keys
+        .rotate(f.owner, f.merchant_id, new.key.key_id, RotateApiKeyRequest { grace_hours: Some(0) })
+        .await
+        .unwrap();
+    assert!(matches!(send(&f, &signed(&new, "{}")).await, Err(ApiKeyError::InvalidKey)));
+
+    let revoked = f.api_keys.revoke(f.owner, f.merchant_id, newest.key.key_id).await.unwrap();
+    assert_eq!(revoked.status, "REVOKED");
+    assert!(matches!(send(&f, &signed(&newest, "{}")).await, Err(ApiKeyError::InvalidKey)));
+    let err = f.api_keys.revoke(f.owner, f.merchant_id, newest.key.key_id).await.unwrap_err();
+    assert!(matches!(err, ApiKeyError::InvalidStatus(s) if s == "REVOKED"));
+}
@@ -0,0 +1,138 @@
+// tests/unit/collect.rs
+use crate::common::{TestContext, Parties, PAYER_MOBILE, credit};
+use payment_system::collect::{CollectService, models::*};
+use payment_system::payment::models::*;
+use payment_system::wallet::models::*;
+use payment_system::ws::server::WsServer;
+use std::sync::Arc;
+
+/// A payer with ₹500, asked for money by the payee (empty wallet).
+async fn setup(ctx: &TestContext) -> (CollectService, Parties) {
+    let f = Parties::new(ctx, 50000).await;
+    let service = CollectService::new(ctx.db.clone(), f.payments.clone(), Arc::new(WsServer::new()));
+    (service, f)
+}
+
+fn collect_req(amount: u64) -> CreateCollectRequest {
+    CreateCollectRequest {
+        payer_mobile: Some(PAYER_MOBILE.to_string()),
+        payer_handle: None,
+        amount,
+        note: Some("dinner".to_string()),
+        expires_in_secs: None,
+    }
+}
+
+#[tokio::test]
+async fn test_approve_pays_requester_once() {
+    let ctx = TestContext::new().await;
+    let (service, f) = setup(&ctx).await;
+
+    let request = service.create(f.payee, collect_req(12000)).await.unwrap();
+    assert_eq!(request.status, "PENDING");
+
+    // Visible to both sides
+    let payer_view = service.list_pending(f.payer).await.unwrap();
+    assert_eq!(payer_view.incoming.len(), 1);
+    let requester_view = service.list_pending(f.payee).await.unwrap();
+    assert_eq!(requester_view.outgoing.len(), 1);
+
+    let resp = service.approve(f.payer, request.request_id).await.unwrap();
+    assert_eq!(resp.status, PaymentStatus::Success);
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 12000);
+
+    // Paid requests cannot be approved again
+    let err = service.approve(f.payer, request.request_id).await.unwrap_err();
+    assert!(matches!(err, CollectError::NotPending(s) if s == "PAID"));
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 38000);
+}
+
+#[tokio::test]
+async fn test_failed_payment_leaves_request_pending() {
+    let ctx = TestContext::new().await;
+    let (service, f) = setup(&ctx).await;
+
+    let request = service.create(f.payee, collect_req(60000)).await.unwrap();
+    let err = service.approve(f.payer, request.request_id).await.unwrap_err();
+    assert!(matches!(err, CollectError::PaymentError(_)));
+
+    let pending = service.list_pending(f.payer).await.unwrap();
+    assert_eq!(pending.incoming[0].status, "PENDING");
+
+    // Approving again is a new attempt, not a replay of the failed one
+    let err = service.approve(f.payer, request.request_id).await.unwrap_err();
+    assert!(matches!(err, CollectError::PaymentError(PaymentError::WalletError(WalletError::InsufficientBalance))));
+    let pending = service.list_pending(f.payer).await.unwrap();
+    assert_eq!((pending.incoming[0].status.as_str(), pending.incoming[0].tx_id), ("PENDING", None));
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 50000);
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 0);
+
+    // Once the payer can afford it, the next attempt goes through
+    credit(&f.wallets, f.payer, 10000).await;
+    let resp = service.approve(f.payer, request.request_id).await.unwrap();
+    assert_eq!(resp.status, PaymentStatus::Success);
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 60000);
+}
+
+#[tokio::test]
+async fn test_concurrent_approvals_pay_once() {
+    let ctx = TestContext::new().await;
+    let (service, f) = setup(&ctx).await;
+
+    let request = service.create(f.payee, collect_req(12000)).await.unwrap();
+    let (first, second) = tokio::join!(
+        service.approve(f.payer, request.request_id),
+        service.approve(f.payer, request.request_id),
+    );
+
+    // The approval that finds the payment still running is refused and
+    // leaves the request to the one paying it
+    for result in [&first, &second] {
+        assert!(matches!(result, Ok(_) | Err(CollectError::NotPending(_))));
+    }
+    assert!(first.is_ok() || second.is_ok());
+
+    let status = sqlx::query_scalar!("SELECT status FROM collect_requests WHERE request_id = $1", request.request_id)
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(status, "PAID");
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 38000);
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 12000);
+}
+
+#[tokio::test]
+async fn test_decline_and_only_payer_can_answer() {
+    let ctx = TestContext::new().await;
+    let (service, f) = setup(&ctx).await;
+
+    let request = service.create(f.payee, collect_req(5000)).await.unwrap();
+
+    let err = service.approve(f.payee, request.request_id).await.unwrap_err();
+    assert!(matches!(err, CollectError::NotFound(_)));
+
+    let declined = service.decline(f.payer, request.request_id).await.unwrap();
+    assert_eq!(declined.status, "DECLINED");
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_expired_requests_cannot_be_approved() {
+    let ctx = TestContext::new().await;
+    let (service, f) = setup(&ctx).await;
+
+    let request = service.create(f.payee, collect_req(5000)).await.unwrap();
+    sqlx::query!(
+        "UPDATE collect_requests SET expires_at = NOW() - INTERVAL '1 second' WHERE request_id = $1",
+        request.request_id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    let err = service.approve(f.payer, request.request_id).await.unwrap_err();
+    assert!(matches!(err, CollectError::NotPending(s) if s == "EXPIRED"));
+
+    assert_eq!(service.expire_pending().await.unwrap(), 1);
+    assert!(service.list_pending(f.payer).await.unwrap().incoming.is_empty());
+}
@@ -0,0 +1,220 @@
+// tests/unit/fee.rs
+use crate::common::{Shop, TestContext, new_uuid, onboarding};
+use payment_system::fee::{FeeService, models::*};
+use payment_system::merchant::models::*;
+use payment_system::payment::models::*;
+use payment_system::payment::payee::PayeeAddress;
+use payment_system::settlement::SettlementService;
+use payment_system::settlement::service::{PayoutClient, RetryPolicy};
+use payment_system::settlement::models::Payout;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use validator::Validate;
+
+struct NoBank;
+
+#[async_trait]
+impl PayoutClient for NoBank {
+    async fn transfer(&self, _payout: &Payout) -> Result<String, String> {
+        Err("not used".to_string())
+    }
+}
+
+fn rule(category_code: Option<&str>, min_amount: i64, max_amount: Option<i64>, mdr_bps: i32, flat_fee: i64, max_fee: Option<i64>) -> FeeRule {
+    FeeRule { rule_id: min_amount, category_code: category_code.map(str::to_string), min_amount, max_amount, mdr_bps, flat_fee, max_fee }
+}
+
+fn rule_input(category_code: Option<&str>, min_amount: u64, max_amount: Option<u64>, mdr_bps: u32, flat_fee: u64, max_fee: Option<u64>) -> FeeRuleInput {
+    FeeRuleInput { category_code: category_code.map(str::to_string), min_amount, max_amount, mdr_bps, flat_fee, max_fee }
+}
+
+#[test]
+fn test_fees_and_gst_round_to_the_paisa() {
+    let standard = FeeSchedule { plan_code: "STANDARD".to_string(), rules: vec![rule(None, 0, None, 180, 0, None)] };
+    let quote = standard.quote(100_000);
+    assert_eq!((quote.fee, quote.gst, quote.net), (1800, 324, 97_876));
+
+    // ₹1.25 at 1.8% is 2.25 paise → 2; GST on 2 paise rounds to 0
+    let quote = standard.quote(125);
+    assert_eq!((quote.fee, quote.gst, quote.net), (2, 0, 123));
+
+    let zero = FeeSchedule { plan_code: "ZERO_MDR".to_string(), rules: vec![] };
+    assert_eq!(zero.quote(100_000).total_fee, 0);
+}
+
+#[test]
+fn test_slabs_categories_flat_fees_and_caps() {
+    let catch_all = vec![
+        rule(None, 0, Some(200_000), 0, 0, None),           // free under ₹2,000
+        rule(None, 200_000, None, 150, 300, Some(5_000)),   // 1.5% + ₹3, at most ₹50
+    ];
+    let others = FeeSchedule { plan_code: "TIERED".to_string(), rules: catch_all.clone() };
+
+    assert_eq!(others.quote(199_999).fee, 0);
+    let quote = others.quote(200_000);
+    assert_eq!((quote.mdr, quote.flat_fee, quote.fee, quote.gst), (3_000, 300, 3_300, 594));
+    assert_eq!(quote.total_fee, 3_894);
+    // The cap applies to MDR and flat fee together
+    assert_eq!(others.quote(1_000_000).fee, 5_000);
+
+    // Restaurants (MCC 5812) have their own slab, which beats the catch-all
+    let mut rules = catch_all;
+    rules.push(rule(Some("5812"), 200_000, None, 100, 0, None));
+    let restaurants = FeeSchedule { plan_code: "TIERED".to_string(), rules };
+    assert_eq!(restaurants.quote(1_000_000).fee, 10_000);
+    assert_eq!(restaurants.quote(199_999).fee, 0);
+}
+
+#[test]
+fn test_gst_split_by_place_of_supply() {
+    assert_eq!(split_gst(325, Some("29AABCC1234D1Z5")), (162, 163, 0));
+    assert_eq!(split_gst(325, Some("27AABCC1234D1Z5")), (0, 0, 325));
+    assert_eq!(split_gst(325, None), (162, 163, 0));
+}
+
+struct Fixture {
+    fees: Arc<FeeService>,
+    settlements: SettlementService,
+    merchant_id: Uuid,
+    shop: Shop,
+}
+
+impl std::ops::Deref for Fixture {
+    type Target = Shop;
+
+    fn deref(&self) -> &Shop {
+        &self.shop
+    }
+}
+
+/// An ACTIVE T0 restaurant registered in Karnataka and a payer with ₹5,000.
+async fn setup(ctx: &TestContext) -> Fixture {
+    let shop = Shop::new(ctx, 500_000).await;
+    let merchant_id = shop.open(onboarding(Some("29AABCC1234D1Z5"))).await;
+    shop.merchants.set_settlement_cycle(merchant_id, SetSettlementCycleRequest { settlement_cycle: SettlementCycle::T0 })
+        .await
+        .unwrap();
+
+    let fees = Arc::new(FeeService::new(ctx.db.clone(), shop.merchants.clone()));
+    let settlements = SettlementService::new(
+        ctx.db.clone(),
+        shop.wallets.clone(),
+        shop.merchants.clone(),
+        fees.clone(),
+        Arc::new(NoBank),
+        RetryPolicy { max_attempts: 1, backoff: Duration::from_secs(60) },
+    );
+
+    Fixture { fees, settlements, merchant_id, shop }
+}
+
+async fn pay_merchant(f: &Fixture, amount: u64) -> PaymentResponse {
+    f.payments.pay(f.payer, PayRequest {
+        payee: PayeeAddress::Merchant { merchant_id: f.merchant_id },
+        amount,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_quote_follows_the_merchants_plan() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    // Default plan
+    let quote = f.fees.quote(f.merchant_id, 10_000).await.unwrap();
+    assert_eq!((quote.plan_code.as_str(), quote.fee, quote.gst), ("STANDARD", 180, 32));
+
+    let plan_code = format!("FOOD_{}", &new_uuid().simple().to_string()[..8]).to_uppercase();
+    let plan = f.fees.upsert_plan(&plan_code, UpsertFeePlanRequest {
+        name: "Restaurants".to_string(),
+        rules: vec![
+            rule_input(None, 0, None, 200, 0, None),
+            rule_input(Some("5812"), 0, None, 90, 100, Some(1_000)),
+        ],
+    }, "ops@test").await.unwrap();
+    assert_eq!(plan.rules.len(), 2);
+    f.merchants.set_fee_plan(f.merchant_id, SetFeePlanRequest { fee_plan: plan_code.clone() }).await.unwrap();
+
+    let quote = f.fees.quote(f.merchant_id, 10_000).await.unwrap();
+    assert_eq!((quote.mdr, quote.flat_fee, quote.fee, quote.gst, quote.net), (90, 100, 190, 34, 9_776));
+    assert_eq!(f.fees.quote(f.merchant_id, 500_000).await.unwrap().fee, 1_000);
+
+    let err = f.merchants.set_fee_plan(f.merchant_id, SetFeePlanRequest { fee_plan: "NO_SUCH_PLAN".to_string() })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MerchantError::UnknownFeePlan(_)));
+
+    let err = f.fees.upsert_plan(&plan_code, UpsertFeePlanRequest {
+        name: "Broken".to_string(),
+        rules: vec![rule_input(None, 1_000, Some(1_000), 100, 0, None)],
+    }, "ops@test").await.unwrap_err();
+    assert!(matches!(err, FeeError::ValidationError(_)));
+
+    // Two slabs for the same category covering 5_000..10_000
+    let err = f.fees.upsert_plan(&plan_code, UpsertFeePlanRequest {
+        name: "Overlapping".to_string(),
+        rules: vec![
+            rule_input(Some("5812"), 0, Some(10_000), 90, 0, None),
+            rule_input(Some("5812"), 5_000, None, 50, 0, None),
+        ],
+    }, "ops@test").await.unwrap_err();
+    assert!(matches!(err, FeeError::ValidationError(_)));
+    assert_eq!(f.fees.get_plan(&plan_code).await.unwrap().name, "Restaurants");
+}
+
+#[test]
+fn test_quoted_amounts_are_bounded() {
+    assert!(QuoteQuery { amount: 500_000 }.validate().is_ok());
+    assert!(QuoteQuery { amount: 0 }.validate().is_err());
+    assert!(QuoteQuery { amount: 500_001 }.validate().is_err());
+
+    // No overflow even far beyond what can be quoted
+    assert_eq!(of_bps(i64::MAX, 10_000), i64::MAX);
+    assert!(of_bps(i64::MAX / 2, GST_BPS) > 0);
+}
+
+#[tokio::test]
+async fn test_monthly_invoice_totals_posted_fees_once() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    pay_merchant(&f, 10_000).await;
+    pay_merchant(&f, 5_000).await;
+    let batch = f.settlements.create_batch(f.merchant_id, chrono::Utc::now()).await.unwrap().unwrap();
+
+    let posted = sqlx::query!(r#"SELECT COUNT(*) AS "count!", SUM(fee)::BIGINT AS "fee!", SUM(gst)::BIGINT AS "gst!" FROM transaction_fees"#)
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!((posted.count, posted.fee, posted.gst), (2, batch.fees, batch.gst));
+
+    // The current month is still open
+    let today_ist = (chrono::Utc::now() + chrono::Duration::minutes(330)).date_naive();
+    let err = f.fees.generate_invoices(today_ist).await.unwrap_err();
+    assert!(matches!(err, FeeError::ValidationError(_)));
+
+    // Posted in a month that has ended
+    let last_month = today_ist - chrono::Duration::days(35);
+    sqlx::query!("UPDATE transaction_fees SET posted_at = posted_at - INTERVAL '35 days' WHERE merchant_id = $1", f.merchant_id)
+        .execute(&ctx.db)
+        .await
+        .unwrap();
+    let invoices = f.fees.generate_invoices(last_month).await.unwrap();
+    assert_eq!(invoices.len(), 1);
+    let invoice = &invoices[0];
+    assert!(invoice.invoice_number.starts_with("FEE/"));
+    assert_eq!((invoice.transaction_count, invoice.taxable_value), (2, 270));
+    // Same state as the platform: CGST + SGST
+    assert_eq!((invoice.cgst, invoice.sgst, invoice.igst), (24, 24, 0));
+    assert_eq!(invoice.total, 318);
+
+    // A second run finds nothing new
+    assert!(f.fees.generate_invoices(last_month).await.unwrap().is_empty());
+
+    let listed = f.fees.list_invoices(f.owner, f.merchant_id).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    let err = f.fees.get_invoice(new_uuid(), f.merchant_id, invoice.invoice_id).await.unwrap_err();
+    assert!(matches!(err, FeeError::MerchantError(MerchantError::NotFound(_))));
+}
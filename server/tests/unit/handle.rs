@@ -0,0 +1,126 @@
+// tests/unit/handle.rs
+use crate::common::{TestContext, payment_service, create_user, create_wallet};
+use payment_system::collect::{CollectService, models::*};
+use payment_system::handle::{HandleService, models::*};
+use payment_system::payment::{PaymentService, models::*};
+use payment_system::wallet::WalletService;
+use payment_system::ws::server::WsServer;
+use std::sync::Arc;
+use uuid::Uuid;
+
+struct Fixture {
+    handles: HandleService,
+    payments: Arc<PaymentService>,
+    wallets: Arc<WalletService>,
+    alice: Uuid,
+    bob: Uuid,
+}
+
+/// Alice (₹500) and Bob (empty), both registered users with wallets.
+async fn setup(ctx: &TestContext) -> Fixture {
+    let wallets = Arc::new(WalletService::new(ctx.db.clone()));
+    let payments = Arc::new(payment_service(&ctx.db, wallets.clone()));
+
+    let alice = create_user(&ctx.db, "+919876543210").await;
+    let bob = create_user(&ctx.db, "+919876543211").await;
+    create_wallet(&wallets, alice, 50000).await;
+    create_wallet(&wallets, bob, 0).await;
+
+    Fixture { handles: HandleService::new(ctx.db.clone()), payments, wallets, alice, bob }
+}
+
+fn claim(handle: &str) -> ClaimHandleRequest {
+    ClaimHandleRequest { handle: handle.to_string() }
+}
+
+#[test]
+fn test_normalize_handle() {
+    assert_eq!(normalize_handle(" Bob.Kumar ").unwrap(), "bob.kumar@pay");
+    assert_eq!(normalize_handle("bob_k@PAY").unwrap(), "bob_k@pay");
+
+    assert!(matches!(normalize_handle("support"), Err(HandleError::Reserved(_))));
+    assert!(matches!(normalize_handle("pay.support"), Err(HandleError::Reserved(_))));
+    for reserved in ["Support-Team", "alice.admin", "paytm.kyc", "pay-rahul", "mysupportdesk", "refund_4u"] {
+        assert!(matches!(normalize_handle(reserved), Err(HandleError::Reserved(_))), "{}", reserved);
+    }
+    // Short reserved words only match a whole name or part
+    for name in ["kapil", "payal", "carey", "roots", "bankim", "tester"] {
+        assert_eq!(normalize_handle(name).unwrap(), format!("{}@pay", name));
+    }
+    for bad in ["ab", "bob@okbank", "-bob", "bob kumar", "9876543210", &Uuid::new_v4().to_string()] {
+        assert!(matches!(normalize_handle(bad), Err(HandleError::Invalid(_))), "{}", bad);
+    }
+}
+
+#[tokio::test]
+async fn test_claimed_handle_is_payable() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    let handle = f.handles.claim(f.bob, claim("bob")).await.unwrap();
+    assert_eq!(handle.handle, "bob@pay");
+
+    let err = f.handles.claim(f.alice, claim("BOB@pay")).await.unwrap_err();
+    assert!(matches!(err, HandleError::Taken(_)));
+    let err = f.handles.claim(f.bob, claim("bobby")).await.unwrap_err();
+    assert!(matches!(err, HandleError::AlreadyClaimed(h) if h == "bob@pay"));
+
+    let resp = f.payments.pay_by_handle(f.alice, PayByHandleRequest {
+        to_handle: "Bob@pay".to_string(),
+        amount: 2500,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+    assert_eq!(resp.to_user_id, f.bob);
+    assert_eq!(f.wallets.get_balance(&f.bob).await.unwrap(), 2500);
+
+    let err = f.payments.resolve_handle("nobody@pay").await.unwrap_err();
+    assert!(matches!(err, PaymentError::UserNotFound(_)));
+}
+
+#[tokio::test]
+async fn test_changed_handle_is_held_for_previous_owner() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    f.handles.claim(f.bob, claim("bob")).await.unwrap();
+    let changed = f.handles.change(f.bob, claim("bobk")).await.unwrap();
+    assert_eq!(changed.handle, "bobk@pay");
+
+    // The old handle still reaches Bob and nobody else can take it yet
+    assert_eq!(f.payments.resolve_handle("bob@pay").await.unwrap(), f.bob);
+    let err = f.handles.claim(f.alice, claim("bob")).await.unwrap_err();
+    assert!(matches!(err, HandleError::Taken(_)));
+
+    // Bob can switch back
+    f.handles.change(f.bob, claim("bob")).await.unwrap();
+    assert_eq!(f.payments.resolve_handle("bobk@pay").await.unwrap(), f.bob);
+
+    let err = f.handles.change(f.alice, claim("alice")).await.unwrap_err();
+    assert!(matches!(err, HandleError::NoHandle));
+}
+
+#[tokio::test]
+async fn test_collect_request_by_handle() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    f.handles.claim(f.alice, claim("alice")).await.unwrap();
+    let collects = CollectService::new(ctx.db.clone(), f.payments.clone(), Arc::new(WsServer::new()));
+
+    let request = collects.create(f.bob, CreateCollectRequest {
+        payer_mobile: None,
+        payer_handle: Some("alice@pay".to_string()),
+        amount: 1000,
+        note: None,
+        expires_in_secs: None,
+    }).await.unwrap();
+    assert_eq!(request.payer_id, f.alice);
+
+    let err = collects.create(f.bob, CreateCollectRequest {
+        payer_mobile: Some("+919876543210".to_string()),
+        payer_handle: Some("alice@pay".to_string()),
+        amount: 1000,
+        note: None,
+        expires_in_secs: None,
+    }).await.unwrap_err();
+    assert!(matches!(err, CollectError::PayerRequired));
+}
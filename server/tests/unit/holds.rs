@@ -0,0 +1,191 @@
+// tests/unit/holds.rs
+use crate::common::{TestContext, new_uuid};
+use payment_system::wallet::{WalletService, models::*};
+use uuid::Uuid;
+
+/// Holder with ₹500 and an empty payee wallet.
+async fn setup(ctx: &TestContext) -> (WalletService, Uuid, Uuid) {
+    let service = WalletService::new(ctx.db.clone());
+    let holder = new_uuid();
+    let payee = new_uuid();
+    service.create_wallet(CreateWalletRequest { user_id: holder }).await.unwrap();
+    service.create_wallet(CreateWalletRequest { user_id: payee }).await.unwrap();
+    service.credit(&CreditDebitRequest {
+        user_id: holder,
+        amount: 50000,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+    (service, holder, payee)
+}
+
+fn hold_req(payee: Uuid, amount: u64) -> AuthorizeHoldRequest {
+    AuthorizeHoldRequest {
+        payee_user_id: payee,
+        amount,
+        expires_in_secs: 600,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_hold_reduces_available_not_ledger_balance() {
+    let ctx = TestContext::new().await;
+    let (service, holder, payee) = setup(&ctx).await;
+
+    let req = hold_req(payee, 20000);
+    let hold = service.authorize_hold(holder, &req).await.unwrap();
+    assert_eq!(hold.status, "ACTIVE");
+
+    // Retry with the same key returns the same hold
+    let again = service.authorize_hold(holder, &req).await.unwrap();
+    assert_eq!(again.hold_id, hold.hold_id);
+
+    let balance = service.get_balance_details(&holder).await.unwrap();
+    assert_eq!(balance, BalanceResponse { balance: 50000, available: 30000, held: 20000 });
+
+    // Held funds cannot be debited
+    let result = service.debit(&CreditDebitRequest {
+        user_id: holder,
+        amount: 40000,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await;
+    assert!(matches!(result, Err(WalletError::InsufficientBalance)));
+}
+
+#[tokio::test]
+async fn test_partial_capture_releases_remainder() {
+    let ctx = TestContext::new().await;
+    let (service, holder, payee) = setup(&ctx).await;
+
+    let hold = service.authorize_hold(holder, &hold_req(payee, 20000)).await.unwrap();
+    service.capture_hold(payee, hold.hold_id, &CaptureHoldRequest {
+        amount: Some(15000),
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+
+    let balance = service.get_balance_details(&holder).await.unwrap();
+    assert_eq!(balance, BalanceResponse { balance: 35000, available: 35000, held: 0 });
+    assert_eq!(service.get_balance(&payee).await.unwrap(), 15000);
+
+    // A hold can only be captured once
+    let result = service.capture_hold(payee, hold.hold_id, &CaptureHoldRequest {
+        amount: None,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await;
+    assert!(matches!(result, Err(WalletError::HoldNotActive(_))));
+}
+
+#[tokio::test]
+async fn test_capture_retry_returns_original_capture() {
+    let ctx = TestContext::new().await;
+    let (service, holder, payee) = setup(&ctx).await;
+
+    let hold = service.authorize_hold(holder, &hold_req(payee, 20000)).await.unwrap();
+    let req = CaptureHoldRequest { amount: Some(15000), idempotency_key: Uuid::new_v4().to_string() };
+    let first = service.capture_hold(payee, hold.hold_id, &req).await.unwrap();
+
+    // Balances move on, the replayed receipt does not
+    service.credit(&CreditDebitRequest {
+        user_id: holder,
+        amount: 1000,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+    let again = service.capture_hold(payee, hold.hold_id, &req).await.unwrap();
+    assert_eq!(again, first);
+    assert_eq!(service.get_balance(&payee).await.unwrap(), 15000);
+}
+
+#[tokio::test]
+async fn test_holds_count_against_daily_limit() {
+    let ctx = TestContext::new().await;
+    let (service, holder, payee) = setup(&ctx).await;
+
+    // ₹100 left of the basic ₹10,000
+    sqlx::query!(
+        "INSERT INTO daily_limits (user_id, kyc_tier, amount_used) VALUES ($1, 'basic', 990000)",
+        holder
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    let result = service.authorize_hold(holder, &hold_req(payee, 20000)).await;
+    assert!(matches!(result, Err(WalletError::DailyLimitExceeded)));
+
+    // Within the limit at authorization, over it by capture time
+    let hold = service.authorize_hold(holder, &hold_req(payee, 10000)).await.unwrap();
+    sqlx::query!("UPDATE daily_limits SET amount_used = 995000 WHERE user_id = $1", holder)
+        .execute(&ctx.db)
+        .await
+        .unwrap();
+    let result = service.capture_hold(payee, hold.hold_id, &CaptureHoldRequest {
+        amount: None,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await;
+    assert!(matches!(result, Err(WalletError::DailyLimitExceeded)));
+    assert_eq!(service.get_balance(&payee).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_active_holds_share_the_daily_limit() {
+    let ctx = TestContext::new().await;
+    let (service, holder, payee) = setup(&ctx).await;
+
+    // ₹200 left of the basic ₹10,000
+    sqlx::query!(
+        "INSERT INTO daily_limits (user_id, kyc_tier, amount_used) VALUES ($1, 'basic', 980000)",
+        holder
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    let first = service.authorize_hold(holder, &hold_req(payee, 15000)).await.unwrap();
+    let result = service.authorize_hold(holder, &hold_req(payee, 10000)).await;
+    assert!(matches!(result, Err(WalletError::DailyLimitExceeded)));
+
+    // Voiding the first hold frees its share of the limit
+    service.void_hold(holder, first.hold_id).await.unwrap();
+    service.authorize_hold(holder, &hold_req(payee, 10000)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_capture_cannot_exceed_hold() {
+    let ctx = TestContext::new().await;
+    let (service, holder, payee) = setup(&ctx).await;
+
+    let hold = service.authorize_hold(holder, &hold_req(payee, 20000)).await.unwrap();
+    let result = service.capture_hold(payee, hold.hold_id, &CaptureHoldRequest {
+        amount: Some(20001),
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await;
+    assert!(matches!(result, Err(WalletError::CaptureExceedsHold { held: 20000 })));
+
+    // Only the payee can capture
+    let result = service.capture_hold(holder, hold.hold_id, &CaptureHoldRequest {
+        amount: None,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await;
+    assert!(matches!(result, Err(WalletError::HoldNotFound(_))));
+}
+
+#[tokio::test]
+async fn test_void_and_expiry_release_funds() {
+    let ctx = TestContext::new().await;
+    let (service, holder, payee) = setup(&ctx).await;
+
+    let voided = service.authorize_hold(holder, &hold_req(payee, 10000)).await.unwrap();
+    let expiring = service.authorize_hold(holder, &hold_req(payee, 5000)).await.unwrap();
+
+    let voided = service.void_hold(holder, voided.hold_id).await.unwrap();
+    assert_eq!(voided.status, "VOIDED");
+
+    sqlx::query!("UPDATE wallet_holds SET expires_at = NOW() - INTERVAL '1 second' WHERE hold_id = $1", expiring.hold_id)
+        .execute(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(service.expire_holds().await.unwrap(), 1);
+
+    let balance = service.get_balance_details(&holder).await.unwrap();
+    assert_eq!(balance, BalanceResponse { balance: 50000, available: 50000, held: 0 });
+}
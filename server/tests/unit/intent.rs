@@ -0,0 +1,219 @@
+// tests/unit/intent.rs
+use crate::common::{Shop, TestContext, credit, new_uuid, onboarding};
+use payment_system::intent::{IntentService, models::*};
+use payment_system::merchant::models::*;
+use uuid::Uuid;
+
+struct Fixture {
+    intents: IntentService,
+    merchant_id: Uuid,
+    shop: Shop,
+}
+
+impl std::ops::Deref for Fixture {
+    type Target = Shop;
+    fn deref(&self) -> &Shop {
+        &self.shop
+    }
+}
+
+/// An ACTIVE merchant and a customer with ₹500.
+async fn setup(ctx: &TestContext) -> Fixture {
+    let shop = Shop::new(ctx, 50000).await;
+    let merchant_id = shop.open(onboarding(None)).await;
+
+    Fixture {
+        intents: IntentService::new(ctx.db.clone(), shop.merchants.clone(), shop.payments.clone()),
+        merchant_id,
+        shop,
+    }
+}
+
+fn intent(f: &Fixture, order_id: &str, amount: u64) -> CreatePaymentIntentRequest {
+    CreatePaymentIntentRequest {
+        merchant_id: f.merchant_id,
+        amount,
+        currency: "INR".to_string(),
+        order_id: order_id.to_string(),
+        return_url: "https://shop.example/orders/complete?ref=42".to_string(),
+        description: Some("2 x masala chai".to_string()),
+    }
+}
+
+fn confirm(intent: &PaymentIntent) -> ConfirmPaymentIntentRequest {
+    ConfirmPaymentIntentRequest { client_secret: intent.client_secret.clone() }
+}
+
+#[tokio::test]
+async fn test_intent_succeeds_once_confirmed_by_customer() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    let created = f.intents.create(f.owner, intent(&f, "ORD-1", 12000)).await.unwrap();
+    assert_eq!(created.status, IntentStatus::RequiresConfirmation);
+    assert_eq!(serde_json::to_value(&created).unwrap()["status"], "requires_confirmation");
+
+    // Same order twice is refused
+    let err = f.intents.create(f.owner, intent(&f, "ORD-1", 12000)).await.unwrap_err();
+    assert!(matches!(err, IntentError::DuplicateOrder(_)));
+
+    let preview = f.intents.checkout_view(created.id, &created.client_secret).await.unwrap();
+    assert_eq!((preview.merchant_name.as_str(), preview.amount), ("Chai Point", 12000));
+    assert!(preview.redirect_url.is_none());
+
+    // A wrong secret does not reveal the intent
+    let err = f.intents.confirm(f.payer, created.id, ConfirmPaymentIntentRequest { client_secret: "guess".to_string() })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, IntentError::NotFound(_)));
+
+    let done = f.intents.confirm(f.payer, created.id, confirm(&created)).await.unwrap();
+    assert_eq!(done.status, IntentStatus::Succeeded);
+    let redirect = done.redirect_url.unwrap();
+    assert!(redirect.starts_with("https://shop.example/orders/complete?ref=42&payment_intent="));
+    assert!(redirect.ends_with("&status=succeeded"));
+    assert_eq!(f.wallets.get_balance(&f.merchant_id).await.unwrap(), 12000);
+
+    // Confirming again neither pays twice nor allows cancelling
+    let err = f.intents.confirm(f.payer, created.id, confirm(&created)).await.unwrap_err();
+    assert!(matches!(err, IntentError::InvalidStatus(s) if s == "SUCCEEDED"));
+    let err = f.intents.cancel(f.owner, created.id, CancelPaymentIntentRequest { reason: None }).await.unwrap_err();
+    assert!(matches!(err, IntentError::InvalidStatus(_)));
+
+    let fetched = f.intents.get(f.owner, created.id).await.unwrap();
+    assert!(fetched.tx_id.is_some());
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 38000);
+}
+
+#[tokio::test]
+async fn test_failed_payment_returns_intent_for_another_try() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let created = f.intents.create(f.owner, intent(&f, "ORD-2", 60000)).await.unwrap();
+
+    // Customer only has ₹500
+    let view = f.intents.confirm(f.payer, created.id, confirm(&created)).await.unwrap();
+    assert_eq!(view.status, IntentStatus::RequiresConfirmation);
+    assert!(view.last_payment_error.is_some());
+
+    // After a top-up the same intent goes through on a fresh attempt
+    credit(&f.wallets, f.payer, 20000).await;
+    let view = f.intents.confirm(f.payer, created.id, confirm(&created)).await.unwrap();
+    assert_eq!(view.status, IntentStatus::Succeeded);
+    assert!(view.last_payment_error.is_none());
+}
+
+#[tokio::test]
+async fn test_merchant_cancels_and_other_users_cannot_see_intents() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let created = f.intents.create(f.owner, intent(&f, "ORD-3", 1000)).await.unwrap();
+
+    let err = f.intents.get(new_uuid(), created.id).await.unwrap_err();
+    assert!(matches!(err, IntentError::NotFound(_)));
+    let err = f.intents.create(new_uuid(), intent(&f, "ORD-4", 1000)).await.unwrap_err();
+    assert!(matches!(err, IntentError::MerchantError(MerchantError::NotFound(_))));
+
+    let canceled = f.intents
+        .cancel(f.owner, created.id, CancelPaymentIntentRequest { reason: Some("out of stock".to_string()) })
+        .await
+        .unwrap();
+    assert_eq!(canceled.status, IntentStatus::Canceled);
+
+    let err = f.intents.confirm(f.payer, created.id, confirm(&created)).await.unwrap_err();
+    assert!(matches!(err, IntentError::InvalidStatus(s) if s == "CANCELED"));
+
+    let mut usd = intent(&f, "ORD-5", 1000);
+    usd.currency = "USD".to_string();
+    assert!(matches!(f.intents.create(f.owner, usd).await, Err(IntentError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_return_url_must_be_https_outside_development() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    let mut plain = intent(&f, "ORD-6", 1000);
+    plain.return_url = "http://shop.example/orders/complete".to_string();
+    let err = f.intents.create(f.owner, plain.clone()).await.unwrap_err();
+    assert!(matches!(err, IntentError::ValidationError(_)));
+
+    let mut script = intent(&f, "ORD-7", 1000);
+    script.return_url = "javascript:alert(1)".to_string();
+    assert!(matches!(f.intents.create(f.owner, script).await, Err(IntentError::ValidationError(_))));
+
+    let dev = f.intents.allow_http_return_urls(true);
+    assert_eq!(dev.create(f.owner, plain).await.unwrap().status, IntentStatus::RequiresConfirmation);
+}
+
+#[tokio::test]
+async fn test_abandoned_confirmation_can_be_canceled_unless_it_paid() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let reason = || CancelPaymentIntentRequest { reason: Some("customer left".to_string()) };
+
+    // The customer's app claimed the intent and was never heard from again
+    let abandoned = f.intents.create(f.owner, intent(&f, "ORD-8", 1000)).await.unwrap();
+    sqlx::query!(
+        "UPDATE payment_intents SET status = 'PROCESSING', customer_user_id = $2, attempts = 1 WHERE intent_id = $1",
+        abandoned.id,
+        f.payer
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    // Not while the confirmation may still be running
+    let err = f.intents.cancel(f.owner, abandoned.id, reason()).await.unwrap_err();
+    assert!(matches!(err, IntentError::InvalidStatus(s) if s == "PROCESSING"));
+
+    sqlx::query!(
+        "UPDATE payment_intents SET updated_at = NOW() - INTERVAL '1 hour' WHERE intent_id = $1",
+        abandoned.id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+    let canceled = f.intents.cancel(f.owner, abandoned.id, reason()).await.unwrap();
+    assert_eq!(canceled.status, IntentStatus::Canceled);
+
+    // One whose payment went through before the confirmation died is paid, not canceled
+    let paid = f.intents.create(f.owner, intent(&f, "ORD-9", 1000)).await.unwrap();
+    f.intents.confirm(f.payer, paid.id, confirm(&paid)).await.unwrap();
+    sqlx::query!(
+        "UPDATE payment_intents SET status = 'PROCESSING', tx_id = NULL, updated_at = NOW() - INTERVAL '1 hour' WHERE intent_id = $1",
+        paid.id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    let err = f.intents.cancel(f.owner, paid.id, reason()).await.unwrap_err();
+    assert!(matches!(err, IntentError::InvalidStatus(s) if s == "SUCCEEDED"));
+    let paid = f.intents.get(f.owner, paid.id).await.unwrap();
+    assert!(paid.tx_id.is_some());
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 49_000);
+}
+
+#[tokio::test]
+async fn test_concurrent_confirmations_pay_once() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let created = f.intents.create(f.owner, intent(&f, "ORD-10", 1000)).await.unwrap();
+
+    let (a, b) = tokio::join!(
+        f.intents.confirm(f.payer, created.id, confirm(&created)),
+        f.intents.confirm(f.payer, created.id, confirm(&created)),
+    );
+
+    // The loser is refused; it never hands the intent back for another try
+    for result in [a, b] {
+        if let Err(err) = result {
+            assert!(matches!(err, IntentError::InvalidStatus(_)));
+        }
+    }
+    let fetched = f.intents.get(f.owner, created.id).await.unwrap();
+    assert_eq!(fetched.status, IntentStatus::Succeeded);
+    assert!(fetched.tx_id.is_some());
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 49_000);
+}
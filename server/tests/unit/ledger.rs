@@ -0,0 +1,51 @@
+// tests/unit/ledger.rs
+use crate::common::{TestContext, new_uuid};
+use payment_system::ledger::{LedgerService, LedgerError, models::*};
+use payment_system::wallet::{WalletService, models::*};
+
+#[tokio::test]
+async fn test_unbalanced_entry_rejected() {
+    let ctx = TestContext::new().await;
+    let ledger = LedgerService::new(ctx.db.clone());
+
+    let mut tx = ctx.db.begin().await.unwrap();
+    let err = ledger.post_entry(
+        &mut tx,
+        "unbalanced_1",
+        "test",
+        &[
+            Posting::debit(SystemAccount::Float.code(), 10000),
+            Posting::credit(SystemAccount::Suspense.code(), 9000),
+        ],
+    ).await.unwrap_err();
+
+    assert!(matches!(err, LedgerError::Unbalanced(-1000)));
+}
+
+#[tokio::test]
+async fn test_credit_and_debit_post_balanced_entries() {
+    let ctx = TestContext::new().await;
+    let ledger = LedgerService::new(ctx.db.clone());
+    let service = WalletService::new(ctx.db.clone());
+
+    let user_id = new_uuid();
+    service.create_wallet(CreateWalletRequest { user_id }).await.unwrap();
+
+    service.credit(&CreditDebitRequest {
+        user_id,
+        amount: 10000,
+        idempotency_key: "ledger_credit_1".to_string(),
+    }).await.unwrap();
+    service.debit(&CreditDebitRequest {
+        user_id,
+        amount: 2500,
+        idempotency_key: "ledger_debit_1".to_string(),
+    }).await.unwrap();
+
+    // Wallet balance is derived from postings
+    assert_eq!(service.get_balance(&user_id).await.unwrap(), 7500);
+    assert_eq!(ledger.balance(&wallet_account(&user_id)).await.unwrap(), 7500);
+
+    // No money created or destroyed
+    assert_eq!(ledger.trial_balance().await.unwrap(), 0);
+}
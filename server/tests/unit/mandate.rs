@@ -0,0 +1,195 @@
+// tests/unit/mandate.rs
+use crate::common::{TestContext, Parties, PAYEE_MOBILE};
+use payment_system::mandate::{MandateService, models::*};
+use payment_system::mandate::scheduler::{MandateScheduler, MandateRunReport, RetryPolicy};
+use payment_system::ws::server::WsServer;
+use chrono::TimeZone;
+use std::sync::Arc;
+use std::time::Duration;
+
+struct Fixture {
+    service: MandateService,
+    scheduler: MandateScheduler,
+    parties: Parties,
+}
+
+impl std::ops::Deref for Fixture {
+    type Target = Parties;
+
+    fn deref(&self) -> &Parties {
+        &self.parties
+    }
+}
+
+/// Payer with `balance` and a payee registered on `PAYEE_MOBILE`. Retries
+/// are immediate so tests can drive them with `run_once`.
+async fn setup(ctx: &TestContext, balance: u64) -> Fixture {
+    let parties = Parties::new(ctx, balance).await;
+    let service = MandateService::new(ctx.db.clone(), parties.payments.clone());
+    let scheduler = MandateScheduler::new(
+        ctx.db.clone(),
+        parties.payments.clone(),
+        Arc::new(WsServer::new()),
+        RetryPolicy { max_attempts: 2, backoff: Duration::from_secs(0) },
+        Duration::from_secs(24 * 60 * 60),
+        Duration::from_secs(60),
+    );
+
+    Fixture { service, scheduler, parties }
+}
+
+/// Monthly ₹100 mandate whose first debit is already due.
+async fn due_mandate(ctx: &TestContext, f: &Fixture) -> Mandate {
+    let mandate = f.service.create(f.payer, CreateMandateRequest {
+        payee_mobile: PAYEE_MOBILE.to_string(),
+        max_amount: 10000,
+        frequency: Frequency::Monthly,
+        first_debit_at: None,
+        end_at: chrono::Utc::now() + chrono::Duration::days(365),
+    }).await.unwrap();
+
+    sqlx::query!(
+        "UPDATE mandates SET first_debit_at = NOW() - INTERVAL '1 minute', next_debit_at = NOW() - INTERVAL '1 minute' WHERE mandate_id = $1",
+        mandate.mandate_id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+    mandate
+}
+
+#[tokio::test]
+async fn test_due_mandate_is_debited_and_advanced() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, 50000).await;
+    let mandate = due_mandate(&ctx, &f).await;
+
+    let report = f.scheduler.run_once().await.unwrap();
+    assert_eq!(report, MandateRunReport { notified: 1, succeeded: 1, ..Default::default() });
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 10000);
+
+    let after = &f.service.list(f.payer).await.unwrap()[0];
+    assert!(after.next_debit_at > chrono::Utc::now() + chrono::Duration::days(27));
+
+    // Not due again until next month
+    let report = f.scheduler.run_once().await.unwrap();
+    assert_eq!(report, MandateRunReport::default());
+    assert_eq!(after.mandate_id, mandate.mandate_id);
+}
+
+#[tokio::test]
+async fn test_insufficient_balance_is_retried_then_failed() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, 5000).await;
+    due_mandate(&ctx, &f).await;
+
+    let report = f.scheduler.run_once().await.unwrap();
+    assert_eq!(report.retrying, 1);
+
+    let report = f.scheduler.run_once().await.unwrap();
+    assert_eq!(report.failed, 1);
+
+    let statuses = sqlx::query_scalar!("SELECT status FROM mandate_executions ORDER BY attempt")
+        .fetch_all(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(statuses, vec!["RETRY_SCHEDULED", "FAILED"]);
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 5000);
+}
+
+#[tokio::test]
+async fn test_paused_and_revoked_mandates_are_not_debited() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, 50000).await;
+    let mandate = due_mandate(&ctx, &f).await;
+
+    f.service.pause(f.payer, mandate.mandate_id).await.unwrap();
+    assert_eq!(f.scheduler.run_once().await.unwrap().succeeded, 0);
+
+    // Resuming skips the missed cycle instead of debiting it
+    let resumed = f.service.resume(f.payer, mandate.mandate_id).await.unwrap();
+    assert!(resumed.next_debit_at > chrono::Utc::now());
+
+    // The payee can end it too
+    let revoked = f.service.revoke(f.payee, mandate.mandate_id).await.unwrap();
+    assert_eq!(revoked.status, "REVOKED");
+    let err = f.service.resume(f.payer, mandate.mandate_id).await.unwrap_err();
+    assert!(matches!(err, MandateError::InvalidStatus(_)));
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_presented_amount_capped_by_mandate() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, 50000).await;
+    let mandate = due_mandate(&ctx, &f).await;
+
+    let err = f.service.present_amount(f.payee, mandate.mandate_id, PresentAmountRequest { amount: 10001 })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MandateError::AmountExceedsMandate { max: 10000 }));
+
+    f.service.present_amount(f.payee, mandate.mandate_id, PresentAmountRequest { amount: 2500 }).await.unwrap();
+    f.scheduler.run_once().await.unwrap();
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 2500);
+}
+
+#[tokio::test]
+async fn test_missed_cycles_are_debited_once() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, 50000).await;
+    let mandate = due_mandate(&ctx, &f).await;
+
+    // Scheduler was down for three months
+    sqlx::query!(
+        "UPDATE mandates SET first_debit_at = NOW() - INTERVAL '95 days', next_debit_at = NOW() - INTERVAL '95 days' WHERE mandate_id = $1",
+        mandate.mandate_id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    assert_eq!(f.scheduler.run_once().await.unwrap().succeeded, 1);
+    assert_eq!(f.scheduler.run_once().await.unwrap().succeeded, 0);
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 10000);
+
+    let after = &f.service.list(f.payer).await.unwrap()[0];
+    assert!(after.next_debit_at > chrono::Utc::now());
+}
+
+#[tokio::test]
+async fn test_presented_amount_is_notified_again() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, 50000).await;
+    let mandate = f.service.create(f.payer, CreateMandateRequest {
+        payee_mobile: PAYEE_MOBILE.to_string(),
+        max_amount: 10000,
+        frequency: Frequency::Monthly,
+        first_debit_at: Some(chrono::Utc::now() + chrono::Duration::hours(1)),
+        end_at: chrono::Utc::now() + chrono::Duration::days(365),
+    }).await.unwrap();
+
+    assert_eq!(f.scheduler.run_once().await.unwrap().notified, 1);
+    assert_eq!(f.scheduler.run_once().await.unwrap().notified, 0);
+
+    // The notice the payer got no longer matches what will be debited
+    f.service.present_amount(f.payee, mandate.mandate_id, PresentAmountRequest { amount: 2500 }).await.unwrap();
+    assert_eq!(f.scheduler.run_once().await.unwrap(), MandateRunReport { notified: 1, ..Default::default() });
+}
+
+#[test]
+fn test_monthly_debits_keep_the_anchor_day() {
+    let anchor = chrono::Utc.with_ymd_and_hms(2025, 1, 31, 4, 30, 0).unwrap();
+
+    let feb = Frequency::Monthly.next_after(anchor, anchor);
+    let mar = Frequency::Monthly.next_after(anchor, feb);
+    let apr = Frequency::Monthly.next_after(anchor, mar);
+
+    assert_eq!(feb, chrono::Utc.with_ymd_and_hms(2025, 2, 28, 4, 30, 0).unwrap());
+    assert_eq!(mar, chrono::Utc.with_ymd_and_hms(2025, 3, 31, 4, 30, 0).unwrap());
+    assert_eq!(apr, chrono::Utc.with_ymd_and_hms(2025, 4, 30, 4, 30, 0).unwrap());
+
+    // Catching up from a later date lands on the same schedule
+    let mid_march = chrono::Utc.with_ymd_and_hms(2025, 3, 15, 0, 0, 0).unwrap();
+    assert_eq!(Frequency::Monthly.next_after(anchor, mid_march), mar);
+}
@@ -0,0 +1,102 @@
+// tests/unit/merchant.rs
+use crate::common::{TestContext, Shop, new_uuid, onboarding};
+use payment_system::merchant::models::*;
+use payment_system::payment::models::*;
+use payment_system::payment::payee::PayeeAddress;
+use payment_system::qr::service::QrService;
+use std::time::Duration;
+use uuid::Uuid;
+
+fn review(status: MerchantStatus) -> UpdateMerchantStatusRequest {
+    UpdateMerchantStatusRequest { status, reason: None }
+}
+
+#[tokio::test]
+async fn test_onboarding_validates_business_identity() {
+    let ctx = TestContext::new().await;
+    let f = Shop::new(&ctx, 50000).await;
+
+    let merchant = f.merchants.onboard(f.owner, onboarding(Some("29AABCC1234D1Z5"))).await.unwrap();
+    assert_eq!(merchant.status, "PENDING");
+    assert_eq!(merchant.fee_plan, "STANDARD");
+
+    let err = f.merchants.onboard(f.owner, onboarding(Some("29AABCC1234D1Z5"))).await.unwrap_err();
+    assert!(matches!(err, MerchantError::DuplicateGstin));
+
+    // The GSTIN must embed the merchant's PAN
+    let err = f.merchants.onboard(f.owner, onboarding(Some("29AAACX9999Z1Z5"))).await.unwrap_err();
+    assert!(matches!(err, MerchantError::GstinPanMismatch(_)));
+
+    let mut bad_mcc = onboarding(None);
+    bad_mcc.category_code = "58".to_string();
+    assert!(matches!(f.merchants.onboard(f.owner, bad_mcc).await, Err(MerchantError::ValidationError(_))));
+
+    // Other users cannot see the merchant
+    let err = f.merchants.get(new_uuid(), merchant.merchant_id).await.unwrap_err();
+    assert!(matches!(err, MerchantError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn test_payments_reach_active_merchant_wallet_tagged_as_merchant() {
+    let ctx = TestContext::new().await;
+    let f = Shop::new(&ctx, 50000).await;
+    let merchant = f.merchants.onboard(f.owner, onboarding(None)).await.unwrap();
+    let payee = PayeeAddress::Merchant { merchant_id: merchant.merchant_id };
+
+    // Still under review
+    let err = f.payments.pay(f.payer, pay(payee.clone(), 1000)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::MerchantNotActive(_)));
+
+    let merchant = f.merchants.set_status(merchant.merchant_id, review(MerchantStatus::Active), "ops@test").await.unwrap();
+    assert_eq!(merchant.status, "ACTIVE");
+
+    let resp = f.payments.pay(f.payer, pay(payee.clone(), 1000)).await.unwrap();
+    assert_eq!(resp.to_user_id, merchant.merchant_id);
+    assert_eq!(f.wallets.get_balance(&merchant.merchant_id).await.unwrap(), 1000);
+    // The owner's personal wallet is untouched
+    assert!(f.wallets.get_balance(&f.owner).await.is_err());
+
+    let row = sqlx::query!("SELECT merchant_id, payment_type FROM transaction_journal WHERE tx_id = $1", resp.tx_id)
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(row.merchant_id, Some(merchant.merchant_id));
+    assert_eq!(row.payment_type.as_deref(), Some("MERCHANT"));
+
+    // Suspension stops payments, including order QRs issued while active
+    let (_, order_qr) = QrService::new("qr_secret".to_string())
+        .generate_dynamic(merchant.merchant_id, 2500, "ORD-7", Duration::from_secs(600))
+        .unwrap();
+    f.merchants.set_status(merchant.merchant_id, review(MerchantStatus::Suspended), "ops@test").await.unwrap();
+    let err = f.payments.pay(f.payer, pay(payee, 1000)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::MerchantNotActive(_)));
+    let err = f.payments.pay(f.payer, pay(PayeeAddress::Qr { qr_code: order_qr }, 2500)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::MerchantNotActive(_)));
+}
+
+#[tokio::test]
+async fn test_review_transitions_are_guarded() {
+    let ctx = TestContext::new().await;
+    let f = Shop::new(&ctx, 50000).await;
+    let merchant = f.merchants.onboard(f.owner, onboarding(None)).await.unwrap();
+
+    f.merchants.set_status(merchant.merchant_id, review(MerchantStatus::Rejected), "ops@test").await.unwrap();
+    let err = f.merchants
+        .set_status(merchant.merchant_id, review(MerchantStatus::Active), "ops@test")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MerchantError::InvalidStatus(s) if s == "REJECTED"));
+    // A refused activation leaves no wallet behind
+    let wallet = sqlx::query_scalar!("SELECT user_id FROM wallets WHERE user_id = $1", merchant.merchant_id)
+        .fetch_optional(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(wallet, None);
+
+    let err = f.merchants.set_status(new_uuid(), review(MerchantStatus::Active), "ops@test").await.unwrap_err();
+    assert!(matches!(err, MerchantError::NotFound(_)));
+}
+
+fn pay(payee: PayeeAddress, amount: u64) -> PayRequest {
+    PayRequest { payee, amount, idempotency_key: Uuid::new_v4().to_string() }
+}
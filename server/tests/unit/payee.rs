@@ -0,0 +1,78 @@
+// tests/unit/payee.rs
+use crate::common::{TestContext, Parties, new_uuid, payment_service};
+use payment_system::payment::models::*;
+use payment_system::payment::payee::{PayeeAddress, PayeeResolver, ResolvedPayee};
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Payer with ₹500 and a registered payee with a linked bank account.
+async fn setup(ctx: &TestContext) -> Parties {
+    let f = Parties::new(ctx, 50000).await;
+    sqlx::query!(
+        "INSERT INTO fake_bank_accounts (user_id, account_number, ifsc, name) VALUES ($1, '123456789012', 'HDFC0001234', 'Payee')",
+        f.payee
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+    f
+}
+
+fn pay(payee: PayeeAddress, amount: u64) -> PayRequest {
+    PayRequest { payee, amount, idempotency_key: Uuid::new_v4().to_string() }
+}
+
+#[test]
+fn test_payee_address_is_tagged() {
+    let req: PayRequest = serde_json::from_value(serde_json::json!({
+        "payee": { "type": "bank_account", "account_number": "123456789012", "ifsc": "hdfc0001234" },
+        "amount": 100,
+        "idempotency_key": Uuid::new_v4().to_string(),
+    }))
+    .unwrap();
+    assert_eq!(req.payee.method(), "bank_account");
+    assert_eq!(req.payee.canonical(), "123456789012|HDFC0001234");
+}
+
+#[tokio::test]
+async fn test_pay_by_bank_account_and_user_id() {
+    let ctx = TestContext::new().await;
+    let Parties { wallets, payments: service, payer, payee } = setup(&ctx).await;
+
+    let bank = PayeeAddress::BankAccount { account_number: "123456789012".to_string(), ifsc: "hdfc0001234".to_string() };
+    let resp = service.pay(payer, pay(bank, 1000)).await.unwrap();
+    assert_eq!(resp.to_user_id, payee);
+
+    let resp = service.pay(payer, pay(PayeeAddress::UserId { user_id: payee }, 2000)).await.unwrap();
+    assert_eq!(resp.to_user_id, payee);
+    assert_eq!(wallets.get_balance(&payee).await.unwrap(), 3000);
+
+    let err = service.pay(payer, pay(PayeeAddress::UserId { user_id: new_uuid() }, 1000)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::UserNotFound(_)));
+
+    let bad_ifsc = PayeeAddress::BankAccount { account_number: "123456789012".to_string(), ifsc: "HDFC1234".to_string() };
+    let err = service.pay(payer, pay(bad_ifsc, 1000)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::InvalidPayee(_)));
+}
+
+/// Sends every mobile payment to one account, to show a registered
+/// resolver takes precedence over the built-in one.
+struct FixedResolver(Uuid);
+
+#[async_trait]
+impl PayeeResolver for FixedResolver {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError> {
+        Ok(matches!(address, PayeeAddress::Mobile { .. }).then(|| ResolvedPayee::user(self.0, "phone")))
+    }
+}
+
+#[tokio::test]
+async fn test_registered_resolver_is_asked_first() {
+    let ctx = TestContext::new().await;
+    let Parties { wallets, payer, payee, .. } = setup(&ctx).await;
+    let service = payment_service(&ctx.db, wallets).with_resolver(Arc::new(FixedResolver(payee)));
+
+    let resp = service.pay(payer, pay(PayeeAddress::Mobile { mobile: "+919999999999".to_string() }, 500)).await.unwrap();
+    assert_eq!(resp.to_user_id, payee);
+}
@@ -0,0 +1,38 @@
+// tests/unit/payee_lookup.rs
+use crate::common::{TestContext, payment_service, create_user};
+use payment_system::payment::models::*;
+use payment_system::payment::payee::{mask_name, PayeeAddress};
+use payment_system::wallet::WalletService;
+use std::sync::Arc;
+
+#[test]
+fn test_mask_name() {
+    assert_eq!(mask_name("Rahul Kumar Sharma"), "Rahul K. S.");
+    assert_eq!(mask_name("  priya   nair "), "priya N.");
+    assert_eq!(mask_name("Madonna"), "Madonna");
+}
+
+#[tokio::test]
+async fn test_lookup_returns_masked_name_and_badge() {
+    let ctx = TestContext::new().await;
+    let service = payment_service(&ctx.db, Arc::new(WalletService::new(ctx.db.clone())));
+
+    let verified = create_user(&ctx.db, "+919876543210").await;
+    create_user(&ctx.db, "+919876543211").await;
+    sqlx::query!(
+        "INSERT INTO fake_kyc_verifications (user_id, name, dob, status) VALUES ($1, 'Rahul Kumar Sharma', '1990-01-01', 'approved')",
+        verified
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    let lookup = service.lookup_payee(&PayeeAddress::Mobile { mobile: "+919876543210".to_string() }).await.unwrap();
+    assert_eq!(lookup, PayeeLookup { display_name: Some("Rahul K. S.".to_string()), kyc_badge: KycBadge::Verified });
+
+    let lookup = service.lookup_payee(&PayeeAddress::Mobile { mobile: "+919876543211".to_string() }).await.unwrap();
+    assert_eq!(lookup, PayeeLookup { display_name: None, kyc_badge: KycBadge::Unverified });
+
+    let err = service.lookup_payee(&PayeeAddress::Mobile { mobile: "+919000000000".to_string() }).await.unwrap_err();
+    assert!(matches!(err, PaymentError::UserNotFound(_)));
+}
@@ -22,6 +22,7 @@ async fn test_pay_by_phone_success() {
         ctx.db.clone(),
         wallet_service.clone(),
         "otp_secret".to_string(),
+        "qr_secret".to_string(),
         nats_client,
     );
 
@@ -0,0 +1,262 @@
+// tests/unit/qr.rs
+use crate::common::{TestContext, Parties, new_uuid, payment_service};
+use payment_system::payment::{PaymentService, models::*};
+use payment_system::payment::payee::{PayeeAddress, PayeeResolver, ResolvedPayee};
+use payment_system::qr::payload::{parse, QrPayload, QrError};
+use payment_system::qr::service::QrService;
+use payment_system::qr::upi::UpiQr;
+use payment_system::qr::models::{QrFormat, QrImageError, QrImageQuery, RenderOptions};
+use payment_system::wallet::WalletService;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[test]
+fn test_dynamic_qr_round_trip_and_tamper() {
+    let qr_service = QrService::new("qr_secret".to_string());
+    let merchant = new_uuid();
+    let (qr, payload) = qr_service.generate_dynamic(merchant, 4999, "order #42", Duration::from_secs(600)).unwrap();
+
+    assert_eq!(parse(&payload, "qr_secret").unwrap(), QrPayload::Dynamic(qr));
+
+    // Changing the amount invalidates the signature
+    let tampered = payload.replace("am=4999", "am=1");
+    assert_eq!(parse(&tampered, "qr_secret"), Err(QrError::BadSignature));
+    // So does a different key
+    assert_eq!(parse(&payload, "other_secret"), Err(QrError::BadSignature));
+
+    // Static codes still parse
+    let static_code = format!("payment://user/{}", merchant);
+    assert_eq!(parse(&static_code, "qr_secret").unwrap(), QrPayload::Static { user_id: merchant });
+}
+
+#[test]
+fn test_upi_uri_round_trip_and_validation() {
+    let merchant = new_uuid();
+    let uri = QrService::new("qr_secret".to_string())
+        .generate_upi(&merchant, Some("Chai Point"), Some(4999), Some("ORD-42"));
+    assert!(uri.starts_with(&format!("upi://pay?pa={}@pay&pn=Chai%20Point&am=49.99", merchant)));
+    assert!(uri.ends_with("&cu=INR"));
+
+    let expected = UpiQr {
+        handle: format!("{}@pay", merchant),
+        payee_name: Some("Chai Point".to_string()),
+        amount: Some(4999),
+        reference: Some("ORD-42".to_string()),
+        note: None,
+    };
+    assert_eq!(parse(&uri, "qr_secret").unwrap(), QrPayload::Upi(expected));
+
+    // Codes from other apps carry extra parameters we ignore
+    let foreign = parse("UPI://PAY?pa=shop@okbank&pn=Shop&mc=5411&am=50&cu=INR", "qr_secret").unwrap();
+    assert!(matches!(foreign, QrPayload::Upi(qr) if qr.amount == Some(5000) && qr.handle == "shop@okbank"));
+
+    for bad in [
+        "upi://pay?pn=NoAddress",
+        "upi://pay?pa=not-a-handle",
+        "upi://pay?pa=shop@okbank&am=49.999",
+        "upi://pay?pa=shop@okbank&am=0",
+        "upi://pay?pa=shop@okbank&cu=USD",
+        "upi://pay?pa=shop@okbank&pa=thief@okbank",
+        "upi://pay?pa=shop@okbank&tr=ORD%2042",
+        "upi://pay?pa=shop@okbank&tr=ORD;42",
+        "upi://pay?pa=shop@okbank&tr=",
+    ] {
+        assert_eq!(parse(bad, "qr_secret"), Err(QrError::Malformed), "{}", bad);
+    }
+}
+
+/// Payer with ₹500 and a merchant registered as a user, with an empty wallet.
+async fn setup(ctx: &TestContext) -> (Arc<PaymentService>, Uuid, Uuid) {
+    let f = Parties::new(ctx, 50000).await;
+    (f.payments, f.payer, f.payee)
+}
+
+fn pay(qr_code: &str, amount: u64) -> PayByQrRequest {
+    PayByQrRequest {
+        qr_code: qr_code.to_string(),
+        amount,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_dynamic_qr_enforces_amount_and_single_use() {
+    let ctx = TestContext::new().await;
+    let (service, payer, merchant) = setup(&ctx).await;
+    let (_, payload) = QrService::new("qr_secret".to_string())
+        .generate_dynamic(merchant, 2500, "INV-1", Duration::from_secs(600))
+        .unwrap();
+
+    let err = service.pay_by_qr(payer, pay(&payload, 2000)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::QrAmountMismatch { expected: 2500 }));
+
+    let resp = service.pay_by_qr(payer, pay(&payload, 2500)).await.unwrap();
+    assert_eq!(resp.to_user_id, merchant);
+
+    let err = service.pay_by_qr(payer, pay(&payload, 2500)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::QrAlreadyUsed));
+
+    let tx_id = sqlx::query_scalar!("SELECT tx_id FROM qr_redemptions WHERE reference = 'INV-1'")
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(tx_id, Some(resp.tx_id));
+}
+
+#[tokio::test]
+async fn test_failed_payment_does_not_burn_dynamic_qr() {
+    let ctx = TestContext::new().await;
+    let (service, payer, merchant) = setup(&ctx).await;
+    let (_, payload) = QrService::new("qr_secret".to_string())
+        .generate_dynamic(merchant, 60000, "INV-2", Duration::from_secs(600))
+        .unwrap();
+
+    // Payer only has ₹500
+    assert!(service.pay_by_qr(payer, pay(&payload, 60000)).await.is_err());
+
+    let redeemed = sqlx::query_scalar!("SELECT COUNT(*) FROM qr_redemptions")
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(redeemed, Some(0));
+}
+
+#[tokio::test]
+async fn test_expired_dynamic_qr_is_rejected() {
+    let ctx = TestContext::new().await;
+    let (service, payer, merchant) = setup(&ctx).await;
+    let (_, payload) = QrService::new("qr_secret".to_string())
+        .generate_dynamic(merchant, 1000, "INV-3", Duration::from_secs(0))
+        .unwrap();
+
+    let err = service.pay_by_qr(payer, pay(&payload, 1000)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::QrExpired));
+}
+
+#[tokio::test]
+async fn test_upi_qr_pays_handle_owner() {
+    let ctx = TestContext::new().await;
+    let (service, payer, merchant) = setup(&ctx).await;
+    let uri = QrService::new("qr_secret".to_string()).generate_upi(&merchant, None, Some(1500), None);
+
+    let err = service.pay_by_qr(payer, pay(&uri, 1000)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::QrAmountMismatch { expected: 1500 }));
+
+    let resp = service.pay_by_qr(payer, pay(&uri, 1500)).await.unwrap();
+    assert_eq!(resp.to_user_id, merchant);
+
+    // Handles at other PSPs do not map to a wallet
+    let err = service.pay_by_qr(payer, pay("upi://pay?pa=shop@okbank&cu=INR", 1500)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::UserNotFound(_)));
+}
+
+#[test]
+fn test_decode_image_reads_rendered_code() {
+    let qr_service = QrService::new("qr_secret".to_string());
+    let merchant = new_uuid();
+
+    let png = qr_service.generate_qr_png(&merchant, QrFormat::Upi, &RenderOptions::default()).unwrap();
+    let decoded = qr_service.decode_image(&png).unwrap();
+    assert_eq!(decoded, format!("upi://pay?pa={}@pay&cu=INR", merchant));
+
+    // SVG (or anything else) is not accepted as an upload
+    let svg = qr_service.generate_qr_svg(&merchant, QrFormat::Payment, &RenderOptions::default()).unwrap();
+    assert!(matches!(qr_service.decode_image(svg.as_bytes()), Err(QrImageError::UnsupportedFormat)));
+}
+
+fn query(params: &str) -> QrImageQuery {
+    serde_json::from_value(serde_json::Value::Object(
+        url::form_urlencoded::parse(params.as_bytes())
+            .map(|(k, v)| {
+                let v = v.parse::<serde_json::Value>().unwrap_or(serde_json::Value::String(v.into_owned()));
+                (k.into_owned(), v)
+            })
+            .collect(),
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_render_options_are_validated() {
+    let opts = query("size=512&ec=Q&margin=2&fg=1a237e&bg=ffffff").render_options().unwrap();
+    assert_eq!((opts.size, opts.margin, opts.dark), (512, 2, [0x1a, 0x23, 0x7e]));
+
+    // A logo defaults to the highest error correction
+    assert_eq!(query("logo=true").render_options().unwrap().ec, qrcode::EcLevel::H);
+
+    for bad in ["size=64", "size=4096", "margin=40", "fg=red", "fg=ffffff&bg=000000", "fg=cccccc", "ec=L&logo=true"] {
+        assert!(query(bad).render_options().is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_custom_rendering_still_scans_and_etag_tracks_options() {
+    let qr_service = QrService::new("qr_secret".to_string());
+    let content = qr_service.static_content(&new_uuid(), QrFormat::Payment, None);
+    let opts = query("size=600&ec=H&margin=1&fg=1a237e&bg=fff8e1").render_options().unwrap();
+
+    let png = qr_service.render_png(&content, &opts).unwrap();
+    assert_eq!(qr_service.decode_image(&png).unwrap(), content);
+
+    let etag = qr_service.etag(&content, &opts, "png");
+    assert_eq!(etag, qr_service.etag(&content, &opts, "png"));
+    assert_ne!(etag, qr_service.etag(&content, &RenderOptions::default(), "png"));
+    assert_ne!(etag, qr_service.etag(&content, &opts, "svg"));
+}
+
+#[tokio::test]
+async fn test_preview_resolves_payee_without_paying() {
+    let ctx = TestContext::new().await;
+    let (service, _payer, merchant) = setup(&ctx).await;
+    let qr_service = QrService::new("qr_secret".to_string());
+
+    let (_, payload) = qr_service.generate_dynamic(merchant, 2500, "INV-9", Duration::from_secs(600)).unwrap();
+    let png = qr_service.render_png(&payload, &RenderOptions::default()).unwrap();
+    let preview = service.preview_qr(&qr_service.decode_image(&png).unwrap()).await.unwrap();
+    assert_eq!(preview.kind, "dynamic");
+    assert_eq!(preview.payee_user_id, merchant);
+    assert_eq!(preview.amount, Some(2500));
+    assert!(!preview.amount_editable);
+
+    let preview = service.preview_qr(&format!("payment://user/{}", merchant)).await.unwrap();
+    assert_eq!((preview.amount, preview.amount_editable), (None, true));
+
+    let journal = sqlx::query_scalar!("SELECT COUNT(*) FROM transaction_journal")
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(journal, Some(0));
+}
+
+/// Reads a partner's `partner://shop/<user_id>` codes.
+struct PartnerQrResolver;
+
+#[async_trait]
+impl PayeeResolver for PartnerQrResolver {
+    async fn resolve(&self, address: &PayeeAddress) -> Result<Option<ResolvedPayee>, PaymentError> {
+        let PayeeAddress::Qr { qr_code } = address else {
+            return Ok(None);
+        };
+        Ok(qr_code
+            .strip_prefix("partner://shop/")
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .map(|user_id| ResolvedPayee::user(user_id, "partner_qr")))
+    }
+}
+
+#[tokio::test]
+async fn test_preview_uses_registered_resolvers() {
+    let ctx = TestContext::new().await;
+    let service = payment_service(&ctx.db, Arc::new(WalletService::new(ctx.db.clone())))
+        .with_resolver(Arc::new(PartnerQrResolver));
+    let shop = new_uuid();
+
+    let preview = service.preview_qr(&format!("partner://shop/{}", shop)).await.unwrap();
+    assert_eq!((preview.kind, preview.payee_user_id), ("partner_qr", shop));
+
+    // Built-in codes still resolve, and a static code must name a real user
+    let err = service.preview_qr(&format!("payment://user/{}", shop)).await.unwrap_err();
+    assert!(matches!(err, PaymentError::UserNotFound(_)));
+}
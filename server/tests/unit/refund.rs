@@ -0,0 +1,109 @@
+// tests/unit/refund.rs
+use crate::common::{TestContext, Parties, PAYEE_MOBILE};
+use payment_system::payment::models::*;
+use uuid::Uuid;
+
+/// Payer sends ₹100 to payee, leaving ₹400 with the payer.
+async fn paid(ctx: &TestContext) -> (Parties, Uuid) {
+    let f = Parties::new(ctx, 50000).await;
+    let resp = f.payments.pay_by_phone(f.payer, PayByPhoneRequest {
+        to_mobile: PAYEE_MOBILE.to_string(),
+        amount: 10000,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+
+    (f, resp.tx_id)
+}
+
+fn refund_req(amount: Option<u64>, key: &str) -> RefundRequest {
+    RefundRequest { amount, reason: None, idempotency_key: key.to_string() }
+}
+
+#[tokio::test]
+async fn test_partial_refunds_capped_at_original() {
+    let ctx = TestContext::new().await;
+    let (f, tx_id) = paid(&ctx).await;
+
+    let key1 = Uuid::new_v4().to_string();
+    f.payments.refund(f.payee, tx_id, refund_req(Some(6000), &key1)).await.unwrap();
+
+    let err = f.payments.refund(f.payee, tx_id, refund_req(Some(5000), &Uuid::new_v4().to_string()))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PaymentError::RefundExceedsOriginal { remaining: 4000 }));
+
+    // Full refund of the rest
+    f.payments.refund(f.payee, tx_id, refund_req(None, &Uuid::new_v4().to_string())).await.unwrap();
+
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 50000);
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_refund_is_idempotent() {
+    let ctx = TestContext::new().await;
+    let (f, tx_id) = paid(&ctx).await;
+
+    let key = Uuid::new_v4().to_string();
+    let first = f.payments.refund(f.payee, tx_id, refund_req(Some(3000), &key)).await.unwrap();
+    let second = f.payments.refund(f.payee, tx_id, refund_req(Some(3000), &key)).await.unwrap();
+
+    assert_eq!(first.tx_id, second.tx_id);
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 43000);
+}
+
+#[tokio::test]
+async fn test_only_payee_can_refund() {
+    let ctx = TestContext::new().await;
+    let (f, tx_id) = paid(&ctx).await;
+
+    let err = f.payments.refund(f.payer, tx_id, refund_req(None, &Uuid::new_v4().to_string()))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PaymentError::TransactionNotFound(_)));
+}
+
+#[tokio::test]
+async fn test_admin_reverse() {
+    let ctx = TestContext::new().await;
+    let (f, tx_id) = paid(&ctx).await;
+
+    f.payments.admin_reverse(tx_id, "ops@test", "duplicate charge").await.unwrap();
+
+    assert_eq!(f.wallets.get_balance(&f.payer).await.unwrap(), 50000);
+    let status = sqlx::query_scalar!("SELECT status FROM transaction_journal WHERE tx_id = $1", tx_id)
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(status, "REVERSED");
+
+    // Reversed payments cannot be refunded
+    let err = f.payments.refund(f.payee, tx_id, refund_req(None, &Uuid::new_v4().to_string()))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PaymentError::RefundNotAllowed(_)));
+}
+
+#[tokio::test]
+async fn test_refund_counts_against_payee_daily_limit() {
+    let ctx = TestContext::new().await;
+    let (f, tx_id) = paid(&ctx).await;
+
+    // Payee has already sent out all but ₹50 of their ₹10,000 today
+    sqlx::query!(
+        "INSERT INTO daily_limits (user_id, kyc_tier, amount_used) VALUES ($1, 'basic', 995000)",
+        f.payee
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    let err = f.payments.refund(f.payee, tx_id, refund_req(Some(6000), &Uuid::new_v4().to_string()))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PaymentError::DailyLimitExceeded));
+    assert_eq!(f.wallets.get_balance(&f.payee).await.unwrap(), 10000);
+
+    // The failed refund no longer counts against what is left
+    f.payments.refund(f.payee, tx_id, refund_req(Some(5000), &Uuid::new_v4().to_string())).await.unwrap();
+}
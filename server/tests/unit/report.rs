@@ -0,0 +1,196 @@
+// tests/unit/report.rs
+use crate::common::{Shop, TestContext, new_uuid, onboarding};
+use payment_system::fee::FeeService;
+use payment_system::merchant::models::*;
+use payment_system::payment::models::*;
+use payment_system::payment::payee::PayeeAddress;
+use payment_system::report::{ReportService, models::*};
+use payment_system::settlement::SettlementService;
+use payment_system::settlement::service::{PayoutClient, RetryPolicy};
+use payment_system::settlement::models::Payout;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+struct Bank;
+
+#[async_trait]
+impl PayoutClient for Bank {
+    async fn transfer(&self, payout: &Payout) -> Result<String, String> {
+        Ok(format!("UTR{}", &payout.reference.simple().to_string()[..10]))
+    }
+}
+
+struct Fixture {
+    reports: ReportService,
+    settlements: SettlementService,
+    merchant_id: Uuid,
+    shop: Shop,
+}
+
+impl std::ops::Deref for Fixture {
+    type Target = Shop;
+
+    fn deref(&self) -> &Shop {
+        &self.shop
+    }
+}
+
+/// An ACTIVE T0 merchant on the STANDARD plan and a payer with ₹5,000.
+async fn setup(ctx: &TestContext) -> Fixture {
+    let shop = Shop::new(ctx, 500_000).await;
+    let merchant_id = shop.open(onboarding(None)).await;
+    shop.merchants.set_settlement_cycle(merchant_id, SetSettlementCycleRequest { settlement_cycle: SettlementCycle::T0 })
+        .await
+        .unwrap();
+
+    let settlements = SettlementService::new(
+        ctx.db.clone(),
+        shop.wallets.clone(),
+        shop.merchants.clone(),
+        Arc::new(FeeService::new(ctx.db.clone(), shop.merchants.clone())),
+        Arc::new(Bank),
+        RetryPolicy { max_attempts: 1, backoff: Duration::from_secs(60) },
+    );
+
+    Fixture {
+        reports: ReportService::new(ctx.db.clone(), shop.merchants.clone()),
+        settlements,
+        merchant_id,
+        shop,
+    }
+}
+
+fn today_ist() -> chrono::NaiveDate {
+    (chrono::Utc::now() + chrono::Duration::minutes(330)).date_naive()
+}
+
+fn query(granularity: Granularity, by_method: bool) -> SalesReportQuery {
+    SalesReportQuery { from: today_ist(), to: today_ist(), granularity, by_method }
+}
+
+async fn refresh(f: &Fixture) {
+    assert!(f.reports.refresh_rollups(500).await.unwrap().hours > 0);
+}
+
+#[test]
+fn test_settlement_status_follows_the_money() {
+    assert_eq!(SettlementStatus::of(100, 0, 0), SettlementStatus::Unsettled);
+    assert_eq!(SettlementStatus::of(0, 100, 0), SettlementStatus::Settling);
+    assert_eq!(SettlementStatus::of(0, 0, 100), SettlementStatus::Settled);
+    assert_eq!(SettlementStatus::of(40, 0, 60), SettlementStatus::Partial);
+}
+
+#[tokio::test]
+async fn test_report_tracks_sales_by_method_through_settlement() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let first = f.payments.pay(f.payer, PayRequest {
+        payee: PayeeAddress::Merchant { merchant_id: f.merchant_id },
+        amount: 10_000,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+    f.payments.pay_user(f.payer, f.merchant_id, 5_000, &Uuid::new_v4().to_string(), "collect", "order-1")
+        .await
+        .unwrap();
+    // Refunds are reported under the payment's method
+    f.payments.refund(f.merchant_id, first.tx_id, RefundRequest {
+        amount: Some(2_000),
+        reason: None,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+
+    // Nothing until the rollups are refreshed, and the report says so
+    let report = f.reports.sales_report(f.owner, f.merchant_id, query(Granularity::Day, true)).await.unwrap();
+    assert!(report.rows.is_empty() && report.refreshing);
+
+    refresh(&f).await;
+    let report = f.reports.sales_report(f.owner, f.merchant_id, query(Granularity::Day, true)).await.unwrap();
+    assert!(!report.refreshing);
+    let methods: Vec<_> = report.rows.iter().map(|r| r.method.as_deref().unwrap()).collect();
+    assert_eq!(methods, ["collect", "merchant"]);
+    let merchant = &report.rows[1];
+    assert_eq!((merchant.payment_count, merchant.gross, merchant.refund_count, merchant.refunds), (1, 10_000, 1, 2_000));
+    assert_eq!((report.totals.gross, report.totals.net, report.totals.unsettled), (15_000, 13_000, 13_000));
+    assert_eq!(report.totals.settlement_status, SettlementStatus::Unsettled);
+
+    // Fees appear once the payments are batched
+    let batch = f.settlements.create_batch(f.merchant_id, chrono::Utc::now()).await.unwrap().unwrap();
+    refresh(&f).await;
+    let totals = f.reports.sales_report(f.owner, f.merchant_id, query(Granularity::Total, false)).await.unwrap().totals;
+    assert_eq!((totals.fees, totals.gst, totals.net), (batch.fees, batch.gst, batch.net));
+    assert_eq!((totals.unsettled, totals.settling), (0, 12_682));
+    assert_eq!(totals.settlement_status, SettlementStatus::Settling);
+
+    f.settlements.pay_out(batch.batch_id).await.unwrap();
+    refresh(&f).await;
+    let report = f.reports.sales_report(f.owner, f.merchant_id, query(Granularity::Day, true)).await.unwrap();
+    assert_eq!((report.totals.settled, report.totals.settlement_status), (12_682, SettlementStatus::Settled));
+
+    // Hourly rows add up to the same totals
+    let hourly = f.reports.sales_report(f.owner, f.merchant_id, query(Granularity::Hour, false)).await.unwrap();
+    assert!(hourly.rows.iter().all(|r| r.period_start.format("%M").to_string() == "00"));
+    assert_eq!(hourly.totals, report.totals);
+
+    let csv = report.to_csv();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("period_start,method,payment_count,gross"));
+    assert!(lines[2].ends_with(",merchant,1,10000,1,2000,180,32,7788,0,0,7788,SETTLED"));
+}
+
+#[tokio::test]
+async fn test_hour_changed_during_a_refresh_is_not_lost() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+    let payment = f.payments.pay(f.payer, PayRequest {
+        payee: PayeeAddress::Merchant { merchant_id: f.merchant_id },
+        amount: 10_000,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+
+    // A change to the already queued hour, not yet committed
+    let mut change = ctx.db.begin().await.unwrap();
+    sqlx::query!("UPDATE transaction_journal SET status = 'FAILED' WHERE tx_id = $1", payment.tx_id)
+        .execute(&mut *change)
+        .await
+        .unwrap();
+
+    // The hour stays queued until the change can be seen
+    assert_eq!(f.reports.refresh_rollups(500).await.unwrap().hours, 0);
+    change.commit().await.unwrap();
+
+    refresh(&f).await;
+    let report = f.reports.sales_report(f.owner, f.merchant_id, query(Granularity::Total, false)).await.unwrap();
+    assert_eq!((report.totals.payment_count, report.totals.gross), (0, 0));
+    assert!(!report.refreshing);
+}
+
+#[tokio::test]
+async fn test_report_checks_owner_and_range() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    let err = f.reports.sales_report(new_uuid(), f.merchant_id, query(Granularity::Day, false)).await.unwrap_err();
+    assert!(matches!(err, ReportError::MerchantError(MerchantError::NotFound(_))));
+
+    let backwards = SalesReportQuery { from: today_ist(), to: today_ist().pred_opt().unwrap(), granularity: Granularity::Day, by_method: false };
+    let err = f.reports.sales_report(f.owner, f.merchant_id, backwards).await.unwrap_err();
+    assert!(matches!(err, ReportError::ValidationError(_)));
+
+    let last_day = SalesReportQuery { from: chrono::NaiveDate::MAX, to: chrono::NaiveDate::MAX, granularity: Granularity::Day, by_method: false };
+    let err = f.reports.sales_report(f.owner, f.merchant_id, last_day).await.unwrap_err();
+    assert!(matches!(err, ReportError::ValidationError(_)));
+
+    let long_hourly = SalesReportQuery {
+        from: today_ist() - chrono::Duration::days(MAX_HOURLY_DAYS),
+        to: today_ist(),
+        granularity: Granularity::Hour,
+        by_method: false,
+    };
+    let err = f.reports.sales_report(f.owner, f.merchant_id, long_hourly.clone()).await.unwrap_err();
+    assert!(matches!(err, ReportError::ValidationError(_)));
+    let daily = SalesReportQuery { granularity: Granularity::Day, ..long_hourly };
+    assert!(f.reports.sales_report(f.owner, f.merchant_id, daily).await.unwrap().rows.is_empty());
+}
@@ -0,0 +1,215 @@
+// tests/unit/settlement.rs
+use crate::common::{TestContext, Shop, new_uuid, onboarding};
+use payment_system::bank::service::FakeBankService;
+use payment_system::fee::FeeService;
+use payment_system::ledger::LedgerService;
+use payment_system::merchant::models::*;
+use payment_system::payment::models::*;
+use payment_system::payment::payee::PayeeAddress;
+use payment_system::settlement::{SettlementService, models::*};
+use payment_system::settlement::service::{PayoutClient, RetryPolicy};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Stand-in for the bank: pays out unless told to reject, and remembers
+/// every payout it was asked for.
+#[derive(Default)]
+struct FakeBank {
+    reject: Mutex<Option<String>>,
+    payouts: Mutex<Vec<Payout>>,
+}
+
+#[async_trait]
+impl PayoutClient for FakeBank {
+    async fn transfer(&self, payout: &Payout) -> Result<String, String> {
+        self.payouts.lock().unwrap().push(payout.clone());
+        match self.reject.lock().unwrap().clone() {
+            Some(reason) => Err(reason),
+            None => Ok(format!("UTR{}", &payout.reference.simple().to_string()[..10])),
+        }
+    }
+}
+
+struct Fixture {
+    settlements: SettlementService,
+    bank: Arc<FakeBank>,
+    merchant_id: Uuid,
+    shop: Shop,
+}
+
+impl std::ops::Deref for Fixture {
+    type Target = Shop;
+
+    fn deref(&self) -> &Shop {
+        &self.shop
+    }
+}
+
+/// An ACTIVE merchant on `cycle` and a payer with ₹500.
+async fn setup(ctx: &TestContext, cycle: SettlementCycle) -> Fixture {
+    let shop = Shop::new(ctx, 50000).await;
+    let merchant_id = shop.open(onboarding(None)).await;
+    shop.merchants.set_settlement_cycle(merchant_id, SetSettlementCycleRequest { settlement_cycle: cycle }).await.unwrap();
+
+    let bank = Arc::new(FakeBank::default());
+    let settlements = SettlementService::new(
+        ctx.db.clone(),
+        shop.wallets.clone(),
+        shop.merchants.clone(),
+        Arc::new(FeeService::new(ctx.db.clone(), shop.merchants.clone())),
+        bank.clone(),
+        RetryPolicy { max_attempts: 2, backoff: Duration::from_secs(60) },
+    );
+
+    Fixture { settlements, bank, merchant_id, shop }
+}
+
+async fn pay_merchant(f: &Fixture, amount: u64) -> PaymentResponse {
+    f.payments.pay(f.payer, PayRequest {
+        payee: PayeeAddress::Merchant { merchant_id: f.merchant_id },
+        amount,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_t0_batch_pays_out_net_and_settles_each_payment_once() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, SettlementCycle::T0).await;
+    let first = pay_merchant(&f, 10_000).await;
+    pay_merchant(&f, 5_000).await;
+    f.payments.refund(f.merchant_id, first.tx_id, RefundRequest {
+        amount: Some(2_000),
+        reason: None,
+        idempotency_key: Uuid::new_v4().to_string(),
+    }).await.unwrap();
+
+    let batch = f.settlements.create_batch(f.merchant_id, chrono::Utc::now()).await.unwrap().unwrap();
+    assert_eq!((batch.payment_count, batch.refund_count), (2, 1));
+    // 1.8% of each payment, GST on each fee: 180 + 32 and 90 + 16
+    assert_eq!((batch.gross, batch.refunds, batch.fees, batch.gst), (15_000, 2_000, 270, 48));
+    assert_eq!(batch.net, 12_682);
+    assert_eq!(batch.status, "PENDING");
+
+    // The wallet is emptied; fees and GST land in the fee account
+    assert_eq!(f.wallets.get_balance(&f.merchant_id).await.unwrap(), 0);
+    assert_eq!(LedgerService::new(ctx.db.clone()).balance("system:fees").await.unwrap(), 318);
+
+    let batch = f.settlements.pay_out(batch.batch_id).await.unwrap();
+    assert_eq!(batch.status, "PAID");
+    assert!(batch.utr.as_deref().unwrap().starts_with("UTR"));
+    assert_eq!(f.bank.payouts.lock().unwrap()[0].amount, 12_682);
+    assert_eq!(LedgerService::new(ctx.db.clone()).trial_balance().await.unwrap(), 0);
+
+    // Nothing left to settle, and a paid batch is not paid twice
+    assert!(f.settlements.create_batch(f.merchant_id, chrono::Utc::now()).await.unwrap().is_none());
+    let err = f.settlements.pay_out(batch.batch_id).await.unwrap_err();
+    assert!(matches!(err, SettlementError::InvalidStatus(s) if s == "PAID"));
+
+    let report = f.settlements.report(f.owner, f.merchant_id, batch.batch_id).await.unwrap();
+    assert_eq!(report.items.len(), 3);
+    assert_eq!(report.items.iter().map(|i| i.amount).sum::<i64>(), 13_000);
+    assert_eq!(report.attempts.len(), 1);
+
+    // Only the owner sees the report
+    let err = f.settlements.report(new_uuid(), f.merchant_id, batch.batch_id).await.unwrap_err();
+    assert!(matches!(err, SettlementError::MerchantError(MerchantError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_t1_waits_for_the_next_day() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, SettlementCycle::T1).await;
+    pay_merchant(&f, 10_000).await;
+
+    assert!(f.settlements.create_batch(f.merchant_id, chrono::Utc::now()).await.unwrap().is_none());
+
+    let tomorrow = chrono::Utc::now() + chrono::Duration::days(1);
+    let batch = f.settlements.create_batch(f.merchant_id, tomorrow).await.unwrap().unwrap();
+    assert_eq!(batch.cycle, "T1");
+    assert_eq!(batch.gross, 10_000);
+}
+
+#[tokio::test]
+async fn test_rejected_payout_is_retried_with_backoff_then_left_for_ops() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, SettlementCycle::T0).await;
+    pay_merchant(&f, 10_000).await;
+    let batch = f.settlements.create_batch(f.merchant_id, chrono::Utc::now()).await.unwrap().unwrap();
+
+    *f.bank.reject.lock().unwrap() = Some("Beneficiary account is closed".to_string());
+    let batch = f.settlements.pay_out(batch.batch_id).await.unwrap();
+    assert_eq!((batch.status.as_str(), batch.attempts), ("FAILED", 1));
+    assert_eq!(batch.last_error.as_deref(), Some("Beneficiary account is closed"));
+    let first_retry = batch.next_retry_at.unwrap();
+    assert!(first_retry > chrono::Utc::now());
+    // Not due yet
+    assert!(f.settlements.due_payouts().await.unwrap().is_empty());
+
+    // Out of automatic attempts
+    let batch = f.settlements.pay_out(batch.batch_id).await.unwrap();
+    assert_eq!((batch.status.as_str(), batch.attempts), ("FAILED", 2));
+    assert!(batch.next_retry_at.is_none());
+
+    // Ops retry once the account is fixed; the money was never released
+    *f.bank.reject.lock().unwrap() = None;
+    let batch = f.settlements.pay_out(batch.batch_id).await.unwrap();
+    assert_eq!((batch.status.as_str(), batch.attempts), ("PAID", 3));
+    assert_eq!(f.bank.payouts.lock().unwrap().iter().filter(|p| p.reference == batch.batch_id).count(), 3);
+
+    let report = f.settlements.report(f.owner, f.merchant_id, batch.batch_id).await.unwrap();
+    let statuses: Vec<&str> = report.attempts.iter().map(|a| a.status.as_str()).collect();
+    assert_eq!(statuses, ["FAILED", "FAILED", "PAID"]);
+}
+
+#[tokio::test]
+async fn test_stale_processing_batch_is_claimed_again() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, SettlementCycle::T0).await;
+    pay_merchant(&f, 10_000).await;
+    let batch = f.settlements.create_batch(f.merchant_id, chrono::Utc::now()).await.unwrap().unwrap();
+
+    // A worker claimed the batch and died before recording the outcome
+    sqlx::query!(
+        "UPDATE settlement_batches SET status = 'PROCESSING', attempts = 1 WHERE batch_id = $1",
+        batch.batch_id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    // A fresh claim is left to its worker
+    assert!(f.settlements.due_payouts().await.unwrap().is_empty());
+    let err = f.settlements.pay_out(batch.batch_id).await.unwrap_err();
+    assert!(matches!(err, SettlementError::InvalidStatus(s) if s == "PROCESSING"));
+
+    sqlx::query!(
+        "UPDATE settlement_batches SET updated_at = NOW() - INTERVAL '1 hour' WHERE batch_id = $1",
+        batch.batch_id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    assert_eq!(f.settlements.due_payouts().await.unwrap(), [batch.batch_id]);
+    let batch = f.settlements.pay_out(batch.batch_id).await.unwrap();
+    assert_eq!((batch.status.as_str(), batch.attempts), ("PAID", 2));
+    assert_eq!(LedgerService::new(ctx.db.clone()).trial_balance().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_bank_pays_a_reference_once() {
+    let ctx = TestContext::new().await;
+    let bank = FakeBankService::new(ctx.db.clone());
+    let (merchant_id, reference) = (new_uuid(), new_uuid());
+
+    let first = bank.transfer("1234567890", merchant_id, Some("123456789012"), 10_000, Some(reference)).await.unwrap();
+    let repeat = bank.transfer("1234567890", merchant_id, Some("123456789012"), 10_000, Some(reference)).await.unwrap();
+    assert_eq!((first.status.as_str(), repeat.status.as_str()), ("success", "success"));
+    assert_eq!(repeat.utr, first.utr);
+
+    let other = bank.transfer("1234567890", merchant_id, Some("123456789012"), 10_000, Some(new_uuid())).await.unwrap();
+    assert_ne!(other.utr, first.utr);
+}
@@ -0,0 +1,193 @@
+// tests/unit/split.rs
+use crate::common::{TestContext, new_uuid, payment_service, create_user, create_wallet, credit};
+use payment_system::collect::CollectService;
+use payment_system::split::{SplitService, models::*, service::compute_shares};
+use payment_system::wallet::WalletService;
+use payment_system::ws::server::WsServer;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const ALICE: &str = "+919876543210";
+const BOB: &str = "+919876543211";
+
+struct Fixture {
+    service: SplitService,
+    collects: Arc<CollectService>,
+    wallets: Arc<WalletService>,
+    creator: Uuid,
+    alice: Uuid,
+    bob: Uuid,
+}
+
+/// Creator with an empty wallet; Alice and Bob hold ₹500 each.
+async fn setup(ctx: &TestContext) -> Fixture {
+    let wallets = Arc::new(WalletService::new(ctx.db.clone()));
+    let payments = Arc::new(payment_service(&ctx.db, wallets.clone()));
+    let collects = Arc::new(CollectService::new(ctx.db.clone(), payments.clone(), Arc::new(WsServer::new())));
+    let service = SplitService::new(ctx.db.clone(), collects.clone(), payments);
+
+    let creator = new_uuid();
+    create_wallet(&wallets, creator, 0).await;
+    let alice = create_user(&ctx.db, ALICE).await;
+    let bob = create_user(&ctx.db, BOB).await;
+    for user_id in [alice, bob] {
+        create_wallet(&wallets, user_id, 50000).await;
+    }
+
+    Fixture { service, collects, wallets, creator, alice, bob }
+}
+
+fn participant(mobile: &str, amount: Option<u64>, percent_bps: Option<u32>) -> SplitParticipantInput {
+    SplitParticipantInput { mobile: mobile.to_string(), amount, percent_bps }
+}
+
+#[test]
+fn test_compute_shares() {
+    let two = [participant(ALICE, None, None), participant(BOB, None, None)];
+
+    // ₹100 three ways: a participant pays the leftover paisa, the creator's 3333 makes up the rest
+    assert_eq!(compute_shares(10000, SplitType::Equal, true, &two).unwrap(), vec![3334, 3333]);
+    assert_eq!(compute_shares(10002, SplitType::Equal, true, &two).unwrap(), vec![3334, 3334]);
+    // ₹100.01 two ways without the creator: first participant pays the extra paisa
+    assert_eq!(compute_shares(10001, SplitType::Equal, false, &two).unwrap(), vec![5001, 5000]);
+
+    let by_amount = [participant(ALICE, Some(7000), None), participant(BOB, Some(4000), None)];
+    assert!(matches!(
+        compute_shares(10000, SplitType::Amount, true, &by_amount),
+        Err(SplitError::InvalidShares(_))
+    ));
+
+    let by_pct = [participant(ALICE, None, Some(2500)), participant(BOB, None, Some(5000))];
+    assert_eq!(compute_shares(10000, SplitType::Percentage, true, &by_pct).unwrap(), vec![2500, 5000]);
+}
+
+#[tokio::test]
+async fn test_split_settles_when_every_share_is_paid() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    let split = f.service.create(f.creator, CreateSplitRequest {
+        description: "Dinner".to_string(),
+        total_amount: 9000,
+        split_type: SplitType::Equal,
+        include_self: Some(true),
+        participants: vec![participant(ALICE, None, None), participant(BOB, None, None)],
+    }).await.unwrap();
+    assert_eq!(split.status, "OPEN");
+    assert!(split.participants.iter().all(|p| p.share == 3000 && p.status == "PENDING"));
+
+    for payer in [f.alice, f.bob] {
+        let request_id = f.service.get(payer, split.split.split_id).await.unwrap()
+            .participants.iter()
+            .find(|p| p.user_id == payer)
+            .and_then(|p| p.collect_request_id)
+            .unwrap();
+        f.collects.approve(payer, request_id).await.unwrap();
+    }
+
+    let settled = f.service.get(f.creator, split.split.split_id).await.unwrap();
+    assert_eq!(settled.status, "SETTLED");
+    assert_eq!(settled.paid_amount, 6000);
+    assert_eq!(f.wallets.get_balance(&f.creator).await.unwrap(), 6000);
+}
+
+#[tokio::test]
+async fn test_share_can_be_paid_after_a_failed_attempt() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    // Alice's ₹600 share is more than her ₹500
+    let split = f.service.create(f.creator, CreateSplitRequest {
+        description: "Concert".to_string(),
+        total_amount: 120000,
+        split_type: SplitType::Amount,
+        include_self: None,
+        participants: vec![participant(ALICE, Some(60000), None), participant(BOB, Some(30000), None)],
+    }).await.unwrap();
+    let request_id = split.participants.iter().find(|p| p.user_id == f.alice).unwrap().collect_request_id.unwrap();
+
+    assert!(f.collects.approve(f.alice, request_id).await.is_err());
+    assert!(f.collects.approve(f.alice, request_id).await.is_err());
+    let after = f.service.get(f.creator, split.split.split_id).await.unwrap();
+    let alice = after.participants.iter().find(|p| p.user_id == f.alice).unwrap();
+    assert_eq!((alice.status.as_str(), after.paid_amount), ("PENDING", 0));
+    assert_eq!(f.wallets.get_balance(&f.alice).await.unwrap(), 50000);
+
+    credit(&f.wallets, f.alice, 10000).await;
+    f.collects.approve(f.alice, request_id).await.unwrap();
+
+    let after = f.service.get(f.creator, split.split.split_id).await.unwrap();
+    assert_eq!(after.paid_amount, 60000);
+    assert_eq!(f.wallets.get_balance(&f.creator).await.unwrap(), 60000);
+}
+
+#[tokio::test]
+async fn test_remind_reraises_declined_shares() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    let split = f.service.create(f.creator, CreateSplitRequest {
+        description: "Cab".to_string(),
+        total_amount: 5000,
+        split_type: SplitType::Amount,
+        include_self: None,
+        participants: vec![participant(ALICE, Some(2000), None), participant(BOB, Some(2000), None)],
+    }).await.unwrap();
+
+    let alice_request = split.participants.iter().find(|p| p.user_id == f.alice).unwrap().collect_request_id.unwrap();
+    f.collects.decline(f.alice, alice_request).await.unwrap();
+
+    assert_eq!(f.service.remind(f.creator, split.split.split_id).await.unwrap(), 2);
+
+    let after = f.service.get(f.creator, split.split.split_id).await.unwrap();
+    let alice = after.participants.iter().find(|p| p.user_id == f.alice).unwrap();
+    assert_eq!(alice.status, "PENDING");
+    assert_ne!(alice.collect_request_id, Some(alice_request));
+
+    // Cooldown: nobody is reminded twice in a row
+    assert_eq!(f.service.remind(f.creator, split.split.split_id).await.unwrap(), 0);
+
+    // Strangers cannot see the split
+    let err = f.service.get(new_uuid(), split.split.split_id).await.unwrap_err();
+    assert!(matches!(err, SplitError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn test_remind_resends_pending_shares_and_reraises_expired_ones() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx).await;
+
+    let split = f.service.create(f.creator, CreateSplitRequest {
+        description: "Groceries".to_string(),
+        total_amount: 4000,
+        split_type: SplitType::Amount,
+        include_self: None,
+        participants: vec![participant(ALICE, Some(2000), None), participant(BOB, Some(2000), None)],
+    }).await.unwrap();
+    let request_of = |detail: &SplitDetail, user_id: Uuid| {
+        detail.participants.iter().find(|p| p.user_id == user_id).unwrap().collect_request_id.unwrap()
+    };
+    let (alice_request, bob_request) = (request_of(&split, f.alice), request_of(&split, f.bob));
+
+    // Alice's request has lapsed but has not been swept to EXPIRED yet
+    sqlx::query!(
+        "UPDATE collect_requests SET expires_at = NOW() - INTERVAL '1 second' WHERE request_id = $1",
+        alice_request
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+    let before = f.service.get(f.creator, split.split.split_id).await.unwrap();
+    assert_eq!(before.participants.iter().find(|p| p.user_id == f.alice).unwrap().status, "EXPIRED");
+
+    assert_eq!(f.service.remind(f.creator, split.split.split_id).await.unwrap(), 2);
+
+    let after = f.service.get(f.creator, split.split.split_id).await.unwrap();
+    assert_ne!(request_of(&after, f.alice), alice_request);
+    assert_eq!(request_of(&after, f.bob), bob_request);
+    let open = sqlx::query_scalar!("SELECT COUNT(*) AS \"count!\" FROM collect_requests WHERE status = 'PENDING' AND expires_at > NOW()")
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(open, 2);
+}
@@ -0,0 +1,80 @@
+// tests/unit/sweeper.rs
+use crate::common::{TestContext, new_uuid};
+use payment_system::payment::sweeper::{PaymentSweeper, SweepReport};
+use payment_system::transaction::state::initiate;
+use payment_system::wallet::{WalletService, models::*};
+use payment_system::ws::server::WsServer;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_stale_initiated_payment_is_failed() {
+    let ctx = TestContext::new().await;
+    let sweeper = PaymentSweeper::new(
+        ctx.db.clone(),
+        Arc::new(WsServer::new()),
+        Duration::from_secs(60),
+        Duration::from_secs(30),
+    );
+
+    // Payment that never got past INITIATED, ten minutes ago
+    let tx_id = new_uuid();
+    let mut conn = ctx.db.acquire().await.unwrap();
+    initiate(&mut conn, tx_id, new_uuid(), new_uuid(), 5000, "stuck_1", None, "user_id").await.unwrap();
+    sqlx::query!(
+        "UPDATE transaction_journal SET created_at = NOW() - INTERVAL '10 minutes' WHERE tx_id = $1",
+        tx_id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    // A fresh one must be left alone
+    initiate(&mut conn, new_uuid(), new_uuid(), new_uuid(), 5000, "fresh_1", None, "user_id").await.unwrap();
+
+    let report = sweeper.sweep_once().await.unwrap();
+    assert_eq!(report, SweepReport { failed: 1, ..Default::default() });
+
+    let status = sqlx::query_scalar!("SELECT status FROM transaction_journal WHERE tx_id = $1", tx_id)
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(status, "FAILED");
+}
+
+#[tokio::test]
+async fn test_reverse_transfer_restores_balances() {
+    let ctx = TestContext::new().await;
+    let service = WalletService::new(ctx.db.clone());
+
+    let sender_id = new_uuid();
+    let receiver_id = new_uuid();
+    service.create_wallet(CreateWalletRequest { user_id: sender_id }).await.unwrap();
+    service.create_wallet(CreateWalletRequest { user_id: receiver_id }).await.unwrap();
+    service.credit(&CreditDebitRequest {
+        user_id: sender_id,
+        amount: 10000,
+        idempotency_key: "sweep_seed".to_string(),
+    }).await.unwrap();
+
+    let tx_id = new_uuid();
+    let mut conn = ctx.db.acquire().await.unwrap();
+    initiate(&mut conn, tx_id, sender_id, receiver_id, 4000, "sweep_transfer", None, "user_id").await.unwrap();
+    service.transfer(&TransferRequest {
+        tx_id,
+        from_user_id: sender_id,
+        to_user_id: receiver_id,
+        amount: 4000,
+        idempotency_key: "sweep_transfer".to_string(),
+    }).await.unwrap();
+
+    service.reverse_transfer(
+        tx_id,
+        "sweep_transfer",
+        payment_system::transaction::state::PaymentState::Success,
+        "test reversal",
+    ).await.unwrap();
+
+    assert_eq!(service.get_balance(&sender_id).await.unwrap(), 10000);
+    assert_eq!(service.get_balance(&receiver_id).await.unwrap(), 0);
+}
@@ -0,0 +1,134 @@
+// tests/unit/transaction.rs
+use crate::common::{TestContext, new_uuid};
+use payment_system::payment::{PaymentService, models::*};
+use payment_system::transaction::TransactionService;
+use payment_system::wallet::{WalletService, models::*};
+use mockall::mock;
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+mock! {
+    pub NatsClient {}
+    #[async_trait]
+    impl NatsClient for NatsClient {
+        async fn publish_fraud_event(&self, event: &payment_system::payment::models::FraudEvent) -> Result<(), Box<dyn std::error::Error>>;
+    }
+}
+
+async fn setup(ctx: &TestContext, sender_balance: u64) -> (PaymentService, Uuid, Uuid) {
+    let wallet_service = Arc::new(WalletService::new(ctx.db.clone()));
+    let service = PaymentService::new(
+        ctx.db.clone(),
+        wallet_service.clone(),
+        "otp_secret".to_string(),
+        "qr_secret".to_string(),
+        Arc::new(MockNatsClient::new()),
+    );
+
+    let sender_id = new_uuid();
+    wallet_service.create_wallet(CreateWalletRequest { user_id: sender_id }).await.unwrap();
+    wallet_service.credit(&CreditDebitRequest {
+        user_id: sender_id,
+        amount: sender_balance,
+        idempotency_key: format!("seed_{}", sender_id),
+    }).await.unwrap();
+
+    let receiver_id = new_uuid();
+    let mobile_hash = payment_system::auth::crypto::hash_mobile("+919876543210", "otp_secret");
+    sqlx::query!("INSERT INTO users (id, mobile_hash) VALUES ($1, $2)", receiver_id, mobile_hash)
+        .execute(&ctx.db)
+        .await
+        .unwrap();
+    wallet_service.create_wallet(CreateWalletRequest { user_id: receiver_id }).await.unwrap();
+
+    (service, sender_id, receiver_id)
+}
+
+#[tokio::test]
+async fn test_successful_payment_timeline() {
+    let ctx = TestContext::new().await;
+    let (service, sender_id, receiver_id) = setup(&ctx, 50000).await;
+
+    let resp = service.pay_by_phone(sender_id, PayByPhoneRequest {
+        to_mobile: "+919876543210".to_string(),
+        amount: 10000,
+        idempotency_key: "timeline_1".to_string(),
+    }).await.unwrap();
+
+    let timeline = TransactionService::new(ctx.db.clone())
+        .get_timeline(receiver_id, resp.tx_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(timeline.status, "SUCCESS");
+    let states: Vec<&str> = timeline.events.iter().map(|e| e.to_state.as_str()).collect();
+    assert_eq!(states, vec!["INITIATED", "SUCCESS"]);
+}
+
+#[tokio::test]
+async fn test_failed_payment_is_recorded() {
+    let ctx = TestContext::new().await;
+    let (service, sender_id, _) = setup(&ctx, 1000).await;
+
+    let err = service.pay_by_phone(sender_id, PayByPhoneRequest {
+        to_mobile: "+919876543210".to_string(),
+        amount: 10000,
+        idempotency_key: "timeline_2".to_string(),
+    }).await.unwrap_err();
+    assert!(matches!(err, PaymentError::WalletError(WalletError::InsufficientBalance)));
+
+    let tx_id = sqlx::query_scalar!(
+        "SELECT tx_id FROM transaction_journal WHERE idempotency_key = 'timeline_2'"
+    )
+    .fetch_one(&ctx.db)
+    .await
+    .unwrap();
+
+    let timeline = TransactionService::new(ctx.db.clone())
+        .get_timeline(sender_id, tx_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(timeline.status, "FAILED");
+    let last = timeline.events.last().unwrap();
+    assert_eq!(last.to_state, "FAILED");
+    assert_eq!(last.reason.as_deref(), Some("Wallet error: Insufficient balance"));
+}
+
+#[tokio::test]
+async fn test_retrying_a_failed_payment_reports_the_failure() {
+    let ctx = TestContext::new().await;
+    let (service, sender_id, _) = setup(&ctx, 1000).await;
+    let req = || PayByPhoneRequest {
+        to_mobile: "+919876543210".to_string(),
+        amount: 10000,
+        idempotency_key: "timeline_4".to_string(),
+    };
+
+    service.pay_by_phone(sender_id, req()).await.unwrap_err();
+    // Same key again: an error naming the first failure, not a 200 with status Failed
+    let err = service.pay_by_phone(sender_id, req()).await.unwrap_err();
+    assert!(matches!(err, PaymentError::AttemptFailed(ref reason) if reason.contains("Insufficient balance")));
+}
+
+#[tokio::test]
+async fn test_timeline_hidden_from_other_users() {
+    let ctx = TestContext::new().await;
+    let (service, sender_id, _) = setup(&ctx, 50000).await;
+
+    let resp = service.pay_by_phone(sender_id, PayByPhoneRequest {
+        to_mobile: "+919876543210".to_string(),
+        amount: 10000,
+        idempotency_key: "timeline_3".to_string(),
+    }).await.unwrap();
+
+    let timeline = TransactionService::new(ctx.db.clone())
+        .get_timeline(new_uuid(), resp.tx_id)
+        .await
+        .unwrap();
+
+    assert!(timeline.is_none());
+}
@@ -1,6 +1,7 @@
 // tests/unit/wallet.rs
 use crate::common::{TestContext, new_uuid};
 use payment_system::wallet::{WalletService, models::*};
+use payment_system::transaction::state::initiate;
 
 #[tokio::test]
 async fn test_create_wallet() {
@@ -51,4 +52,227 @@ async fn test_debit_insufficient_balance() {
 
     let err = service.debit(&req).await.unwrap_err();
     assert!(matches!(err, WalletError::InsufficientBalance));
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_transfer_moves_funds_atomically() {
+    let ctx = TestContext::new().await;
+    let service = WalletService::new(ctx.db.clone());
+
+    let sender_id = new_uuid();
+    let receiver_id = new_uuid();
+    service.create_wallet(CreateWalletRequest { user_id: sender_id }).await.unwrap();
+    service.create_wallet(CreateWalletRequest { user_id: receiver_id }).await.unwrap();
+    service.credit(&CreditDebitRequest {
+        user_id: sender_id,
+        amount: 10000,
+        idempotency_key: "test_key_3".to_string(),
+    }).await.unwrap();
+
+    let tx_id = new_uuid();
+    let mut conn = ctx.db.acquire().await.unwrap();
+    initiate(&mut conn, tx_id, sender_id, receiver_id, 4000, "transfer_1", None, "user_id").await.unwrap();
+
+    let receipt = service.transfer(&TransferRequest {
+        tx_id,
+        from_user_id: sender_id,
+        to_user_id: receiver_id,
+        amount: 4000,
+        idempotency_key: "transfer_1".to_string(),
+    }).await.unwrap();
+
+    assert_eq!(receipt.tx_id, tx_id);
+    assert_eq!(receipt.from_balance, 6000);
+    assert_eq!(receipt.to_balance, 4000);
+    assert_eq!(service.get_balance(&sender_id).await.unwrap(), 6000);
+    assert_eq!(service.get_balance(&receiver_id).await.unwrap(), 4000);
+}
+
+#[tokio::test]
+async fn test_transfer_to_missing_wallet_is_refused() {
+    let ctx = TestContext::new().await;
+    let service = WalletService::new(ctx.db.clone());
+
+    let sender_id = new_uuid();
+    service.create_wallet(CreateWalletRequest { user_id: sender_id }).await.unwrap();
+    service.credit(&CreditDebitRequest {
+        user_id: sender_id,
+        amount: 10000,
+        idempotency_key: "test_key_4".to_string(),
+    }).await.unwrap();
+
+    // Receiver has no wallet — refused before anything is written
+    let missing_id = new_uuid();
+    let tx_id = new_uuid();
+    let mut conn = ctx.db.acquire().await.unwrap();
+    initiate(&mut conn, tx_id, sender_id, missing_id, 4000, "transfer_2", None, "user_id").await.unwrap();
+
+    let err = service.transfer(&TransferRequest {
+        tx_id,
+        from_user_id: sender_id,
+        to_user_id: missing_id,
+        amount: 4000,
+        idempotency_key: "transfer_2".to_string(),
+    }).await.unwrap_err();
+
+    assert!(matches!(err, WalletError::WalletNotFound(id) if id == missing_id));
+    assert_eq!(service.get_balance(&sender_id).await.unwrap(), 10000);
+}
+
+#[tokio::test]
+async fn test_failed_transfer_leaves_sender_untouched() {
+    let ctx = TestContext::new().await;
+    let service = WalletService::new(ctx.db.clone());
+
+    let sender_id = new_uuid();
+    let receiver_id = new_uuid();
+    service.create_wallet(CreateWalletRequest { user_id: sender_id }).await.unwrap();
+    service.create_wallet(CreateWalletRequest { user_id: receiver_id }).await.unwrap();
+    service.credit(&CreditDebitRequest {
+        user_id: sender_id,
+        amount: 10000,
+        idempotency_key: "test_key_5".to_string(),
+    }).await.unwrap();
+
+    // A journal row another worker already failed: the sender is debited,
+    // then the INITIATED -> SUCCESS transition is refused and everything
+    // written so far must roll back
+    let tx_id = new_uuid();
+    let mut conn = ctx.db.acquire().await.unwrap();
+    initiate(&mut conn, tx_id, sender_id, receiver_id, 4000, "transfer_3", None, "user_id").await.unwrap();
+    sqlx::query!("UPDATE transaction_journal SET status = 'FAILED' WHERE tx_id = $1", tx_id)
+        .execute(&ctx.db)
+        .await
+        .unwrap();
+
+    let err = service.transfer(&TransferRequest {
+        tx_id,
+        from_user_id: sender_id,
+        to_user_id: receiver_id,
+        amount: 4000,
+        idempotency_key: "transfer_3".to_string(),
+    }).await.unwrap_err();
+
+    assert!(matches!(err, WalletError::StateError(_)));
+    assert_eq!(service.get_balance(&sender_id).await.unwrap(), 10000);
+    assert_eq!(service.get_balance(&receiver_id).await.unwrap(), 0);
+
+    let postings = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM ledger_postings p
+        JOIN ledger_entries e ON e.entry_id = p.entry_id
+        WHERE e.reference = 'transfer_3' OR p.account_code = $1
+        "#,
+        format!("wallet:{}", receiver_id)
+    )
+    .fetch_one(&ctx.db)
+    .await
+    .unwrap();
+    assert_eq!(postings, 0);
+
+    let transitions = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM payment_state_transitions WHERE tx_id = $1"#,
+        tx_id
+    )
+    .fetch_one(&ctx.db)
+    .await
+    .unwrap();
+    assert_eq!(transitions, 1); // only INITIATED
+}
+
+#[tokio::test]
+async fn test_concurrent_transfers_share_one_daily_limit() {
+    let ctx = TestContext::new().await;
+    let service = WalletService::new(ctx.db.clone());
+
+    let sender_id = new_uuid();
+    let receiver_id = new_uuid();
+    service.create_wallet(CreateWalletRequest { user_id: sender_id }).await.unwrap();
+    service.create_wallet(CreateWalletRequest { user_id: receiver_id }).await.unwrap();
+    for _ in 0..2 {
+        service.credit(&CreditDebitRequest {
+            user_id: sender_id,
+            amount: 500000,
+            idempotency_key: new_uuid().to_string(),
+        }).await.unwrap();
+    }
+
+    // ₹7,000 left of the basic ₹10,000; each transfer fits, both together do not
+    sqlx::query!(
+        "INSERT INTO daily_limits (user_id, kyc_tier, amount_used, reset_date) VALUES ($1, 'basic', 300000, $2)",
+        sender_id,
+        chrono::Utc::now().date_naive()
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    let mut conn = ctx.db.acquire().await.unwrap();
+    let mut requests = Vec::new();
+    for key in ["limit_1", "limit_2"] {
+        let tx_id = new_uuid();
+        initiate(&mut conn, tx_id, sender_id, receiver_id, 400000, key, None, "user_id").await.unwrap();
+        requests.push(TransferRequest {
+            tx_id,
+            from_user_id: sender_id,
+            to_user_id: receiver_id,
+            amount: 400000,
+            idempotency_key: key.to_string(),
+        });
+    }
+
+    let (first, second) = tokio::join!(service.transfer(&requests[0]), service.transfer(&requests[1]));
+    let refused = [&first, &second].iter().filter(|r| matches!(r, Err(WalletError::DailyLimitExceeded))).count();
+    assert_eq!((first.is_ok() as u32 + second.is_ok() as u32, refused), (1, 1));
+    assert_eq!(service.get_balance(&receiver_id).await.unwrap(), 400000);
+
+    let used = sqlx::query_scalar!("SELECT amount_used FROM daily_limits WHERE user_id = $1", sender_id)
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(used, 700000);
+}
+
+#[tokio::test]
+async fn test_daily_limit_starts_over_on_a_new_day() {
+    let ctx = TestContext::new().await;
+    let service = WalletService::new(ctx.db.clone());
+
+    let sender_id = new_uuid();
+    let receiver_id = new_uuid();
+    service.create_wallet(CreateWalletRequest { user_id: sender_id }).await.unwrap();
+    service.create_wallet(CreateWalletRequest { user_id: receiver_id }).await.unwrap();
+    service.credit(&CreditDebitRequest {
+        user_id: sender_id,
+        amount: 500000,
+        idempotency_key: new_uuid().to_string(),
+    }).await.unwrap();
+
+    // Yesterday's usage was at the limit
+    let yesterday = chrono::Utc::now().date_naive().pred_opt().unwrap();
+    sqlx::query!(
+        "INSERT INTO daily_limits (user_id, kyc_tier, amount_used, reset_date) VALUES ($1, 'basic', 1000000, $2)",
+        sender_id,
+        yesterday
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+
+    let tx_id = new_uuid();
+    let mut conn = ctx.db.acquire().await.unwrap();
+    initiate(&mut conn, tx_id, sender_id, receiver_id, 400000, "new_day", None, "user_id").await.unwrap();
+    service.transfer(&TransferRequest {
+        tx_id,
+        from_user_id: sender_id,
+        to_user_id: receiver_id,
+        amount: 400000,
+        idempotency_key: "new_day".to_string(),
+    }).await.unwrap();
+
+    let row = sqlx::query!("SELECT amount_used, reset_date FROM daily_limits WHERE user_id = $1", sender_id)
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!((row.amount_used, row.reset_date), (400000, chrono::Utc::now().date_naive()));
+}
@@ -0,0 +1,313 @@
+// tests/unit/webhook.rs
+use crate::common::{Shop, TestContext, new_uuid, onboarding};
+use payment_system::intent::{IntentService, models::*};
+use payment_system::merchant::models::*;
+use payment_system::payment::models::RefundRequest;
+use payment_system::webhook::{WebhookService, models::*, signature};
+use payment_system::webhook::destination::{DestinationError, DestinationPolicy, is_public};
+use payment_system::webhook::service::RetryPolicy;
+use axum::{extract::State, http::{HeaderMap, StatusCode}};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Stand-in for a merchant's server: answers with `status` and keeps
+/// everything it was sent.
+#[derive(Clone)]
+struct Receiver {
+    status: Arc<Mutex<u16>>,
+    received: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+}
+
+async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: String) -> StatusCode {
+    receiver.received.lock().unwrap().push((headers, body));
+    StatusCode::from_u16(*receiver.status.lock().unwrap()).unwrap()
+}
+
+async fn start_receiver() -> (Receiver, String) {
+    let receiver = Receiver {
+        status: Arc::new(Mutex::new(200)),
+        received: Arc::new(Mutex::new(Vec::new())),
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+    let app = axum::Router::new()
+        .route("/hooks", axum::routing::post(receive))
+        .with_state(receiver.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (receiver, url)
+}
+
+struct Fixture {
+    webhooks: Arc<WebhookService>,
+    intents: IntentService,
+    receiver: Receiver,
+    url: String,
+    merchant_id: Uuid,
+    shop: Shop,
+}
+
+impl std::ops::Deref for Fixture {
+    type Target = Shop;
+    fn deref(&self) -> &Shop {
+        &self.shop
+    }
+}
+
+/// An ACTIVE merchant, a customer with ₹500 and a receiver listening.
+async fn setup(ctx: &TestContext, retry: RetryPolicy) -> Fixture {
+    let shop = Shop::new(ctx, 50000).await;
+    let merchant_id = shop.open(onboarding(None)).await;
+    // The receiver listens on loopback, which only development allows
+    let webhooks = Arc::new(
+        WebhookService::new(ctx.db.clone(), shop.merchants.clone(), retry).allow_loopback_endpoints(true),
+    );
+
+    let (receiver, url) = start_receiver().await;
+    Fixture {
+        intents: IntentService::new(ctx.db.clone(), shop.merchants.clone(), shop.payments.clone())
+            .with_webhooks(webhooks.clone()),
+        webhooks,
+        receiver,
+        url,
+        merchant_id,
+        shop,
+    }
+}
+
+fn endpoint(url: &str, events: &[&str]) -> RegisterEndpointRequest {
+    RegisterEndpointRequest {
+        url: url.to_string(),
+        events: events.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+async fn create_intent(f: &Fixture, order_id: &str) -> PaymentIntent {
+    f.intents.create(f.owner, CreatePaymentIntentRequest {
+        merchant_id: f.merchant_id,
+        amount: 12000,
+        currency: "INR".to_string(),
+        order_id: order_id.to_string(),
+        return_url: "https://shop.example/orders/complete".to_string(),
+        description: None,
+    }).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_succeeded_intent_is_delivered_signed() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, RetryPolicy::default()).await;
+    let registered = f.webhooks
+        .register_endpoint(f.owner, f.merchant_id, endpoint(&f.url, &["payment_intent.succeeded"]))
+        .await
+        .unwrap();
+    assert!(registered.secret.starts_with("whsec_"));
+    // The secret is not part of the endpoint as listed
+    let listed = f.webhooks.list_endpoints(f.owner, f.merchant_id).await.unwrap();
+    assert!(serde_json::to_value(&listed[0]).unwrap().get("secret").is_none());
+
+    let intent = create_intent(&f, "ORD-1").await;
+    f.intents.confirm(f.payer, intent.id, ConfirmPaymentIntentRequest { client_secret: intent.client_secret.clone() })
+        .await
+        .unwrap();
+    // Not subscribed to cancellations
+    let other = create_intent(&f, "ORD-2").await;
+    f.intents.cancel(f.owner, other.id, CancelPaymentIntentRequest { reason: None }).await.unwrap();
+
+    let report = f.webhooks.deliver_due().await.unwrap();
+    assert_eq!(report, DeliveryRunReport { delivered: 1, retrying: 0, failed: 0 });
+
+    let received = f.receiver.received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    let header = headers[signature::SIGNATURE_HEADER].to_str().unwrap();
+    let now = chrono::Utc::now().timestamp();
+    assert!(signature::verify(&registered.secret, header, body, now, signature::DEFAULT_TOLERANCE_SECS));
+    assert!(!signature::verify(&registered.secret, header, &body.replace("12000", "1"), now, signature::DEFAULT_TOLERANCE_SECS));
+    assert!(!signature::verify("whsec_other", header, body, now, signature::DEFAULT_TOLERANCE_SECS));
+    // A captured request replayed much later is refused
+    assert!(!signature::verify(&registered.secret, header, body, now + 3600, signature::DEFAULT_TOLERANCE_SECS));
+
+    let event: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(event["type"], "payment_intent.succeeded");
+    assert_eq!(event["data"]["id"], intent.id.to_string());
+    assert_eq!(event["data"]["status"], "succeeded");
+    assert_eq!(headers["Webhook-Id"].to_str().unwrap(), event["id"].as_str().unwrap());
+
+    let deliveries = f.webhooks.list_deliveries(f.owner, f.merchant_id).await.unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!((deliveries[0].status.as_str(), deliveries[0].last_status_code), ("SUCCEEDED", Some(200)));
+}
+
+#[tokio::test]
+async fn test_failing_endpoint_is_retried_with_backoff_and_can_be_redelivered() {
+    let ctx = TestContext::new().await;
+    let retry = RetryPolicy { max_attempts: 2, backoff: Duration::from_secs(60), max_backoff: Duration::from_secs(600) };
+    let f = setup(&ctx, retry).await;
+    f.webhooks.register_endpoint(f.owner, f.merchant_id, endpoint(&f.url, &["*"])).await.unwrap();
+    *f.receiver.status.lock().unwrap() = 500;
+
+    let intent = create_intent(&f, "ORD-3").await;
+    f.intents.cancel(f.owner, intent.id, CancelPaymentIntentRequest { reason: Some("out of stock".to_string()) })
+        .await
+        .unwrap();
+
+    let report = f.webhooks.deliver_due().await.unwrap();
+    assert_eq!(report, DeliveryRunReport { delivered: 0, retrying: 1, failed: 0 });
+    let delivery = f.webhooks.list_deliveries(f.owner, f.merchant_id).await.unwrap().remove(0);
+    assert_eq!((delivery.status.as_str(), delivery.attempts, delivery.last_status_code), ("PENDING", 1, Some(500)));
+    assert!(delivery.next_attempt_at.unwrap() > chrono::Utc::now() + chrono::Duration::seconds(50));
+    // Not due yet
+    assert_eq!(f.webhooks.deliver_due().await.unwrap(), DeliveryRunReport::default());
+
+    // Out of attempts
+    sqlx::query!("UPDATE webhook_deliveries SET next_attempt_at = NOW()")
+        .execute(&ctx.db)
+        .await
+        .unwrap();
+    let report = f.webhooks.deliver_due().await.unwrap();
+    assert_eq!(report, DeliveryRunReport { delivered: 0, retrying: 0, failed: 1 });
+
+    // The merchant fixes their server and asks for it again
+    *f.receiver.status.lock().unwrap() = 204;
+    let log = f.webhooks.redeliver(f.owner, f.merchant_id, delivery.delivery_id).await.unwrap();
+    assert_eq!(log.delivery.status, "SUCCEEDED");
+    let codes: Vec<Option<i32>> = log.attempts.iter().map(|a| a.status_code).collect();
+    assert_eq!(codes, [Some(500), Some(500), Some(204)]);
+
+    // Every attempt carried the same event
+    let received = f.receiver.received.lock().unwrap().clone();
+    assert_eq!(received.len(), 3);
+    assert!(received.iter().all(|(_, body)| *body == log.payload));
+    let event: serde_json::Value = serde_json::from_str(&log.payload).unwrap();
+    assert_eq!(event["type"], "payment_intent.canceled");
+
+    // Someone else's merchant cannot replay it
+    let err = f.webhooks.redeliver(new_uuid(), f.merchant_id, delivery.delivery_id).await.unwrap_err();
+    assert!(matches!(err, WebhookError::MerchantError(MerchantError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_endpoint_registration_rules() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, RetryPolicy::default()).await;
+
+    let err = f.webhooks
+        .register_endpoint(f.owner, f.merchant_id, endpoint("http://shop.example/hooks", &["*"]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, WebhookError::ValidationError(_)));
+    let err = f.webhooks
+        .register_endpoint(f.owner, f.merchant_id, endpoint("https://shop.example/hooks", &["payment.created"]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, WebhookError::ValidationError(_)));
+    let err = f.webhooks
+        .register_endpoint(new_uuid(), f.merchant_id, endpoint("https://shop.example/hooks", &["*"]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, WebhookError::MerchantError(MerchantError::NotFound(_))));
+
+    // A disabled endpoint gets nothing
+    let registered = f.webhooks.register_endpoint(f.owner, f.merchant_id, endpoint(&f.url, &["*"])).await.unwrap();
+    let disabled = f.webhooks
+        .disable_endpoint(f.owner, f.merchant_id, registered.endpoint.endpoint_id)
+        .await
+        .unwrap();
+    assert_eq!(disabled.status, "DISABLED");
+    f.webhooks.emit(f.merchant_id, "settlement.paid", serde_json::json!({})).await.unwrap();
+    assert!(f.webhooks.list_deliveries(f.owner, f.merchant_id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_endpoints_must_resolve_to_public_addresses() {
+    let strict = DestinationPolicy { allow_loopback: false };
+    for url in [
+        "https://127.0.0.1/hooks",
+        "https://localhost/hooks",
+        "https://10.1.2.3/hooks",
+        "https://192.168.0.10/hooks",
+        "https://169.254.169.254/latest/meta-data",
+        "https://100.64.0.1/hooks",
+        "https://[::1]/hooks",
+        "https://[fd00::1]/hooks",
+        "https://[::ffff:10.0.0.1]/hooks",
+    ] {
+        assert!(matches!(strict.resolve(url).await, Err(DestinationError::NonPublicAddress(_))), "{url}");
+    }
+    assert!(matches!(strict.resolve("http://127.0.0.1:8080/hooks").await, Err(DestinationError::HttpsRequired)));
+    assert!(strict.resolve("https://93.184.216.34/hooks").await.is_ok());
+
+    // Development allows this machine, and only this machine
+    let dev = DestinationPolicy { allow_loopback: true };
+    assert!(dev.resolve("http://127.0.0.1:8080/hooks").await.is_ok());
+    assert!(matches!(dev.resolve("https://10.1.2.3/hooks").await, Err(DestinationError::NonPublicAddress(_))));
+    assert!(matches!(dev.resolve("http://93.184.216.34/hooks").await, Err(DestinationError::HttpsRequired)));
+
+    assert!(is_public("8.8.8.8".parse().unwrap()));
+    assert!(!is_public("172.20.0.1".parse().unwrap()));
+    assert!(!is_public("fe80::1".parse().unwrap()));
+}
+
+#[tokio::test]
+async fn test_destination_is_checked_again_before_each_delivery() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, RetryPolicy::default()).await;
+    let registered = f.webhooks.register_endpoint(f.owner, f.merchant_id, endpoint(&f.url, &["*"])).await.unwrap();
+
+    // The host now points into the private network
+    sqlx::query!(
+        "UPDATE webhook_endpoints SET url = 'https://10.0.0.7/hooks' WHERE endpoint_id = $1",
+        registered.endpoint.endpoint_id
+    )
+    .execute(&ctx.db)
+    .await
+    .unwrap();
+    f.webhooks.emit(f.merchant_id, "settlement.paid", serde_json::json!({})).await.unwrap();
+
+    let report = f.webhooks.deliver_due().await.unwrap();
+    assert_eq!(report, DeliveryRunReport { delivered: 0, retrying: 1, failed: 0 });
+    let delivery = f.webhooks.list_deliveries(f.owner, f.merchant_id).await.unwrap().remove(0);
+    assert!(delivery.last_error.unwrap().contains("non-public"));
+    assert!(delivery.last_status_code.is_none());
+}
+
+#[tokio::test]
+async fn test_disabled_endpoint_is_not_redelivered_to() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, RetryPolicy::default()).await;
+    let registered = f.webhooks.register_endpoint(f.owner, f.merchant_id, endpoint(&f.url, &["*"])).await.unwrap();
+    f.webhooks.emit(f.merchant_id, "settlement.paid", serde_json::json!({})).await.unwrap();
+    f.webhooks.deliver_due().await.unwrap();
+    let delivery = f.webhooks.list_deliveries(f.owner, f.merchant_id).await.unwrap().remove(0);
+
+    f.webhooks.disable_endpoint(f.owner, f.merchant_id, registered.endpoint.endpoint_id).await.unwrap();
+    let err = f.webhooks.redeliver(f.owner, f.merchant_id, delivery.delivery_id).await.unwrap_err();
+    assert!(matches!(err, WebhookError::EndpointDisabled(id) if id == registered.endpoint.endpoint_id));
+    assert_eq!(f.receiver.received.lock().unwrap().len(), 1);
+
+    let err = f.webhooks.redeliver(f.owner, f.merchant_id, new_uuid()).await.unwrap_err();
+    assert!(matches!(err, WebhookError::DeliveryNotFound(_)));
+}
+
+#[tokio::test]
+async fn test_replayed_refund_is_reported_once() {
+    let ctx = TestContext::new().await;
+    let f = setup(&ctx, RetryPolicy::default()).await;
+    f.webhooks.register_endpoint(f.owner, f.merchant_id, endpoint(&f.url, &["refund.succeeded"])).await.unwrap();
+    let intent = create_intent(&f, "ORD-6").await;
+    f.intents.confirm(f.payer, intent.id, ConfirmPaymentIntentRequest { client_secret: intent.client_secret.clone() })
+        .await
+        .unwrap();
+
+    let refund = || RefundRequest { amount: Some(2000), reason: None, idempotency_key: "refund-ord-6".to_string() };
+    let first = f.intents.refund(f.owner, intent.id, refund()).await.unwrap();
+    let replay = f.intents.refund(f.owner, intent.id, refund()).await.unwrap();
+    assert_eq!(first.tx_id, replay.tx_id);
+
+    let events = sqlx::query_scalar!("SELECT COUNT(*) FROM webhook_events WHERE event_type = 'refund.succeeded'")
+        .fetch_one(&ctx.db)
+        .await
+        .unwrap();
+    assert_eq!(events, Some(1));
+}